@@ -0,0 +1,87 @@
+//! Mock Swap Program
+//!
+//! Stands in for a DEX in the `leverage` integration tests: the caller names
+//! both amounts, the input is taken from the user and the output is paid out
+//! of a pool held by the `pool` PDA. Tests only; never deployed.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+declare_id!("EGfa1CbE4cDJ677BiRZJ8ts7eu6DmfsGtkr3yrTQVsGF");
+
+#[program]
+pub mod mock_swap {
+    use super::*;
+
+    /// Swap `amount_in` of the user's tokens for `amount_out` from the pool
+    ///
+    /// **Accounts:**
+    /// - `user_source`: User's input token account
+    /// - `user_destination`: User's output token account
+    /// - `pool_source`: Pool account receiving the input
+    /// - `pool_destination`: Pool account paying the output
+    /// - `pool_authority`: Pool PDA (owns both pool accounts)
+    /// - `user`: User wallet (signer, authorizes the input transfer)
+    /// - `token_program`: SPL token program
+    pub fn swap(ctx: Context<Swap>, amount_in: u64, amount_out: u64) -> Result<()> {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_source.to_account_info(),
+                    to: ctx.accounts.pool_source.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount_in,
+        )?;
+
+        let seeds = &[POOL_SEED, &[ctx.bumps.pool_authority]];
+        let signer_seeds = &[&seeds[..]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_destination.to_account_info(),
+                    to: ctx.accounts.user_destination.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount_out,
+        )
+    }
+}
+
+/// PDA seed of the pool authority
+pub const POOL_SEED: &[u8] = b"pool";
+
+/// Accounts for a fixed-rate swap against the pool
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    /// User's input token account
+    #[account(mut)]
+    pub user_source: Account<'info, TokenAccount>,
+
+    /// User's output token account
+    #[account(mut)]
+    pub user_destination: Account<'info, TokenAccount>,
+
+    /// Pool account receiving the input
+    #[account(mut, constraint = pool_source.owner == pool_authority.key())]
+    pub pool_source: Account<'info, TokenAccount>,
+
+    /// Pool account paying the output
+    #[account(mut, constraint = pool_destination.owner == pool_authority.key())]
+    pub pool_destination: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA signing for the pool accounts, holds no data
+    #[account(seeds = [POOL_SEED], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    /// User wallet (signer)
+    pub user: Signer<'info>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+}
@@ -52,6 +52,15 @@ pub const LLTV_PRECISION: u64 = 100_000_000;
 /// **Future Enhancement:** Replace with Pyth/Switchboard oracle integration
 pub const FIXED_ORACLE_PRICE: u64 = 100_000; // 100 * PRICE_PRECISION / 1000 for decimal adjustment
 
+/// Default collateral price per whole token, before decimal scaling
+///
+/// **Value:** 100,000,000 (100 whole loan tokens per whole collateral token)
+///
+/// **Usage:** `initialize_market` with `fixed_price == 0` scales this to the
+/// mints' decimals via `base_unit_price`; for SOL/USDC (9/6) the result is
+/// FIXED_ORACLE_PRICE
+pub const DEFAULT_WHOLE_TOKEN_PRICE: u64 = 100 * PRICE_PRECISION;
+
 /// Maximum LLTV allowed (100%)
 ///
 /// **Value:** 100,000,000 (100% * LLTV_PRECISION)
@@ -59,3 +68,146 @@ pub const FIXED_ORACLE_PRICE: u64 = 100_000; // 100 * PRICE_PRECISION / 1000 for
 /// **Purpose:** Validation boundary for market initialization
 /// - LLTV must be: 0 < lltv <= MAX_LLTV
 pub const MAX_LLTV: u64 = LLTV_PRECISION;
+
+/// Basis points denominator (100%)
+///
+/// **Value:** 10,000 (1 bps = 0.01%)
+///
+/// **Usage:** Ratios and percentages expressed in basis points
+/// - Example: 50% → 5_000 bps
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Default tolerated loan vault shortfall, in whole loan tokens
+///
+/// **Value:** 1 token (1,000,000 base units for USDC), scaled by the loan
+/// mint's decimals into `market.vault_tolerance` at initialization
+///
+/// **Purpose:** Defense-in-depth against accounting drift
+/// - Expected vault balance: total_supply_assets + reserves - total_borrow_assets
+/// - A vault holding less than expected by more than the tolerance blocks
+///   `supply` until the market is reconciled
+/// - Surpluses (direct donations) never reach total_supply_assets, so they
+///   cannot move the share price; they pass and can be removed with `skim_vault`
+pub const VAULT_ACCOUNTING_TOLERANCE_TOKENS: u64 = 1;
+
+/// Maximum protocol fee on accrued interest
+///
+/// **Value:** 2,500 bps (25%)
+///
+/// **Purpose:** Upper bound for `market.fee_bps` so suppliers always keep
+/// at least 75% of the interest paid by borrowers (matches Pelago.sol MAX_FEE)
+pub const MAX_FEE_BPS: u16 = 2_500;
+
+/// Sentinel `fee_bps` for `initialize_market`: inherit the protocol default
+///
+/// **Value:** u16::MAX (never a valid fee, see MAX_FEE_BPS)
+///
+/// **Usage:** Resolved against `ProtocolConfig::default_fee_bps`
+pub const USE_PROTOCOL_DEFAULT_FEE: u16 = u16::MAX;
+
+/// Maximum one-time origination fee on borrows
+///
+/// **Value:** 500 bps (5%)
+///
+/// **Purpose:** Upper bound for `market.origination_fee_bps`
+pub const MAX_ORIGINATION_FEE_BPS: u16 = 500;
+
+/// Default cap on the interest a single accrual may add to total borrows
+///
+/// **Value:** 1,000 bps (10%)
+///
+/// **Purpose:** Safety rail for `market.max_accrual_interest_bps`; at the
+/// fixed 5% rate only a market idle for about two years reaches it
+pub const DEFAULT_MAX_ACCRUAL_INTEREST_BPS: u16 = 1_000;
+
+/// Liquidation bonus paid to liquidators in seized collateral
+///
+/// **Value:** 500 bps (5%)
+///
+/// **Usage:** `seized_value = repaid_assets × (10_000 + LIQUIDATION_BONUS_BPS) / 10_000`
+pub const LIQUIDATION_BONUS_BPS: u64 = 500;
+
+/// Maximum share of a position's debt repayable in one liquidation
+///
+/// **Value:** 5,000 bps (50%)
+///
+/// **Purpose:** Partial liquidations restore health without wiping out the
+/// borrower; a still-unhealthy position can be liquidated again
+pub const CLOSE_FACTOR_BPS: u64 = 5_000;
+
+/// Maximum positions processed by one `batch_liquidate` call
+///
+/// **Value:** 8
+///
+/// **Purpose:** Keeps the instruction within the compute budget
+/// (see `utils::batch`)
+pub const MAX_BATCH_LIQUIDATIONS: usize = 8;
+
+/// Maximum positions read by one `check_bad_debt` or
+/// `resync_total_collateral` call
+///
+/// **Value:** 32
+///
+/// **Purpose:** Keeps the read-only loops within the compute budget
+/// (see `utils::batch`)
+pub const MAX_BATCH_POSITION_READS: usize = 32;
+
+/// Seed assets whose shares are locked forever by `seed_market`
+///
+/// **Value:** 1,000 base units (0.001 USDC with 6 decimals)
+///
+/// **Purpose:** Dead shares keep the market from ever returning to empty,
+/// closing the first-depositor inflation window for good
+pub const MIN_SEED_ASSETS: u64 = 1_000;
+
+/// Largest residue `sweep_dust` may move out of a market's totals
+///
+/// **Value:** 1,000 base units (0.001 USDC with 6 decimals)
+///
+/// **Purpose:** Rounding leaves a few base units at most; a larger residue
+/// points to an accounting bug that sweeping would hide
+pub const MAX_DUST_SWEEP_ASSETS: u64 = 1_000;
+
+/// Largest `first_supply_dead_shares` a market may configure
+///
+/// **Value:** 1e9 shares (1,000 base units at the empty-market price,
+/// the same lock as `seed_market`). Loan tokens with more than 6 decimals
+/// may go up to `recommended_first_supply_dead_shares` instead.
+///
+/// **Purpose:** The dead shares are carved out of the first supplier's
+/// deposit, so the lock must stay a rounding-sized cost
+pub const MAX_FIRST_SUPPLY_DEAD_SHARES: u64 = 1_000_000_000;
+
+/// Highest supply reward emission a market may configure
+///
+/// **Value:** 1e12 base units per second (1,000,000 tokens/s with 6 decimals)
+///
+/// **Purpose:** Keeps `rate × elapsed × 1e18` within u128 for accrual gaps
+/// of several years, so reward accrual can never block interest accrual
+pub const MAX_REWARD_RATE_PER_SECOND: u64 = 1_000_000_000_000;
+
+/// Largest factor the borrow share ratio may drift from its baseline
+///
+/// **Value:** 1,000 (shares per asset within VIRTUAL_SHARES / 1,000 and
+/// VIRTUAL_SHARES × 1,000)
+///
+/// **Purpose:** Interest moves the ratio by a few percent a year; a
+/// thousandfold drift only comes from manipulated or written-off accounting,
+/// where new borrows would be priced in distorted shares
+pub const MAX_SHARE_RATIO_DEVIATION: u128 = 1_000;
+
+/// Highest annual borrow rate a market's kinked rate model may reach
+///
+/// **Value:** 5e18 (500% APR, precision 1e18)
+///
+/// **Purpose:** Bounds `base + slope1 + slope2` so a misconfigured curve
+/// cannot run interest away at full utilization
+pub const MAX_IRM_RATE_WAD: u128 = 5_000_000_000_000_000_000;
+
+/// Largest `dust_shares_threshold` a market may configure
+///
+/// **Value:** 1e6 shares (one base unit at the empty-market price)
+///
+/// **Purpose:** Only sub-unit rounding dust may be ignored by health checks;
+/// anything larger is real debt
+pub const MAX_DUST_SHARES_THRESHOLD: u64 = 1_000_000;
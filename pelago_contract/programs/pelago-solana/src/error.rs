@@ -76,8 +76,230 @@ pub enum PelagoError {
     InvalidTimestamp,
 
     /// Error code: 6012
-    /// Invalid vault account
-    /// Triggered when: provided vault doesn't match market's vault
+    /// Invalid token account
+    /// Triggered when: a payer/user token account has the wrong mint (vault mismatches use WrongLoanVault/WrongCollateralVault)
     #[msg("Invalid vault: vault account mismatch")]
     InvalidVault,
+
+    /// Error code: 6013
+    /// Loan vault balance doesn't cover market accounting
+    /// Triggered when: (total_supply_assets + reserves - total_borrow_assets) - loan_vault.amount > market.vault_tolerance
+    #[msg("Vault accounting mismatch: loan vault holds less than market accounting")]
+    VaultAccountingMismatch,
+
+    /// Error code: 6014
+    /// Operation result is worse than the caller's bound
+    /// Triggered when: received amount < caller-specified minimum
+    #[msg("Slippage exceeded: result is outside the caller's bound")]
+    SlippageExceeded,
+
+    /// Error code: 6015
+    /// Position leverage exceeds the maximum allowed by LLTV
+    /// Triggered when: collateral_value / equity > 1 / (1 - lltv)
+    #[msg("Leverage too high: exceeds the maximum derived from LLTV")]
+    LeverageTooHigh,
+
+    /// Error code: 6016
+    /// Market is paused
+    /// Triggered when: supply/borrow/withdraw/withdraw_collateral on a paused market
+    #[msg("Market paused: operation is disabled while the market is paused")]
+    MarketPaused,
+
+    /// Error code: 6017
+    /// Borrow would exceed the market's borrow cap
+    /// Triggered when: total_borrow_assets > min(borrow_cap, supply × borrow_cap_ratio_bps / 10_000)
+    #[msg("Borrow cap exceeded: market borrow cap reached")]
+    BorrowCapExceeded,
+
+    /// Error code: 6018
+    /// Invalid market parameter
+    /// Triggered when: a configured value is outside its allowed range
+    #[msg("Invalid parameter: value is outside the allowed range")]
+    InvalidParameter,
+
+    /// Error code: 6019
+    /// Withdrawal would leave outstanding borrows uncovered by supply
+    /// Triggered when: total_borrow_assets > total_supply_assets after a withdraw
+    #[msg("Withdraw breaks liquidity: remaining supply would not cover outstanding borrows")]
+    WithdrawBreaksLiquidity,
+
+    /// Error code: 6020
+    /// Position has no collateral to borrow against
+    /// Triggered when: borrow is called with collateral_amount == 0
+    #[msg("No collateral: supply collateral before borrowing")]
+    NoCollateral,
+
+    /// Error code: 6021
+    /// Invalid collateral price
+    /// Triggered when: set_manual_price is called with price == 0
+    #[msg("Invalid price: price must be greater than zero")]
+    InvalidPrice,
+
+    /// Error code: 6022
+    /// Market has reached its maximum number of open positions
+    /// Triggered when: a new position is created while open_positions == max_positions
+    #[msg("Position limit reached: market cannot open more positions")]
+    PositionLimitReached,
+
+    /// Error code: 6023
+    /// Position debt would exceed the per-position borrow limit
+    /// Triggered when: position debt assets > max_borrow_per_position
+    #[msg("Position borrow limit: this position cannot borrow more")]
+    PositionBorrowLimit,
+
+    /// Error code: 6024
+    /// Resync was not given exactly the market's open positions
+    /// Triggered when: position count != open_positions, duplicates, or foreign positions
+    #[msg("Incomplete resync: all open positions of the market must be provided once")]
+    IncompleteResync,
+
+    /// Error code: 6025
+    /// Virtual share/asset offsets can overflow u128 for this loan token
+    /// Triggered when: worst-case conversion product exceeds u128::MAX at market init
+    #[msg("Unsafe virtual offsets: share conversions could overflow for this token")]
+    UnsafeVirtualOffsets,
+
+    /// Error code: 6026
+    /// Timelocked change applied too early
+    /// Triggered when: apply_lltv is called before lltv_effective_at
+    #[msg("Timelock not elapsed: the pending change cannot be applied yet")]
+    TimelockNotElapsed,
+
+    /// Error code: 6027
+    /// Market accounting invariant broken
+    /// Triggered when: total_borrow_assets > total_supply_assets or fee_shares > total_supply_shares
+    #[msg("Invariant violation: market accounting is inconsistent")]
+    InvariantViolation,
+
+    /// Error code: 6028
+    /// New borrows refused by the utilization circuit breaker
+    /// Triggered when: utilization > auto_pause_utilization_bps at borrow time
+    #[msg("Auto-paused: market utilization is above the borrow watermark")]
+    AutoPaused,
+
+    /// Error code: 6029
+    /// Position borrowed again before the market's cooldown elapsed
+    /// Triggered when: now - last_borrow_ts < borrow_cooldown_secs
+    #[msg("Borrow cooldown: this position borrowed too recently")]
+    BorrowCooldown,
+
+    /// Error code: 6030
+    /// User registry has no room for another market
+    /// Triggered when: a position is opened in a new market with UserRegistry::CAPACITY markets recorded
+    #[msg("User registry full: cannot track positions in more markets")]
+    UserRegistryFull,
+
+    /// Error code: 6031
+    /// Market has no liquidity left to borrow
+    /// Triggered when: total_supply_assets == total_borrow_assets at borrow time
+    #[msg("No liquidity: market is fully utilized")]
+    NoLiquidity,
+
+    /// Error code: 6032
+    /// Collateral price outside the market's sanity band
+    /// Triggered when: price < min_sane_price or price > max_sane_price
+    #[msg("Oracle price out of bounds: price is outside the market's plausible range")]
+    OraclePriceOutOfBounds,
+
+    /// Error code: 6033
+    /// LLTV not in the protocol's enabled set
+    /// Triggered when: initialize_market is given an LLTV that governance has not enabled
+    #[msg("LLTV not enabled: this LLTV is not whitelisted for new markets")]
+    LltvNotEnabled,
+
+    /// Error code: 6034
+    /// Position still holds supply shares, debt or collateral
+    /// Triggered when: close_position is called on a non-empty position
+    #[msg("Position not empty: withdraw, repay and remove collateral before closing")]
+    PositionNotEmpty,
+
+    /// Error code: 6035
+    /// Liquidation repays less than the market's minimum
+    /// Triggered when: a partial liquidation repays below min_liquidation_assets
+    #[msg("Liquidation too small: partial liquidation repays below the market minimum")]
+    LiquidationTooSmall,
+
+    /// Error code: 6036
+    /// Collateral price is older than the liquidation staleness limit
+    /// Triggered when: batch_liquidate runs on a manual price older than liquidation_max_staleness_secs
+    #[msg("Stale oracle: price is too old to liquidate on")]
+    StaleOracle,
+
+    /// Error code: 6037
+    /// A token transfer moved a different amount than requested
+    /// Triggered when: a vault's balance change after a transfer differs from the amount (e.g. fee-bearing mints)
+    #[msg("Transfer amount mismatch: vault balance changed by an unexpected amount")]
+    TransferAmountMismatch,
+
+    /// Error code: 6038
+    /// Loan vault account does not match the market's loan vault
+    /// Triggered when: the loan_vault passed is not market.loan_vault (e.g. the collateral vault)
+    #[msg("Wrong loan vault: account is not this market's loan vault")]
+    WrongLoanVault,
+
+    /// Error code: 6039
+    /// Collateral vault account does not match the market's collateral vault
+    /// Triggered when: the collateral_vault passed is not market.collateral_vault (e.g. the loan vault)
+    #[msg("Wrong collateral vault: account is not this market's collateral vault")]
+    WrongCollateralVault,
+
+    /// Error code: 6040
+    /// Borrow shares per asset drifted too far from the virtual baseline
+    /// Triggered when: a borrow leaves the borrow share price beyond MAX_SHARE_RATIO_DEVIATION of VIRTUAL_SHARES per asset
+    #[msg("Share ratio distorted: borrow share accounting is outside sane bounds")]
+    ShareRatioDistorted,
+
+    /// Error code: 6041
+    /// A batch instruction was given more accounts than it can process
+    /// Triggered when: remaining_accounts exceeds the instruction's batch limit (see utils::batch)
+    #[msg("Batch too large: too many accounts for one instruction")]
+    BatchTooLarge,
+
+    /// Error code: 6042
+    /// Repay targets a position without debt
+    /// Triggered when: repay is called for a borrower whose borrow_shares are 0 (e.g. fully liquidated)
+    #[msg("No debt to repay: borrower position has no borrow shares")]
+    NoDebtToRepay,
+
+    /// Error code: 6043
+    /// First supply into an empty market is below the market's minimum
+    /// Triggered when: supply or seed_market into a market without supply shares deposits less than min_initial_deposit
+    #[msg("Initial deposit too small: first supply is below the market minimum")]
+    InitialDepositTooSmall,
+
+    /// Error code: 6044
+    /// Position targeted for liquidation is healthy
+    /// Triggered when: liquidate or liquidate_to_target is called on a position within its borrowing power
+    #[msg("Position healthy: nothing to liquidate")]
+    PositionHealthy,
+
+    /// Error code: 6045
+    /// Position is frozen by the market authority
+    /// Triggered when: borrow, withdraw, withdraw_collateral, leverage, open_position or close_position targets a frozen position
+    #[msg("Position frozen: borrows and withdrawals are blocked")]
+    PositionFrozen,
+
+    /// Error code: 6046
+    /// Market still holds positions or balances
+    /// Triggered when: reset_market is called while positions, shares, assets or collateral remain
+    #[msg("Market not empty: positions or balances remain")]
+    MarketNotEmpty,
+
+    /// Error code: 6047
+    /// Liquidator is the borrower
+    /// Triggered when: batch_liquidate or liquidate_to_target targets the liquidator's own position while the market disallows self-liquidation
+    #[msg("Self-liquidation disallowed for this market")]
+    SelfLiquidationDisallowed,
+
+    /// Error code: 6048
+    /// Collateral deposit would exceed the market's collateral cap
+    /// Triggered when: supply_collateral, open_position or leverage pushes total_collateral above a non-zero collateral_cap
+    #[msg("Collateral cap exceeded")]
+    CollateralCapExceeded,
+
+    /// Error code: 6049
+    /// Market has more open positions than one resync can read
+    /// Triggered when: resync_total_collateral is called on a market with more than MAX_BATCH_POSITION_READS open positions
+    #[msg("Too many open positions to resync in one instruction")]
+    ResyncLimitExceeded,
 }
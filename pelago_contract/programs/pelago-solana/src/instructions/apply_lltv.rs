@@ -0,0 +1,159 @@
+//! Apply LLTV Instruction
+//!
+//! Second phase of a timelocked LLTV change: commits `market.pending_lltv`
+//! once `market.lltv_effective_at` has passed.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Apply a pending LLTV change after its timelock
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct ApplyLltv<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for apply_lltv instruction
+///
+/// **State Changes:**
+/// - market.lltv = market.pending_lltv
+/// - market.pending_lltv = 0, market.lltv_effective_at = 0
+///
+/// **Errors:**
+/// - InvalidParameter: No pending LLTV change
+/// - TimelockNotElapsed: now < market.lltv_effective_at
+pub fn handler(ctx: Context<ApplyLltv>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let market = &mut ctx.accounts.market;
+
+    let previous_lltv = apply_lltv_at(market, now)?;
+
+    msg!(
+        "LLTV applied: market={}, previous={}, new={}",
+        market.key(),
+        previous_lltv,
+        market.lltv
+    );
+
+    emit!(LltvAppliedEvent {
+        market: market.key(),
+        previous_lltv,
+        new_lltv: market.lltv,
+    });
+
+    Ok(())
+}
+
+/// Commits the pending LLTV if its timelock has elapsed at `now`
+///
+/// **Returns:** The LLTV that was replaced
+pub fn apply_lltv_at(market: &mut Market, now: i64) -> Result<u64> {
+    require!(market.pending_lltv > 0, PelagoError::InvalidParameter);
+    require!(
+        now >= market.lltv_effective_at,
+        PelagoError::TimelockNotElapsed
+    );
+
+    let previous_lltv = market.lltv;
+    market.lltv = market.pending_lltv;
+    market.pending_lltv = 0;
+    market.lltv_effective_at = 0;
+
+    Ok(previous_lltv)
+}
+
+/// Event emitted when a pending LLTV change is applied
+#[event]
+pub struct LltvAppliedEvent {
+    /// Market public key
+    pub market: Pubkey,
+
+    /// LLTV before the change
+    pub previous_lltv: u64,
+
+    /// LLTV now in force
+    pub new_lltv: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::propose_lltv::propose_lltv_at;
+
+    const NOW: i64 = 1_700_000_000;
+    const DAY: i64 = 86_400;
+
+    fn timelocked_market() -> Market {
+        Market {
+            lltv: 80_000_000,
+            lltv_timelock: DAY,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_before_timelock_rejected() {
+        let mut market = timelocked_market();
+        propose_lltv_at(&mut market, 70_000_000, NOW).unwrap();
+        assert_eq!(market.lltv_effective_at, NOW + DAY);
+
+        assert_eq!(
+            apply_lltv_at(&mut market, NOW + DAY - 1).unwrap_err(),
+            PelagoError::TimelockNotElapsed.into()
+        );
+        assert_eq!(market.lltv, 80_000_000);
+        assert_eq!(market.pending_lltv, 70_000_000);
+    }
+
+    #[test]
+    fn test_apply_after_timelock() {
+        let mut market = timelocked_market();
+        propose_lltv_at(&mut market, 70_000_000, NOW).unwrap();
+
+        assert_eq!(apply_lltv_at(&mut market, NOW + DAY).unwrap(), 80_000_000);
+        assert_eq!(market.lltv, 70_000_000);
+        assert_eq!(market.pending_lltv, 0);
+
+        // Nothing left to apply
+        assert_eq!(
+            apply_lltv_at(&mut market, NOW + 2 * DAY).unwrap_err(),
+            PelagoError::InvalidParameter.into()
+        );
+    }
+
+    #[test]
+    fn test_reproposing_restarts_timelock() {
+        let mut market = timelocked_market();
+        propose_lltv_at(&mut market, 70_000_000, NOW).unwrap();
+        propose_lltv_at(&mut market, 75_000_000, NOW + DAY - 1).unwrap();
+
+        assert_eq!(
+            apply_lltv_at(&mut market, NOW + DAY).unwrap_err(),
+            PelagoError::TimelockNotElapsed.into()
+        );
+        apply_lltv_at(&mut market, NOW + 2 * DAY - 1).unwrap();
+        assert_eq!(market.lltv, 75_000_000);
+
+        assert_eq!(
+            propose_lltv_at(&mut market, 0, NOW).unwrap_err(),
+            PelagoError::InvalidLltv.into()
+        );
+    }
+}
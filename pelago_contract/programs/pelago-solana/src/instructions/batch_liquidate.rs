@@ -0,0 +1,258 @@
+//! Batch Liquidate Instruction
+//!
+//! Lets keepers liquidate several unhealthy positions of one market in a
+//! single transaction. Interest is accrued once; each position passed via
+//! `remaining_accounts` is then liquidated if unhealthy and skipped if
+//! healthy, so a position repaid in the meantime doesn't fail the batch.
+//!
+//! Repaid loan tokens and seized collateral are netted into one transfer
+//! each. See `utils::liquidation` for the close factor and bonus math.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::MAX_BATCH_LIQUIDATIONS;
+use crate::error::PelagoError;
+use crate::state::{Market, UserPosition};
+use crate::utils::batch::check_batch_size;
+use crate::utils::interest::accrue_interest;
+use crate::utils::invariants::check_recovery_invariants;
+use crate::utils::oracle::check_price_freshness;
+use crate::utils::vault_snapshot::snapshot_balance_delta;
+use crate::utils::liquidation::{
+    apply_liquidation, check_min_liquidation, check_min_seize, check_self_liquidation,
+    compute_liquidation, liquidation_bonus, position_health, PositionHealthEvent,
+};
+
+/// Liquidate unhealthy positions in one market
+///
+/// **Remaining Accounts:** Up to `MAX_BATCH_LIQUIDATIONS` distinct
+/// `UserPosition` accounts of this market (writable)
+///
+/// Liquidations stay open while the market is paused, so an emergency pause
+/// can't let bad debt build up.
+#[derive(Accounts)]
+pub struct BatchLiquidate<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market's loan token vault (receives repaid debt)
+    #[account(
+        mut,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
+    )]
+    pub loan_vault: Account<'info, TokenAccount>,
+
+    /// Market's collateral token vault (source of seized collateral)
+    #[account(
+        mut,
+        constraint = collateral_vault.key() == market.collateral_vault @ PelagoError::WrongCollateralVault,
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+
+    /// Liquidator's loan token account (pays the repaid debt)
+    #[account(mut)]
+    pub liquidator_loan_account: Account<'info, TokenAccount>,
+
+    /// Liquidator's collateral token account (receives seized collateral)
+    #[account(mut)]
+    pub liquidator_collateral_account: Account<'info, TokenAccount>,
+
+    /// Liquidator wallet (signer)
+    pub liquidator: Signer<'info>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler for batch_liquidate instruction
+///
+/// **Processing Steps:**
+/// 1. Validate the position count
+/// 2. Accrue interest once and check the price against
+///    `liquidation_max_staleness_secs`
+/// 3. Liquidate each unhealthy position (close factor + bonus), skip healthy ones;
+///    partial liquidations must repay at least `min_liquidation_assets`
+/// 4. Check the total seized collateral against `min_seize`
+/// 5. Transfer total repaid loan tokens in and total seized collateral out
+///
+/// **Slippage:** `min_seize > 0` bounds the total collateral seized across
+/// the batch, protecting keepers from prices or positions that moved
+/// between simulation and execution
+///
+/// **Errors:**
+/// - InvalidParameter: No positions, a duplicate, or a position from
+///   another market
+/// - BatchTooLarge: More than MAX_BATCH_LIQUIDATIONS positions
+/// - SelfLiquidationDisallowed: The liquidator owns one of the positions
+///   and the market disallows self-liquidation
+/// - LiquidationTooSmall: A partial liquidation repays less than the
+///   market's `min_liquidation_assets`
+/// - SlippageExceeded: Total seized collateral is below `min_seize`
+/// - StaleOracle: Manual price older than `liquidation_max_staleness_secs`
+/// - MathOverflow: Calculation overflow
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BatchLiquidate<'info>>,
+    min_seize: u64,
+) -> Result<()> {
+    let count = ctx.remaining_accounts.len();
+    require!(count > 0, PelagoError::InvalidParameter);
+    check_batch_size(count, MAX_BATCH_LIQUIDATIONS)?;
+
+    let market = &mut ctx.accounts.market;
+    let market_key = market.key();
+    let liquidator = ctx.accounts.liquidator.key();
+
+    // Step 2: Accrue once for the whole batch, on a fresh enough price
+    accrue_interest(market)?;
+    let borrow_assets_before = market.total_borrow_assets;
+    check_price_freshness(
+        market,
+        Clock::get()?.unix_timestamp,
+        market.liquidation_max_staleness_secs,
+    )?;
+
+    // Step 3: Liquidate unhealthy positions
+    let mut seen: Vec<Pubkey> = Vec::with_capacity(count);
+    let mut total_repaid: u64 = 0;
+    let mut total_seized: u64 = 0;
+    let mut liquidated: u32 = 0;
+
+    for account_info in ctx.remaining_accounts.iter() {
+        let mut position: Account<UserPosition> = Account::try_from(account_info)?;
+        require!(
+            position.market == market_key && !seen.contains(account_info.key),
+            PelagoError::InvalidParameter
+        );
+        seen.push(account_info.key());
+        check_self_liquidation(market, &position, &liquidator)?;
+
+        let Some(liquidation) = compute_liquidation(market, &position)? else {
+            msg!("Skipping healthy position: {}", account_info.key());
+            continue;
+        };
+
+        check_min_liquidation(market, &position, &liquidation)?;
+        apply_liquidation(market, &mut position, &liquidation)?;
+        position.exit(&crate::ID)?;
+
+        total_repaid = total_repaid
+            .checked_add(liquidation.repaid_assets)
+            .ok_or(PelagoError::MathOverflow)?;
+        total_seized = total_seized
+            .checked_add(liquidation.seized_collateral)
+            .ok_or(PelagoError::MathOverflow)?;
+        liquidated += 1;
+
+        emit!(LiquidateEvent {
+            market: market_key,
+            liquidator,
+            borrower: position.user,
+            repaid_assets: liquidation.repaid_assets,
+            repaid_shares: liquidation.repaid_shares,
+            seized_collateral: liquidation.seized_collateral,
+            bonus_collateral: liquidation_bonus(market, &liquidation)?,
+            remaining_borrow_shares: position.borrow_shares,
+            remaining_collateral: position.collateral_amount,
+        });
+        let health = position_health(market, &position)?;
+        emit!(PositionHealthEvent {
+            market: market_key,
+            user: position.user,
+            health_factor: health.health_factor,
+            collateral_value: health.collateral_value,
+            debt_value: health.debt_value,
+        });
+    }
+
+    check_recovery_invariants(market, borrow_assets_before)?;
+
+    msg!(
+        "Batch liquidation: positions={}, liquidated={}, repaid={}, seized={}",
+        count,
+        liquidated,
+        total_repaid,
+        total_seized
+    );
+
+    check_min_seize(total_seized, min_seize)?;
+
+    if liquidated == 0 {
+        return Ok(());
+    }
+
+    // Step 5: Net transfers
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.liquidator_loan_account.to_account_info(),
+            to: ctx.accounts.loan_vault.to_account_info(),
+            authority: ctx.accounts.liquidator.to_account_info(),
+        },
+    );
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || token::transfer(cpi_ctx, total_repaid))?
+        .require_increase(total_repaid)?;
+
+    let seeds = &[
+        Market::SEED_PREFIX,
+        market.loan_token_mint.as_ref(),
+        market.collateral_token_mint.as_ref(),
+        &[market.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.collateral_vault.to_account_info(),
+            to: ctx.accounts.liquidator_collateral_account.to_account_info(),
+            authority: market.to_account_info(),
+        },
+        signer_seeds,
+    );
+    snapshot_balance_delta(&mut ctx.accounts.collateral_vault, || {
+        token::transfer(cpi_ctx, total_seized)
+    })?
+    .require_decrease(total_seized)?;
+
+    Ok(())
+}
+
+/// Event emitted for each liquidated position
+#[event]
+pub struct LiquidateEvent {
+    /// Market public key
+    pub market: Pubkey,
+
+    /// Liquidator public key
+    pub liquidator: Pubkey,
+
+    /// Borrower whose position was liquidated
+    pub borrower: Pubkey,
+
+    /// Loan tokens repaid by the liquidator
+    pub repaid_assets: u64,
+
+    /// Borrow shares burned
+    pub repaid_shares: u64,
+
+    /// Collateral seized by the liquidator (incl. bonus)
+    pub seized_collateral: u64,
+
+    /// Part of `seized_collateral` paid as the liquidation bonus
+    pub bonus_collateral: u64,
+
+    /// Borrow shares left on the position
+    pub remaining_borrow_shares: u64,
+
+    /// Collateral left on the position
+    pub remaining_collateral: u64,
+}
@@ -1,11 +1,18 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
-use crate::constants::{FIXED_ORACLE_PRICE, LLTV_PRECISION, PRICE_PRECISION};
+use crate::constants::{BPS_DENOMINATOR, LLTV_PRECISION, MAX_SHARE_RATIO_DEVIATION, PRICE_PRECISION};
 use crate::error::PelagoError;
 use crate::state::{Market, UserPosition};
-use crate::utils::shares_math::{to_shares_up, to_assets_down, to_assets_up};
+use crate::utils::shares_math::{to_shares_up, to_assets_down, to_assets_up, VIRTUAL_ASSETS, VIRTUAL_SHARES};
 use crate::utils::interest::accrue_interest;
+use crate::utils::invariants::check_market_invariants;
+use crate::utils::liquidation::{
+    position_health, require_healthy, PositionHealth, PositionHealthEvent,
+};
+use crate::utils::oracle::collateral_price;
+use crate::utils::vault_snapshot::snapshot_balance_delta;
 
 /// Borrow loan assets from the market
 ///
@@ -54,7 +61,7 @@ pub struct Borrow<'info> {
     /// Market's loan token vault (source of borrowed funds)
     #[account(
         mut,
-        constraint = loan_vault.key() == market.loan_vault @ PelagoError::UninitializedMarket,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
     )]
     pub loan_vault: Account<'info, TokenAccount>,
 
@@ -89,8 +96,8 @@ pub struct Borrow<'info> {
 /// - Rounding UP: Borrower gets more debt shares → conservative → favors protocol
 ///
 /// **Health Factor Calculation (P1):**
-/// ```
-/// collateral_value_usd = collateral_amount × FIXED_ORACLE_PRICE / PRICE_PRECISION
+/// ```text
+/// collateral_value_usd = collateral_amount × market.collateral_price() / PRICE_PRECISION
 /// borrow_value_usd = to_assets_up(user_borrow_shares, totalBorrowAssets, totalBorrowShares)
 /// healthy = collateral_value_usd × lltv ≥ borrow_value_usd × LLTV_PRECISION
 /// ```
@@ -107,20 +114,36 @@ pub struct Borrow<'info> {
 /// - Mode 2: `assets = 0, shares > 0` → User specifies shares, calculate assets
 ///
 /// **State Changes:**
-/// - user_position.borrow_shares += calculated_shares
-/// - market.total_borrow_assets += calculated_assets
-/// - market.total_borrow_shares += calculated_shares
+/// - user_position.borrow_shares += calculated_shares (+ fee shares)
+/// - market.total_borrow_assets += calculated_assets (+ origination fee)
+/// - market.total_borrow_shares += calculated_shares (+ fee shares)
+/// - market.reserves += origination fee
 /// - loan_vault.amount -= calculated_assets (via token transfer)
 ///
+/// **Liquidation Buffer Preview:**
+/// - If `preview_liquidation_buffer` is true, the post-borrow distance to
+///   liquidation (in bps of collateral price) is written via `set_return_data`
+/// - See `liquidation_buffer_bps` for the formula
+///
 /// **Error Cases:**
 /// - InconsistentInput: Both or neither of (assets, shares) are non-zero
-/// - InsufficientLiquidity: available_liquidity < assets
-/// - InsufficientCollateral: position becomes undercollateralized
+/// - PositionFrozen: position is under a compliance hold
+/// - NoCollateral: position has no collateral (rejected before any share math)
+/// - NoLiquidity: available_liquidity == 0 (market fully utilized)
+/// - InsufficientLiquidity: 0 < available_liquidity < assets
+/// - AutoPaused: utilization above the market's auto-pause watermark
+/// - BorrowCooldown: position borrowed within the market's cooldown
+/// - InsufficientCollateral: position becomes undercollateralized (the
+///   collateral shortfall is written as return data)
+/// - BorrowCapExceeded: market borrow cap reached
+/// - PositionBorrowLimit: position debt exceeds max_borrow_per_position
+/// - ShareRatioDistorted: borrow share price far from the virtual baseline
 /// - MathOverflow: Calculation overflow
 pub fn handler(
     ctx: Context<Borrow>,
     assets: u64,
     shares: u64,
+    preview_liquidation_buffer: bool,
 ) -> Result<()> {
     // Step 1: Validate input mutual exclusivity
     // Exactly one of (assets, shares) must be non-zero (Pelago: exactlyOneZero)
@@ -130,11 +153,21 @@ pub fn handler(
     );
 
     let market = &mut ctx.accounts.market;
+    require!(!market.paused, PelagoError::MarketPaused);
     let user_position = &mut ctx.accounts.user_position;
+    user_position.check_not_frozen()?;
+
+    // Fail fast: without collateral the health check can never pass
+    require!(
+        user_position.collateral_amount > 0,
+        PelagoError::NoCollateral
+    );
 
     // Step 2: Accrue interest before any calculation (P1)
     // This ensures share conversion and health check use up-to-date values
     accrue_interest(market)?;
+    check_auto_pause(market)?;
+    enforce_borrow_cooldown(market, user_position, Clock::get()?.unix_timestamp)?;
 
     // Step 3: Convert between assets and shares using virtual shares (P1)
     // Dual-parameter mode following Pelago design
@@ -167,41 +200,24 @@ pub fn handler(
     );
 
     // Step 4: Check market liquidity
-    let available_liquidity = market
-        .total_supply_assets
-        .checked_sub(market.total_borrow_assets)
-        .ok_or(PelagoError::MathOverflow)?;
+    check_available_liquidity(market, final_assets)?;
 
-    require!(
-        available_liquidity >= final_assets,
-        PelagoError::InsufficientLiquidity
-    );
-
-    // Step 5: Update user position and market totals
-    user_position.borrow_shares = user_position
-        .borrow_shares
-        .checked_add(final_shares)
-        .ok_or(PelagoError::MathOverflow)?;
-
-    market.total_borrow_assets = market
-        .total_borrow_assets
-        .checked_add(final_assets)
-        .ok_or(PelagoError::MathOverflow)?;
-
-    market.total_borrow_shares = market
-        .total_borrow_shares
-        .checked_add(final_shares)
-        .ok_or(PelagoError::MathOverflow)?;
+    // Step 5: Update user position and market totals (incl. origination fee)
+    let (fee, fee_shares) = record_borrow(market, user_position, final_assets, final_shares)?;
 
     // Step 6: Health check with virtual shares (P1)
     // Uses updated market state and to_assets_up for precise debt calculation
-    check_health_p1(market, user_position)?;
+    let health = check_health_p1(market, user_position)?;
 
-    // Step 7: Validate liquidity constraint
+    // Step 7: Validate liquidity constraint and borrow caps
     require!(
         market.total_borrow_assets <= market.total_supply_assets,
         PelagoError::InsufficientLiquidity
     );
+    check_borrow_caps(market)?;
+    check_borrow_share_ratio(market)?;
+    check_position_borrow_limit(market, user_position)?;
+    check_market_invariants(market)?;
 
     // Step 8: Transfer loan tokens from vault to user (PDA signs)
     let seeds = &[
@@ -222,7 +238,8 @@ pub fn handler(
         transfer_accounts,
         signer_seeds,
     );
-    token::transfer(cpi_ctx, final_assets)?;
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || token::transfer(cpi_ctx, final_assets))?
+        .require_decrease(final_assets)?;
 
     msg!(
         "Borrow success: user={}, assets={}, shares={}, user_total_borrow_shares={}, market_total_borrow={}, collateral={}",
@@ -234,26 +251,111 @@ pub fn handler(
         user_position.collateral_amount
     );
 
+    // Record position activity for dormancy tracking
+    user_position.last_activity = Clock::get()?.unix_timestamp;
+
+    // Step 9: Optionally return the distance to liquidation
+    if preview_liquidation_buffer {
+        let buffer_bps = liquidation_buffer_bps(market, user_position)?;
+        msg!("Liquidation buffer: {} bps", buffer_bps);
+        set_return_data(&buffer_bps.to_le_bytes());
+    }
+
     // Emit event for off-chain tracking
     emit!(BorrowEvent {
+        market: market.key(),
         user: ctx.accounts.user.key(),
         assets: final_assets,
         shares: final_shares,
+        fee,
+        fee_shares,
         total_borrow_shares: market.total_borrow_shares,
         total_borrow_assets: market.total_borrow_assets,
     });
+    emit!(PositionHealthEvent {
+        market: market.key(),
+        user: user_position.user,
+        health_factor: health.health_factor,
+        collateral_value: health.collateral_value,
+        debt_value: health.debt_value,
+    });
 
     Ok(())
 }
 
+/// Origination fee charged on `assets`
+///
+/// **Formula:**
+/// ```text
+/// fee = assets × origination_fee_bps / 10_000   (rounded down)
+/// ```
+pub fn origination_fee(assets: u64, origination_fee_bps: u16) -> Result<u64> {
+    let fee = (assets as u128)
+        .checked_mul(origination_fee_bps as u128)
+        .ok_or(PelagoError::MathOverflow)?
+        / (BPS_DENOMINATOR as u128);
+    Ok(u64::try_from(fee).map_err(|_| PelagoError::MathOverflow)?)
+}
+
+/// Records a borrow of `assets` / `shares` plus the origination fee
+///
+/// The fee is added to the borrower's debt (as extra borrow shares, rounded
+/// up) and credited to `market.reserves`; only `assets` leave the vault.
+///
+/// **State Changes:**
+/// - user_position.borrow_shares += shares + fee_shares
+/// - market.total_borrow_shares += shares + fee_shares
+/// - market.total_borrow_assets += assets + fee
+/// - market.reserves += fee
+///
+/// **Returns:** `(fee, fee_shares)` charged on top of `assets` / `shares`
+pub fn record_borrow(
+    market: &mut Market,
+    user_position: &mut UserPosition,
+    assets: u64,
+    shares: u64,
+) -> Result<(u64, u64)> {
+    let fee = origination_fee(assets, market.origination_fee_bps)?;
+    let fee_shares = if fee > 0 {
+        to_shares_up(fee, market.total_borrow_assets, market.total_borrow_shares)?
+    } else {
+        0
+    };
+
+    let debt_assets = assets.checked_add(fee).ok_or(PelagoError::MathOverflow)?;
+    let debt_shares = shares.checked_add(fee_shares).ok_or(PelagoError::MathOverflow)?;
+
+    user_position.borrow_shares = user_position
+        .borrow_shares
+        .checked_add(debt_shares)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    market.total_borrow_assets = market
+        .total_borrow_assets
+        .checked_add(debt_assets)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    market.total_borrow_shares = market
+        .total_borrow_shares
+        .checked_add(debt_shares)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    market.reserves = market
+        .reserves
+        .checked_add(fee)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    Ok((fee, fee_shares))
+}
+
 /// P1 Health check using virtual shares
 ///
 /// Validates that user position remains healthy after borrow operation.
 /// Uses `to_assets_up` to convert borrow shares to assets (conservative rounding).
 ///
 /// **P1 Health Formula:**
-/// ```
-/// collateral_value_usd = collateral_amount × FIXED_ORACLE_PRICE / PRICE_PRECISION
+/// ```text
+/// collateral_value_usd = collateral_amount × market.collateral_price() / PRICE_PRECISION
 /// borrow_value_usd = to_assets_up(borrow_shares, totalBorrowAssets, totalBorrowShares)
 /// healthy = collateral_value_usd × lltv ≥ borrow_value_usd × LLTV_PRECISION
 /// ```
@@ -267,71 +369,539 @@ pub fn handler(
 /// - `user_position`: User position (for collateral and borrow shares)
 ///
 /// **Returns:**
-/// - Ok(()) if position is healthy
-/// - Err(InsufficientCollateral) if position is undercollateralized
-fn check_health_p1(market: &Market, user_position: &UserPosition) -> Result<()> {
+/// - Ok(health) if position is healthy (see `position_health`)
+/// - Err(InsufficientCollateral) if position is undercollateralized, with the
+///   collateral shortfall as return data (see `require_healthy`)
+fn check_health_p1(market: &Market, user_position: &UserPosition) -> Result<PositionHealth> {
+    let health = position_health(market, user_position)?;
+
     // If user has no borrows, they are always healthy
     if user_position.borrow_shares == 0 {
+        return Ok(health);
+    }
+
+    msg!(
+        "P1 Health check: collateral_value={}, borrow_value={}, max_borrow={}, lltv={}, user_borrow_shares={}",
+        health.collateral_value,
+        health.debt_value,
+        health.max_borrow,
+        market.lltv,
+        user_position.borrow_shares
+    );
+
+    // Validate health: borrow_value <= max_borrow_value (reports the shortfall)
+    require_healthy(market, &health)?;
+
+    Ok(health)
+}
+
+/// Effective borrow cap for a market
+///
+/// Combines the absolute cap (`borrow_cap`) and the cap relative to supply
+/// (`borrow_cap_ratio_bps`); the tighter of the two applies.
+///
+/// **Formula:**
+/// ```text
+/// relative_cap = total_supply_assets × borrow_cap_ratio_bps / 10_000
+/// effective_cap = min(borrow_cap, relative_cap)
+/// ```
+///
+/// **Returns:** `None` if neither cap is configured
+pub fn effective_borrow_cap(market: &Market) -> Option<u64> {
+    let absolute_cap = (market.borrow_cap > 0).then_some(market.borrow_cap);
+
+    let relative_cap = (market.borrow_cap_ratio_bps > 0).then(|| {
+        // ratio ≤ 10_000, so the result always fits in u64
+        ((market.total_supply_assets as u128) * (market.borrow_cap_ratio_bps as u128)
+            / (BPS_DENOMINATOR as u128)) as u64
+    });
+
+    match (absolute_cap, relative_cap) {
+        (Some(a), Some(r)) => Some(a.min(r)),
+        (cap, None) | (None, cap) => cap,
+    }
+}
+
+/// Validates total_borrow_assets against the effective borrow cap
+///
+/// **Errors:**
+/// - BorrowCapExceeded: total_borrow_assets > effective cap
+pub fn check_borrow_caps(market: &Market) -> Result<()> {
+    if let Some(cap) = effective_borrow_cap(market) {
+        require!(
+            market.total_borrow_assets <= cap,
+            PelagoError::BorrowCapExceeded
+        );
+    }
+    Ok(())
+}
+
+/// Validates the borrow share price against the virtual baseline
+///
+/// A fresh market prices debt at VIRTUAL_SHARES shares per asset. Interest
+/// lowers that slowly; a ratio more than MAX_SHARE_RATIO_DEVIATION away in
+/// either direction means the share accounting was manipulated, and new
+/// borrows would mint shares that misstate their debt.
+///
+/// **Formula (virtual offsets included):**
+/// ```text
+/// ratio = (total_borrow_shares + VIRTUAL_SHARES) / (total_borrow_assets + VIRTUAL_ASSETS)
+/// VIRTUAL_SHARES / MAX_SHARE_RATIO_DEVIATION <= ratio <= VIRTUAL_SHARES × MAX_SHARE_RATIO_DEVIATION
+/// ```
+///
+/// **Errors:**
+/// - ShareRatioDistorted: The ratio is outside the bounds
+pub fn check_borrow_share_ratio(market: &Market) -> Result<()> {
+    let shares = market.total_borrow_shares as u128 + VIRTUAL_SHARES;
+    let assets = market.total_borrow_assets as u128 + VIRTUAL_ASSETS;
+
+    // u64 totals × (1e6 × 1e3) fit in u128
+    let baseline = assets * VIRTUAL_SHARES;
+    require!(
+        shares <= baseline * MAX_SHARE_RATIO_DEVIATION
+            && shares * MAX_SHARE_RATIO_DEVIATION >= baseline,
+        PelagoError::ShareRatioDistorted
+    );
+    Ok(())
+}
+
+/// Checks that the vault can fund a borrow of `assets`
+///
+/// Distinguishes a fully utilized market from a borrow that is merely too
+/// large, so clients can tell "no liquidity" from "reduce the amount".
+///
+/// **Errors:**
+/// - NoLiquidity: `total_supply_assets - total_borrow_assets == 0`
+/// - InsufficientLiquidity: some liquidity, but less than `assets`
+/// - MathOverflow: total_borrow_assets > total_supply_assets
+pub fn check_available_liquidity(market: &Market, assets: u64) -> Result<()> {
+    let available_liquidity = market
+        .total_supply_assets
+        .checked_sub(market.total_borrow_assets)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    require!(available_liquidity > 0, PelagoError::NoLiquidity);
+    require!(
+        available_liquidity >= assets,
+        PelagoError::InsufficientLiquidity
+    );
+    Ok(())
+}
+
+/// Utilization circuit breaker for new borrows
+///
+/// Computed from the current (post-accrual) totals on every call rather
+/// than stored, so borrowing resumes on its own once repays or new supply
+/// bring utilization back under the watermark.
+///
+/// **Formula:**
+/// ```text
+/// paused = total_borrow_assets × 10_000 > total_supply_assets × auto_pause_utilization_bps
+/// ```
+///
+/// **Errors:**
+/// - AutoPaused: utilization above `auto_pause_utilization_bps`
+pub fn check_auto_pause(market: &Market) -> Result<()> {
+    if market.auto_pause_utilization_bps == 0 {
         return Ok(());
     }
 
-    // Calculate actual borrow assets using virtual shares (P1)
-    // Rounding UP to be conservative (favors protocol safety)
-    let borrow_value_usdc = to_assets_up(
+    let utilization_scaled = (market.total_borrow_assets as u128) * (BPS_DENOMINATOR as u128);
+    let watermark_scaled =
+        (market.total_supply_assets as u128) * (market.auto_pause_utilization_bps as u128);
+    require!(
+        utilization_scaled <= watermark_scaled,
+        PelagoError::AutoPaused
+    );
+    Ok(())
+}
+
+/// Enforces `market.borrow_cooldown_secs` and records the borrow time
+///
+/// Discourages rapid borrow/repay cycling against per-borrow fees. A
+/// position that never borrowed (`last_borrow_ts == 0`) is never blocked.
+///
+/// **State Changes:**
+/// - user_position.last_borrow_ts = now
+///
+/// **Errors:**
+/// - BorrowCooldown: now - last_borrow_ts < borrow_cooldown_secs
+pub fn enforce_borrow_cooldown(
+    market: &Market,
+    user_position: &mut UserPosition,
+    now: i64,
+) -> Result<()> {
+    require!(
+        now.saturating_sub(user_position.last_borrow_ts) >= market.borrow_cooldown_secs as i64,
+        PelagoError::BorrowCooldown
+    );
+    user_position.last_borrow_ts = now;
+    Ok(())
+}
+
+/// Validates a position's debt against `max_borrow_per_position`
+///
+/// **Errors:**
+/// - PositionBorrowLimit: position debt assets (rounded up) > max_borrow_per_position
+pub fn check_position_borrow_limit(market: &Market, user_position: &UserPosition) -> Result<()> {
+    if market.max_borrow_per_position == 0 {
+        return Ok(());
+    }
+
+    let debt_assets = to_assets_up(
         user_position.borrow_shares,
         market.total_borrow_assets,
         market.total_borrow_shares,
     )?;
+    require!(
+        debt_assets <= market.max_borrow_per_position,
+        PelagoError::PositionBorrowLimit
+    );
+    Ok(())
+}
+
+/// Distance to liquidation in basis points of the collateral price
+///
+/// Returns how far the collateral price can fall (in bps) before the position
+/// crosses the liquidation threshold (`lltv`). Liquidation happens at the price
+/// where `collateral_value × lltv == borrow_value × LLTV_PRECISION`.
+///
+/// **Formula:**
+/// ```text
+/// max_borrow = collateral_value_usd × lltv / LLTV_PRECISION
+/// liquidation_price / price = borrow_value / max_borrow
+/// buffer_bps = (max_borrow - borrow_value) × 10_000 / max_borrow
+/// ```
+///
+/// **Rounding:** DOWN (reports a slightly smaller buffer → conservative)
+///
+/// **Returns:**
+/// - 10_000 if the position has no debt (price can fall to zero)
+/// - 0 if the position is at or past the liquidation threshold
+pub fn liquidation_buffer_bps(market: &Market, user_position: &UserPosition) -> Result<u64> {
+    if user_position.borrow_shares == 0 {
+        return Ok(BPS_DENOMINATOR);
+    }
+
+    let borrow_value_usdc = to_assets_up(
+        user_position.borrow_shares,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )? as u128;
 
-    // Calculate collateral value in USDC
-    // collateral_value = (collateral_amount × price) / price_precision
-    let collateral_value_usd = (user_position.collateral_amount as u128)
-        .checked_mul(FIXED_ORACLE_PRICE as u128)
+    let max_borrow_value = (user_position.collateral_amount as u128)
+        .checked_mul(collateral_price(market)? as u128)
         .ok_or(PelagoError::MathOverflow)?
         .checked_div(PRICE_PRECISION as u128)
-        .ok_or(PelagoError::MathOverflow)?;
-
-    // Calculate max allowed borrow value
-    // max_borrow = (collateral_value × lltv) / LLTV_PRECISION
-    let max_borrow_value = collateral_value_usd
+        .ok_or(PelagoError::MathOverflow)?
         .checked_mul(market.lltv as u128)
         .ok_or(PelagoError::MathOverflow)?
         .checked_div(LLTV_PRECISION as u128)
         .ok_or(PelagoError::MathOverflow)?;
 
-    msg!(
-        "P1 Health check: collateral_value={}, borrow_value={}, max_borrow={}, lltv={}, user_borrow_shares={}",
-        collateral_value_usd,
-        borrow_value_usdc,
-        max_borrow_value,
-        market.lltv,
-        user_position.borrow_shares
-    );
+    if borrow_value_usdc >= max_borrow_value {
+        return Ok(0);
+    }
 
-    // Validate health: borrow_value <= max_borrow_value
-    require!(
-        (borrow_value_usdc as u128) <= max_borrow_value,
-        PelagoError::InsufficientCollateral
-    );
+    let buffer_bps = (max_borrow_value - borrow_value_usdc)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(PelagoError::MathOverflow)?
+        / max_borrow_value;
 
-    Ok(())
+    u64::try_from(buffer_bps).map_err(|_| PelagoError::MathOverflow.into())
 }
 
 /// Event emitted on successful borrow
 #[event]
 pub struct BorrowEvent {
+    /// Market public key
+    pub market: Pubkey,
+
     /// User public key (borrower)
     pub user: Pubkey,
 
     /// Assets borrowed
     pub assets: u64,
 
-    /// Shares issued
+    /// Shares issued for `assets` (excludes the fee's shares)
     pub shares: u64,
 
+    /// Origination fee added to the borrower's debt
+    pub fee: u64,
+
+    /// Borrow shares issued for the fee (`shares + fee_shares` is the
+    /// position's increase)
+    pub fee_shares: u64,
+
     /// Total borrow shares in market
     pub total_borrow_shares: u64,
 
     /// Total borrow assets in market
     pub total_borrow_assets: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::shares_math::to_shares_up;
+
+    #[test]
+    fn test_distorted_borrow_share_ratio_rejected() {
+        // Healthy market: a year of interest barely moves the ratio
+        let (market, _) = position_with_debt(500_000_000);
+        assert!(check_borrow_share_ratio(&market).is_ok());
+        let grown = Market {
+            total_borrow_assets: 525_000_000,
+            ..market.clone()
+        };
+        assert!(check_borrow_share_ratio(&grown).is_ok());
+
+        // Debt written down 2000x while the shares remain: a new borrow
+        // prices in a flood of shares per asset
+        let mut distorted = Market {
+            total_borrow_assets: 250_000,
+            ..market.clone()
+        };
+        let shares = to_shares_up(1_000_000, distorted.total_borrow_assets, distorted.total_borrow_shares)
+            .unwrap();
+        assert!(shares > 1_000_000 * VIRTUAL_SHARES as u64 * 1_000);
+        distorted.total_borrow_assets += 1_000_000;
+        distorted.total_borrow_shares += shares;
+        assert_eq!(
+            check_borrow_share_ratio(&distorted).unwrap_err(),
+            PelagoError::ShareRatioDistorted.into()
+        );
+
+        // The opposite direction: assets inflated far above the shares
+        let inflated = Market {
+            total_borrow_assets: 500_000_000_000_000,
+            ..market
+        };
+        assert_eq!(
+            check_borrow_share_ratio(&inflated).unwrap_err(),
+            PelagoError::ShareRatioDistorted.into()
+        );
+    }
+
+    /// 10 SOL of collateral (1000 USDC at the fixed price), 80% LLTV
+    fn position_with_debt(borrow_assets: u64) -> (Market, UserPosition) {
+        let borrow_shares = to_shares_up(borrow_assets, 0, 0).unwrap();
+        let market = Market {
+            total_borrow_assets: borrow_assets,
+            total_borrow_shares: borrow_shares,
+            lltv: 80_000_000,
+            ..Default::default()
+        };
+        let position = UserPosition {
+            borrow_shares,
+            collateral_amount: 10_000_000_000,
+            ..Default::default()
+        };
+        (market, position)
+    }
+
+    fn capped_market(borrow_cap: u64, borrow_cap_ratio_bps: u16) -> Market {
+        Market {
+            total_supply_assets: 1_000_000_000,
+            borrow_cap,
+            borrow_cap_ratio_bps,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_relative_cap_binds_before_absolute() {
+        // 50% of 1000 USDC supply = 500 USDC, tighter than the 800 USDC absolute cap
+        let mut market = capped_market(800_000_000, 5_000);
+        assert_eq!(effective_borrow_cap(&market), Some(500_000_000));
+
+        market.total_borrow_assets = 500_000_000;
+        assert!(check_borrow_caps(&market).is_ok());
+
+        market.total_borrow_assets = 500_000_001;
+        assert_eq!(
+            check_borrow_caps(&market).unwrap_err(),
+            PelagoError::BorrowCapExceeded.into()
+        );
+    }
+
+    #[test]
+    fn test_absolute_cap_binds_before_relative() {
+        // 90% of 1000 USDC supply = 900 USDC, looser than the 300 USDC absolute cap
+        let mut market = capped_market(300_000_000, 9_000);
+        assert_eq!(effective_borrow_cap(&market), Some(300_000_000));
+
+        market.total_borrow_assets = 300_000_001;
+        assert_eq!(
+            check_borrow_caps(&market).unwrap_err(),
+            PelagoError::BorrowCapExceeded.into()
+        );
+    }
+
+    #[test]
+    fn test_caps_disabled_by_default() {
+        let mut market = capped_market(0, 0);
+        assert_eq!(effective_borrow_cap(&market), None);
+
+        market.total_borrow_assets = u64::MAX;
+        assert!(check_borrow_caps(&market).is_ok());
+    }
+
+    #[test]
+    fn test_origination_fee_added_to_debt_and_reserves() {
+        let mut market = Market {
+            total_supply_assets: 10_000_000_000,
+            origination_fee_bps: 100, // 1%
+            ..Default::default()
+        };
+        let mut position = UserPosition::default();
+
+        let assets = 1_000_000_000;
+        let shares = to_shares_up(assets, 0, 0).unwrap();
+        let (fee, fee_shares) = record_borrow(&mut market, &mut position, assets, shares).unwrap();
+
+        assert_eq!(fee, 10_000_000);
+        assert_eq!(position.borrow_shares, shares + fee_shares);
+        assert_eq!(market.reserves, 10_000_000);
+        assert_eq!(market.total_borrow_assets, 1_010_000_000);
+
+        // Borrower owes the received amount plus the fee
+        let debt = to_assets_up(
+            position.borrow_shares,
+            market.total_borrow_assets,
+            market.total_borrow_shares,
+        )
+        .unwrap();
+        assert!(debt >= assets + fee);
+        assert!(debt <= assets + fee + 1);
+    }
+
+    #[test]
+    fn test_position_borrow_limit_caps_healthy_position() {
+        // 100 SOL of collateral ($10,000) could back $8,000, but the cap is $1,000
+        let borrow_shares = to_shares_up(1_000_000_001, 0, 0).unwrap();
+        let market = Market {
+            total_supply_assets: 10_000_000_000,
+            total_borrow_assets: 1_000_000_001,
+            total_borrow_shares: borrow_shares,
+            lltv: 80_000_000,
+            max_borrow_per_position: 1_000_000_000,
+            ..Default::default()
+        };
+        let position = UserPosition {
+            borrow_shares,
+            collateral_amount: 100_000_000_000,
+            ..Default::default()
+        };
+
+        assert!(check_health_p1(&market, &position).is_ok());
+        assert_eq!(
+            check_position_borrow_limit(&market, &position).unwrap_err(),
+            PelagoError::PositionBorrowLimit.into()
+        );
+
+        let uncapped = Market {
+            max_borrow_per_position: 0,
+            ..market
+        };
+        assert!(check_position_borrow_limit(&uncapped, &position).is_ok());
+    }
+
+    #[test]
+    fn test_liquidation_buffer_half_of_max_borrow() {
+        // Borrowing 400 of a max 800 USDC: price can halve before liquidation
+        let (market, position) = position_with_debt(400_000_000);
+        assert_eq!(liquidation_buffer_bps(&market, &position).unwrap(), 5_000);
+    }
+
+    #[test]
+    fn test_liquidation_buffer_at_limit_and_no_debt() {
+        let (market, position) = position_with_debt(800_000_000);
+        assert_eq!(liquidation_buffer_bps(&market, &position).unwrap(), 0);
+
+        let (market, mut position) = position_with_debt(0);
+        position.borrow_shares = 0;
+        assert_eq!(liquidation_buffer_bps(&market, &position).unwrap(), BPS_DENOMINATOR);
+    }
+
+    #[test]
+    fn test_no_liquidity_vs_insufficient_liquidity() {
+        let mut market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_borrow_assets: 600_000_000,
+            ..Default::default()
+        };
+
+        // Partial liquidity: too large a borrow is told to shrink
+        assert!(check_available_liquidity(&market, 400_000_000).is_ok());
+        assert_eq!(
+            check_available_liquidity(&market, 400_000_001).unwrap_err(),
+            PelagoError::InsufficientLiquidity.into()
+        );
+
+        // Fully utilized: any borrow reports no liquidity
+        market.total_borrow_assets = market.total_supply_assets;
+        assert_eq!(
+            check_available_liquidity(&market, 1).unwrap_err(),
+            PelagoError::NoLiquidity.into()
+        );
+    }
+
+    #[test]
+    fn test_auto_pause_crosses_and_recovers() {
+        let mut market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_borrow_assets: 900_000_000,
+            auto_pause_utilization_bps: 9_000, // 90%
+            ..Default::default()
+        };
+
+        // Exactly at the watermark: still open
+        assert!(check_auto_pause(&market).is_ok());
+
+        // One unit over: borrows refused
+        market.total_borrow_assets += 1;
+        assert_eq!(
+            check_auto_pause(&market).unwrap_err(),
+            PelagoError::AutoPaused.into()
+        );
+
+        // A repay brings utilization back under the watermark
+        market.total_borrow_assets -= 100_000_000;
+        assert!(check_auto_pause(&market).is_ok());
+
+        // Disabled watermark never pauses, even at 100%
+        market.total_borrow_assets = market.total_supply_assets;
+        market.auto_pause_utilization_bps = 0;
+        assert!(check_auto_pause(&market).is_ok());
+    }
+
+    #[test]
+    fn test_borrow_cooldown() {
+        let now = 1_700_000_000;
+        let market = Market {
+            borrow_cooldown_secs: 60,
+            ..Default::default()
+        };
+        let mut position = UserPosition::default();
+
+        // First borrow is never blocked
+        enforce_borrow_cooldown(&market, &mut position, now).unwrap();
+        assert_eq!(position.last_borrow_ts, now);
+
+        // Rapid re-borrow is rejected and leaves the timestamp alone
+        assert_eq!(
+            enforce_borrow_cooldown(&market, &mut position, now + 59).unwrap_err(),
+            PelagoError::BorrowCooldown.into()
+        );
+        assert_eq!(position.last_borrow_ts, now);
+
+        // Allowed once the cooldown has elapsed
+        enforce_borrow_cooldown(&market, &mut position, now + 60).unwrap();
+        assert_eq!(position.last_borrow_ts, now + 60);
+
+        // Disabled cooldown never blocks
+        let open = Market::default();
+        enforce_borrow_cooldown(&open, &mut position, now + 60).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+//! Check Bad Debt Instruction
+//!
+//! Read-only risk view for suppliers. Sums the shortfall of every given
+//! position whose debt exceeds its collateral value at the current price,
+//! i.e. the loss suppliers would absorb if those positions were abandoned.
+//!
+//! Positions are supplied via `remaining_accounts`. The view only reports
+//! on the positions it is given; pass every open position for a
+//! market-wide figure.
+
+use anchor_lang::prelude::*;
+
+use crate::constants::{MAX_BATCH_POSITION_READS, PRICE_PRECISION};
+use crate::error::PelagoError;
+use crate::state::{Market, UserPosition};
+use crate::utils::batch::check_batch_size;
+use crate::utils::interest::project_interest_at;
+use crate::utils::shares_math::to_assets_up;
+use crate::utils::oracle::collateral_price;
+
+/// Report potential bad debt across user positions
+///
+/// Read-only view: interest is accrued on a local copy of the market, so the
+/// market account itself is never modified.
+///
+/// **Remaining Accounts:** `UserPosition` accounts of this market (read-only)
+#[derive(Accounts)]
+pub struct CheckBadDebt<'info> {
+    /// Market account
+    #[account(
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Handler for check_bad_debt view
+///
+/// **Returns:** Total shortfall in loan token units (via return data)
+///
+/// **Errors:**
+/// - InvalidParameter: Duplicate position or a position from another market
+/// - BatchTooLarge: More than MAX_BATCH_POSITION_READS positions
+/// - MathOverflow: Calculation overflow
+pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, CheckBadDebt<'info>>) -> Result<u64> {
+    check_batch_size(ctx.remaining_accounts.len(), MAX_BATCH_POSITION_READS)?;
+
+    let mut positions = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account_info in ctx.remaining_accounts.iter() {
+        let position: Account<UserPosition> = Account::try_from(account_info)?;
+        positions.push((account_info.key(), position.into_inner()));
+    }
+
+    let mut market = ctx.accounts.market.clone().into_inner();
+    project_interest_at(&mut market, Clock::get()?.unix_timestamp)?;
+
+    let bad_debt = total_bad_debt(ctx.accounts.market.key(), &market, &positions)?;
+
+    msg!(
+        "Bad debt check: market={}, positions={}, bad_debt={}",
+        ctx.accounts.market.key(),
+        positions.len(),
+        bad_debt
+    );
+
+    Ok(bad_debt)
+}
+
+/// Sums `position_shortfall` over distinct positions of `market_key`
+pub fn total_bad_debt(
+    market_key: Pubkey,
+    market: &Market,
+    positions: &[(Pubkey, UserPosition)],
+) -> Result<u64> {
+    let mut seen: Vec<Pubkey> = Vec::with_capacity(positions.len());
+    let mut total: u64 = 0;
+    for (key, position) in positions {
+        require!(
+            position.market == market_key && !seen.contains(key),
+            PelagoError::InvalidParameter
+        );
+        seen.push(*key);
+
+        total = total
+            .checked_add(position_shortfall(market, position)?)
+            .ok_or(PelagoError::MathOverflow)?;
+    }
+
+    Ok(total)
+}
+
+/// Debt exceeding collateral value for a single position (0 if solvent)
+///
+/// **Formula:**
+/// ```text
+/// debt = to_assets_up(borrow_shares)
+/// collateral_value = collateral_amount × collateral_price / PRICE_PRECISION
+/// shortfall = max(debt - collateral_value, 0)
+/// ```
+pub fn position_shortfall(market: &Market, position: &UserPosition) -> Result<u64> {
+    if position.borrow_shares == 0 {
+        return Ok(0);
+    }
+
+    let debt = to_assets_up(
+        position.borrow_shares,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+    let collateral_value = (position.collateral_amount as u128)
+        .checked_mul(collateral_price(market)? as u128)
+        .ok_or(PelagoError::MathOverflow)?
+        .checked_div(PRICE_PRECISION as u128)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    // collateral_value < debt ≤ u64::MAX whenever the difference is positive
+    Ok((debt as u128).saturating_sub(collateral_value) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::shares_math::to_shares_up;
+
+    fn borrower(market: Pubkey, collateral_amount: u64, borrow_shares: u64) -> (Pubkey, UserPosition) {
+        (
+            Pubkey::new_unique(),
+            UserPosition {
+                market,
+                collateral_amount,
+                borrow_shares,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_reports_shortfall_of_insolvent_position() {
+        let market_key = Pubkey::new_unique();
+        let insolvent_shares = to_shares_up(700_000_000, 0, 0).unwrap();
+        let solvent_shares = to_shares_up(100_000_000, 700_000_000, insolvent_shares).unwrap();
+        let market = Market {
+            total_borrow_assets: 800_000_000,
+            total_borrow_shares: insolvent_shares + solvent_shares,
+            // 50 USDC/SOL
+            manual_price: 50_000,
+            manual_price_enabled: true,
+            ..Default::default()
+        };
+
+        // 10 SOL = 500 USDC backing 700 USDC of debt; 10 SOL backing 100 USDC
+        let positions = vec![
+            borrower(market_key, 10_000_000_000, insolvent_shares),
+            borrower(market_key, 10_000_000_000, solvent_shares),
+            borrower(market_key, 0, 0),
+        ];
+
+        assert_eq!(
+            total_bad_debt(market_key, &market, &positions).unwrap(),
+            200_000_000
+        );
+    }
+
+    #[test]
+    fn test_rejects_duplicate_or_foreign_positions() {
+        let market_key = Pubkey::new_unique();
+        let market = Market::default();
+        let a = borrower(market_key, 1, 0);
+
+        assert_eq!(
+            total_bad_debt(market_key, &market, &[a.clone(), a.clone()]).unwrap_err(),
+            PelagoError::InvalidParameter.into()
+        );
+        let foreign = borrower(Pubkey::new_unique(), 1, 0);
+        assert_eq!(
+            total_bad_debt(market_key, &market, &[a, foreign]).unwrap_err(),
+            PelagoError::InvalidParameter.into()
+        );
+    }
+}
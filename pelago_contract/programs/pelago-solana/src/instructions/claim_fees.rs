@@ -0,0 +1,280 @@
+//! Claim Fees Instruction
+//!
+//! Pays the protocol's fee shares out to `ProtocolConfig.fee_recipient`.
+//! Interest accrual mints fee shares into `market.fee_shares` (see
+//! `utils::interest`); claiming burns them like a withdrawal, so a market's
+//! `total_supply_shares` can return to zero once every supplier has left.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::error::PelagoError;
+use crate::state::{Market, ProtocolConfig};
+use crate::utils::interest::accrue_interest;
+use crate::utils::invariants::{
+    check_empty_supply, check_market_invariants, sweep_orphaned_supply,
+};
+use crate::utils::shares_math::{to_assets_down, to_shares_up};
+use crate::utils::vault_snapshot::snapshot_balance_delta;
+
+/// Withdraw a market's accumulated protocol fee shares
+///
+/// **Access Control:** Only `ProtocolConfig.fee_recipient`
+#[derive(Accounts)]
+pub struct ClaimFees<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Protocol config holding the fee recipient
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        constraint = protocol_config.fee_recipient == fee_recipient.key() @ PelagoError::Unauthorized,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Market's loan token vault (source of the payout)
+    #[account(
+        mut,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
+    )]
+    pub loan_vault: Account<'info, TokenAccount>,
+
+    /// Receiver loan token account (can be the recipient's own or different account)
+    #[account(
+        mut,
+        constraint = receiver_token_account.mint == market.loan_token_mint @ PelagoError::UninitializedMarket,
+    )]
+    pub receiver_token_account: Account<'info, TokenAccount>,
+
+    /// Fee recipient wallet (signer)
+    pub fee_recipient: Signer<'info>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler for claim_fees instruction
+///
+/// **Processing Steps:**
+/// 1. Accrue interest so pending fees are minted
+/// 2. Burn the fee shares, capped by idle liquidity (see `claim_fee_shares`)
+/// 3. Sweep orphaned supply if the last supply share was burned
+/// 4. Transfer the claimed assets from the vault to the receiver
+///
+/// **Errors:**
+/// - Unauthorized: Signer is not the protocol fee recipient
+/// - MarketPaused: Market is paused (like `withdraw`)
+/// - ZeroAmount: No fee shares, or no idle liquidity to pay them from
+/// - MathOverflow: Calculation overflow
+pub fn handler(ctx: Context<ClaimFees>) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    require!(!market.paused, PelagoError::MarketPaused);
+    accrue_interest(market)?;
+
+    let (assets, shares) = claim_fee_shares(market)?;
+    let swept = sweep_orphaned_supply(market)?;
+    if swept > 0 {
+        msg!("Swept orphaned supply to reserves: assets={}", swept);
+    }
+    check_empty_supply(market)?;
+    check_market_invariants(market)?;
+
+    let seeds = &[
+        Market::SEED_PREFIX,
+        market.loan_token_mint.as_ref(),
+        market.collateral_token_mint.as_ref(),
+        &[market.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.loan_vault.to_account_info(),
+            to: ctx.accounts.receiver_token_account.to_account_info(),
+            authority: market.to_account_info(),
+        },
+        signer_seeds,
+    );
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || {
+        token::transfer(cpi_ctx, assets)
+    })?
+    .require_decrease(assets)?;
+
+    msg!(
+        "Fees claimed: market={}, assets={}, shares={}, remaining_fee_shares={}",
+        market.key(),
+        assets,
+        shares,
+        market.fee_shares
+    );
+
+    emit!(FeesClaimedEvent {
+        market: market.key(),
+        fee_recipient: ctx.accounts.fee_recipient.key(),
+        receiver: ctx.accounts.receiver_token_account.key(),
+        assets,
+        shares,
+        remaining_fee_shares: market.fee_shares,
+    });
+
+    Ok(())
+}
+
+/// Burns the market's fee shares for the assets they are worth
+///
+/// All fee shares are redeemed (rounding down, like a shares-mode
+/// withdrawal) when idle liquidity covers them. Otherwise only the idle
+/// liquidity is paid out, burning the shares for it rounded up; the rest
+/// stays claimable.
+///
+/// **State Changes:**
+/// - market.fee_shares -= shares
+/// - market.total_supply_shares -= shares
+/// - market.total_supply_assets -= assets
+///
+/// **Returns:** `(assets, shares)` paid out and burned
+///
+/// **Errors:**
+/// - ZeroAmount: No fee shares, or nothing to pay them from
+/// - MathOverflow: Calculation overflow
+pub fn claim_fee_shares(market: &mut Market) -> Result<(u64, u64)> {
+    require!(market.fee_shares > 0, PelagoError::ZeroAmount);
+
+    let owed = to_assets_down(
+        market.fee_shares,
+        market.total_supply_assets,
+        market.total_supply_shares,
+    )?;
+    let liquidity = market
+        .total_supply_assets
+        .saturating_sub(market.total_borrow_assets);
+
+    let (assets, shares) = if owed <= liquidity {
+        (owed, market.fee_shares)
+    } else {
+        let shares = to_shares_up(
+            liquidity,
+            market.total_supply_assets,
+            market.total_supply_shares,
+        )?;
+        (liquidity, shares.min(market.fee_shares))
+    };
+    require!(assets > 0, PelagoError::ZeroAmount);
+
+    market.fee_shares -= shares;
+    market.total_supply_shares = market
+        .total_supply_shares
+        .checked_sub(shares)
+        .ok_or(PelagoError::MathOverflow)?;
+    market.total_supply_assets = market
+        .total_supply_assets
+        .checked_sub(assets)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    Ok((assets, shares))
+}
+
+/// Event emitted when protocol fees are claimed
+#[event]
+pub struct FeesClaimedEvent {
+    /// Market public key
+    pub market: Pubkey,
+
+    /// Fee recipient (signer)
+    pub fee_recipient: Pubkey,
+
+    /// Receiver token account
+    pub receiver: Pubkey,
+
+    /// Loan assets paid out
+    pub assets: u64,
+
+    /// Fee shares burned
+    pub shares: u64,
+
+    /// Fee shares left unclaimed
+    pub remaining_fee_shares: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::invariants::sweep_dust;
+    use crate::utils::shares_math::to_shares_down;
+
+    #[test]
+    fn test_claim_fee_shares_empties_market() {
+        // 1000 USDC supplied by one user; a year of interest minted fee shares
+        let user_shares = to_shares_down(1_000_000_000, 0, 0).unwrap();
+        let fee_shares = to_shares_down(5_000_000, 1_050_000_000, user_shares).unwrap();
+        let mut market = Market {
+            total_supply_assets: 1_055_000_000,
+            total_supply_shares: user_shares + fee_shares,
+            fee_shares,
+            ..Default::default()
+        };
+
+        // The user leaves: only the fee shares keep the market open
+        let user_assets = to_assets_down(
+            user_shares,
+            market.total_supply_assets,
+            market.total_supply_shares,
+        )
+        .unwrap();
+        market.total_supply_shares -= user_shares;
+        market.total_supply_assets -= user_assets;
+        assert_eq!(market.total_supply_shares, fee_shares);
+
+        let (assets, shares) = claim_fee_shares(&mut market).unwrap();
+        assert_eq!(shares, fee_shares);
+        assert!(assets > 0 && assets <= 5_000_000);
+        assert_eq!(market.fee_shares, 0);
+        assert_eq!(market.total_supply_shares, 0);
+
+        // The market can now be torn down
+        sweep_orphaned_supply(&mut market).unwrap();
+        assert_eq!(market.total_supply_assets, 0);
+        assert!(sweep_dust(&mut market).is_ok());
+
+        assert_eq!(
+            claim_fee_shares(&mut market).unwrap_err(),
+            PelagoError::ZeroAmount.into()
+        );
+    }
+
+    #[test]
+    fn test_claim_fee_shares_capped_by_liquidity() {
+        let fee_shares = to_shares_down(100_000_000, 0, 0).unwrap();
+        let mut market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_supply_shares: to_shares_down(1_000_000_000, 0, 0).unwrap(),
+            total_borrow_assets: 960_000_000,
+            fee_shares,
+            ..Default::default()
+        };
+
+        // 100 USDC owed, 40 USDC idle: 40 paid, the rest stays claimable
+        let (assets, shares) = claim_fee_shares(&mut market).unwrap();
+        assert_eq!(assets, 40_000_000);
+        assert!(shares < fee_shares);
+        assert_eq!(market.fee_shares, fee_shares - shares);
+        assert_eq!(market.total_supply_assets, market.total_borrow_assets);
+        assert!(check_market_invariants(&market).is_ok());
+
+        // Fully utilized: nothing left to pay from
+        assert_eq!(
+            claim_fee_shares(&mut market).unwrap_err(),
+            PelagoError::ZeroAmount.into()
+        );
+    }
+}
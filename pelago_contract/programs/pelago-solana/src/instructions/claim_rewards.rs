@@ -0,0 +1,141 @@
+//! Claim Rewards Instruction
+//!
+//! Pays a supplier the reward tokens earned by their supply shares (see
+//! `utils::rewards`).
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::error::PelagoError;
+use crate::state::{Market, UserPosition};
+use crate::utils::interest::accrue_interest;
+use crate::utils::rewards::settle_rewards;
+use crate::utils::vault_snapshot::snapshot_balance_delta;
+
+/// Claim accrued supply rewards
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// User position PDA
+    #[account(
+        mut,
+        seeds = [
+            UserPosition::SEED_PREFIX,
+            market.key().as_ref(),
+            user.key().as_ref(),
+        ],
+        bump = user_position.bump,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Market's reward token vault (source of the payout)
+    #[account(
+        mut,
+        constraint = reward_vault.key() == market.reward_vault @ PelagoError::InvalidVault,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    /// Receiver reward token account (can be user's own or different account)
+    #[account(
+        mut,
+        constraint = receiver_reward_account.mint == reward_vault.mint @ PelagoError::InvalidVault,
+    )]
+    pub receiver_reward_account: Account<'info, TokenAccount>,
+
+    /// User wallet (signer)
+    pub user: Signer<'info>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler for claim_rewards instruction
+///
+/// **Processing Steps:**
+/// 1. Accrue interest (advances the reward index)
+/// 2. Settle the position's rewards at the current index
+/// 3. Pay out as much as the reward vault holds; the rest stays claimable
+///
+/// **State Changes:**
+/// - user_position.reward_accrued -= paid
+/// - reward_vault.amount -= paid (via transfer)
+///
+/// **Errors:**
+/// - InvalidVault: Reward vault or receiver mint mismatch
+/// - ZeroAmount: Nothing to claim, or the reward vault is empty
+pub fn handler(ctx: Context<ClaimRewards>) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    let user_position = &mut ctx.accounts.user_position;
+
+    // Step 1-2: Book everything earned up to now
+    accrue_interest(market)?;
+    settle_rewards(market, user_position)?;
+
+    // Step 3: Pay out what the vault can cover
+    let paid = user_position.reward_accrued.min(ctx.accounts.reward_vault.amount);
+    require!(paid > 0, PelagoError::ZeroAmount);
+    user_position.reward_accrued -= paid;
+
+    let loan_token_mint = market.loan_token_mint;
+    let collateral_token_mint = market.collateral_token_mint;
+    let bump = market.bump;
+
+    let market_seeds = &[
+        Market::SEED_PREFIX,
+        loan_token_mint.as_ref(),
+        collateral_token_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&market_seeds[..]];
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.receiver_reward_account.to_account_info(),
+            authority: market.to_account_info(),
+        },
+        signer_seeds,
+    );
+    snapshot_balance_delta(&mut ctx.accounts.reward_vault, || token::transfer(cpi_ctx, paid))?
+        .require_decrease(paid)?;
+
+    msg!(
+        "Rewards claimed: user={}, paid={}, remaining={}",
+        user_position.user,
+        paid,
+        user_position.reward_accrued
+    );
+
+    emit!(RewardsClaimedEvent {
+        user: user_position.user,
+        paid,
+        remaining: user_position.reward_accrued,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a supplier claims rewards
+#[event]
+pub struct RewardsClaimedEvent {
+    /// User public key (position owner)
+    pub user: Pubkey,
+
+    /// Reward tokens transferred
+    pub paid: u64,
+
+    /// Settled rewards still owed (reward vault ran short)
+    pub remaining: u64,
+}
@@ -0,0 +1,87 @@
+//! Close Position Instruction
+//!
+//! Closes an empty user position to reclaim its rent. The position can be
+//! recreated later by any `init_if_needed` creator (supply, supply_collateral,
+//! open_position), which reopens it from a zeroed account and registers it
+//! again under `max_positions` and in the user's registry.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::{Market, UserPosition, UserRegistry};
+
+/// Close an empty user position
+///
+/// **State Changes:**
+/// - `user_position` closed, rent refunded to `user`
+/// - `market.open_positions` decremented
+/// - Market removed from `user_registry`
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// User position PDA (closed)
+    #[account(
+        mut,
+        close = user,
+        seeds = [
+            UserPosition::SEED_PREFIX,
+            market.key().as_ref(),
+            user.key().as_ref(),
+        ],
+        bump = user_position.bump,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// User's market registry PDA
+    #[account(
+        mut,
+        seeds = [
+            UserRegistry::SEED_PREFIX,
+            user.key().as_ref(),
+        ],
+        bump = user_registry.bump,
+    )]
+    pub user_registry: Account<'info, UserRegistry>,
+
+    /// User wallet (signer, receives reclaimed rent)
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+/// Handler for close_position instruction
+///
+/// **Errors:**
+/// - PositionNotEmpty: The position still has supply shares, debt, collateral
+///   or unclaimed rewards
+/// - PositionFrozen: Closing would drop a compliance hold
+pub fn handler(ctx: Context<ClosePosition>) -> Result<()> {
+    require!(
+        ctx.accounts.user_position.is_empty(),
+        PelagoError::PositionNotEmpty
+    );
+    ctx.accounts.user_position.check_not_frozen()?;
+
+    let market = &mut ctx.accounts.market;
+    market.release_position();
+    ctx.accounts.user_registry.remove_market(market.key());
+
+    msg!(
+        "Position closed: user={}, market={}, open_positions={}",
+        ctx.accounts.user.key(),
+        market.key(),
+        market.open_positions
+    );
+
+    Ok(())
+}
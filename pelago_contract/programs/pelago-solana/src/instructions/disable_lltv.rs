@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::{Config, ProtocolConfig};
+
+/// Disable an LLTV value for new markets
+///
+/// **Access Control:** Only the config admin
+#[derive(Accounts)]
+pub struct DisableLltv<'info> {
+    /// Config PDA (source of the admin key)
+    #[account(
+        seeds = [Config::SEED_PREFIX],
+        bump = config.bump,
+        has_one = admin @ PelagoError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// ProtocolConfig PDA
+    #[account(
+        mut,
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Protocol admin (signer)
+    pub admin: Signer<'info>,
+}
+
+/// Handler for disable_lltv instruction
+///
+/// Markets already using `lltv` keep it; only new markets are affected.
+///
+/// **State Changes:**
+/// - protocol_config.enabled_lltvs -= `lltv`
+///
+/// **Errors:**
+/// - LltvNotEnabled: `lltv` is not enabled
+pub fn handler(ctx: Context<DisableLltv>, lltv: u64) -> Result<()> {
+    ctx.accounts.protocol_config.disable_lltv(lltv)?;
+
+    msg!("LLTV disabled: lltv={}", lltv);
+
+    Ok(())
+}
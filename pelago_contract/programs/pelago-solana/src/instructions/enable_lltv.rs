@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_LLTV;
+use crate::error::PelagoError;
+use crate::state::{Config, ProtocolConfig};
+
+/// Enable an LLTV value for new markets
+///
+/// **Access Control:** Only the config admin
+#[derive(Accounts)]
+pub struct EnableLltv<'info> {
+    /// Config PDA (source of the admin key)
+    #[account(
+        seeds = [Config::SEED_PREFIX],
+        bump = config.bump,
+        has_one = admin @ PelagoError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// ProtocolConfig PDA
+    #[account(
+        mut,
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Protocol admin (signer)
+    pub admin: Signer<'info>,
+}
+
+/// Handler for enable_lltv instruction
+///
+/// Enabling an already enabled value is a no-op.
+///
+/// **Validation:**
+/// - `lltv` must be > 0 and <= MAX_LLTV
+///
+/// **State Changes:**
+/// - protocol_config.enabled_lltvs += `lltv`
+///
+/// **Errors:**
+/// - InvalidLltv: `lltv` out of range
+/// - InvalidParameter: MAX_ENABLED_LLTVS values already enabled
+pub fn handler(ctx: Context<EnableLltv>, lltv: u64) -> Result<()> {
+    require!(lltv > 0 && lltv <= MAX_LLTV, PelagoError::InvalidLltv);
+
+    ctx.accounts.protocol_config.enable_lltv(lltv)?;
+
+    msg!("LLTV enabled: lltv={}", lltv);
+
+    Ok(())
+}
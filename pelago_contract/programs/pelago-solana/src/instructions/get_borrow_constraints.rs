@@ -0,0 +1,262 @@
+use anchor_lang::prelude::*;
+
+use crate::instructions::borrow::{check_auto_pause, effective_borrow_cap};
+use crate::state::Market;
+use crate::utils::interest::project_interest_at;
+use crate::utils::oracle::{check_price_bounds, check_price_freshness};
+
+/// Read every market rule a borrow is checked against
+///
+/// Read-only view: interest is accrued on a local copy of the market, so the
+/// market account itself is never modified.
+#[derive(Accounts)]
+pub struct GetBorrowConstraints<'info> {
+    /// Market account
+    #[account(
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Pre-borrow rules returned by `get_borrow_constraints`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BorrowConstraints {
+    /// Maximum loan-to-value for borrowing (precision: 1e8)
+    pub lltv: u64,
+
+    /// Loan-to-value at which positions become liquidatable (precision: 1e8)
+    /// Equal to `lltv` in the current model
+    pub liquidation_threshold: u64,
+
+    /// Scheduled LLTV (0 = none pending)
+    pub pending_lltv: u64,
+
+    /// Unix timestamp at which `pending_lltv` can be applied
+    pub lltv_effective_at: i64,
+
+    /// Absolute borrow cap (0 = disabled)
+    pub borrow_cap: u64,
+
+    /// Borrow cap as a share of supply (0 = disabled)
+    pub borrow_cap_ratio_bps: u16,
+
+    /// Tighter of the two caps at the accrued supply (0 = uncapped)
+    pub effective_borrow_cap: u64,
+
+    /// Per-position debt limit (0 = disabled)
+    pub max_borrow_per_position: u64,
+
+    /// Utilization above which new borrows are refused (0 = disabled)
+    pub auto_pause_utilization_bps: u16,
+
+    /// Minimum seconds between borrows of one position (0 = disabled)
+    pub borrow_cooldown_secs: u32,
+
+    /// Origination fee added to each borrow's debt
+    pub origination_fee_bps: u16,
+
+    /// Accrued total borrow assets
+    pub total_borrow_assets: u64,
+
+    /// Accrued supply not lent out
+    pub available_liquidity: u64,
+
+    /// Most a single borrow can take now: available liquidity, further
+    /// limited by the effective cap's headroom
+    pub max_borrowable: u64,
+
+    /// Collateral price health checks would use (PRICE_PRECISION scale)
+    pub collateral_price: u64,
+
+    /// Whether the price lies within the market's sanity band
+    pub price_in_bounds: bool,
+
+    /// Whether a manual price is within `liquidation_max_staleness_secs`
+    /// (always true for fixed prices or with staleness checks off)
+    pub price_fresh: bool,
+
+    /// Whether the market authority paused the market
+    pub paused: bool,
+
+    /// Whether utilization is above the auto-pause watermark
+    pub auto_paused: bool,
+
+    /// Whether a borrow could currently succeed, collateral permitting
+    pub borrowing_enabled: bool,
+}
+
+/// Handler for get_borrow_constraints view
+///
+/// **Returns:** `BorrowConstraints` (via return data)
+pub fn handler(ctx: Context<GetBorrowConstraints>) -> Result<BorrowConstraints> {
+    let now = Clock::get()?.unix_timestamp;
+    let constraints = borrow_constraints_at(&ctx.accounts.market, now)?;
+
+    msg!(
+        "Borrow constraints: market={}, borrowing_enabled={}, max_borrowable={}",
+        ctx.accounts.market.key(),
+        constraints.borrowing_enabled,
+        constraints.max_borrowable
+    );
+
+    Ok(constraints)
+}
+
+/// Borrow constraints once interest is accrued up to `timestamp`
+///
+/// A stale or out-of-band price disables borrowing: the price is what every
+/// health check would be evaluated against.
+pub fn borrow_constraints_at(market: &Market, timestamp: i64) -> Result<BorrowConstraints> {
+    let mut projected = market.clone();
+    project_interest_at(&mut projected, timestamp)?;
+
+    let available_liquidity = projected
+        .total_supply_assets
+        .saturating_sub(projected.total_borrow_assets);
+    let effective_cap = effective_borrow_cap(&projected);
+    let max_borrowable = match effective_cap {
+        Some(cap) => available_liquidity.min(cap.saturating_sub(projected.total_borrow_assets)),
+        None => available_liquidity,
+    };
+
+    let collateral_price = projected.collateral_price();
+    let price_in_bounds = check_price_bounds(&projected, collateral_price).is_ok();
+    let price_fresh = check_price_freshness(
+        &projected,
+        timestamp,
+        projected.liquidation_max_staleness_secs,
+    )
+    .is_ok();
+    let auto_paused = check_auto_pause(&projected).is_err();
+
+    Ok(BorrowConstraints {
+        lltv: projected.lltv,
+        liquidation_threshold: projected.lltv,
+        pending_lltv: projected.pending_lltv,
+        lltv_effective_at: projected.lltv_effective_at,
+        borrow_cap: projected.borrow_cap,
+        borrow_cap_ratio_bps: projected.borrow_cap_ratio_bps,
+        effective_borrow_cap: effective_cap.unwrap_or(0),
+        max_borrow_per_position: projected.max_borrow_per_position,
+        auto_pause_utilization_bps: projected.auto_pause_utilization_bps,
+        borrow_cooldown_secs: projected.borrow_cooldown_secs,
+        origination_fee_bps: projected.origination_fee_bps,
+        total_borrow_assets: projected.total_borrow_assets,
+        available_liquidity,
+        max_borrowable,
+        collateral_price,
+        price_in_bounds,
+        price_fresh,
+        paused: projected.paused,
+        auto_paused,
+        borrowing_enabled: !projected.paused
+            && !auto_paused
+            && price_in_bounds
+            && price_fresh
+            && max_borrowable > 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn configured_market() -> Market {
+        Market {
+            total_supply_assets: 1_000_000_000,
+            total_borrow_assets: 400_000_000,
+            total_borrow_shares: 400_000_000_000_000,
+            lltv: 80_000_000,
+            borrow_cap: 450_000_000,
+            borrow_cap_ratio_bps: 9_000,
+            max_borrow_per_position: 100_000_000,
+            auto_pause_utilization_bps: 9_500,
+            borrow_cooldown_secs: 60,
+            origination_fee_bps: 25,
+            fixed_price: 100_000,
+            last_update: NOW,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_constraints_mirror_market_config() {
+        let market = configured_market();
+        let c = borrow_constraints_at(&market, NOW).unwrap();
+
+        assert_eq!(c.lltv, 80_000_000);
+        assert_eq!(c.liquidation_threshold, 80_000_000);
+        assert_eq!(c.borrow_cap, 450_000_000);
+        assert_eq!(c.borrow_cap_ratio_bps, 9_000);
+        assert_eq!(c.effective_borrow_cap, 450_000_000);
+        assert_eq!(c.max_borrow_per_position, 100_000_000);
+        assert_eq!(c.auto_pause_utilization_bps, 9_500);
+        assert_eq!(c.borrow_cooldown_secs, 60);
+        assert_eq!(c.origination_fee_bps, 25);
+        assert_eq!(c.collateral_price, 100_000);
+        assert_eq!(c.available_liquidity, 600_000_000);
+        // The absolute cap leaves less room than the vault
+        assert_eq!(c.max_borrowable, 50_000_000);
+        assert!(c.price_in_bounds && c.price_fresh && !c.paused && !c.auto_paused);
+        assert!(c.borrowing_enabled);
+    }
+
+    #[test]
+    fn test_constraints_accrue_on_a_copy() {
+        let market = configured_market();
+        let c = borrow_constraints_at(&market, NOW + 365 * 86_400).unwrap();
+
+        assert!(c.total_borrow_assets > market.total_borrow_assets);
+        assert_eq!(market.last_update, NOW, "view must not mutate the market");
+    }
+
+    #[test]
+    fn test_stale_price_disables_borrowing() {
+        let mut market = configured_market();
+        market.manual_price = 90_000;
+        market.manual_price_enabled = true;
+        market.price_updated_at = NOW - 600;
+        market.liquidation_max_staleness_secs = 300;
+
+        let c = borrow_constraints_at(&market, NOW).unwrap();
+        assert!(!c.price_fresh);
+        assert!(!c.borrowing_enabled);
+
+        market.price_updated_at = NOW - 60;
+        assert!(
+            borrow_constraints_at(&market, NOW)
+                .unwrap()
+                .borrowing_enabled
+        );
+    }
+
+    #[test]
+    fn test_pause_and_exhausted_cap_disable_borrowing() {
+        let mut market = configured_market();
+        market.paused = true;
+        assert!(
+            !borrow_constraints_at(&market, NOW)
+                .unwrap()
+                .borrowing_enabled
+        );
+
+        let mut market = configured_market();
+        market.borrow_cap = 400_000_000;
+        let c = borrow_constraints_at(&market, NOW).unwrap();
+        assert_eq!(c.max_borrowable, 0);
+        assert!(!c.borrowing_enabled);
+
+        let mut market = configured_market();
+        market.min_sane_price = 200_000;
+        let c = borrow_constraints_at(&market, NOW).unwrap();
+        assert!(!c.price_in_bounds);
+        assert!(!c.borrowing_enabled);
+    }
+}
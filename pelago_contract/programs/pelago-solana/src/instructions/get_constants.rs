@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{LLTV_PRECISION, PRICE_PRECISION};
+use crate::utils::interest::{SECONDS_PER_YEAR, WAD};
+use crate::utils::shares_math::{VIRTUAL_ASSETS, VIRTUAL_SHARES};
+
+/// Read the protocol's precision constants
+///
+/// Read-only view, so clients can fetch the values the program was compiled
+/// with instead of hardcoding them off-chain.
+#[derive(Accounts)]
+pub struct GetConstants<'info> {
+    /// System program (unused; Anchor's CPI client needs an accounts lifetime)
+    pub system_program: Program<'info, System>,
+}
+
+/// Compiled protocol constants returned by `get_constants`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProtocolConstants {
+    /// Virtual share offset used by share/asset conversions
+    pub virtual_shares: u128,
+
+    /// Virtual asset offset used by share/asset conversions
+    pub virtual_assets: u128,
+
+    /// Fixed-point precision of interest rates (1e18)
+    pub wad: u128,
+
+    /// Precision of LLTV values (1e8 = 100%)
+    pub lltv_precision: u64,
+
+    /// Precision of collateral prices (1e6)
+    pub price_precision: u64,
+
+    /// Seconds per year used to derive the per-second rate
+    pub seconds_per_year: u128,
+}
+
+impl ProtocolConstants {
+    /// Values compiled into this program
+    pub const COMPILED: Self = Self {
+        virtual_shares: VIRTUAL_SHARES,
+        virtual_assets: VIRTUAL_ASSETS,
+        wad: WAD,
+        lltv_precision: LLTV_PRECISION,
+        price_precision: PRICE_PRECISION,
+        seconds_per_year: SECONDS_PER_YEAR,
+    };
+}
+
+/// Handler for get_constants view
+///
+/// **Returns:** `ProtocolConstants` (via return data)
+pub fn handler(_ctx: Context<GetConstants>) -> Result<ProtocolConstants> {
+    Ok(ProtocolConstants::COMPILED)
+}
@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Market, RateSnapshot};
+use crate::utils::rate_history::{annualized_growth_wad, window_bounds};
+
+/// Read the realized borrow and supply APY of a market
+///
+/// Read-only view over the market's rate snapshots.
+#[derive(Accounts)]
+pub struct GetHistoricalApy<'info> {
+    /// Market account
+    #[account(
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Rate snapshot PDA of this market
+    #[account(
+        seeds = [RateSnapshot::SEED_PREFIX, market.key().as_ref()],
+        bump = rate_snapshot.bump,
+    )]
+    pub rate_snapshot: Account<'info, RateSnapshot>,
+}
+
+/// Realized yields returned by `get_historical_apy`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HistoricalApy {
+    /// Whether at least two snapshots exist; all fields below are zero when false
+    pub available: bool,
+
+    /// Timestamp of the snapshot the window starts at
+    pub from_timestamp: i64,
+
+    /// Timestamp of the latest snapshot
+    pub to_timestamp: i64,
+
+    /// Annualized borrow yield over the window (precision: 1e18)
+    pub borrow_apy_wad: u128,
+
+    /// Annualized supply yield over the window (precision: 1e18)
+    pub supply_apy_wad: u128,
+}
+
+/// Handler for get_historical_apy view
+///
+/// **Parameters:**
+/// - `window`: Lookback in seconds from the latest snapshot (0 = whole
+///   history); shortened to the oldest snapshot if the history is shorter
+///
+/// **Returns:** `HistoricalApy` (via return data)
+pub fn handler(ctx: Context<GetHistoricalApy>, window: u32) -> Result<HistoricalApy> {
+    let samples = ctx.accounts.rate_snapshot.chronological();
+
+    let Some((start, end)) = window_bounds(&samples, window) else {
+        msg!(
+            "Historical APY unavailable: market={}, samples={}",
+            ctx.accounts.market.key(),
+            samples.len()
+        );
+        return Ok(HistoricalApy::default());
+    };
+
+    let elapsed = end.timestamp - start.timestamp;
+    let apy = HistoricalApy {
+        available: true,
+        from_timestamp: start.timestamp,
+        to_timestamp: end.timestamp,
+        borrow_apy_wad: annualized_growth_wad(start.borrow_index, end.borrow_index, elapsed)?,
+        supply_apy_wad: annualized_growth_wad(start.supply_index, end.supply_index, elapsed)?,
+    };
+
+    msg!(
+        "Historical APY: market={}, elapsed={}s, borrow={}, supply={}",
+        ctx.accounts.market.key(),
+        elapsed,
+        apy.borrow_apy_wad,
+        apy.supply_apy_wad
+    );
+
+    Ok(apy)
+}
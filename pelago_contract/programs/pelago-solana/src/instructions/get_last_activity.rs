@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+use crate::state::UserPosition;
+
+/// Read the last-activity timestamp of a user position
+///
+/// Read-only view for dormancy analytics. Off-chain tools can combine it with
+/// `UserPosition::is_stale` semantics to filter dormant positions.
+#[derive(Accounts)]
+pub struct GetLastActivity<'info> {
+    /// User position PDA to inspect
+    pub user_position: Account<'info, UserPosition>,
+}
+
+/// Handler for get_last_activity view
+///
+/// **Returns:** `user_position.last_activity` (via return data)
+pub fn handler(ctx: Context<GetLastActivity>) -> Result<i64> {
+    let last_activity = ctx.accounts.user_position.last_activity;
+
+    msg!(
+        "Last activity: user={}, market={}, timestamp={}",
+        ctx.accounts.user_position.user,
+        ctx.accounts.user_position.market,
+        last_activity
+    );
+
+    Ok(last_activity)
+}
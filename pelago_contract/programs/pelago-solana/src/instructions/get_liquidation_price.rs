@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::{Market, UserPosition};
+use crate::utils::interest::project_interest_at;
+use crate::utils::liquidation::liquidation_price;
+
+/// Quote the collateral price at which a position becomes liquidatable
+///
+/// Read-only view: interest is accrued on a local copy of the market, so the
+/// market account itself is never modified.
+#[derive(Accounts)]
+pub struct GetLiquidationPrice<'info> {
+    /// Market account
+    #[account(
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Borrower's position in this market
+    #[account(
+        constraint = user_position.market == market.key() @ PelagoError::InvalidParameter,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+}
+
+/// Handler for get_liquidation_price view
+///
+/// **Returns:** Lowest healthy collateral price at PRICE_PRECISION scale
+/// (via return data); 0 if the position has no debt (see `liquidation_price`)
+pub fn handler(ctx: Context<GetLiquidationPrice>) -> Result<u64> {
+    let mut projected = (*ctx.accounts.market).clone();
+    project_interest_at(&mut projected, Clock::get()?.unix_timestamp)?;
+
+    let price = liquidation_price(&projected, &ctx.accounts.user_position)?;
+
+    msg!(
+        "Liquidation price: user={}, price={}, current_price={}",
+        ctx.accounts.user_position.user,
+        price,
+        projected.collateral_price()
+    );
+
+    Ok(price)
+}
@@ -0,0 +1,148 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+use crate::utils::interest::compute_borrow_rate;
+
+/// Layout version of `MarketInfo`
+///
+/// Bumped whenever fields are added or reinterpreted. New fields are only
+/// ever appended, so clients can decode the prefix they know about.
+pub const MARKET_INFO_VERSION: u8 = 2;
+
+/// Read a versioned summary of a market
+///
+/// Read-only view addressed by mint pair, so it also answers whether the
+/// market exists: an uninitialized market PDA returns `initialized = false`
+/// instead of failing deserialization.
+#[derive(Accounts)]
+pub struct GetMarketInfo<'info> {
+    /// Market PDA for the mint pair (may be uninitialized)
+    /// CHECK: Address verified by seeds; owner and data checked in the handler
+    #[account(
+        seeds = [
+            Market::SEED_PREFIX,
+            loan_token_mint.key().as_ref(),
+            collateral_token_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub market: UncheckedAccount<'info>,
+
+    /// Loan token mint
+    /// CHECK: Only used as a PDA seed
+    pub loan_token_mint: UncheckedAccount<'info>,
+
+    /// Collateral token mint
+    /// CHECK: Only used as a PDA seed
+    pub collateral_token_mint: UncheckedAccount<'info>,
+}
+
+/// Stable market summary returned by `get_market_info`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MarketInfo {
+    /// Layout version (MARKET_INFO_VERSION)
+    pub version: u8,
+
+    /// Whether the market account exists; all fields below except the mints
+    /// are zero when false
+    pub initialized: bool,
+
+    /// Loan token mint
+    pub loan_token_mint: Pubkey,
+
+    /// Collateral token mint
+    pub collateral_token_mint: Pubkey,
+
+    /// Loan token vault
+    pub loan_vault: Pubkey,
+
+    /// Collateral token vault
+    pub collateral_vault: Pubkey,
+
+    /// Maximum loan-to-value for borrowing (precision: 1e8)
+    pub lltv: u64,
+
+    /// Loan-to-value at which positions become liquidatable (precision: 1e8)
+    /// Equal to `lltv` in the current model
+    pub liquidation_threshold: u64,
+
+    /// Annual borrow rate at the current utilization (precision: 1e18)
+    pub annual_rate_wad: u128,
+
+    /// Absolute borrow cap (0 = disabled)
+    pub borrow_cap: u64,
+
+    /// Borrow cap as a share of supply (0 = disabled)
+    pub borrow_cap_ratio_bps: u16,
+
+    /// Per-position debt limit (0 = disabled)
+    pub max_borrow_per_position: u64,
+
+    /// Maximum open positions (0 = unlimited)
+    pub max_positions: u32,
+
+    /// Whether user operations are paused
+    pub paused: bool,
+
+    /// Open user positions (`market.open_positions`, since version 2)
+    pub position_count: u64,
+}
+
+impl MarketInfo {
+    /// Summary of an initialized market
+    pub fn from_market(market: &Market) -> Self {
+        Self {
+            version: MARKET_INFO_VERSION,
+            initialized: true,
+            loan_token_mint: market.loan_token_mint,
+            collateral_token_mint: market.collateral_token_mint,
+            loan_vault: market.loan_vault,
+            collateral_vault: market.collateral_vault,
+            lltv: market.lltv,
+            liquidation_threshold: market.lltv,
+            annual_rate_wad: compute_borrow_rate(market),
+            borrow_cap: market.borrow_cap,
+            borrow_cap_ratio_bps: market.borrow_cap_ratio_bps,
+            max_borrow_per_position: market.max_borrow_per_position,
+            max_positions: market.max_positions,
+            paused: market.paused,
+            position_count: market.open_positions as u64,
+        }
+    }
+
+    /// Summary of a mint pair without a market
+    pub fn uninitialized(loan_token_mint: Pubkey, collateral_token_mint: Pubkey) -> Self {
+        Self {
+            version: MARKET_INFO_VERSION,
+            loan_token_mint,
+            collateral_token_mint,
+            ..Default::default()
+        }
+    }
+}
+
+/// Handler for get_market_info view
+///
+/// **Returns:** `MarketInfo` (via return data)
+pub fn handler(ctx: Context<GetMarketInfo>) -> Result<MarketInfo> {
+    let market_info = ctx.accounts.market.to_account_info();
+
+    let info = if market_info.owner == &crate::ID && !market_info.data_is_empty() {
+        let market = Market::try_deserialize(&mut &market_info.data.borrow()[..])?;
+        MarketInfo::from_market(&market)
+    } else {
+        MarketInfo::uninitialized(
+            ctx.accounts.loan_token_mint.key(),
+            ctx.accounts.collateral_token_mint.key(),
+        )
+    };
+
+    msg!(
+        "Market info: market={}, initialized={}, version={}",
+        market_info.key(),
+        info.initialized,
+        info.version
+    );
+
+    Ok(info)
+}
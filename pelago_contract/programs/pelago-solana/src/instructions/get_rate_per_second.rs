@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Market;
+use crate::utils::interest::rate_per_second;
+
+/// Query the per-second borrow rate the market's accrual applies
+///
+/// Read-only view for checking off-chain interest projections.
+#[derive(Accounts)]
+pub struct GetRatePerSecond<'info> {
+    /// Market account
+    #[account(
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Handler for get_rate_per_second view
+///
+/// **Returns:** Per-second borrow rate (precision: 1e18, via return data),
+/// exactly as `interest_split` uses it; 0 while the market is paused
+/// without accrual
+pub fn handler(ctx: Context<GetRatePerSecond>) -> Result<u128> {
+    let rate = rate_per_second(&ctx.accounts.market)?;
+
+    msg!("Rate per second: market={}, rate_wad={}", ctx.accounts.market.key(), rate);
+
+    Ok(rate)
+}
@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::{Market, UserPosition};
+use crate::utils::interest::project_interest_at;
+use crate::utils::shares_math::to_assets_up;
+
+/// Quote the loan tokens needed to fully repay a position
+///
+/// Read-only view: interest is accrued on a local copy of the market, so the
+/// market account itself is never modified.
+#[derive(Accounts)]
+pub struct GetRepayAmount<'info> {
+    /// Market account
+    #[account(
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Borrower's position in this market
+    #[account(
+        constraint = user_position.market == market.key() @ PelagoError::InvalidParameter,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+}
+
+/// Full-repayment quote returned by `get_repay_amount`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RepayQuote {
+    /// Exact assets that zero the debt at the current timestamp
+    pub assets: u64,
+
+    /// Assets that zero the debt `buffer_seconds` from now
+    /// Upper bound for token approvals when the repay may land later;
+    /// repay by shares to pay exactly what is owed at landing time
+    pub assets_with_buffer: u64,
+}
+
+/// Handler for get_repay_amount view
+///
+/// **Formula:**
+/// ```text
+/// assets = to_assets_up(borrow_shares, accrued_total_borrow_assets, total_borrow_shares)
+/// ```
+///
+/// **Returns:** `RepayQuote` (via return data)
+pub fn handler(ctx: Context<GetRepayAmount>, buffer_seconds: u32) -> Result<RepayQuote> {
+    let now = Clock::get()?.unix_timestamp;
+    let borrow_shares = ctx.accounts.user_position.borrow_shares;
+
+    let quote = RepayQuote {
+        assets: repay_amount_at(&ctx.accounts.market, borrow_shares, now)?,
+        assets_with_buffer: repay_amount_at(
+            &ctx.accounts.market,
+            borrow_shares,
+            now.checked_add(buffer_seconds as i64)
+                .ok_or(PelagoError::MathOverflow)?,
+        )?,
+    };
+
+    msg!(
+        "Repay quote: user={}, borrow_shares={}, assets={}, assets_with_buffer={}",
+        ctx.accounts.user_position.user,
+        borrow_shares,
+        quote.assets,
+        quote.assets_with_buffer
+    );
+
+    Ok(quote)
+}
+
+/// Assets needed to burn `borrow_shares` once interest is accrued up to `timestamp`
+pub fn repay_amount_at(market: &Market, borrow_shares: u64, timestamp: i64) -> Result<u64> {
+    let mut projected = market.clone();
+    project_interest_at(&mut projected, timestamp)?;
+
+    to_assets_up(
+        borrow_shares,
+        projected.total_borrow_assets,
+        projected.total_borrow_shares,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::interest::accrue_interest_at;
+    use crate::utils::shares_math::to_shares_down;
+
+    #[test]
+    fn test_repay_quote_clears_debt_after_interest() {
+        let start = 1_700_000_000;
+        let mut market = Market {
+            total_supply_assets: 2_000_000_000,
+            total_borrow_assets: 1_000_000_000,
+            total_borrow_shares: 1_000_000_000_000_000,
+            last_update: start,
+            ..Default::default()
+        };
+        let borrow_shares = 333_333_333_333_333;
+
+        // Quote one day later, then replay repay's accrual + assets→shares path
+        let now = start + 86_400;
+        let quote = repay_amount_at(&market, borrow_shares, now).unwrap();
+        assert_eq!(market.last_update, start, "quote must not mutate the market");
+
+        accrue_interest_at(&mut market, now).unwrap();
+        let burned = to_shares_down(
+            quote,
+            market.total_borrow_assets,
+            market.total_borrow_shares,
+        )
+        .unwrap();
+        assert!(burned >= borrow_shares);
+
+        // One unit less leaves dust behind
+        let short = to_shares_down(
+            quote - 1,
+            market.total_borrow_assets,
+            market.total_borrow_shares,
+        )
+        .unwrap();
+        assert!(short < borrow_shares);
+    }
+
+    #[test]
+    fn test_buffer_quote_covers_later_landing() {
+        let start = 1_700_000_000;
+        let market = Market {
+            total_supply_assets: 2_000_000_000,
+            total_borrow_assets: 1_000_000_000,
+            total_borrow_shares: 1_000_000_000_000_000,
+            last_update: start,
+            ..Default::default()
+        };
+
+        let exact = repay_amount_at(&market, 1_000_000_000_000_000, start + 60).unwrap();
+        let buffered = repay_amount_at(&market, 1_000_000_000_000_000, start + 120).unwrap();
+        assert!(buffered > exact);
+    }
+}
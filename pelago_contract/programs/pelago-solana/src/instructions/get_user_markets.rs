@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::state::UserRegistry;
+
+/// List the markets a user holds positions in
+///
+/// Read-only view over the user's `UserRegistry`. A user without any
+/// position has no registry yet and gets an empty list instead of an error.
+#[derive(Accounts)]
+pub struct GetUserMarkets<'info> {
+    /// User registry PDA (may be uninitialized)
+    /// CHECK: Address verified by seeds; owner and data checked in the handler
+    #[account(
+        seeds = [
+            UserRegistry::SEED_PREFIX,
+            user.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub user_registry: UncheckedAccount<'info>,
+
+    /// User wallet
+    /// CHECK: Only used as a PDA seed
+    pub user: UncheckedAccount<'info>,
+}
+
+/// Handler for get_user_markets view
+///
+/// **Returns:** Market pubkeys in creation order (via return data)
+pub fn handler(ctx: Context<GetUserMarkets>) -> Result<Vec<Pubkey>> {
+    let registry_info = ctx.accounts.user_registry.to_account_info();
+
+    let markets = if registry_info.owner == &crate::ID && !registry_info.data_is_empty() {
+        UserRegistry::try_deserialize(&mut &registry_info.data.borrow()[..])?.markets
+    } else {
+        Vec::new()
+    };
+
+    msg!(
+        "User markets: user={}, count={}",
+        ctx.accounts.user.key(),
+        markets.len()
+    );
+
+    Ok(markets)
+}
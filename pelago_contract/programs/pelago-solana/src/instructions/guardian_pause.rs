@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::{Config, Market};
+
+/// Emergency pause of a market by the protocol guardian
+///
+/// The guardian can pause any market without being its authority.
+/// This instruction can only set `paused = true`; unpausing requires the
+/// market authority via `set_paused`.
+#[derive(Accounts)]
+pub struct GuardianPause<'info> {
+    /// Config PDA (source of the guardian key)
+    #[account(
+        seeds = [Config::SEED_PREFIX],
+        bump = config.bump,
+        has_one = guardian @ PelagoError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Market to pause
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Protocol guardian (signer)
+    pub guardian: Signer<'info>,
+}
+
+/// Handler for guardian_pause instruction
+///
+/// Interest is not settled first, so the emergency pause cannot fail on
+/// accrual; with `accrue_while_paused` off, interest since the last accrual
+/// is forgone.
+///
+/// **State Changes:**
+/// - market.paused = true
+pub fn handler(ctx: Context<GuardianPause>) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.paused = true;
+
+    msg!(
+        "Market paused by guardian: market={}, guardian={}",
+        market.key(),
+        ctx.accounts.guardian.key()
+    );
+
+    emit!(MarketPausedEvent {
+        market: market.key(),
+        by: ctx.accounts.guardian.key(),
+        paused: true,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a market's pause flag changes
+#[event]
+pub struct MarketPausedEvent {
+    /// Market public key
+    pub market: Pubkey,
+
+    /// Signer that changed the flag (authority or guardian)
+    pub by: Pubkey,
+
+    /// New pause flag
+    pub paused: bool,
+}
@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::program::PelagoSolana;
+use crate::state::Config;
+
+/// Initialize the protocol-wide config singleton
+///
+/// Creates the Config PDA and records the admin (signer) and emergency guardian.
+/// Can only succeed once: the PDA seed is fixed, so re-initialization fails.
+///
+/// **Access Control:** Only the program's upgrade authority, so nobody can
+/// front-run the deployer and claim the admin role
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    /// Config PDA (to be initialized)
+    /// Seeds: ["config"]
+    #[account(
+        init,
+        payer = admin,
+        space = Config::LEN,
+        seeds = [Config::SEED_PREFIX],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Protocol admin (signer, pays for the account)
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// This program, to locate its ProgramData account
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ PelagoError::InvalidParameter,
+    )]
+    pub program: Program<'info, PelagoSolana>,
+
+    /// This program's ProgramData account (holds the upgrade authority)
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ PelagoError::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    /// Solana system program
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for initialize_config instruction
+///
+/// **State Changes:**
+/// - config.admin = admin signer
+/// - config.guardian = `guardian`
+///
+/// **Errors:**
+/// - Unauthorized: Signer is not the program's upgrade authority
+/// - InvalidParameter: `program_data` is not this program's ProgramData
+pub fn handler(ctx: Context<InitializeConfig>, guardian: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    config.admin = ctx.accounts.admin.key();
+    config.guardian = guardian;
+    config.bump = ctx.bumps.config;
+
+    msg!(
+        "Config initialized: admin={}, guardian={}",
+        config.admin,
+        config.guardian
+    );
+
+    Ok(())
+}
@@ -1,9 +1,16 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
-use crate::constants::MAX_LLTV;
+use crate::constants::{
+    DEFAULT_MAX_ACCRUAL_INTEREST_BPS, DEFAULT_WHOLE_TOKEN_PRICE, MAX_FEE_BPS, MAX_LLTV,
+    VAULT_ACCOUNTING_TOLERANCE_TOKENS,
+};
 use crate::error::PelagoError;
-use crate::state::Market;
+use crate::instructions::set_irm::{irm_rate_range, validate_irm, IrmParams};
+use crate::instructions::supply::min_initial_deposit_base_units;
+use crate::state::{Market, ProtocolConfig};
+use crate::utils::oracle::base_unit_price;
+use crate::utils::shares_math::{validate_virtual_offsets, VIRTUAL_ASSETS, VIRTUAL_SHARES};
 
 /// Initialize a new lending market with dual token vaults
 ///
@@ -74,12 +81,30 @@ pub struct InitializeMarket<'info> {
 
     /// Rent sysvar for rent-exempt calculations
     pub rent: Sysvar<'info, Rent>,
+
+    /// Protocol policy (LLTV whitelist, max LLTV, rate bounds) and defaults
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
 }
 
 /// Handler for initialize_market instruction
 ///
 /// **Validation:**
 /// - LLTV must be > 0 and <= 100% (MAX_LLTV)
+/// - `fixed_price` of 0 selects DEFAULT_WHOLE_TOKEN_PRICE scaled to the mints'
+///   decimals (FIXED_ORACLE_PRICE for SOL/USDC); pairs whose default cannot
+///   be expressed per base unit are rejected
+/// - `fee_bps` of USE_PROTOCOL_DEFAULT_FEE inherits the protocol default;
+///   otherwise it must be <= MAX_FEE_BPS
+/// - LLTV <= `protocol_config.max_lltv`, LLTV enabled (else LltvNotEnabled)
+/// - `irm` must pass `validate_irm`, and its rates at 0% and 100%
+///   utilization must lie within the protocol rate bounds (a model without
+///   a kink charges FIXED_ANNUAL_RATE_WAD)
+/// - `lltv_timelock` must be >= 0 (0 = LLTV changes apply immediately)
+/// - Virtual share offsets must be overflow-safe for the loan mint decimals
 /// - Loan and collateral mints must be valid SPL tokens
 /// - Authority must sign the transaction
 ///
@@ -91,10 +116,32 @@ pub struct InitializeMarket<'info> {
 ///
 /// **P0 Behavior:**
 /// - No interest accrual setup (last_update is informational only)
-/// - No oracle integration (uses fixed price in borrow instruction)
-pub fn handler(ctx: Context<InitializeMarket>, lltv: u64) -> Result<()> {
+/// - No oracle integration (uses the market's fixed price in health checks)
+pub fn handler(
+    ctx: Context<InitializeMarket>,
+    lltv: u64,
+    fixed_price: u64,
+    lltv_timelock: i64,
+    fee_bps: u16,
+    irm: IrmParams,
+) -> Result<()> {
     // Validate LLTV parameter
     require!(lltv > 0 && lltv <= MAX_LLTV, PelagoError::InvalidLltv);
+    require!(lltv_timelock >= 0, PelagoError::InvalidParameter);
+    validate_irm(&irm)?;
+
+    // Every new market goes through the protocol policy
+    let protocol_config = &ctx.accounts.protocol_config;
+    let (min_rate_wad, max_rate_wad) = irm_rate_range(&irm);
+    protocol_config.check_market_params(lltv, min_rate_wad)?;
+    protocol_config.check_market_params(lltv, max_rate_wad)?;
+    let fee_bps = protocol_config.resolve_fee_bps(fee_bps);
+    require!(fee_bps <= MAX_FEE_BPS, PelagoError::InvalidParameter);
+    validate_virtual_offsets(
+        VIRTUAL_SHARES,
+        VIRTUAL_ASSETS,
+        ctx.accounts.loan_token_mint.decimals,
+    )?;
 
     let market = &mut ctx.accounts.market;
     let clock = Clock::get()?;
@@ -115,13 +162,78 @@ pub fn handler(ctx: Context<InitializeMarket>, lltv: u64) -> Result<()> {
     // Set LLTV and timestamp
     market.lltv = lltv;
     market.last_update = clock.unix_timestamp;
+    market.paused = false;
+    market.borrow_cap = 0;
+    market.borrow_cap_ratio_bps = 0;
+    market.round_interest_up = false;
+    market.manual_price = 0;
+    market.manual_price_enabled = false;
+    market.max_positions = 0;
+    market.open_positions = 0;
+    market.fee_bps = fee_bps;
+    market.fee_shares = 0;
+    market.max_borrow_per_position = 0;
+    market.total_collateral = 0;
+    market.origination_fee_bps = 0;
+    market.reserves = 0;
+    market.twap_window = 0;
+    market.twap_price = 0;
+    market.twap_updated_at = 0;
+    market.loan_decimals = ctx.accounts.loan_token_mint.decimals;
+    market.collateral_decimals = ctx.accounts.collateral_token_mint.decimals;
+    market.fixed_price = if fixed_price == 0 {
+        base_unit_price(
+            DEFAULT_WHOLE_TOKEN_PRICE,
+            market.collateral_decimals,
+            market.loan_decimals,
+        )?
+    } else {
+        fixed_price
+    };
+    market.lltv_timelock = lltv_timelock;
+    market.pending_lltv = 0;
+    market.lltv_effective_at = 0;
+    market.auto_pause_utilization_bps = 0;
+    market.borrow_cooldown_secs = 0;
+    market.accrue_while_paused = true;
+    market.favor_user_on_supply = false;
+    market.dead_shares = 0;
+    market.min_sane_price = 0;
+    market.max_sane_price = 0;
+    market.current_borrow_rate_wad = 0;
+    market.current_supply_rate_wad = 0;
+    market.max_accrual_interest_bps = DEFAULT_MAX_ACCRUAL_INTEREST_BPS;
+    market.fee_kink_utilization_bps = 0;
+    market.min_liquidation_assets = 0;
+    market.max_borrow_ratio_bps = 0;
+    market.price_updated_at = 0;
+    market.liquidation_max_staleness_secs = 0;
+    market.compound_period_secs = 0;
+    market.first_supply_dead_shares = 0;
+    market.reward_vault = Pubkey::default();
+    market.reward_rate_per_second = 0;
+    market.reward_index = 0;
+    market.min_initial_deposit = 0;
+    market.irm_base_rate_wad = irm.base_rate_wad;
+    market.irm_slope1_wad = irm.slope1_wad;
+    market.irm_slope2_wad = irm.slope2_wad;
+    market.irm_kink_utilization_bps = irm.kink_utilization_bps;
+    market.dust_shares_threshold = 0;
+    market.version = 0;
+    market.disallow_self_liquidation = false;
+    market.manager = Pubkey::default();
+    market.impaired = false;
+    market.collateral_cap = 0;
+    market.vault_tolerance =
+        min_initial_deposit_base_units(VAULT_ACCOUNTING_TOLERANCE_TOKENS, market.loan_decimals)?;
     market.bump = ctx.bumps.market;
 
     msg!(
-        "Market initialized: loan_mint={}, collateral_mint={}, lltv={}",
+        "Market initialized: loan_mint={}, collateral_mint={}, lltv={}, fee_bps={}",
         market.loan_token_mint,
         market.collateral_token_mint,
-        market.lltv
+        market.lltv,
+        market.fee_bps
     );
 
     Ok(())
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::{Market, PriceHistory};
+
+/// Create the price history account for a market
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct InitializePriceHistory<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Price history PDA (to be initialized)
+    /// Seeds: ["price-history", market]
+    #[account(
+        init,
+        payer = authority,
+        space = PriceHistory::LEN,
+        seeds = [PriceHistory::SEED_PREFIX, market.key().as_ref()],
+        bump
+    )]
+    pub price_history: Account<'info, PriceHistory>,
+
+    /// Market authority (signer, pays for the account)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Solana system program
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for initialize_price_history instruction
+///
+/// **State Changes:**
+/// - price_history.market = market
+/// - market.twap_window = `twap_window`
+pub fn handler(ctx: Context<InitializePriceHistory>, twap_window: u32) -> Result<()> {
+    let price_history = &mut ctx.accounts.price_history;
+    price_history.market = ctx.accounts.market.key();
+    price_history.bump = ctx.bumps.price_history;
+
+    let market = &mut ctx.accounts.market;
+    market.twap_window = twap_window;
+    market.twap_price = 0;
+    market.twap_updated_at = 0;
+
+    msg!(
+        "Price history initialized: market={}, twap_window={}s",
+        market.key(),
+        twap_window
+    );
+
+    Ok(())
+}
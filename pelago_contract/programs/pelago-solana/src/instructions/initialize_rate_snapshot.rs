@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::{Market, RateSnapshot};
+use crate::utils::interest::accrue_interest;
+use crate::utils::rate_history::rate_sample;
+
+/// Create the rate snapshot account for a market
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct InitializeRateSnapshot<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Rate snapshot PDA (to be initialized)
+    /// Seeds: ["rate-snapshot", market]
+    #[account(
+        init,
+        payer = authority,
+        space = RateSnapshot::LEN,
+        seeds = [RateSnapshot::SEED_PREFIX, market.key().as_ref()],
+        bump
+    )]
+    pub rate_snapshot: Account<'info, RateSnapshot>,
+
+    /// Market authority (signer, pays for the account)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Solana system program
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for initialize_rate_snapshot instruction
+///
+/// Interest is accrued first so the initial snapshot reflects the current
+/// share prices.
+///
+/// **State Changes:**
+/// - rate_snapshot.market = market
+/// - rate_snapshot.samples = [current share prices]
+pub fn handler(ctx: Context<InitializeRateSnapshot>) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    accrue_interest(market)?;
+
+    let rate_snapshot = &mut ctx.accounts.rate_snapshot;
+    rate_snapshot.market = market.key();
+    rate_snapshot.bump = ctx.bumps.rate_snapshot;
+    rate_snapshot.push(rate_sample(market, market.last_update)?);
+
+    msg!("Rate snapshot initialized: market={}", market.key());
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! Initialize Rewards Instruction
+//!
+//! Creates a market's reward vault and starts streaming reward tokens to
+//! suppliers (see `utils::rewards`). The vault is funded with plain token
+//! transfers; claims pay out of whatever it holds.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::constants::MAX_REWARD_RATE_PER_SECOND;
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::utils::interest::accrue_interest;
+
+/// Create a market's reward vault and set the emission rate
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct InitializeRewards<'info> {
+    /// Market account (rewards not configured yet)
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Reward token mint (may be any mint, including the loan token)
+    pub reward_mint: Account<'info, Mint>,
+
+    /// Reward token vault (to be created)
+    /// Token account owned by market PDA for holding reward tokens
+    #[account(
+        init,
+        payer = authority,
+        token::mint = reward_mint,
+        token::authority = market,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    /// Market authority (signer, payer)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Solana system program
+    pub system_program: Program<'info, System>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+
+    /// Rent sysvar for rent-exempt calculations
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Handler for initialize_rewards instruction
+///
+/// **State Changes:**
+/// - market.reward_vault = `reward_vault`
+/// - market.reward_rate_per_second = `reward_rate_per_second`
+///
+/// **Errors:**
+/// - InvalidParameter: Rewards are already configured, or the rate exceeds
+///   MAX_REWARD_RATE_PER_SECOND
+pub fn handler(ctx: Context<InitializeRewards>, reward_rate_per_second: u64) -> Result<()> {
+    require!(
+        reward_rate_per_second <= MAX_REWARD_RATE_PER_SECOND,
+        PelagoError::InvalidParameter
+    );
+
+    let market = &mut ctx.accounts.market;
+    market.require_initialized()?;
+    require!(market.reward_vault == Pubkey::default(), PelagoError::InvalidParameter);
+
+    // Emission starts now, not at the last accrual
+    accrue_interest(market)?;
+
+    market.reward_vault = ctx.accounts.reward_vault.key();
+    market.reward_rate_per_second = reward_rate_per_second;
+
+    msg!(
+        "Rewards initialized: market={}, reward_mint={}, reward_vault={}, rate={}/s",
+        market.key(),
+        ctx.accounts.reward_mint.key(),
+        market.reward_vault,
+        reward_rate_per_second
+    );
+
+    Ok(())
+}
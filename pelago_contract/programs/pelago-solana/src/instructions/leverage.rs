@@ -0,0 +1,413 @@
+//! Leverage Instruction
+//!
+//! Opens or increases a leveraged position atomically:
+//! 1. Borrow loan tokens from the market
+//! 2. Swap them to collateral via a caller-provided swap program (CPI callback)
+//! 3. Deposit the received collateral into the position
+//! 4. Check health once at the end
+//!
+//! Intermediate states (debt without the matching collateral) are never
+//! observable outside the transaction, so the position only needs to be
+//! healthy after the final deposit.
+//!
+//! **Max Leverage:**
+//! - Derived from LLTV: `max_leverage = 1 / (1 - lltv)`
+//! - Example: 80% LLTV → 5x max leverage
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::{LLTV_PRECISION, PRICE_PRECISION};
+use crate::error::PelagoError;
+use crate::state::{Market, UserPosition};
+use crate::utils::interest::{accrue_interest, WAD};
+use crate::utils::invariants::check_market_invariants;
+use crate::utils::shares_math::{to_assets_up, to_shares_up};
+use crate::utils::vault_snapshot::snapshot_balance_delta;
+use crate::instructions::borrow::{
+    check_auto_pause, check_available_liquidity, check_borrow_caps, check_borrow_share_ratio,
+    check_position_borrow_limit, enforce_borrow_cooldown, record_borrow,
+};
+use crate::instructions::supply_collateral::check_collateral_cap;
+use crate::instructions::withdraw_collateral::check_health_p1;
+use crate::utils::oracle::collateral_price;
+
+/// Leverage loop: borrow → swap → supply collateral in one instruction
+///
+/// **Swap Callback:**
+/// - `swap_program` is invoked with `swap_data` and all `remaining_accounts`
+/// - The swap must send collateral tokens to `user_collateral_account`
+/// - The user signs the outer transaction, so the swap can move tokens out of
+///   `user_loan_account` on the user's behalf
+#[derive(Accounts)]
+pub struct Leverage<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// User position PDA (must exist)
+    #[account(
+        mut,
+        seeds = [
+            UserPosition::SEED_PREFIX,
+            market.key().as_ref(),
+            user.key().as_ref(),
+        ],
+        bump = user_position.bump,
+        constraint = user_position.user == user.key() @ PelagoError::Unauthorized,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Market's loan token vault (source of borrowed funds)
+    #[account(
+        mut,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
+    )]
+    pub loan_vault: Account<'info, TokenAccount>,
+
+    /// Market's collateral token vault (receives swapped collateral)
+    #[account(
+        mut,
+        constraint = collateral_vault.key() == market.collateral_vault @ PelagoError::WrongCollateralVault,
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+
+    /// User's loan token account (receives borrowed funds, swap input)
+    #[account(
+        mut,
+        constraint = user_loan_account.mint == market.loan_token_mint @ PelagoError::InvalidVault,
+        constraint = user_loan_account.owner == user.key() @ PelagoError::Unauthorized,
+    )]
+    pub user_loan_account: Account<'info, TokenAccount>,
+
+    /// User's collateral token account (swap output, source of deposit)
+    #[account(
+        mut,
+        constraint = user_collateral_account.mint == market.collateral_token_mint @ PelagoError::InvalidVault,
+        constraint = user_collateral_account.owner == user.key() @ PelagoError::Unauthorized,
+    )]
+    pub user_collateral_account: Account<'info, TokenAccount>,
+
+    /// User wallet (signer)
+    pub user: Signer<'info>,
+
+    /// Swap program invoked as the callback
+    /// CHECK: Arbitrary swap program chosen by the user; only the collateral
+    /// actually received is credited. The swap cannot re-enter this program:
+    /// the runtime rejects indirect reentrancy (Pelago → swap → Pelago), and
+    /// the key check below rules out Pelago as its own swap program.
+    #[account(
+        executable,
+        constraint = swap_program.key() != crate::ID @ PelagoError::Unauthorized,
+    )]
+    pub swap_program: UncheckedAccount<'info>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler for leverage instruction
+///
+/// **Processing Steps:**
+/// 1. Validate inputs
+/// 2. Accrue interest
+/// 3. Record the borrow (virtual shares, rounding UP)
+/// 4. Transfer borrowed loan tokens to the user
+/// 5. Invoke the swap callback and measure collateral received
+/// 6. Deposit received collateral into the collateral vault
+/// 7. Check health and max leverage once
+///
+/// **Parameters:**
+/// - `borrow_assets`: Loan tokens to borrow and swap
+/// - `min_collateral_out`: Minimum collateral the swap must deliver
+/// - `swap_data`: Instruction data forwarded to `swap_program`
+///
+/// **Errors:**
+/// - ZeroAmount: borrow_assets == 0
+/// - PositionFrozen: position is under a compliance hold
+/// - NoLiquidity: market fully utilized
+/// - InsufficientLiquidity: not enough liquidity to borrow
+/// - SlippageExceeded: swap delivered less than `min_collateral_out`
+/// - CollateralCapExceeded: swapped collateral exceeds the market's collateral cap
+/// - InsufficientCollateral: final position is unhealthy
+/// - LeverageTooHigh: final leverage exceeds the LLTV-derived maximum
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Leverage<'info>>,
+    borrow_assets: u64,
+    min_collateral_out: u64,
+    swap_data: Vec<u8>,
+) -> Result<()> {
+    // Step 1: Validate inputs
+    require!(borrow_assets > 0, PelagoError::ZeroAmount);
+
+    let market = &mut ctx.accounts.market;
+    require!(!market.paused, PelagoError::MarketPaused);
+    let user_position = &mut ctx.accounts.user_position;
+    user_position.check_not_frozen()?;
+
+    // Step 2: Accrue interest before any calculation
+    accrue_interest(market)?;
+    check_auto_pause(market)?;
+    enforce_borrow_cooldown(market, user_position, Clock::get()?.unix_timestamp)?;
+
+    // Step 3: Record the borrow (rounding UP → favors protocol)
+    let borrow_shares = to_shares_up(
+        borrow_assets,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+
+    check_available_liquidity(market, borrow_assets)?;
+
+    let (fee, fee_shares) = record_borrow(market, user_position, borrow_assets, borrow_shares)?;
+    check_borrow_caps(market)?;
+    check_borrow_share_ratio(market)?;
+    check_market_invariants(market)?;
+
+    // Step 4: Transfer borrowed loan tokens to the user (PDA signs)
+    let seeds = &[
+        Market::SEED_PREFIX,
+        market.loan_token_mint.as_ref(),
+        market.collateral_token_mint.as_ref(),
+        &[market.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let transfer_accounts = Transfer {
+        from: ctx.accounts.loan_vault.to_account_info(),
+        to: ctx.accounts.user_loan_account.to_account_info(),
+        authority: market.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        transfer_accounts,
+        signer_seeds,
+    );
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || token::transfer(cpi_ctx, borrow_assets))?
+        .require_decrease(borrow_assets)?;
+
+    // Step 5: Invoke the swap callback and measure collateral received
+    let swap_ix = Instruction {
+        program_id: ctx.accounts.swap_program.key(),
+        accounts: ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: swap_data,
+    };
+    let mut swap_account_infos = ctx.remaining_accounts.to_vec();
+    swap_account_infos.push(ctx.accounts.swap_program.to_account_info());
+    let collateral_delta = snapshot_balance_delta(
+        &mut ctx.accounts.user_collateral_account,
+        || Ok(invoke(&swap_ix, &swap_account_infos)?),
+    )?;
+    let collateral_received = collateral_delta
+        .increase()
+        .ok_or(PelagoError::SlippageExceeded)?;
+
+    require!(
+        collateral_received > 0 && collateral_received >= min_collateral_out,
+        PelagoError::SlippageExceeded
+    );
+
+    // Step 6: Deposit received collateral into the collateral vault
+    let transfer_accounts = Transfer {
+        from: ctx.accounts.user_collateral_account.to_account_info(),
+        to: ctx.accounts.collateral_vault.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        transfer_accounts,
+    );
+    snapshot_balance_delta(&mut ctx.accounts.collateral_vault, || {
+        token::transfer(cpi_ctx, collateral_received)
+    })?
+    .require_increase(collateral_received)?;
+
+    user_position.collateral_amount = user_position
+        .collateral_amount
+        .checked_add(collateral_received)
+        .ok_or(PelagoError::MathOverflow)?;
+    market.total_collateral = market
+        .total_collateral
+        .checked_add(collateral_received)
+        .ok_or(PelagoError::MathOverflow)?;
+    check_collateral_cap(market)?;
+
+    // Step 7: Health and leverage checks on the final position
+    check_health_p1(market, user_position)?;
+    check_position_borrow_limit(market, user_position)?;
+
+    let collateral_value = collateral_value_usd(market, user_position.collateral_amount)?;
+    let borrow_value = to_assets_up(
+        user_position.borrow_shares,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+    let leverage = leverage_wad(collateral_value, borrow_value)?;
+    require!(
+        leverage <= max_leverage_wad(market.lltv)?,
+        PelagoError::LeverageTooHigh
+    );
+
+    msg!(
+        "Leverage success: user={}, borrowed={}, collateral_received={}, leverage_wad={}",
+        user_position.user,
+        borrow_assets,
+        collateral_received,
+        leverage
+    );
+
+    // Record position activity for dormancy tracking
+    user_position.last_activity = Clock::get()?.unix_timestamp;
+
+    emit!(LeverageEvent {
+        market: market.key(),
+        user: ctx.accounts.user.key(),
+        borrow_assets,
+        borrow_shares,
+        fee,
+        fee_shares,
+        collateral_received,
+        leverage_wad: leverage,
+    });
+
+    Ok(())
+}
+
+/// Collateral value in loan token units at the market's collateral price
+fn collateral_value_usd(market: &Market, collateral_amount: u64) -> Result<u128> {
+    Ok((collateral_amount as u128)
+        .checked_mul(collateral_price(market)? as u128)
+        .ok_or(PelagoError::MathOverflow)?
+        .checked_div(PRICE_PRECISION as u128)
+        .ok_or(PelagoError::MathOverflow)?)
+}
+
+/// Position leverage in WAD
+///
+/// **Formula:**
+/// ```text
+/// leverage = collateral_value / (collateral_value - borrow_value)
+/// ```
+///
+/// **Rounding:** UP (conservative against the max leverage bound)
+///
+/// **Errors:**
+/// - LeverageTooHigh: Position has no equity (debt ≥ collateral value)
+pub fn leverage_wad(collateral_value: u128, borrow_value: u64) -> Result<u128> {
+    let equity = collateral_value
+        .checked_sub(borrow_value as u128)
+        .filter(|equity| *equity > 0)
+        .ok_or(PelagoError::LeverageTooHigh)?;
+
+    let numerator = collateral_value
+        .checked_mul(WAD)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    Ok(numerator
+        .checked_add(equity - 1)
+        .ok_or(PelagoError::MathOverflow)?
+        / equity)
+}
+
+/// Maximum leverage allowed by an LLTV, in WAD
+///
+/// **Formula:**
+/// ```text
+/// max_leverage = LLTV_PRECISION / (LLTV_PRECISION - lltv)
+/// ```
+///
+/// A 100% LLTV has no finite bound, so `u128::MAX` is returned.
+pub fn max_leverage_wad(lltv: u64) -> Result<u128> {
+    let headroom = LLTV_PRECISION.saturating_sub(lltv);
+    if headroom == 0 {
+        return Ok(u128::MAX);
+    }
+
+    Ok((LLTV_PRECISION as u128)
+        .checked_mul(WAD)
+        .ok_or(PelagoError::MathOverflow)?
+        / headroom as u128)
+}
+
+/// Event emitted on successful leverage
+#[event]
+pub struct LeverageEvent {
+    /// Market public key
+    pub market: Pubkey,
+
+    /// User public key
+    pub user: Pubkey,
+
+    /// Loan assets borrowed and swapped
+    pub borrow_assets: u64,
+
+    /// Borrow shares issued for `borrow_assets`
+    pub borrow_shares: u64,
+
+    /// Origination fee added to the debt
+    pub fee: u64,
+
+    /// Borrow shares issued for the fee
+    pub fee_shares: u64,
+
+    /// Collateral received from the swap and deposited
+    pub collateral_received: u64,
+
+    /// Final position leverage (WAD)
+    pub leverage_wad: u128,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_leverage_from_lltv() {
+        assert_eq!(max_leverage_wad(80_000_000).unwrap(), 5 * WAD);
+        assert_eq!(max_leverage_wad(50_000_000).unwrap(), 2 * WAD);
+        assert_eq!(max_leverage_wad(LLTV_PRECISION).unwrap(), u128::MAX);
+    }
+
+    #[test]
+    fn test_mock_swap_reaches_target_leverage() {
+        // Start: 10 SOL collateral (1000 USDC). Borrow 2000 USDC and swap at
+        // the fixed oracle price (mock swap: 1 USDC unit → 10 lamports)
+        let initial_collateral = 10_000_000_000u64;
+        let borrow_assets = 2_000_000_000u64;
+        let swapped_collateral = borrow_assets * 10;
+
+        let collateral_value = collateral_value_usd(&Market::default(), initial_collateral + swapped_collateral).unwrap();
+        assert_eq!(collateral_value, 3_000_000_000);
+
+        // 3000 collateral / 1000 equity = 3x, below the 5x max at 80% LLTV
+        let leverage = leverage_wad(collateral_value, borrow_assets).unwrap();
+        assert_eq!(leverage, 3 * WAD);
+        assert!(leverage <= max_leverage_wad(80_000_000).unwrap());
+    }
+
+    #[test]
+    fn test_leverage_without_equity_rejected() {
+        assert_eq!(
+            leverage_wad(1_000_000_000, 1_000_000_000).unwrap_err(),
+            PelagoError::LeverageTooHigh.into()
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Liquidate Instruction
+//!
+//! Liquidates one unhealthy position with a repay chosen by the liquidator,
+//! up to the close factor. The liquidator pays loan tokens and receives
+//! the matching collateral plus the liquidation bonus.
+//!
+//! **Edge Cases:**
+//! - A repay that would leave dust debt closes the whole debt
+//! - Seized collateral is capped at the position's collateral, and the
+//!   repay scaled down to match
+//!
+//! See `utils::liquidation::compute_liquidation_for_repay` for the math.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::error::PelagoError;
+use crate::instructions::batch_liquidate::LiquidateEvent;
+use crate::state::{Market, UserPosition};
+use crate::utils::interest::accrue_interest;
+use crate::utils::invariants::check_recovery_invariants;
+use crate::utils::liquidation::{
+    apply_liquidation, check_min_liquidation, check_min_seize, check_self_liquidation,
+    compute_liquidation_for_repay, is_liquidatable, liquidation_bonus, position_health,
+    PositionHealthEvent,
+};
+use crate::utils::oracle::check_price_freshness;
+use crate::utils::vault_snapshot::snapshot_balance_delta;
+
+/// Liquidate one unhealthy position, repaying up to a chosen amount
+///
+/// Like `batch_liquidate`, stays open while the market is paused.
+#[derive(Accounts)]
+pub struct Liquidate<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Borrower's position in this market
+    #[account(
+        mut,
+        constraint = user_position.market == market.key() @ PelagoError::InvalidParameter,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Market's loan token vault (receives repaid debt)
+    #[account(
+        mut,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
+    )]
+    pub loan_vault: Account<'info, TokenAccount>,
+
+    /// Market's collateral token vault (source of seized collateral)
+    #[account(
+        mut,
+        constraint = collateral_vault.key() == market.collateral_vault @ PelagoError::WrongCollateralVault,
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+
+    /// Liquidator's loan token account (pays the repaid debt)
+    #[account(mut)]
+    pub liquidator_loan_account: Account<'info, TokenAccount>,
+
+    /// Liquidator's collateral token account (receives seized collateral)
+    #[account(mut)]
+    pub liquidator_collateral_account: Account<'info, TokenAccount>,
+
+    /// Liquidator wallet (signer)
+    pub liquidator: Signer<'info>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler for liquidate instruction
+///
+/// **Processing Steps:**
+/// 1. Accrue interest and check the price against
+///    `liquidation_max_staleness_secs`
+/// 2. Reject healthy positions
+/// 3. Size the repay: at most `repay_assets` and the close factor, the whole
+///    debt if the rest would be dust, capped by the position's collateral
+/// 4. Apply the liquidation; partial liquidations must repay at least
+///    `min_liquidation_assets`
+/// 5. Check the seized collateral against `min_seize`
+/// 6. Transfer repaid loan tokens in and seized collateral out
+///
+/// **Errors:**
+/// - InvalidParameter: Position from another market
+/// - PositionHealthy: The position is within its borrowing power
+/// - SelfLiquidationDisallowed: The liquidator owns the position and the
+///   market disallows self-liquidation
+/// - ZeroAmount: The repay is too small to burn a share or seize collateral
+/// - LiquidationTooSmall: A partial liquidation repays less than the
+///   market's `min_liquidation_assets`
+/// - SlippageExceeded: Seized collateral is below `min_seize`
+/// - StaleOracle: Manual price older than `liquidation_max_staleness_secs`
+/// - MathOverflow: Calculation overflow
+pub fn handler(ctx: Context<Liquidate>, repay_assets: u64, min_seize: u64) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    let position = &mut ctx.accounts.user_position;
+    let market_key = market.key();
+
+    // Step 1: Accrue on a fresh enough price
+    accrue_interest(market)?;
+    let borrow_assets_before = market.total_borrow_assets;
+    check_price_freshness(
+        market,
+        Clock::get()?.unix_timestamp,
+        market.liquidation_max_staleness_secs,
+    )?;
+
+    // Step 2: Recompute health after accrual
+    require!(
+        is_liquidatable(market, position)?,
+        PelagoError::PositionHealthy
+    );
+    check_self_liquidation(market, position, &ctx.accounts.liquidator.key())?;
+
+    // Step 3-4: Size and apply the liquidation
+    let liquidation = compute_liquidation_for_repay(market, position, repay_assets)?
+        .ok_or(PelagoError::ZeroAmount)?;
+    check_min_liquidation(market, position, &liquidation)?;
+    let bonus_collateral = liquidation_bonus(market, &liquidation)?;
+    apply_liquidation(market, position, &liquidation)?;
+
+    emit!(LiquidateEvent {
+        market: market_key,
+        liquidator: ctx.accounts.liquidator.key(),
+        borrower: position.user,
+        repaid_assets: liquidation.repaid_assets,
+        repaid_shares: liquidation.repaid_shares,
+        seized_collateral: liquidation.seized_collateral,
+        bonus_collateral,
+        remaining_borrow_shares: position.borrow_shares,
+        remaining_collateral: position.collateral_amount,
+    });
+    let health = position_health(market, position)?;
+    emit!(PositionHealthEvent {
+        market: market_key,
+        user: position.user,
+        health_factor: health.health_factor,
+        collateral_value: health.collateral_value,
+        debt_value: health.debt_value,
+    });
+
+    check_recovery_invariants(market, borrow_assets_before)?;
+
+    msg!(
+        "Liquidated: user={}, health={}, repaid={}, seized={}, bonus={}",
+        position.user,
+        health.health_factor,
+        liquidation.repaid_assets,
+        liquidation.seized_collateral,
+        bonus_collateral
+    );
+
+    // Step 5: Slippage
+    check_min_seize(liquidation.seized_collateral, min_seize)?;
+
+    // Step 6: Transfers
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.liquidator_loan_account.to_account_info(),
+            to: ctx.accounts.loan_vault.to_account_info(),
+            authority: ctx.accounts.liquidator.to_account_info(),
+        },
+    );
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || {
+        token::transfer(cpi_ctx, liquidation.repaid_assets)
+    })?
+    .require_increase(liquidation.repaid_assets)?;
+
+    let seeds = &[
+        Market::SEED_PREFIX,
+        market.loan_token_mint.as_ref(),
+        market.collateral_token_mint.as_ref(),
+        &[market.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.collateral_vault.to_account_info(),
+            to: ctx.accounts.liquidator_collateral_account.to_account_info(),
+            authority: market.to_account_info(),
+        },
+        signer_seeds,
+    );
+    snapshot_balance_delta(&mut ctx.accounts.collateral_vault, || {
+        token::transfer(cpi_ctx, liquidation.seized_collateral)
+    })?
+    .require_decrease(liquidation.seized_collateral)?;
+
+    Ok(())
+}
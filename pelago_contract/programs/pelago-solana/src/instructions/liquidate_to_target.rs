@@ -0,0 +1,193 @@
+//! Liquidate To Target Instruction
+//!
+//! Partially liquidates one unhealthy position, repaying just enough debt
+//! to bring its health factor back to a caller-chosen target instead of
+//! the full close factor. Borrowers lose less collateral to the bonus,
+//! and keepers repay no more than needed.
+//!
+//! See `utils::liquidation::compute_liquidation_to_target` for the math.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::error::PelagoError;
+use crate::instructions::batch_liquidate::LiquidateEvent;
+use crate::state::{Market, UserPosition};
+use crate::utils::interest::accrue_interest;
+use crate::utils::invariants::check_recovery_invariants;
+use crate::utils::liquidation::{
+    apply_liquidation, check_min_liquidation, check_min_seize, check_self_liquidation,
+    compute_liquidation_to_target, liquidation_bonus, position_health, PositionHealthEvent,
+};
+use crate::utils::oracle::check_price_freshness;
+use crate::utils::vault_snapshot::snapshot_balance_delta;
+
+/// Liquidate one unhealthy position down to a target health factor
+///
+/// Like `batch_liquidate`, stays open while the market is paused.
+#[derive(Accounts)]
+pub struct LiquidateToTarget<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Borrower's position in this market
+    #[account(
+        mut,
+        constraint = user_position.market == market.key() @ PelagoError::InvalidParameter,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Market's loan token vault (receives repaid debt)
+    #[account(
+        mut,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
+    )]
+    pub loan_vault: Account<'info, TokenAccount>,
+
+    /// Market's collateral token vault (source of seized collateral)
+    #[account(
+        mut,
+        constraint = collateral_vault.key() == market.collateral_vault @ PelagoError::WrongCollateralVault,
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+
+    /// Liquidator's loan token account (pays the repaid debt)
+    #[account(mut)]
+    pub liquidator_loan_account: Account<'info, TokenAccount>,
+
+    /// Liquidator's collateral token account (receives seized collateral)
+    #[account(mut)]
+    pub liquidator_collateral_account: Account<'info, TokenAccount>,
+
+    /// Liquidator wallet (signer)
+    pub liquidator: Signer<'info>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler for liquidate_to_target instruction
+///
+/// **Processing Steps:**
+/// 1. Accrue interest and check the price against
+///    `liquidation_max_staleness_secs`
+/// 2. Solve for the repay restoring `target_health`, clamped to the close
+///    factor and the position's collateral
+/// 3. Apply the liquidation; partial liquidations must repay at least
+///    `min_liquidation_assets`
+/// 4. Check the seized collateral against `min_seize`
+/// 5. Transfer repaid loan tokens in and seized collateral out
+///
+/// **Errors:**
+/// - InvalidParameter: Position from another market, or a target not
+///   above 1.0 or the bonus-weighted LLTV
+/// - PositionHealthy: The position is within its borrowing power
+/// - SelfLiquidationDisallowed: The liquidator owns the position and the
+///   market disallows self-liquidation
+/// - LiquidationTooSmall: A partial liquidation repays less than the
+///   market's `min_liquidation_assets`
+/// - SlippageExceeded: Seized collateral is below `min_seize`
+/// - StaleOracle: Manual price older than `liquidation_max_staleness_secs`
+/// - MathOverflow: Calculation overflow
+pub fn handler(ctx: Context<LiquidateToTarget>, target_health: u64, min_seize: u64) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    let position = &mut ctx.accounts.user_position;
+    let market_key = market.key();
+
+    // Step 1: Accrue on a fresh enough price
+    accrue_interest(market)?;
+    let borrow_assets_before = market.total_borrow_assets;
+    check_price_freshness(
+        market,
+        Clock::get()?.unix_timestamp,
+        market.liquidation_max_staleness_secs,
+    )?;
+
+    check_self_liquidation(market, position, &ctx.accounts.liquidator.key())?;
+
+    // Step 2-3: Size and apply the liquidation
+    let liquidation = compute_liquidation_to_target(market, position, target_health)?
+        .ok_or(PelagoError::PositionHealthy)?;
+    check_min_liquidation(market, position, &liquidation)?;
+    apply_liquidation(market, position, &liquidation)?;
+
+    emit!(LiquidateEvent {
+        market: market_key,
+        liquidator: ctx.accounts.liquidator.key(),
+        borrower: position.user,
+        repaid_assets: liquidation.repaid_assets,
+        repaid_shares: liquidation.repaid_shares,
+        seized_collateral: liquidation.seized_collateral,
+        bonus_collateral: liquidation_bonus(market, &liquidation)?,
+        remaining_borrow_shares: position.borrow_shares,
+        remaining_collateral: position.collateral_amount,
+    });
+    let health = position_health(market, position)?;
+    emit!(PositionHealthEvent {
+        market: market_key,
+        user: position.user,
+        health_factor: health.health_factor,
+        collateral_value: health.collateral_value,
+        debt_value: health.debt_value,
+    });
+
+    check_recovery_invariants(market, borrow_assets_before)?;
+
+    msg!(
+        "Liquidated to target: user={}, target={}, health={}, repaid={}, seized={}",
+        position.user,
+        target_health,
+        health.health_factor,
+        liquidation.repaid_assets,
+        liquidation.seized_collateral
+    );
+
+    // Step 4: Slippage
+    check_min_seize(liquidation.seized_collateral, min_seize)?;
+
+    // Step 5: Transfers
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.liquidator_loan_account.to_account_info(),
+            to: ctx.accounts.loan_vault.to_account_info(),
+            authority: ctx.accounts.liquidator.to_account_info(),
+        },
+    );
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || {
+        token::transfer(cpi_ctx, liquidation.repaid_assets)
+    })?
+    .require_increase(liquidation.repaid_assets)?;
+
+    let seeds = &[
+        Market::SEED_PREFIX,
+        market.loan_token_mint.as_ref(),
+        market.collateral_token_mint.as_ref(),
+        &[market.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.collateral_vault.to_account_info(),
+            to: ctx.accounts.liquidator_collateral_account.to_account_info(),
+            authority: market.to_account_info(),
+        },
+        signer_seeds,
+    );
+    snapshot_balance_delta(&mut ctx.accounts.collateral_vault, || {
+        token::transfer(cpi_ctx, liquidation.seized_collateral)
+    })?
+    .require_decrease(liquidation.seized_collateral)?;
+
+    Ok(())
+}
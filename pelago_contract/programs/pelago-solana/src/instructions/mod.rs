@@ -1,3 +1,5 @@
+#![allow(ambiguous_glob_reexports)]
+
 pub mod initialize_market;
 pub mod supply;
 pub mod supply_collateral;
@@ -5,6 +7,75 @@ pub mod borrow;
 pub mod withdraw;
 pub mod withdraw_collateral;
 pub mod repay;
+pub mod leverage;
+pub mod get_last_activity;
+pub mod initialize_config;
+pub mod set_guardian;
+pub mod guardian_pause;
+pub mod set_paused;
+pub mod set_borrow_caps;
+pub mod set_interest_rounding;
+pub mod get_repay_amount;
+pub mod set_manual_price;
+pub mod set_max_positions;
+pub mod set_fee;
+pub mod resync_total_collateral;
+pub mod initialize_price_history;
+pub mod update_price_history;
+pub mod open_position;
+pub mod reset_accrual_clock;
+pub mod get_market_info;
+pub mod check_bad_debt;
+pub mod propose_lltv;
+pub mod apply_lltv;
+pub mod set_auto_pause_utilization;
+pub mod set_borrow_cooldown;
+pub mod batch_liquidate;
+pub mod get_constants;
+pub mod set_accrue_while_paused;
+pub mod get_user_markets;
+pub mod set_favor_user_on_supply;
+pub mod seed_market;
+pub mod set_price_bounds;
+pub mod set_protocol_config;
+pub mod set_max_accrual_interest;
+pub mod enable_lltv;
+pub mod disable_lltv;
+pub mod set_fee_kink;
+pub mod close_position;
+pub mod set_min_liquidation_assets;
+pub mod initialize_rate_snapshot;
+pub mod update_rate_snapshot;
+pub mod get_historical_apy;
+pub mod set_max_borrow_ratio;
+pub mod sweep_dust;
+pub mod set_liquidation_staleness;
+pub mod set_compound_period;
+pub mod get_liquidation_price;
+pub mod set_first_supply_dead_shares;
+pub mod initialize_rewards;
+pub mod set_reward_rate;
+pub mod claim_rewards;
+pub mod get_rate_per_second;
+pub mod set_min_initial_deposit;
+pub mod set_irm;
+pub mod liquidate_to_target;
+pub mod set_position_frozen;
+pub mod set_dust_shares_threshold;
+pub mod reset_market;
+pub mod set_disallow_self_liquidation;
+pub mod get_borrow_constraints;
+pub mod set_manager;
+pub mod quote;
+pub mod set_collateral_cap;
+pub mod preview_liquidation;
+pub mod liquidate;
+pub mod claim_fees;
+pub mod withdraw_reserves;
+pub mod set_vault_tolerance;
+pub mod skim_vault;
+#[cfg(feature = "test-utils")]
+pub mod set_last_update;
 
 pub use initialize_market::*;
 pub use supply::*;
@@ -13,3 +84,72 @@ pub use borrow::*;
 pub use withdraw::*;
 pub use withdraw_collateral::*;
 pub use repay::*;
+pub use leverage::*;
+pub use get_last_activity::*;
+pub use initialize_config::*;
+pub use set_guardian::*;
+pub use guardian_pause::*;
+pub use set_paused::*;
+pub use set_borrow_caps::*;
+pub use set_interest_rounding::*;
+pub use get_repay_amount::*;
+pub use set_manual_price::*;
+pub use set_max_positions::*;
+pub use set_fee::*;
+pub use resync_total_collateral::*;
+pub use initialize_price_history::*;
+pub use update_price_history::*;
+pub use open_position::*;
+pub use reset_accrual_clock::*;
+pub use get_market_info::*;
+pub use check_bad_debt::*;
+pub use propose_lltv::*;
+pub use apply_lltv::*;
+pub use set_auto_pause_utilization::*;
+pub use set_borrow_cooldown::*;
+pub use batch_liquidate::*;
+pub use get_constants::*;
+pub use set_accrue_while_paused::*;
+pub use get_user_markets::*;
+pub use set_favor_user_on_supply::*;
+pub use seed_market::*;
+pub use set_price_bounds::*;
+pub use set_protocol_config::*;
+pub use set_max_accrual_interest::*;
+pub use enable_lltv::*;
+pub use disable_lltv::*;
+pub use set_fee_kink::*;
+pub use close_position::*;
+pub use set_min_liquidation_assets::*;
+pub use initialize_rate_snapshot::*;
+pub use update_rate_snapshot::*;
+pub use get_historical_apy::*;
+pub use set_max_borrow_ratio::*;
+pub use sweep_dust::*;
+pub use set_liquidation_staleness::*;
+pub use set_compound_period::*;
+pub use get_liquidation_price::*;
+pub use set_first_supply_dead_shares::*;
+pub use initialize_rewards::*;
+pub use set_reward_rate::*;
+pub use claim_rewards::*;
+pub use get_rate_per_second::*;
+pub use set_min_initial_deposit::*;
+pub use set_irm::*;
+pub use liquidate_to_target::*;
+pub use set_position_frozen::*;
+pub use set_dust_shares_threshold::*;
+pub use reset_market::*;
+pub use set_disallow_self_liquidation::*;
+pub use get_borrow_constraints::*;
+pub use set_manager::*;
+pub use quote::*;
+pub use set_collateral_cap::*;
+pub use preview_liquidation::*;
+pub use liquidate::*;
+pub use claim_fees::*;
+pub use withdraw_reserves::*;
+pub use set_vault_tolerance::*;
+pub use skim_vault::*;
+#[cfg(feature = "test-utils")]
+pub use set_last_update::*;
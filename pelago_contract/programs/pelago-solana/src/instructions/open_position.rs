@@ -0,0 +1,256 @@
+//! Open Position Instruction
+//!
+//! Onboarding shortcut: supplies collateral and borrows against it in a
+//! single transaction. The `UserPosition` is created once, interest is
+//! accrued once, and health is checked once on the final position.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::error::PelagoError;
+use crate::instructions::borrow::{
+    check_auto_pause, check_available_liquidity, check_borrow_caps, check_borrow_share_ratio,
+    check_position_borrow_limit, enforce_borrow_cooldown, record_borrow, BorrowEvent,
+};
+use crate::instructions::supply_collateral::{check_collateral_cap, SupplyCollateralEvent};
+use crate::instructions::withdraw_collateral::check_health_p1;
+use crate::state::{Market, UserPosition, UserRegistry};
+use crate::utils::interest::accrue_interest;
+use crate::utils::invariants::check_market_invariants;
+use crate::utils::shares_math::to_shares_up;
+use crate::utils::vault_snapshot::snapshot_balance_delta;
+
+/// Supply collateral and borrow in one instruction
+///
+/// **State Changes:**
+/// - user_position.collateral_amount += collateral_amount
+/// - market.total_collateral += collateral_amount
+/// - Borrow state updated as in `borrow` (incl. origination fee)
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    /// Market account (must be initialized)
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// User position PDA (created if first interaction, otherwise loaded)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::LEN,
+        seeds = [
+            UserPosition::SEED_PREFIX,
+            market.key().as_ref(),
+            user.key().as_ref(),
+        ],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// User's market registry PDA (created on the user's first position)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserRegistry::LEN,
+        seeds = [
+            UserRegistry::SEED_PREFIX,
+            user.key().as_ref(),
+        ],
+        bump
+    )]
+    pub user_registry: Account<'info, UserRegistry>,
+
+    /// Market's collateral token vault (receives the deposit)
+    #[account(
+        mut,
+        constraint = collateral_vault.key() == market.collateral_vault @ PelagoError::WrongCollateralVault,
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+
+    /// Market's loan token vault (source of borrowed funds)
+    #[account(
+        mut,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
+    )]
+    pub loan_vault: Account<'info, TokenAccount>,
+
+    /// User's collateral token account (source of deposit)
+    #[account(
+        mut,
+        constraint = user_collateral_account.mint == market.collateral_token_mint @ PelagoError::UninitializedMarket,
+    )]
+    pub user_collateral_account: Account<'info, TokenAccount>,
+
+    /// User's loan token account (destination of borrowed funds)
+    #[account(
+        mut,
+        constraint = user_loan_account.mint == market.loan_token_mint @ PelagoError::UninitializedMarket,
+    )]
+    pub user_loan_account: Account<'info, TokenAccount>,
+
+    /// User wallet (signer, pays for position creation)
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Solana system program (for PDA creation if needed)
+    pub system_program: Program<'info, System>,
+
+    /// SPL token program (for token transfers)
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler for open_position instruction
+///
+/// **Operation Flow:**
+/// 1. Validate both legs (non-zero amounts, market not paused)
+/// 2. Initialize UserPosition if first interaction
+/// 3. Accrue interest once
+/// 4. Deposit collateral
+/// 5. Record the borrow (shares rounded up, origination fee)
+/// 6. Liquidity, cap and health checks on the final position
+/// 7. Transfer borrowed loan tokens to the user
+///
+/// **Errors:**
+/// - ZeroAmount: collateral_amount == 0 or borrow_assets == 0
+/// - MarketPaused: market is paused
+/// - PositionFrozen: position is under a compliance hold
+/// - NoLiquidity: market fully utilized
+/// - InsufficientLiquidity: not enough liquidity for the borrow
+/// - BorrowCapExceeded / PositionBorrowLimit: caps exceeded
+/// - CollateralCapExceeded: deposit exceeds the market's collateral cap
+/// - InsufficientCollateral: final position is unhealthy
+/// - MathOverflow: Calculation overflow
+pub fn handler(ctx: Context<OpenPosition>, collateral_amount: u64, borrow_assets: u64) -> Result<()> {
+    // Step 1: Validate both legs
+    require!(collateral_amount > 0, PelagoError::ZeroAmount);
+    require!(borrow_assets > 0, PelagoError::ZeroAmount);
+
+    let market = &mut ctx.accounts.market;
+    require!(!market.paused, PelagoError::MarketPaused);
+    let user_position = &mut ctx.accounts.user_position;
+    user_position.check_not_frozen()?;
+
+    // Step 2: Initialize user position fields if this is first interaction
+    if user_position.user == Pubkey::default() {
+        user_position.open(ctx.accounts.user.key(), market.key(), ctx.bumps.user_position)?;
+        market.register_position()?;
+
+        let user_registry = &mut ctx.accounts.user_registry;
+        if user_registry.user == Pubkey::default() {
+            user_registry.user = ctx.accounts.user.key();
+            user_registry.bump = ctx.bumps.user_registry;
+        }
+        user_registry.record_market(market.key())?;
+    }
+
+    // Step 3: Accrue interest once for both legs
+    accrue_interest(market)?;
+    check_auto_pause(market)?;
+    enforce_borrow_cooldown(market, user_position, Clock::get()?.unix_timestamp)?;
+
+    // Step 4: Deposit collateral
+    let transfer_accounts = Transfer {
+        from: ctx.accounts.user_collateral_account.to_account_info(),
+        to: ctx.accounts.collateral_vault.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        transfer_accounts,
+    );
+    snapshot_balance_delta(&mut ctx.accounts.collateral_vault, || {
+        token::transfer(cpi_ctx, collateral_amount)
+    })?
+    .require_increase(collateral_amount)?;
+
+    user_position.collateral_amount = user_position
+        .collateral_amount
+        .checked_add(collateral_amount)
+        .ok_or(PelagoError::MathOverflow)?;
+    market.total_collateral = market
+        .total_collateral
+        .checked_add(collateral_amount)
+        .ok_or(PelagoError::MathOverflow)?;
+    check_collateral_cap(market)?;
+
+    // Step 5: Record the borrow
+    let borrow_shares = to_shares_up(
+        borrow_assets,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+
+    check_available_liquidity(market, borrow_assets)?;
+
+    let (fee, fee_shares) = record_borrow(market, user_position, borrow_assets, borrow_shares)?;
+
+    // Step 6: Checks on the final position
+    check_health_p1(market, user_position)?;
+    check_position_borrow_limit(market, user_position)?;
+    require!(
+        market.total_borrow_assets <= market.total_supply_assets,
+        PelagoError::InsufficientLiquidity
+    );
+    check_borrow_caps(market)?;
+    check_borrow_share_ratio(market)?;
+    check_market_invariants(market)?;
+
+    // Step 7: Transfer borrowed loan tokens to the user (PDA signs)
+    let seeds = &[
+        Market::SEED_PREFIX,
+        market.loan_token_mint.as_ref(),
+        market.collateral_token_mint.as_ref(),
+        &[market.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let transfer_accounts = Transfer {
+        from: ctx.accounts.loan_vault.to_account_info(),
+        to: ctx.accounts.user_loan_account.to_account_info(),
+        authority: market.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        transfer_accounts,
+        signer_seeds,
+    );
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || token::transfer(cpi_ctx, borrow_assets))?
+        .require_decrease(borrow_assets)?;
+
+    user_position.last_activity = Clock::get()?.unix_timestamp;
+
+    msg!(
+        "Open position: user={}, collateral={}, borrowed={}, borrow_shares={}",
+        user_position.user,
+        collateral_amount,
+        borrow_assets,
+        user_position.borrow_shares
+    );
+
+    emit!(SupplyCollateralEvent {
+        market: market.key(),
+        user: user_position.user,
+        amount: collateral_amount,
+        collateral_amount: user_position.collateral_amount,
+        total_collateral: market.total_collateral,
+    });
+    emit!(BorrowEvent {
+        market: market.key(),
+        user: user_position.user,
+        assets: borrow_assets,
+        shares: borrow_shares,
+        fee,
+        fee_shares,
+        total_borrow_shares: market.total_borrow_shares,
+        total_borrow_assets: market.total_borrow_assets,
+    });
+
+    Ok(())
+}
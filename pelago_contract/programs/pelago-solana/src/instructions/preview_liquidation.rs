@@ -0,0 +1,255 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::PRICE_PRECISION;
+use crate::error::PelagoError;
+use crate::state::{Market, UserPosition};
+use crate::utils::interest::project_interest_at;
+use crate::utils::liquidation::{
+    apply_liquidation, check_min_liquidation, compute_liquidation_for_repay, position_health,
+};
+use crate::utils::oracle::{check_price_freshness, collateral_price};
+
+/// Simulate a liquidation of one position without executing it
+///
+/// Read-only view: interest is accrued and the liquidation applied on local
+/// copies of the market and position, so neither account is modified and no
+/// tokens move.
+#[derive(Accounts)]
+pub struct PreviewLiquidation<'info> {
+    /// Market account
+    #[account(
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Borrower's position in this market
+    #[account(
+        constraint = user_position.market == market.key() @ PelagoError::InvalidParameter,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+}
+
+/// Outcome returned by `preview_liquidation`
+///
+/// All zero, with `liquidatable == false`, when the position is healthy or
+/// the repay is too small to liquidate anything.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LiquidationPreview {
+    /// Whether the repay would liquidate the position
+    pub liquidatable: bool,
+
+    /// Loan tokens the liquidator would pay
+    pub repaid_assets: u64,
+
+    /// Borrow shares that would be burned
+    pub repaid_shares: u64,
+
+    /// Collateral the liquidator would receive (incl. bonus)
+    pub seized_collateral: u64,
+
+    /// Seized collateral valued at the collateral price (loan token base units)
+    pub seized_value: u64,
+
+    /// Liquidator profit: `seized_value - repaid_assets` (loan token base units)
+    pub liquidator_profit: u64,
+
+    /// Health factor before the liquidation (precision: 1e8)
+    pub health_factor_before: u64,
+
+    /// Health factor after the liquidation (precision: 1e8, u64::MAX = no debt)
+    pub health_factor_after: u64,
+
+    /// Borrow shares left on the position
+    pub remaining_borrow_shares: u64,
+
+    /// Collateral left on the position
+    pub remaining_collateral: u64,
+
+    /// Whether the repay satisfies the market's `min_liquidation_assets`
+    pub meets_min_liquidation: bool,
+
+    /// Whether the price passes `liquidation_max_staleness_secs`
+    pub price_fresh: bool,
+}
+
+/// Handler for preview_liquidation view
+///
+/// **Returns:** `LiquidationPreview` (via return data)
+pub fn handler(ctx: Context<PreviewLiquidation>, repay_assets: u64) -> Result<LiquidationPreview> {
+    let now = Clock::get()?.unix_timestamp;
+    let preview = preview_liquidation_at(
+        &ctx.accounts.market,
+        &ctx.accounts.user_position,
+        repay_assets,
+        now,
+    )?;
+
+    msg!(
+        "Liquidation preview: user={}, liquidatable={}, repaid={}, seized={}, profit={}",
+        ctx.accounts.user_position.user,
+        preview.liquidatable,
+        preview.repaid_assets,
+        preview.seized_collateral,
+        preview.liquidator_profit
+    );
+
+    Ok(preview)
+}
+
+/// Liquidation outcome for a repay of at most `repay_assets` once interest is
+/// accrued up to `timestamp`
+///
+/// Sized exactly as `liquidate` would: the repay is clamped to the close
+/// factor (`u64::MAX` previews the full close factor, as `batch_liquidate`
+/// repays). See `compute_liquidation_for_repay`.
+///
+/// **Errors:**
+/// - OraclePriceOutOfBounds: Price outside the market's sanity band
+/// - MathOverflow: Calculation overflow
+pub fn preview_liquidation_at(
+    market: &Market,
+    position: &UserPosition,
+    repay_assets: u64,
+    timestamp: i64,
+) -> Result<LiquidationPreview> {
+    let mut market = market.clone();
+    let mut position = position.clone();
+    project_interest_at(&mut market, timestamp)?;
+
+    let Some(liquidation) = compute_liquidation_for_repay(&market, &position, repay_assets)? else {
+        return Ok(LiquidationPreview::default());
+    };
+
+    let before = position_health(&market, &position)?;
+    let meets_min_liquidation = check_min_liquidation(&market, &position, &liquidation).is_ok();
+    let price_fresh =
+        check_price_freshness(&market, timestamp, market.liquidation_max_staleness_secs).is_ok();
+
+    let seized_value = liquidation.seized_collateral as u128 * collateral_price(&market)? as u128
+        / PRICE_PRECISION as u128;
+    let seized_value = u64::try_from(seized_value).map_err(|_| PelagoError::MathOverflow)?;
+
+    apply_liquidation(&mut market, &mut position, &liquidation)?;
+    let after = position_health(&market, &position)?;
+
+    Ok(LiquidationPreview {
+        liquidatable: true,
+        repaid_assets: liquidation.repaid_assets,
+        repaid_shares: liquidation.repaid_shares,
+        seized_collateral: liquidation.seized_collateral,
+        seized_value,
+        liquidator_profit: seized_value.saturating_sub(liquidation.repaid_assets),
+        health_factor_before: before.health_factor,
+        health_factor_after: after.health_factor,
+        remaining_borrow_shares: position.borrow_shares,
+        remaining_collateral: position.collateral_amount,
+        meets_min_liquidation,
+        price_fresh,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::interest::accrue_interest_at;
+    use crate::utils::liquidation::compute_liquidation;
+    use crate::utils::shares_math::to_shares_up;
+
+    const NOW: i64 = 1_700_000_000;
+
+    /// 10 SOL at 85 USDC/SOL against 700 USDC of debt at 80% LLTV
+    fn unhealthy_borrower() -> (Market, UserPosition) {
+        let borrow_shares = to_shares_up(700_000_000, 0, 0).unwrap();
+        let market = Market {
+            total_supply_assets: 2_000_000_000,
+            total_borrow_assets: 700_000_000,
+            total_borrow_shares: borrow_shares,
+            total_collateral: 10_000_000_000,
+            lltv: 80_000_000,
+            fixed_price: 85_000,
+            last_update: NOW - 7 * 86_400,
+            ..Default::default()
+        };
+        let position = UserPosition {
+            borrow_shares,
+            collateral_amount: 10_000_000_000,
+            ..Default::default()
+        };
+        (market, position)
+    }
+
+    #[test]
+    fn test_preview_matches_executed_liquidation() {
+        let (market, position) = unhealthy_borrower();
+        let preview = preview_liquidation_at(&market, &position, u64::MAX, NOW).unwrap();
+
+        // What batch_liquidate does after its own accrual
+        let (mut executed_market, mut executed) = (market.clone(), position.clone());
+        accrue_interest_at(&mut executed_market, NOW).unwrap();
+        let liquidation = compute_liquidation(&executed_market, &executed)
+            .unwrap()
+            .unwrap();
+        apply_liquidation(&mut executed_market, &mut executed, &liquidation).unwrap();
+
+        assert!(preview.liquidatable);
+        assert_eq!(preview.repaid_assets, liquidation.repaid_assets);
+        assert_eq!(preview.repaid_shares, liquidation.repaid_shares);
+        assert_eq!(preview.seized_collateral, liquidation.seized_collateral);
+        assert_eq!(preview.remaining_borrow_shares, executed.borrow_shares);
+        assert_eq!(preview.remaining_collateral, executed.collateral_amount);
+        assert_eq!(
+            preview.health_factor_after,
+            position_health(&executed_market, &executed)
+                .unwrap()
+                .health_factor
+        );
+        assert!(preview.health_factor_before < 100_000_000);
+        assert!(preview.health_factor_after > preview.health_factor_before);
+
+        // The 5% bonus, less rounding
+        assert_eq!(
+            preview.liquidator_profit,
+            preview.seized_value - preview.repaid_assets
+        );
+        assert!(preview.liquidator_profit <= preview.repaid_assets * 5 / 100);
+        assert!(preview.liquidator_profit >= preview.repaid_assets * 5 / 100 - 1);
+        assert!(preview.meets_min_liquidation && preview.price_fresh);
+
+        assert_eq!(
+            market.last_update,
+            NOW - 7 * 86_400,
+            "view must not mutate the market"
+        );
+        assert_eq!(position.collateral_amount, 10_000_000_000);
+    }
+
+    #[test]
+    fn test_preview_clamps_repay_to_close_factor() {
+        let (market, position) = unhealthy_borrower();
+        let full = preview_liquidation_at(&market, &position, u64::MAX, NOW).unwrap();
+
+        let partial = preview_liquidation_at(&market, &position, 100_000_000, NOW).unwrap();
+        assert!(partial.repaid_assets <= 100_000_000);
+        assert!(partial.repaid_assets >= 99_999_999);
+        assert!(partial.seized_collateral < full.seized_collateral);
+        assert!(partial.health_factor_after < full.health_factor_after);
+
+        let capped = preview_liquidation_at(&market, &position, 600_000_000, NOW).unwrap();
+        assert_eq!(capped, full);
+    }
+
+    #[test]
+    fn test_healthy_position_previews_zeroed_result() {
+        let (mut market, position) = unhealthy_borrower();
+        market.fixed_price = 100_000;
+
+        let preview = preview_liquidation_at(&market, &position, u64::MAX, NOW).unwrap();
+        assert_eq!(preview, LiquidationPreview::default());
+        assert!(!preview.liquidatable);
+    }
+}
@@ -0,0 +1,95 @@
+//! Propose LLTV Instruction
+//!
+//! First phase of a timelocked LLTV change. The proposal is stored on the
+//! market and can only be committed with `apply_lltv` once
+//! `market.lltv_timelock` seconds have passed, giving borrowers time to
+//! react to a lower LLTV. Proposing again replaces the pending value and
+//! restarts the timelock.
+
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_LLTV;
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Propose a new LLTV for a market
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct ProposeLltv<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for propose_lltv instruction
+///
+/// **State Changes:**
+/// - market.pending_lltv = `lltv`
+/// - market.lltv_effective_at = now + market.lltv_timelock
+///
+/// **Errors:**
+/// - InvalidLltv: lltv == 0 or lltv > MAX_LLTV
+pub fn handler(ctx: Context<ProposeLltv>, lltv: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let market = &mut ctx.accounts.market;
+
+    propose_lltv_at(market, lltv, now)?;
+
+    msg!(
+        "LLTV proposed: market={}, current={}, pending={}, effective_at={}",
+        market.key(),
+        market.lltv,
+        market.pending_lltv,
+        market.lltv_effective_at
+    );
+
+    emit!(LltvProposedEvent {
+        market: market.key(),
+        current_lltv: market.lltv,
+        pending_lltv: market.pending_lltv,
+        effective_at: market.lltv_effective_at,
+    });
+
+    Ok(())
+}
+
+/// Stores `lltv` as pending, effective `lltv_timelock` seconds after `now`
+pub fn propose_lltv_at(market: &mut Market, lltv: u64, now: i64) -> Result<()> {
+    require!(lltv > 0 && lltv <= MAX_LLTV, PelagoError::InvalidLltv);
+
+    market.pending_lltv = lltv;
+    market.lltv_effective_at = now
+        .checked_add(market.lltv_timelock)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Event emitted when an LLTV change is proposed
+#[event]
+pub struct LltvProposedEvent {
+    /// Market public key
+    pub market: Pubkey,
+
+    /// LLTV in force until the change is applied
+    pub current_lltv: u64,
+
+    /// Proposed LLTV
+    pub pending_lltv: u64,
+
+    /// Earliest timestamp at which the change can be applied
+    pub effective_at: i64,
+}
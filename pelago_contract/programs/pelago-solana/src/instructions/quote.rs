@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+
+use crate::instructions::supply::first_supply_split;
+use crate::state::Market;
+use crate::utils::interest::project_interest_at;
+use crate::utils::shares_math::{to_shares_down, to_shares_up};
+
+/// Quote the shares an asset-mode operation would produce
+///
+/// Read-only view: interest is accrued on a local copy of the market, so the
+/// market account itself is never modified.
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    /// Market account
+    #[account(
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Side of the market whose shares are quoted
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteSide {
+    /// Supply shares (supply, withdraw)
+    Supply,
+
+    /// Borrow shares (borrow, repay)
+    Borrow,
+}
+
+/// Rounding direction of the assets → shares conversion
+///
+/// The handlers round in the protocol's favor: supply and repay round
+/// down, borrow and withdraw round up. Markets with
+/// `favor_user_on_supply` round supply up.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteRounding {
+    /// `to_shares_up`
+    Up,
+
+    /// `to_shares_down`
+    Down,
+}
+
+/// Handler for quote view
+///
+/// **Returns:** Shares for `assets` (via return data)
+pub fn handler(
+    ctx: Context<Quote>,
+    side: QuoteSide,
+    assets: u64,
+    round: QuoteRounding,
+) -> Result<u64> {
+    let now = Clock::get()?.unix_timestamp;
+    let shares = quote_shares_at(&ctx.accounts.market, side, assets, round, now)?;
+
+    msg!(
+        "Quote: side={:?}, assets={}, round={:?}, shares={}",
+        side,
+        assets,
+        round,
+        shares
+    );
+
+    Ok(shares)
+}
+
+/// Shares for `assets` on `side` once interest is accrued up to `timestamp`
+///
+/// Uses the same conversions as the handlers. On the first supply into a
+/// market, the dead shares locked out of it are deducted, so the quote is
+/// what the position receives.
+///
+/// **Errors:**
+/// - ZeroAmount: A first supply that does not exceed the dead shares
+/// - MathOverflow: Calculation overflow
+pub fn quote_shares_at(
+    market: &Market,
+    side: QuoteSide,
+    assets: u64,
+    round: QuoteRounding,
+    timestamp: i64,
+) -> Result<u64> {
+    let mut projected = market.clone();
+    project_interest_at(&mut projected, timestamp)?;
+
+    let (total_assets, total_shares) = match side {
+        QuoteSide::Supply => (projected.total_supply_assets, projected.total_supply_shares),
+        QuoteSide::Borrow => (projected.total_borrow_assets, projected.total_borrow_shares),
+    };
+    let shares = match round {
+        QuoteRounding::Up => to_shares_up(assets, total_assets, total_shares)?,
+        QuoteRounding::Down => to_shares_down(assets, total_assets, total_shares)?,
+    };
+
+    match side {
+        QuoteSide::Supply => Ok(first_supply_split(&projected, shares)?.0),
+        QuoteSide::Borrow => Ok(shares),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::interest::accrue_interest_at;
+    use crate::instructions::supply::supply_shares_for_assets;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn market_with_interest_due() -> Market {
+        Market {
+            total_supply_assets: 2_000_000_000,
+            total_supply_shares: 2_000_000_000_000_000,
+            total_borrow_assets: 1_000_000_000,
+            total_borrow_shares: 1_000_000_000_000_000,
+            last_update: NOW - 30 * 86_400,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_quote_matches_handler_conversions_after_accrual() {
+        let market = market_with_interest_due();
+        let assets = 123_456_789;
+
+        // What supply and borrow compute after their own accrual
+        let mut accrued = market.clone();
+        accrue_interest_at(&mut accrued, NOW).unwrap();
+        let supplied = supply_shares_for_assets(&accrued, assets).unwrap();
+        let borrowed = to_shares_up(
+            assets,
+            accrued.total_borrow_assets,
+            accrued.total_borrow_shares,
+        )
+        .unwrap();
+
+        let quote = |side, round| quote_shares_at(&market, side, assets, round, NOW).unwrap();
+        assert_eq!(quote(QuoteSide::Supply, QuoteRounding::Down), supplied);
+        assert_eq!(quote(QuoteSide::Borrow, QuoteRounding::Up), borrowed);
+        assert_eq!(
+            market.last_update,
+            NOW - 30 * 86_400,
+            "quote must not mutate the market"
+        );
+
+        // Interest moved the share prices: a stale quote would differ
+        let stale = to_shares_down(
+            assets,
+            market.total_supply_assets,
+            market.total_supply_shares,
+        )
+        .unwrap();
+        assert_ne!(stale, supplied);
+        assert!(
+            quote(QuoteSide::Borrow, QuoteRounding::Down)
+                < quote(QuoteSide::Borrow, QuoteRounding::Up)
+        );
+    }
+
+    #[test]
+    fn test_first_supply_quote_excludes_dead_shares() {
+        let market = Market {
+            first_supply_dead_shares: 1_000,
+            last_update: NOW,
+            ..Default::default()
+        };
+
+        let shares = quote_shares_at(
+            &market,
+            QuoteSide::Supply,
+            1_000_000,
+            QuoteRounding::Down,
+            NOW,
+        )
+        .unwrap();
+        assert_eq!(shares, to_shares_down(1_000_000, 0, 0).unwrap() - 1_000);
+    }
+}
@@ -9,6 +9,7 @@
 //! - Uses virtual shares mechanism (SharesMathLib)
 //! - Accrues interest before repayment
 //! - Handles overpayment gracefully (saturating subtraction)
+//! - Optionally credits an asset-mode overpayment as supply (`supply_excess`)
 //! - Supports third-party repayment (payer ≠ borrower)
 //!
 //! **Pelago.sol Reference:** repay() function (L269-298)
@@ -17,9 +18,13 @@ use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
 use crate::error::PelagoError;
+use crate::instructions::supply::{check_vault_accounting, supply_shares_for_assets, SupplyEvent};
 use crate::state::{Market, UserPosition};
 use crate::utils::shares_math::{to_shares_down, to_assets_up};
 use crate::utils::interest::accrue_interest;
+use crate::utils::invariants::check_recovery_invariants;
+use crate::utils::rewards::settle_rewards;
+use crate::utils::vault_snapshot::snapshot_balance_delta;
 
 /// Repay borrowed loan assets
 ///
@@ -70,6 +75,19 @@ pub struct Repay<'info> {
     )]
     pub borrower_position: Account<'info, UserPosition>,
 
+    /// Payer's position PDA (receives supply shares for an overpayment)
+    /// Only needed with `supply_excess` when the payer is not the borrower
+    #[account(
+        mut,
+        seeds = [
+            UserPosition::SEED_PREFIX,
+            market.key().as_ref(),
+            payer.key().as_ref(),
+        ],
+        bump = payer_position.bump,
+    )]
+    pub payer_position: Option<Account<'info, UserPosition>>,
+
     /// Payer wallet (signer, source of repayment funds)
     /// Can be the borrower themselves or a third party
     #[account(mut)]
@@ -89,7 +107,7 @@ pub struct Repay<'info> {
     /// Market's loan token vault (receives repayment)
     #[account(
         mut,
-        constraint = loan_vault.key() == market.loan_vault @ PelagoError::InvalidVault,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
     )]
     pub loan_vault: Account<'info, TokenAccount>,
 
@@ -116,15 +134,24 @@ pub struct Repay<'info> {
 /// - Due to rounding, `assets` may exceed `totalBorrowAssets` by 1
 /// - Uses `saturating_sub` to prevent underflow
 /// - This is expected behavior and matches Pelago's `zeroFloorSub`
+/// - `supply_excess = true` (asset mode): only the borrower's full debt is
+///   repaid and the rest is supplied on behalf of the payer, credited to the
+///   borrower's position when payer == borrower, otherwise to `payer_position`
 ///
 /// **Errors:**
 /// - InconsistentInput: Both or neither of (assets, shares) are non-zero
+/// - InvalidParameter: Excess to supply but no `payer_position` for a third-party
+///   payer, or `payer_position` passed when payer == borrower
+/// - MarketPaused: Excess to supply while the market is paused
+/// - VaultAccountingMismatch: Excess to supply while the vault diverged from accounting
+/// - NoDebtToRepay: Borrower has no borrow shares (nothing is transferred)
 /// - InsufficientBorrow: User doesn't have enough borrow shares
 /// - MathOverflow: Calculation overflow
 pub fn handler(
     ctx: Context<Repay>,
     assets: u64,
     shares: u64,
+    supply_excess: bool,
 ) -> Result<()> {
     // Step 1: Validate input mutual exclusivity
     require!(
@@ -132,14 +159,28 @@ pub fn handler(
         PelagoError::InconsistentInput
     );
 
+    let payer_is_borrower = ctx.accounts.payer.key() == ctx.accounts.borrower.key();
+    require!(
+        !(payer_is_borrower && ctx.accounts.payer_position.is_some()),
+        PelagoError::InvalidParameter
+    );
+
     let market = &mut ctx.accounts.market;
     let borrower_position = &mut ctx.accounts.borrower_position;
+    check_has_debt(borrower_position)?;
 
     // Step 2: Accrue interest before any calculation
     accrue_interest(market)?;
+    let borrow_assets_before = market.total_borrow_assets;
 
     // Step 3: Convert between assets and shares using virtual shares
-    let (final_assets, final_shares) = if assets > 0 {
+    let mut excess_assets = 0;
+    let (final_assets, final_shares) = if assets > 0 && supply_excess {
+        // Repay at most the full debt; the rest is supplied below
+        let split = split_repay(market, borrower_position.borrow_shares, assets)?;
+        excess_assets = split.excess_assets;
+        (split.repaid_assets, split.repaid_shares)
+    } else if assets > 0 {
         // User specifies assets to repay
         // Calculate shares to burn (rounding DOWN to favor protocol)
         let s = to_shares_down(
@@ -158,6 +199,12 @@ pub fn handler(
         )?;
         (a, shares)
     };
+    let (final_assets, final_shares) = cap_repay_to_debt(
+        market,
+        borrower_position.borrow_shares,
+        final_assets,
+        final_shares,
+    )?;
 
     msg!(
         "Repay calculation: assets={}, shares={}, borrower_shares={}",
@@ -166,20 +213,44 @@ pub fn handler(
         borrower_position.borrow_shares
     );
 
+    // The excess is a supply: check the vault while the borrow totals still
+    // match its balance, before the repaid assets are transferred in
+    if excess_assets > 0 {
+        require!(!market.paused, PelagoError::MarketPaused);
+        check_vault_accounting(ctx.accounts.loan_vault.amount, market)?;
+    }
+
     // Step 4: Update borrower position and market totals
-    // Use saturating_sub to handle overpayment gracefully
-    // This matches Pelago.sol's UtilsLib.zeroFloorSub() behavior
-    borrower_position.borrow_shares = borrower_position
-        .borrow_shares
-        .saturating_sub(final_shares);
+    apply_repay(market, borrower_position, final_assets, final_shares);
 
-    market.total_borrow_shares = market
-        .total_borrow_shares
-        .saturating_sub(final_shares);
+    // Step 4b: Supply the overpayment on behalf of the payer
+    let mut excess_shares = 0;
+    if excess_assets > 0 {
+        excess_shares = supply_shares_for_assets(market, excess_assets)?;
+        let supplier_position = if payer_is_borrower {
+            &mut **borrower_position
+        } else {
+            ctx.accounts
+                .payer_position
+                .as_deref_mut()
+                .ok_or(PelagoError::InvalidParameter)?
+        };
+        settle_rewards(market, supplier_position)?;
+        supplier_position.supply_shares = supplier_position
+            .supply_shares
+            .checked_add(excess_shares)
+            .ok_or(PelagoError::MathOverflow)?;
 
-    market.total_borrow_assets = market
-        .total_borrow_assets
-        .saturating_sub(final_assets);
+        market.total_supply_assets = market
+            .total_supply_assets
+            .checked_add(excess_assets)
+            .ok_or(PelagoError::MathOverflow)?;
+        market.total_supply_shares = market
+            .total_supply_shares
+            .checked_add(excess_shares)
+            .ok_or(PelagoError::MathOverflow)?;
+    }
+    check_recovery_invariants(market, borrow_assets_before)?;
 
     msg!(
         "Repay: payer={}, borrower={}, assets={}, shares={}, remaining_borrow_shares={}",
@@ -202,7 +273,14 @@ pub fn handler(
         transfer_accounts,
     );
 
-    token::transfer(cpi_ctx, final_assets)?;
+    let total_in = final_assets
+        .checked_add(excess_assets)
+        .ok_or(PelagoError::MathOverflow)?;
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || token::transfer(cpi_ctx, total_in))?
+        .require_increase(total_in)?;
+
+    // Record position activity for dormancy tracking
+    borrower_position.last_activity = Clock::get()?.unix_timestamp;
 
     // Emit event for off-chain tracking
     emit!(RepayEvent {
@@ -216,6 +294,111 @@ pub fn handler(
         total_borrow_shares: market.total_borrow_shares,
     });
 
+    if excess_assets > 0 {
+        emit!(SupplyEvent {
+            market: market.key(),
+            user: ctx.accounts.payer.key(),
+            assets: excess_assets,
+            shares: excess_shares,
+            total_supply_shares: market.total_supply_shares,
+            total_supply_assets: market.total_supply_assets,
+        });
+    }
+
+    Ok(())
+}
+
+/// Asset-mode repay split into the debt repaid and the overpayment
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RepaySplit {
+    /// Loan assets applied to the debt
+    pub repaid_assets: u64,
+
+    /// Borrow shares burned
+    pub repaid_shares: u64,
+
+    /// Loan assets beyond the debt (0 unless overpaying)
+    pub excess_assets: u64,
+}
+
+/// Splits an asset-mode repay of `assets` against a position's borrow shares
+///
+/// If `assets` covers the full debt (`toAssetsUp(borrow_shares)`), all shares
+/// are burned for exactly that debt and the rest is excess. Otherwise the
+/// repay is the regular `toSharesDown` conversion with no excess.
+pub fn split_repay(market: &Market, borrow_shares: u64, assets: u64) -> Result<RepaySplit> {
+    let debt = to_assets_up(
+        borrow_shares,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+
+    if assets >= debt {
+        return Ok(RepaySplit {
+            repaid_assets: debt,
+            repaid_shares: borrow_shares,
+            excess_assets: assets - debt,
+        });
+    }
+
+    let repaid_shares = to_shares_down(
+        assets,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+    Ok(RepaySplit {
+        repaid_assets: assets,
+        repaid_shares,
+        excess_assets: 0,
+    })
+}
+
+/// Burns repaid borrow shares from the position and the market totals
+///
+/// Shares are capped to the debt beforehand (`cap_repay_to_debt`);
+/// saturating_sub absorbs asset rounding, matching Pelago.sol's
+/// UtilsLib.zeroFloorSub() behavior.
+pub fn apply_repay(market: &mut Market, position: &mut UserPosition, assets: u64, shares: u64) {
+    position.borrow_shares = position.borrow_shares.saturating_sub(shares);
+    market.total_borrow_shares = market.total_borrow_shares.saturating_sub(shares);
+    market.total_borrow_assets = market.total_borrow_assets.saturating_sub(assets);
+}
+
+/// Caps a repay at the borrower's debt
+///
+/// A repay converting to more shares than `borrow_shares` settles the full
+/// debt instead: exactly `borrow_shares` are burned for their
+/// `to_assets_up` value. The payer is never charged for shares the
+/// borrower doesn't owe, and the market total only loses this borrower's
+/// shares.
+///
+/// **Returns:** `(assets, shares)` to charge and burn
+pub fn cap_repay_to_debt(
+    market: &Market,
+    borrow_shares: u64,
+    assets: u64,
+    shares: u64,
+) -> Result<(u64, u64)> {
+    if shares <= borrow_shares {
+        return Ok((assets, shares));
+    }
+    let full_debt = to_assets_up(
+        borrow_shares,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+    Ok((full_debt, borrow_shares))
+}
+
+/// Rejects a repay for a position that owes nothing
+///
+/// Without it, a third party repaying an already liquidated borrower would
+/// send tokens into the vault with no debt to reduce.
+///
+/// **Errors:**
+/// - NoDebtToRepay: `borrow_shares == 0`
+pub fn check_has_debt(position: &UserPosition) -> Result<()> {
+    require!(position.borrow_shares > 0, PelagoError::NoDebtToRepay);
     Ok(())
 }
 
@@ -246,3 +429,172 @@ pub struct RepayEvent {
     /// Remaining total borrow shares in market
     pub total_borrow_shares: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::shares_math::to_shares_up;
+
+    #[test]
+    fn test_oversized_repay_capped_to_debt() {
+        // Two borrowers of 500 USDC each
+        let market = Market {
+            total_borrow_assets: 1_000_000_000,
+            total_borrow_shares: to_shares_up(1_000_000_000, 0, 0).unwrap(),
+            ..Default::default()
+        };
+        let debt_shares = market.total_borrow_shares / 2;
+
+        // 10x the shares: only the borrower's own shares are burned and charged
+        let oversized = 10 * debt_shares;
+        let quoted = to_assets_up(oversized, market.total_borrow_assets, market.total_borrow_shares)
+            .unwrap();
+        assert_eq!(
+            cap_repay_to_debt(&market, debt_shares, quoted, oversized).unwrap(),
+            (500_000_000, debt_shares)
+        );
+
+        // 600 USDC against a 500 USDC debt: charged 500
+        let excess = to_shares_down(600_000_000, market.total_borrow_assets, market.total_borrow_shares)
+            .unwrap();
+        assert_eq!(
+            cap_repay_to_debt(&market, debt_shares, 600_000_000, excess).unwrap(),
+            (500_000_000, debt_shares)
+        );
+
+        // Within the debt nothing changes
+        assert_eq!(
+            cap_repay_to_debt(&market, debt_shares, 100_000_000, 7).unwrap(),
+            (100_000_000, 7)
+        );
+    }
+
+    #[test]
+    fn test_repay_without_debt_rejected() {
+        // Fully liquidated: collateral may remain, debt is gone
+        let liquidated = UserPosition {
+            collateral_amount: 1_000_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            check_has_debt(&liquidated).unwrap_err(),
+            PelagoError::NoDebtToRepay.into()
+        );
+
+        let indebted = UserPosition {
+            borrow_shares: 1,
+            ..Default::default()
+        };
+        assert!(check_has_debt(&indebted).is_ok());
+    }
+
+    #[test]
+    fn test_split_repay_overpayment() {
+        // 100 USDC of debt held by one position
+        let borrow_shares = to_shares_up(100_000_000, 0, 0).unwrap();
+        let market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_borrow_assets: 100_000_000,
+            total_borrow_shares: borrow_shares,
+            ..Default::default()
+        };
+
+        // Paying 150 USDC repays the full debt and leaves 50 USDC to supply
+        let split = split_repay(&market, borrow_shares, 150_000_000).unwrap();
+        assert_eq!(split.repaid_shares, borrow_shares);
+        assert_eq!(split.repaid_assets + split.excess_assets, 150_000_000);
+        assert_eq!(split.excess_assets, 50_000_000);
+
+        // An exact payoff has no excess
+        let split = split_repay(&market, borrow_shares, 100_000_000).unwrap();
+        assert_eq!(split.repaid_shares, borrow_shares);
+        assert_eq!(split.excess_assets, 0);
+
+        // A partial repay is unchanged
+        let split = split_repay(&market, borrow_shares, 40_000_000).unwrap();
+        assert_eq!(split.repaid_assets, 40_000_000);
+        assert_eq!(
+            split.repaid_shares,
+            to_shares_down(40_000_000, 100_000_000, borrow_shares).unwrap()
+        );
+        assert_eq!(split.excess_assets, 0);
+    }
+
+    #[test]
+    fn test_supply_excess_vault_check_precedes_repay() {
+        // 1000 USDC supplied, 100 USDC of it borrowed by one position
+        let borrow_shares = to_shares_up(100_000_000, 0, 0).unwrap();
+        let mut market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_supply_shares: to_shares_up(1_000_000_000, 0, 0).unwrap(),
+            total_borrow_assets: 100_000_000,
+            total_borrow_shares: borrow_shares,
+            ..Default::default()
+        };
+        let mut position = UserPosition {
+            borrow_shares,
+            ..Default::default()
+        };
+        let vault = 900_000_000;
+
+        // Overpay by 50 USDC: the vault still holds its pre-repay balance
+        let split = split_repay(&market, borrow_shares, 150_000_000).unwrap();
+        assert!(check_vault_accounting(vault, &market).is_ok());
+
+        // Checked after the borrow totals drop, the same vault looks short
+        let mut repaid = market.clone();
+        apply_repay(&mut repaid, &mut position.clone(), split.repaid_assets, split.repaid_shares);
+        assert_eq!(
+            check_vault_accounting(vault, &repaid).unwrap_err(),
+            PelagoError::VaultAccountingMismatch.into()
+        );
+
+        // Once the repay and excess land, vault and accounting agree again
+        apply_repay(&mut market, &mut position, split.repaid_assets, split.repaid_shares);
+        market.total_supply_assets += split.excess_assets;
+        assert_eq!(position.borrow_shares, 0);
+        assert_eq!(market.total_borrow_assets, 0);
+        assert!(check_vault_accounting(vault + 150_000_000, &market).is_ok());
+    }
+
+    #[test]
+    fn test_partial_repay_on_impaired_market() {
+        use crate::utils::interest::accrue_interest_at;
+        use crate::utils::invariants::check_market_invariants;
+
+        // Bad debt left 1000 USDC owed against 900 USDC of supply
+        let borrow_shares = to_shares_up(1_000_000_000, 0, 0).unwrap();
+        let mut market = Market {
+            total_supply_assets: 900_000_000,
+            total_supply_shares: to_shares_up(900_000_000, 0, 0).unwrap(),
+            total_borrow_assets: 1_000_000_000,
+            total_borrow_shares: borrow_shares,
+            last_update: 1_700_000_000,
+            ..Default::default()
+        };
+        let mut position = UserPosition {
+            borrow_shares,
+            ..Default::default()
+        };
+        accrue_interest_at(&mut market, 1_700_000_000 + 86_400).unwrap();
+        assert!(market.impaired);
+        let borrow_assets_before = market.total_borrow_assets;
+
+        // Repaying 50 USDC leaves borrow > supply, yet the repay goes through
+        let shares = to_shares_down(50_000_000, market.total_borrow_assets, market.total_borrow_shares)
+            .unwrap();
+        apply_repay(&mut market, &mut position, 50_000_000, shares);
+        assert!(market.total_borrow_assets > market.total_supply_assets);
+        assert!(check_market_invariants(&market).is_err());
+        assert!(check_recovery_invariants(&market, borrow_assets_before).is_ok());
+
+        // Debt growing on an impaired market is still rejected
+        assert_eq!(
+            check_recovery_invariants(&market, market.total_borrow_assets - 1).unwrap_err(),
+            PelagoError::InvariantViolation.into()
+        );
+
+        // Debt above supply + reserves: the expected vault balance floors at zero
+        assert!(check_vault_accounting(0, &market).is_ok());
+    }
+}
@@ -0,0 +1,115 @@
+//! Reset Accrual Clock Instruction
+//!
+//! Recovery path for a market whose `last_update` ended up in the future
+//! (bug, migration). In that state `accrue_interest` fails with
+//! `InvalidTimestamp` on every call, which blocks every accruing
+//! instruction. Resetting the clock to `now` unblocks the market without
+//! accruing any interest.
+//!
+//! Only a future `last_update` can be reset, so this can't be used to
+//! skip interest that is legitimately owed.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Reset a market's future-dated accrual clock to the current time
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct ResetAccrualClock<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for reset_accrual_clock instruction
+///
+/// **State Changes:**
+/// - market.last_update = current timestamp (no interest is accrued)
+///
+/// **Errors:**
+/// - InvalidTimestamp: `last_update` is not in the future
+pub fn handler(ctx: Context<ResetAccrualClock>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let market = &mut ctx.accounts.market;
+    let previous = market.last_update;
+
+    reset_accrual_clock_at(market, now)?;
+
+    msg!(
+        "Accrual clock reset: market={}, previous={}, new={}",
+        market.key(),
+        previous,
+        now
+    );
+
+    Ok(())
+}
+
+/// Sets `last_update = now` if it is currently in the future
+pub fn reset_accrual_clock_at(market: &mut Market, now: i64) -> Result<()> {
+    require!(market.last_update > now, PelagoError::InvalidTimestamp);
+    market.last_update = now;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::interest::accrue_interest_at;
+
+    #[test]
+    fn test_recover_from_future_last_update() {
+        let now = 1_700_000_000;
+        let mut market = Market {
+            total_supply_assets: 2_000_000_000,
+            total_borrow_assets: 1_000_000_000,
+            last_update: now + 10 * 365 * 86_400, // corrupted: 10 years ahead
+            ..Default::default()
+        };
+
+        // Every accrual fails while the clock is in the future
+        assert_eq!(
+            accrue_interest_at(&mut market, now).unwrap_err(),
+            PelagoError::InvalidTimestamp.into()
+        );
+
+        reset_accrual_clock_at(&mut market, now).unwrap();
+        assert_eq!(market.last_update, now);
+        assert_eq!(market.total_borrow_assets, 1_000_000_000);
+
+        // Accrual resumes from the reset point
+        accrue_interest_at(&mut market, now + 86_400).unwrap();
+        assert!(market.total_borrow_assets > 1_000_000_000);
+    }
+
+    #[test]
+    fn test_reset_rejected_for_past_last_update() {
+        let now = 1_700_000_000;
+        let mut market = Market {
+            last_update: now - 3_600,
+            ..Default::default()
+        };
+
+        // Would skip an hour of owed interest
+        assert_eq!(
+            reset_accrual_clock_at(&mut market, now).unwrap_err(),
+            PelagoError::InvalidTimestamp.into()
+        );
+        assert_eq!(market.last_update, now - 3_600);
+    }
+}
@@ -0,0 +1,420 @@
+//! Reset Market Instruction
+//!
+//! Reuses a fully wound-down market with new parameters instead of creating
+//! a new one. The market PDA, mints, vaults and authority are kept; the
+//! risk and rate parameters are replaced and `version` is bumped so
+//! indexers can tell the generations apart.
+//!
+//! The new LLTV and rate model are held to the same `ProtocolConfig`
+//! policy as a new market (see `initialize_market`).
+//!
+//! A market is empty once every position is closed and its totals read
+//! zero (run `sweep_dust` for rounding residue). Fee shares must be
+//! claimed first (`claim_fees`). Dead shares locked by `seed_market` or
+//! the first supply (`first_supply_dead_shares`) can't be redeemed, so a
+//! market holding only dead shares and the assets backing them counts as
+//! empty; both carry over, keeping the new generation guarded against
+//! first-depositor inflation. Reserves are protocol-owned and carry over.
+
+use anchor_lang::prelude::*;
+
+use crate::constants::{BPS_DENOMINATOR, MAX_LLTV};
+use crate::error::PelagoError;
+use crate::instructions::set_irm::{irm_rate_range, validate_irm, IrmParams};
+use crate::state::{Market, ProtocolConfig};
+use crate::utils::interest::{accrue_interest_at, compute_borrow_rate, supply_rate_wad};
+
+/// Reset an emptied market's parameters
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct ResetMarket<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+
+    /// Protocol policy (LLTV whitelist, max LLTV, rate bounds)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+/// Parameters a reset market starts its new generation with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResetMarketParams {
+    /// Liquidation LTV (precision: 1e8), applied immediately
+    pub lltv: u64,
+
+    /// Kinked rate model (kink 0 = FIXED_ANNUAL_RATE_WAD)
+    pub irm: IrmParams,
+
+    /// Absolute borrow cap (0 = unlimited)
+    pub borrow_cap: u64,
+
+    /// Borrow cap as bps of total supply (0 = unlimited)
+    pub borrow_cap_ratio_bps: u16,
+
+    /// Debt cap per position (0 = unlimited)
+    pub max_borrow_per_position: u64,
+}
+
+/// Handler for reset_market instruction
+///
+/// **State Changes:**
+/// - market.lltv = `params.lltv`, any pending LLTV change dropped
+/// - market.irm_* = `params.irm`, current rates refreshed
+/// - Borrow caps replaced
+/// - market.version += 1
+/// - Dead shares and the supply assets backing them are kept
+///
+/// **Errors:**
+/// - MarketNotEmpty: See `check_market_empty`
+/// - InvalidLltv: lltv == 0, lltv > MAX_LLTV or lltv above
+///   `protocol_config.max_lltv`
+/// - LltvNotEnabled: lltv not in `protocol_config.enabled_lltvs`
+/// - InvalidParameter: Invalid rate model, a rate outside the protocol's
+///   bounds, or cap ratio above 100%
+pub fn handler(ctx: Context<ResetMarket>, params: ResetMarketParams) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    let now = Clock::get()?.unix_timestamp;
+
+    reset_market_at(market, &ctx.accounts.protocol_config, &params, now)?;
+
+    msg!(
+        "Market reset: market={}, version={}, lltv={}, borrow_rate_wad={}",
+        market.key(),
+        market.version,
+        market.lltv,
+        market.current_borrow_rate_wad
+    );
+
+    emit!(MarketResetEvent {
+        market: market.key(),
+        version: market.version,
+        lltv: market.lltv,
+        borrow_rate_wad: market.current_borrow_rate_wad,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Checks that no position or balance is left in `market`
+///
+/// The only supply shares allowed are `market.dead_shares`, which no
+/// position owns; the supply assets left belong to them. Unclaimed fee
+/// shares count as supply shares, so they block the reset.
+///
+/// **Errors:**
+/// - MarketNotEmpty: open positions, supply shares beyond the dead shares,
+///   borrows, or collateral remain, or supply assets remain without dead
+///   shares
+pub fn check_market_empty(market: &Market) -> Result<()> {
+    require!(
+        market.open_positions == 0
+            && market.total_supply_shares == market.dead_shares
+            && (market.dead_shares > 0 || market.total_supply_assets == 0)
+            && market.total_borrow_shares == 0
+            && market.total_borrow_assets == 0
+            && market.total_collateral == 0,
+        PelagoError::MarketNotEmpty
+    );
+    Ok(())
+}
+
+/// Replaces the parameters of an empty `market` as of `now`
+///
+/// Both ends of the new rate curve (0% and 100% utilization) must lie within
+/// the protocol's rate bounds, so no utilization prices outside them.
+pub fn reset_market_at(
+    market: &mut Market,
+    protocol_config: &ProtocolConfig,
+    params: &ResetMarketParams,
+    now: i64,
+) -> Result<()> {
+    check_market_empty(market)?;
+    require!(
+        params.lltv > 0 && params.lltv <= MAX_LLTV,
+        PelagoError::InvalidLltv
+    );
+    validate_irm(&params.irm)?;
+    let (min_rate_wad, max_rate_wad) = irm_rate_range(&params.irm);
+    protocol_config.check_market_params(params.lltv, min_rate_wad)?;
+    protocol_config.check_market_params(params.lltv, max_rate_wad)?;
+    require!(
+        (params.borrow_cap_ratio_bps as u64) <= BPS_DENOMINATOR,
+        PelagoError::InvalidParameter
+    );
+
+    // Nothing to accrue without borrows; this moves the clock (and the
+    // reward index) to now so the new generation starts fresh
+    accrue_interest_at(market, now)?;
+
+    market.lltv = params.lltv;
+    market.pending_lltv = 0;
+    market.lltv_effective_at = 0;
+    market.irm_base_rate_wad = params.irm.base_rate_wad;
+    market.irm_slope1_wad = params.irm.slope1_wad;
+    market.irm_slope2_wad = params.irm.slope2_wad;
+    market.irm_kink_utilization_bps = params.irm.kink_utilization_bps;
+    market.current_borrow_rate_wad = compute_borrow_rate(market);
+    market.current_supply_rate_wad = supply_rate_wad(market)?;
+    market.borrow_cap = params.borrow_cap;
+    market.borrow_cap_ratio_bps = params.borrow_cap_ratio_bps;
+    market.max_borrow_per_position = params.max_borrow_per_position;
+    market.version = market
+        .version
+        .checked_add(1)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Event emitted when an emptied market is reset
+#[event]
+pub struct MarketResetEvent {
+    /// Market public key
+    pub market: Pubkey,
+
+    /// New parameter generation
+    pub version: u32,
+
+    /// New liquidation LTV
+    pub lltv: u64,
+
+    /// Borrow rate of the new rate model at 0% utilization
+    pub borrow_rate_wad: u128,
+
+    /// Timestamp of the reset
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::borrow::record_borrow;
+    use crate::instructions::withdraw_collateral::check_health_p1;
+    use crate::state::UserPosition;
+    use crate::utils::interest::{FIXED_ANNUAL_RATE_WAD, WAD};
+    use crate::utils::shares_math::to_shares_up;
+
+    const START: i64 = 1_700_000_000;
+
+    /// 50% LLTV and a flat 10% rate (zero slopes, kink at 90%)
+    const PARAMS: ResetMarketParams = ResetMarketParams {
+        lltv: 50_000_000,
+        irm: IrmParams {
+            base_rate_wad: 100_000_000_000_000_000,
+            slope1_wad: 0,
+            slope2_wad: 0,
+            kink_utilization_bps: 9_000,
+        },
+        borrow_cap: 0,
+        borrow_cap_ratio_bps: 0,
+        max_borrow_per_position: 0,
+    };
+
+    /// Protocol policy with 50% enabled and rates bounded to [1%, 20%]
+    fn config() -> ProtocolConfig {
+        ProtocolConfig {
+            max_lltv: 80_000_000,
+            min_rate_wad: WAD / 100,
+            max_rate_wad: WAD / 5,
+            enabled_lltvs: vec![50_000_000],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_emptied_market_reset_applies_new_parameters() {
+        // Wound down: every position closed, totals zero, reserves left over
+        let mut market = Market {
+            lltv: 80_000_000,
+            pending_lltv: 90_000_000,
+            lltv_effective_at: START + 86_400,
+            reserves: 1_000,
+            last_update: START - 86_400,
+            ..Default::default()
+        };
+        reset_market_at(&mut market, &config(), &PARAMS, START).unwrap();
+
+        assert_eq!(market.version, 1);
+        assert_eq!(market.lltv, 50_000_000);
+        assert_eq!(market.pending_lltv, 0);
+        assert_eq!(market.last_update, START);
+        assert_eq!(market.reserves, 1_000);
+        assert_eq!(market.current_borrow_rate_wad, WAD / 10);
+
+        // A new borrow is checked against the new 50% LLTV:
+        // 10 SOL ($1000) supports $500, not the $800 the old LLTV allowed
+        market.total_supply_assets = 1_000_000_000;
+        market.total_supply_shares = 1_000_000_000 * 1_000_000;
+        let mut position = UserPosition {
+            collateral_amount: 10_000_000_000,
+            ..Default::default()
+        };
+        let shares = to_shares_up(600_000_000, 0, 0).unwrap();
+        record_borrow(&mut market, &mut position, 600_000_000, shares).unwrap();
+        assert_eq!(
+            check_health_p1(&market, &position).unwrap_err(),
+            PelagoError::InsufficientCollateral.into()
+        );
+    }
+
+    #[test]
+    fn test_reset_rejects_market_with_balances() {
+        let not_empty = PelagoError::MarketNotEmpty.into();
+        let leftovers = [
+            Market {
+                open_positions: 1,
+                ..Default::default()
+            },
+            Market {
+                total_supply_shares: 1_000_000,
+                ..Default::default()
+            },
+            Market {
+                total_borrow_shares: 1,
+                ..Default::default()
+            },
+            Market {
+                total_supply_assets: 1,
+                ..Default::default()
+            },
+            Market {
+                total_borrow_assets: 1,
+                ..Default::default()
+            },
+            Market {
+                total_collateral: 1,
+                ..Default::default()
+            },
+        ];
+        for mut market in leftovers {
+            assert_eq!(
+                reset_market_at(&mut market, &config(), &PARAMS, START).unwrap_err(),
+                not_empty
+            );
+            assert_eq!(market.version, 0);
+        }
+
+        // Parameters are validated like their dedicated setters
+        let bad_lltv = ResetMarketParams { lltv: 0, ..PARAMS };
+        assert_eq!(
+            reset_market_at(&mut Market::default(), &config(), &bad_lltv, START).unwrap_err(),
+            PelagoError::InvalidLltv.into()
+        );
+        let bad_ratio = ResetMarketParams {
+            borrow_cap_ratio_bps: 10_001,
+            ..PARAMS
+        };
+        assert_eq!(
+            reset_market_at(&mut Market::default(), &config(), &bad_ratio, START).unwrap_err(),
+            PelagoError::InvalidParameter.into()
+        );
+    }
+
+    #[test]
+    fn test_reset_held_to_protocol_policy() {
+        // LLTV allowed by MAX_LLTV but not enabled by the protocol
+        let unlisted = ResetMarketParams {
+            lltv: 70_000_000,
+            ..PARAMS
+        };
+        assert_eq!(
+            reset_market_at(&mut Market::default(), &config(), &unlisted, START).unwrap_err(),
+            PelagoError::LltvNotEnabled.into()
+        );
+
+        // Enabled but above the protocol's max LLTV
+        let mut above_max = config();
+        above_max.enabled_lltvs.push(90_000_000);
+        let too_high = ResetMarketParams {
+            lltv: 90_000_000,
+            ..PARAMS
+        };
+        assert_eq!(
+            reset_market_at(&mut Market::default(), &above_max, &too_high, START).unwrap_err(),
+            PelagoError::InvalidLltv.into()
+        );
+
+        // A 10% base within bounds, but the curve reaches 40% at full utilization
+        let steep = ResetMarketParams {
+            irm: IrmParams {
+                slope2_wad: 300_000_000_000_000_000,
+                ..PARAMS.irm
+            },
+            ..PARAMS
+        };
+        assert_eq!(
+            reset_market_at(&mut Market::default(), &config(), &steep, START).unwrap_err(),
+            PelagoError::InvalidParameter.into()
+        );
+
+        // No kink falls back to the fixed 5% rate, which is within bounds
+        let fixed = ResetMarketParams {
+            irm: IrmParams::default(),
+            ..PARAMS
+        };
+        let mut market = Market::default();
+        reset_market_at(&mut market, &config(), &fixed, START).unwrap();
+        assert_eq!(market.current_borrow_rate_wad, FIXED_ANNUAL_RATE_WAD);
+    }
+    #[test]
+    fn test_seeded_market_resets_keeping_dead_shares() {
+        use crate::instructions::seed_market::credit_seed;
+        use crate::utils::shares_math::to_assets_down;
+
+        // Seeded, then the authority withdraws everything it owns
+        let mut market = Market::default();
+        let mut position = UserPosition::default();
+        market.register_position().unwrap();
+        let (position_shares, dead_shares) =
+            credit_seed(&mut market, &mut position, 1_000_000_000, START).unwrap();
+        let withdrawn = to_assets_down(
+            position_shares,
+            market.total_supply_assets,
+            market.total_supply_shares,
+        )
+        .unwrap();
+        market.total_supply_shares -= position_shares;
+        market.total_supply_assets -= withdrawn;
+
+        // The emptied position still counts until it is closed
+        assert_eq!(
+            reset_market_at(&mut market.clone(), &config(), &PARAMS, START).unwrap_err(),
+            PelagoError::MarketNotEmpty.into()
+        );
+        market.open_positions = 0;
+
+        let backing = market.total_supply_assets;
+        assert!(backing > 0);
+        reset_market_at(&mut market, &config(), &PARAMS, START + 86_400).unwrap();
+        assert_eq!(market.version, 1);
+        assert_eq!(market.lltv, PARAMS.lltv);
+        assert_eq!(market.dead_shares, dead_shares);
+        assert_eq!(market.total_supply_shares, dead_shares);
+        assert_eq!(market.total_supply_assets, backing);
+
+        // Shares beyond the dead ones (e.g. unclaimed fees) still block it
+        market.total_supply_shares += 1;
+        assert_eq!(
+            reset_market_at(&mut market, &config(), &PARAMS, START + 86_400).unwrap_err(),
+            PelagoError::MarketNotEmpty.into()
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! Resync Total Collateral Instruction
+//!
+//! Operational repair path for `market.total_collateral`. If the aggregate
+//! ever drifts from the sum of per-position `collateral_amount` (bug,
+//! migration), the authority can recompute it from the positions themselves.
+//!
+//! All open positions must be supplied via `remaining_accounts`; the count is
+//! checked against `market.open_positions` so a partial list can't
+//! under-report the total.
+//!
+//! **Hard Cap:** The sum is taken in a single instruction, so only markets
+//! with at most MAX_BATCH_POSITION_READS open positions can be resynced;
+//! larger markets are rejected with ResyncLimitExceeded. Set
+//! `max_positions` to that limit on markets that need the repair path.
+
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_BATCH_POSITION_READS;
+use crate::error::PelagoError;
+use crate::state::{Market, UserPosition};
+use crate::utils::batch::check_batch_size;
+
+/// Recompute `market.total_collateral` from user positions
+///
+/// **Access Control:** Only the market authority
+///
+/// **Remaining Accounts:** Every open `UserPosition` of this market (read-only)
+#[derive(Accounts)]
+pub struct ResyncTotalCollateral<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for resync_total_collateral instruction
+///
+/// **State Changes:**
+/// - market.total_collateral = Σ position.collateral_amount
+///
+/// **Errors:**
+/// - ResyncLimitExceeded: More than MAX_BATCH_POSITION_READS open positions
+/// - IncompleteResync: Position count != market.open_positions, duplicate
+///   position, or a position from another market
+/// - BatchTooLarge: More than MAX_BATCH_POSITION_READS positions
+/// - MathOverflow: Sum overflow
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ResyncTotalCollateral<'info>>,
+) -> Result<()> {
+    check_batch_size(ctx.remaining_accounts.len(), MAX_BATCH_POSITION_READS)?;
+
+    let mut positions = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account_info in ctx.remaining_accounts.iter() {
+        let position: Account<UserPosition> = Account::try_from(account_info)?;
+        positions.push((account_info.key(), position.into_inner()));
+    }
+
+    let market = &mut ctx.accounts.market;
+    let previous = market.total_collateral;
+    market.total_collateral =
+        sum_position_collateral(market.key(), market.open_positions, &positions)?;
+
+    msg!(
+        "Total collateral resynced: market={}, positions={}, previous={}, resynced={}",
+        market.key(),
+        positions.len(),
+        previous,
+        market.total_collateral
+    );
+
+    Ok(())
+}
+
+/// Rejects markets with more open positions than one resync can read
+///
+/// **Errors:**
+/// - ResyncLimitExceeded: `open_positions > MAX_BATCH_POSITION_READS`
+pub fn check_resync_limit(open_positions: u32) -> Result<()> {
+    require!(
+        open_positions as usize <= MAX_BATCH_POSITION_READS,
+        PelagoError::ResyncLimitExceeded
+    );
+    Ok(())
+}
+
+/// Sums the collateral of every open position of a market
+///
+/// **Parameters:**
+/// - `market_key`: Market the positions must belong to
+/// - `open_positions`: `market.open_positions`; the list must match it
+/// - `positions`: `(address, position)` pairs from `remaining_accounts`
+///
+/// **Errors:**
+/// - ResyncLimitExceeded: `open_positions > MAX_BATCH_POSITION_READS`
+/// - IncompleteResync: Wrong count, duplicate or foreign position
+/// - MathOverflow: Sum overflow
+pub fn sum_position_collateral(
+    market_key: Pubkey,
+    open_positions: u32,
+    positions: &[(Pubkey, UserPosition)],
+) -> Result<u64> {
+    check_resync_limit(open_positions)?;
+    require!(
+        positions.len() == open_positions as usize,
+        PelagoError::IncompleteResync
+    );
+
+    let mut seen: Vec<Pubkey> = Vec::with_capacity(positions.len());
+    let mut total: u64 = 0;
+    for (key, position) in positions {
+        require!(
+            position.market == market_key && !seen.contains(key),
+            PelagoError::IncompleteResync
+        );
+        seen.push(*key);
+
+        total = total
+            .checked_add(position.collateral_amount)
+            .ok_or(PelagoError::MathOverflow)?;
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(market: Pubkey, collateral_amount: u64) -> (Pubkey, UserPosition) {
+        (
+            Pubkey::new_unique(),
+            UserPosition {
+                market,
+                collateral_amount,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_resync_repairs_corrupted_total() {
+        let market_key = Pubkey::new_unique();
+        let mut market = Market {
+            open_positions: 3,
+            total_collateral: 42, // corrupted
+            ..Default::default()
+        };
+        let positions = vec![
+            position(market_key, 10_000_000_000),
+            position(market_key, 5_000_000_000),
+            position(market_key, 0),
+        ];
+
+        market.total_collateral =
+            sum_position_collateral(market_key, market.open_positions, &positions).unwrap();
+        assert_eq!(market.total_collateral, 15_000_000_000);
+    }
+
+    #[test]
+    fn test_resync_rejects_incomplete_or_invalid_sets() {
+        let market_key = Pubkey::new_unique();
+        let a = position(market_key, 1);
+        let b = position(market_key, 2);
+
+        // Missing a position
+        assert_eq!(
+            sum_position_collateral(market_key, 3, &[a.clone(), b.clone()]).unwrap_err(),
+            PelagoError::IncompleteResync.into()
+        );
+        // Duplicate padding the count
+        assert_eq!(
+            sum_position_collateral(market_key, 3, &[a.clone(), b.clone(), a.clone()]).unwrap_err(),
+            PelagoError::IncompleteResync.into()
+        );
+        // Position from another market
+        let foreign = position(Pubkey::new_unique(), 3);
+        assert_eq!(
+            sum_position_collateral(market_key, 3, &[a, b, foreign]).unwrap_err(),
+            PelagoError::IncompleteResync.into()
+        );
+    }
+    #[test]
+    fn test_resync_rejects_markets_above_the_read_limit() {
+        let market_key = Pubkey::new_unique();
+        let positions: Vec<_> = (0..MAX_BATCH_POSITION_READS)
+            .map(|_| position(market_key, 1))
+            .collect();
+        assert_eq!(
+            sum_position_collateral(market_key, positions.len() as u32, &positions).unwrap(),
+            MAX_BATCH_POSITION_READS as u64
+        );
+
+        // One more open position can never be listed in full
+        assert_eq!(
+            sum_position_collateral(market_key, positions.len() as u32 + 1, &positions)
+                .unwrap_err(),
+            PelagoError::ResyncLimitExceeded.into()
+        );
+    }
+}
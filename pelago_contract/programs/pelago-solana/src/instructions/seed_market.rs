@@ -0,0 +1,242 @@
+//! Seed Market Instruction
+//!
+//! Lets the market authority make the first supply of a market, with part
+//! of it locked forever. Bundled with `initialize_market` in one
+//! transaction, the market is never observable empty, which closes the
+//! first-depositor inflation window.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::MIN_SEED_ASSETS;
+use crate::error::PelagoError;
+use crate::instructions::supply::check_initial_deposit;
+use crate::state::{Market, UserPosition, UserRegistry};
+use crate::utils::interest::accrue_interest_at;
+use crate::utils::invariants::check_market_invariants;
+use crate::utils::rewards::settle_rewards;
+use crate::utils::shares_math::to_shares_down;
+use crate::utils::vault_snapshot::snapshot_balance_delta;
+
+/// Seed the initial liquidity of an empty market
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SeedMarket<'info> {
+    /// Market account (must have no supply yet)
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Authority's position PDA (receives the shares above the dead amount)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = UserPosition::LEN,
+        seeds = [
+            UserPosition::SEED_PREFIX,
+            market.key().as_ref(),
+            authority.key().as_ref(),
+        ],
+        bump
+    )]
+    pub authority_position: Account<'info, UserPosition>,
+
+    /// Authority's market registry PDA (created if needed)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = UserRegistry::LEN,
+        seeds = [
+            UserRegistry::SEED_PREFIX,
+            authority.key().as_ref(),
+        ],
+        bump
+    )]
+    pub authority_registry: Account<'info, UserRegistry>,
+
+    /// Market's loan token vault (receives the seed)
+    #[account(
+        mut,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
+    )]
+    pub loan_vault: Account<'info, TokenAccount>,
+
+    /// Authority's loan token account (source of the seed)
+    #[account(
+        mut,
+        constraint = authority_token_account.mint == market.loan_token_mint @ PelagoError::UninitializedMarket,
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+
+    /// Market authority (signer, payer)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Solana system program (for PDA creation if needed)
+    pub system_program: Program<'info, System>,
+
+    /// SPL token program (for token transfer)
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler for seed_market instruction
+///
+/// **Share Split:**
+/// - `shares = toSharesDown(assets)` on the empty market
+/// - `dead_shares = toSharesDown(MIN_SEED_ASSETS)` are owned by no position
+/// - The authority's position receives `shares - dead_shares`
+///
+/// **Errors:**
+/// - MarketPaused: Market is paused
+/// - InvalidParameter: The market already has supply shares
+/// - ZeroAmount: `assets < MIN_SEED_ASSETS`
+/// - InitialDepositTooSmall: `assets < market.min_initial_deposit`
+pub fn handler(ctx: Context<SeedMarket>, assets: u64) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.require_initialized()?;
+    require!(!market.paused, PelagoError::MarketPaused);
+    require!(market.total_supply_shares == 0, PelagoError::InvalidParameter);
+
+    check_initial_deposit(market, assets)?;
+
+    let position = &mut ctx.accounts.authority_position;
+    if position.user == Pubkey::default() {
+        position.open(ctx.accounts.authority.key(), market.key(), ctx.bumps.authority_position)?;
+        market.register_position()?;
+
+        let registry = &mut ctx.accounts.authority_registry;
+        if registry.user == Pubkey::default() {
+            registry.user = ctx.accounts.authority.key();
+            registry.bump = ctx.bumps.authority_registry;
+        }
+        registry.record_market(market.key())?;
+    }
+
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.authority_token_account.to_account_info(),
+            to: ctx.accounts.loan_vault.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        },
+    );
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || token::transfer(cpi_ctx, assets))?
+        .require_increase(assets)?;
+
+    let (position_shares, dead_shares) =
+        credit_seed(market, position, assets, Clock::get()?.unix_timestamp)?;
+    check_market_invariants(market)?;
+
+    msg!(
+        "Market seeded: market={}, assets={}, authority_shares={}, dead_shares={}",
+        market.key(),
+        assets,
+        position_shares,
+        dead_shares
+    );
+
+    Ok(())
+}
+
+/// Accrues the market to `now` and mints the seed's shares
+///
+/// Accrual first moves `last_update` to `now`, so the time the market sat
+/// empty isn't credited as reward emission to the seed's shares.
+///
+/// **Returns:** `(position_shares, dead_shares)`
+pub fn credit_seed(
+    market: &mut Market,
+    position: &mut UserPosition,
+    assets: u64,
+    now: i64,
+) -> Result<(u64, u64)> {
+    let (position_shares, dead_shares) = seed_split(assets)?;
+
+    accrue_interest_at(market, now)?;
+    settle_rewards(market, position)?;
+    position.supply_shares = position
+        .supply_shares
+        .checked_add(position_shares)
+        .ok_or(PelagoError::MathOverflow)?;
+    position.last_activity = now;
+
+    market.total_supply_assets = market
+        .total_supply_assets
+        .checked_add(assets)
+        .ok_or(PelagoError::MathOverflow)?;
+    market.total_supply_shares = position_shares
+        .checked_add(dead_shares)
+        .ok_or(PelagoError::MathOverflow)?;
+    market.dead_shares = dead_shares;
+    Ok((position_shares, dead_shares))
+}
+
+/// Splits the shares of a seed into `(position_shares, dead_shares)`
+///
+/// Both are priced on an empty market; `dead_shares` is the value of
+/// MIN_SEED_ASSETS.
+///
+/// **Errors:**
+/// - ZeroAmount: `assets < MIN_SEED_ASSETS`
+pub fn seed_split(assets: u64) -> Result<(u64, u64)> {
+    require!(assets >= MIN_SEED_ASSETS, PelagoError::ZeroAmount);
+    let shares = to_shares_down(assets, 0, 0)?;
+    let dead_shares = to_shares_down(MIN_SEED_ASSETS, 0, 0)?;
+    Ok((shares - dead_shares, dead_shares))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_split_locks_minimum() {
+        let (position, dead) = seed_split(MIN_SEED_ASSETS).unwrap();
+        assert_eq!(position, 0);
+        assert_eq!(dead, MIN_SEED_ASSETS * 1_000_000);
+
+        let (position, dead) = seed_split(1_000_000_000).unwrap();
+        assert_eq!(dead, MIN_SEED_ASSETS * 1_000_000);
+        assert_eq!(position + dead, 1_000_000_000 * 1_000_000);
+
+        assert_eq!(
+            seed_split(MIN_SEED_ASSETS - 1).unwrap_err(),
+            PelagoError::ZeroAmount.into()
+        );
+    }
+    #[test]
+    fn test_seed_after_idle_period_earns_no_backlog() {
+        use crate::utils::rewards::pending_rewards;
+
+        // Rewards configured at creation, market left unseeded for 30 days
+        let mut market = Market {
+            reward_rate_per_second: 1_000,
+            last_update: 0,
+            ..Default::default()
+        };
+        let mut position = UserPosition::default();
+        let idle = 30 * 86_400;
+
+        let (position_shares, _) =
+            credit_seed(&mut market, &mut position, 1_000_000_000, idle).unwrap();
+        assert_eq!(market.last_update, idle);
+        assert_eq!(market.reward_index, 0);
+        assert_eq!(position.last_activity, idle);
+
+        // Only the emission after the seed is shared out, pro rata with the
+        // dead shares
+        accrue_interest_at(&mut market, idle + 100).unwrap();
+        let expected = 100 * 1_000 * position_shares as u128 / market.total_supply_shares as u128;
+        let earned = pending_rewards(&market, &position).unwrap() as u128;
+        assert!(earned <= expected && expected - earned <= 1);
+    }
+}
@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::utils::interest::accrue_interest;
+
+/// Choose whether interest accrues while a market is paused
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetAccrueWhilePaused<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_accrue_while_paused instruction
+///
+/// Interest accrued so far is settled under the old setting before switching.
+///
+/// **State Changes:**
+/// - market.accrue_while_paused = `accrue`
+pub fn handler(ctx: Context<SetAccrueWhilePaused>, accrue: bool) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    accrue_interest(market)?;
+    market.accrue_while_paused = accrue;
+
+    msg!(
+        "Accrue-while-paused updated: market={}, accrue={}",
+        market.key(),
+        accrue
+    );
+
+    Ok(())
+}
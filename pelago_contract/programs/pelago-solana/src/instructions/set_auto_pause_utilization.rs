@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::BPS_DENOMINATOR;
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Configure a market's utilization circuit breaker
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetAutoPauseUtilization<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_auto_pause_utilization instruction
+///
+/// **Validation:**
+/// - `auto_pause_utilization_bps` must be <= 10_000 (100% utilization)
+///
+/// **State Changes:**
+/// - market.auto_pause_utilization_bps = `auto_pause_utilization_bps` (0 = disabled)
+pub fn handler(
+    ctx: Context<SetAutoPauseUtilization>,
+    auto_pause_utilization_bps: u16,
+) -> Result<()> {
+    require!(
+        (auto_pause_utilization_bps as u64) <= BPS_DENOMINATOR,
+        PelagoError::InvalidParameter
+    );
+
+    let market = &mut ctx.accounts.market;
+    market.auto_pause_utilization_bps = auto_pause_utilization_bps;
+
+    msg!(
+        "Auto-pause watermark updated: market={}, auto_pause_utilization_bps={}",
+        market.key(),
+        auto_pause_utilization_bps
+    );
+
+    Ok(())
+}
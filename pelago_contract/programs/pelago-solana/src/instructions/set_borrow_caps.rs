@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::BPS_DENOMINATOR;
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Configure a market's borrow caps
+///
+/// **Access Control:** The market authority or manager
+#[derive(Accounts)]
+pub struct SetBorrowCaps<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        constraint = market.is_operator(&authority.key()) @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority or manager (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_borrow_caps instruction
+///
+/// **Validation:**
+/// - `borrow_cap_ratio_bps` must be <= 10_000 (100% of supply)
+///
+/// **State Changes:**
+/// - market.borrow_cap = `borrow_cap` (0 = unlimited)
+/// - market.borrow_cap_ratio_bps = `borrow_cap_ratio_bps` (0 = disabled)
+/// - market.max_borrow_per_position = `max_borrow_per_position` (0 = unlimited)
+///
+/// Caps only gate new borrows; existing debt above a lowered cap is untouched.
+pub fn handler(
+    ctx: Context<SetBorrowCaps>,
+    borrow_cap: u64,
+    borrow_cap_ratio_bps: u16,
+    max_borrow_per_position: u64,
+) -> Result<()> {
+    require!(
+        (borrow_cap_ratio_bps as u64) <= BPS_DENOMINATOR,
+        PelagoError::InvalidParameter
+    );
+
+    let market = &mut ctx.accounts.market;
+    market.borrow_cap = borrow_cap;
+    market.borrow_cap_ratio_bps = borrow_cap_ratio_bps;
+    market.max_borrow_per_position = max_borrow_per_position;
+
+    msg!(
+        "Borrow caps updated: market={}, borrow_cap={}, borrow_cap_ratio_bps={}, max_borrow_per_position={}",
+        market.key(),
+        borrow_cap,
+        borrow_cap_ratio_bps,
+        max_borrow_per_position
+    );
+
+    Ok(())
+}
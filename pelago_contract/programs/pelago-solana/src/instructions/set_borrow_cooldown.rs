@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Configure the minimum time between borrows of one position
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetBorrowCooldown<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_borrow_cooldown instruction
+///
+/// **State Changes:**
+/// - market.borrow_cooldown_secs = `borrow_cooldown_secs` (0 = disabled)
+pub fn handler(ctx: Context<SetBorrowCooldown>, borrow_cooldown_secs: u32) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.borrow_cooldown_secs = borrow_cooldown_secs;
+
+    msg!(
+        "Borrow cooldown updated: market={}, borrow_cooldown_secs={}",
+        market.key(),
+        borrow_cooldown_secs
+    );
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Configure a market's collateral cap
+///
+/// **Access Control:** The market authority or manager
+#[derive(Accounts)]
+pub struct SetCollateralCap<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        constraint = market.is_operator(&authority.key()) @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority or manager (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_collateral_cap instruction
+///
+/// Like the borrow caps, only new deposits are gated; collateral already
+/// above a lowered cap is untouched.
+///
+/// **State Changes:**
+/// - market.collateral_cap = `collateral_cap` (0 = unlimited)
+pub fn handler(ctx: Context<SetCollateralCap>, collateral_cap: u64) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.collateral_cap = collateral_cap;
+
+    msg!(
+        "Collateral cap updated: market={}, collateral_cap={}",
+        market.key(),
+        collateral_cap
+    );
+
+    Ok(())
+}
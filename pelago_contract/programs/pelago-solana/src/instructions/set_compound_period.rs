@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::utils::interest::{accrue_interest, ACCRUAL_CHECKPOINT_SECS, MAX_ACCRUAL_CHECKPOINTS};
+
+/// Configure how often a market's interest compounds
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetCompoundPeriod<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_compound_period instruction
+///
+/// Interest is accrued under the old period before switching; with a
+/// period already set, an incomplete period still pending at that point
+/// is accrued under the new one.
+///
+/// **Validation:**
+/// - `compound_period_secs` must be <= ACCRUAL_CHECKPOINT_SECS × MAX_ACCRUAL_CHECKPOINTS (365 days)
+///
+/// **State Changes:**
+/// - market.compound_period_secs = `compound_period_secs` (0 = per accrual)
+pub fn handler(ctx: Context<SetCompoundPeriod>, compound_period_secs: u32) -> Result<()> {
+    require!(
+        (compound_period_secs as i64) <= ACCRUAL_CHECKPOINT_SECS * MAX_ACCRUAL_CHECKPOINTS,
+        PelagoError::InvalidParameter
+    );
+
+    let market = &mut ctx.accounts.market;
+    accrue_interest(market)?;
+    market.compound_period_secs = compound_period_secs;
+
+    msg!(
+        "Compound period updated: market={}, compound_period_secs={}",
+        market.key(),
+        compound_period_secs
+    );
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Allow or reject liquidators liquidating their own positions
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetDisallowSelfLiquidation<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_disallow_self_liquidation instruction
+///
+/// **State Changes:**
+/// - market.disallow_self_liquidation = `disallow`
+pub fn handler(ctx: Context<SetDisallowSelfLiquidation>, disallow: bool) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.disallow_self_liquidation = disallow;
+
+    msg!(
+        "Self-liquidation updated: market={}, disallow={}",
+        market.key(),
+        disallow
+    );
+
+    Ok(())
+}
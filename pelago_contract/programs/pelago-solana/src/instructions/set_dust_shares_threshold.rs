@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_DUST_SHARES_THRESHOLD;
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Configure the borrow shares collateral health checks treat as zero debt
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetDustSharesThreshold<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_dust_shares_threshold instruction
+///
+/// **State Changes:**
+/// - market.dust_shares_threshold = `dust_shares_threshold` (0 = disabled)
+///
+/// **Errors:**
+/// - InvalidParameter: `dust_shares_threshold > MAX_DUST_SHARES_THRESHOLD`
+pub fn handler(ctx: Context<SetDustSharesThreshold>, dust_shares_threshold: u64) -> Result<()> {
+    require!(
+        dust_shares_threshold <= MAX_DUST_SHARES_THRESHOLD,
+        PelagoError::InvalidParameter
+    );
+
+    let market = &mut ctx.accounts.market;
+    market.dust_shares_threshold = dust_shares_threshold;
+
+    msg!(
+        "Dust shares threshold updated: market={}, dust_shares_threshold={}",
+        market.key(),
+        dust_shares_threshold
+    );
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Choose which way asset-mode supply shares are rounded for a market
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetFavorUserOnSupply<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_favor_user_on_supply instruction
+///
+/// Rounding shares up hands sub-share dust to suppliers at the expense of
+/// existing suppliers; see `supply_shares_for_assets` before enabling it.
+///
+/// **State Changes:**
+/// - market.favor_user_on_supply = `favor_user`
+pub fn handler(ctx: Context<SetFavorUserOnSupply>, favor_user: bool) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.favor_user_on_supply = favor_user;
+
+    msg!(
+        "Supply rounding updated: market={}, favor_user={}",
+        market.key(),
+        favor_user
+    );
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{MAX_FEE_BPS, MAX_ORIGINATION_FEE_BPS};
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::utils::interest::accrue_interest;
+
+/// Set the protocol fees: interest fee and borrow origination fee
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetFee<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_fee instruction
+///
+/// Interest accrued so far is settled under the old fee before switching
+/// (Pelago.sol: setFee accrues first).
+///
+/// **Validation:**
+/// - `fee_bps` must be <= MAX_FEE_BPS (25%)
+/// - `origination_fee_bps` must be <= MAX_ORIGINATION_FEE_BPS (5%)
+///
+/// **State Changes:**
+/// - market.fee_bps = `fee_bps`
+/// - market.origination_fee_bps = `origination_fee_bps`
+pub fn handler(ctx: Context<SetFee>, fee_bps: u16, origination_fee_bps: u16) -> Result<()> {
+    require!(fee_bps <= MAX_FEE_BPS, PelagoError::InvalidParameter);
+    require!(
+        origination_fee_bps <= MAX_ORIGINATION_FEE_BPS,
+        PelagoError::InvalidParameter
+    );
+
+    let market = &mut ctx.accounts.market;
+    accrue_interest(market)?;
+    market.fee_bps = fee_bps;
+    market.origination_fee_bps = origination_fee_bps;
+
+    msg!(
+        "Fees updated: market={}, fee_bps={}, origination_fee_bps={}",
+        market.key(),
+        fee_bps,
+        origination_fee_bps
+    );
+
+    Ok(())
+}
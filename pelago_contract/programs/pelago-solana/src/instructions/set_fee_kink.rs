@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::BPS_DENOMINATOR;
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::utils::interest::accrue_interest;
+
+/// Configure the utilization kink below which no interest fee is taken
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetFeeKink<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_fee_kink instruction
+///
+/// Interest accrued so far is settled under the old kink before switching.
+///
+/// **Validation:**
+/// - `fee_kink_utilization_bps` must be < 10_000
+///
+/// **State Changes:**
+/// - market.fee_kink_utilization_bps = `fee_kink_utilization_bps` (0 = fee always applies)
+pub fn handler(ctx: Context<SetFeeKink>, fee_kink_utilization_bps: u16) -> Result<()> {
+    require!(
+        (fee_kink_utilization_bps as u64) < BPS_DENOMINATOR,
+        PelagoError::InvalidParameter
+    );
+
+    let market = &mut ctx.accounts.market;
+    accrue_interest(market)?;
+    market.fee_kink_utilization_bps = fee_kink_utilization_bps;
+
+    msg!(
+        "Fee kink updated: market={}, fee_kink_utilization_bps={}",
+        market.key(),
+        fee_kink_utilization_bps
+    );
+
+    Ok(())
+}
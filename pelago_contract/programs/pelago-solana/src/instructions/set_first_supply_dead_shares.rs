@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_FIRST_SUPPLY_DEAD_SHARES;
+use crate::error::PelagoError;
+use crate::instructions::supply::recommended_first_supply_dead_shares;
+use crate::state::Market;
+
+/// Configure the supply shares locked out of a market's first supply
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetFirstSupplyDeadShares<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_first_supply_dead_shares instruction
+///
+/// Only affects a supply into an empty market; once the market has supply
+/// shares the setting has no effect.
+///
+/// **State Changes:**
+/// - market.first_supply_dead_shares = `dead_shares` (0 = disabled)
+///
+/// **Errors:**
+/// - InvalidParameter: `dead_shares` above both MAX_FIRST_SUPPLY_DEAD_SHARES
+///   and the recommendation for the loan decimals
+///   (see `recommended_first_supply_dead_shares`)
+pub fn handler(ctx: Context<SetFirstSupplyDeadShares>, dead_shares: u64) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    let max_dead_shares =
+        MAX_FIRST_SUPPLY_DEAD_SHARES.max(recommended_first_supply_dead_shares(market.loan_decimals));
+    require!(dead_shares <= max_dead_shares, PelagoError::InvalidParameter);
+
+    market.first_supply_dead_shares = dead_shares;
+
+    msg!(
+        "First supply dead shares updated: market={}, dead_shares={}",
+        market.key(),
+        dead_shares
+    );
+
+    Ok(())
+}
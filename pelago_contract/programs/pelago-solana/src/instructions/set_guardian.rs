@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Config;
+
+/// Rotate the protocol emergency guardian
+///
+/// **Access Control:** Only the config admin
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    /// Config PDA
+    #[account(
+        mut,
+        seeds = [Config::SEED_PREFIX],
+        bump = config.bump,
+        has_one = admin @ PelagoError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Protocol admin (signer)
+    pub admin: Signer<'info>,
+}
+
+/// Handler for set_guardian instruction
+///
+/// **State Changes:**
+/// - config.guardian = `guardian`
+pub fn handler(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    let previous = config.guardian;
+    config.guardian = guardian;
+
+    msg!("Guardian updated: previous={}, new={}", previous, guardian);
+
+    Ok(())
+}
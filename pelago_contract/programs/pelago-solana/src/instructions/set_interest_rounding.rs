@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::utils::interest::accrue_interest;
+
+/// Choose how accrued interest is rounded for a market
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetInterestRounding<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_interest_rounding instruction
+///
+/// Interest accrued so far is settled under the old mode before switching.
+///
+/// **State Changes:**
+/// - market.round_interest_up = `round_up`
+pub fn handler(ctx: Context<SetInterestRounding>, round_up: bool) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    accrue_interest(market)?;
+    market.round_interest_up = round_up;
+
+    msg!(
+        "Interest rounding updated: market={}, round_up={}",
+        market.key(),
+        round_up
+    );
+
+    Ok(())
+}
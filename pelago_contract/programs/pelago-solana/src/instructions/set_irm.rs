@@ -0,0 +1,237 @@
+//! Set IRM Instruction
+//!
+//! Tunes a market's kinked interest rate model (see `compute_borrow_rate`).
+//! Interest up to now is accrued on the old curve first, so past interest
+//! is never repriced by the new one.
+
+use anchor_lang::prelude::*;
+
+use crate::constants::{BPS_DENOMINATOR, MAX_IRM_RATE_WAD};
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::utils::interest::{
+    accrue_interest_at, compute_borrow_rate, supply_rate_wad, FIXED_ANNUAL_RATE_WAD,
+};
+
+/// Update the kinked interest rate model parameters
+///
+/// **Access Control:** The market authority or manager
+#[derive(Accounts)]
+pub struct SetIrm<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        constraint = market.is_operator(&authority.key()) @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority or manager (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Kinked rate model parameters (see `compute_borrow_rate`)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IrmParams {
+    /// Annual rate at 0% utilization (precision: 1e18)
+    pub base_rate_wad: u64,
+
+    /// Rate added from 0% up to the kink (precision: 1e18)
+    pub slope1_wad: u64,
+
+    /// Rate added from the kink up to 100% (precision: 1e18)
+    pub slope2_wad: u64,
+
+    /// Utilization where slope2 takes over (bps, 0 = back to the fixed rate)
+    pub kink_utilization_bps: u16,
+}
+
+/// Handler for set_irm instruction
+///
+/// **State Changes:**
+/// - Interest accrued up to now on the old curve
+/// - market.irm_* = `params`
+/// - market.current_borrow_rate_wad / current_supply_rate_wad refreshed
+///
+/// **Errors:**
+/// - InvalidParameter: See `validate_irm`
+pub fn handler(ctx: Context<SetIrm>, params: IrmParams) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    let now = Clock::get()?.unix_timestamp;
+    update_irm_at(market, params, now)?;
+
+    msg!(
+        "IRM updated: market={}, base={}, slope1={}, slope2={}, kink_bps={}",
+        market.key(),
+        params.base_rate_wad,
+        params.slope1_wad,
+        params.slope2_wad,
+        params.kink_utilization_bps
+    );
+
+    emit!(IrmUpdatedEvent {
+        market: market.key(),
+        base_rate_wad: params.base_rate_wad,
+        slope1_wad: params.slope1_wad,
+        slope2_wad: params.slope2_wad,
+        kink_utilization_bps: params.kink_utilization_bps,
+        borrow_rate_wad: market.current_borrow_rate_wad,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Accrues `market` to `now` on its current curve, then switches to `params`
+pub fn update_irm_at(market: &mut Market, params: IrmParams, now: i64) -> Result<()> {
+    validate_irm(&params)?;
+    accrue_interest_at(market, now)?;
+
+    market.irm_base_rate_wad = params.base_rate_wad;
+    market.irm_slope1_wad = params.slope1_wad;
+    market.irm_slope2_wad = params.slope2_wad;
+    market.irm_kink_utilization_bps = params.kink_utilization_bps;
+    market.current_borrow_rate_wad = compute_borrow_rate(market);
+    market.current_supply_rate_wad = supply_rate_wad(market)?;
+    Ok(())
+}
+
+/// Checks the ordering and ranges of rate model parameters
+///
+/// **Rules:**
+/// - `kink_utilization_bps == 0` turns the model off; the rates must be 0
+/// - Otherwise `0 < kink <= 10_000`, `slope1 <= slope2` (the curve
+///   steepens past the kink) and `base + slope1 + slope2 <= MAX_IRM_RATE_WAD`
+///
+/// **Errors:**
+/// - InvalidParameter: Any rule is broken
+pub fn validate_irm(params: &IrmParams) -> Result<()> {
+    let max_rate = params.base_rate_wad as u128 + params.slope1_wad as u128 + params.slope2_wad as u128;
+    if params.kink_utilization_bps == 0 {
+        require!(max_rate == 0, PelagoError::InvalidParameter);
+        return Ok(());
+    }
+
+    require!(
+        params.kink_utilization_bps as u64 <= BPS_DENOMINATOR
+            && params.slope1_wad <= params.slope2_wad
+            && max_rate <= MAX_IRM_RATE_WAD,
+        PelagoError::InvalidParameter
+    );
+    Ok(())
+}
+
+/// Annual borrow rates (WAD) of `irm` at 0% and 100% utilization
+///
+/// Mirrors `compute_borrow_rate`: a model without a kink charges
+/// FIXED_ANNUAL_RATE_WAD throughout.
+pub fn irm_rate_range(irm: &IrmParams) -> (u128, u128) {
+    if irm.kink_utilization_bps == 0 {
+        return (FIXED_ANNUAL_RATE_WAD, FIXED_ANNUAL_RATE_WAD);
+    }
+    let base = irm.base_rate_wad as u128;
+    (base, base + irm.slope1_wad as u128 + irm.slope2_wad as u128)
+}
+
+/// Event emitted when a market's rate model changes
+#[event]
+pub struct IrmUpdatedEvent {
+    /// Market public key
+    pub market: Pubkey,
+
+    /// New annual rate at 0% utilization (precision: 1e18)
+    pub base_rate_wad: u64,
+
+    /// New slope below the kink (precision: 1e18)
+    pub slope1_wad: u64,
+
+    /// New slope above the kink (precision: 1e18)
+    pub slope2_wad: u64,
+
+    /// New kink utilization (bps)
+    pub kink_utilization_bps: u16,
+
+    /// Borrow rate at the current utilization under the new curve
+    pub borrow_rate_wad: u128,
+
+    /// Timestamp of the change (interest accrued up to here)
+    pub timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::interest::{
+        accrue_interest_at, w_taylor_compounded, FIXED_ANNUAL_RATE_WAD, SECONDS_PER_YEAR, WAD,
+    };
+
+    /// 2%, +8% up to 80% utilization, +100% above
+    const CURVE: IrmParams = IrmParams {
+        base_rate_wad: 20_000_000_000_000_000,
+        slope1_wad: 80_000_000_000_000_000,
+        slope2_wad: 1_000_000_000_000_000_000,
+        kink_utilization_bps: 8_000,
+    };
+
+    #[test]
+    fn test_irm_update_accrues_on_old_curve_first() {
+        // 1000 USDC supplied, 900 borrowed: 90% utilization
+        let start = 1_700_000_000;
+        let mut market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_supply_shares: 1_000_000_000_000_000,
+            total_borrow_assets: 900_000_000,
+            total_borrow_shares: 900_000_000_000_000,
+            last_update: start,
+            ..Default::default()
+        };
+
+        // A day under the fixed 5% rate
+        let mut fixed_only = market.clone();
+        accrue_interest_at(&mut fixed_only, start + 86_400).unwrap();
+        update_irm_at(&mut market, CURVE, start + 86_400).unwrap();
+        assert_eq!(market.total_borrow_assets, fixed_only.total_borrow_assets);
+        assert_eq!(market.last_update, start + 86_400);
+
+        // 90% utilization: 2% + 8% + 100% × (90% - 80%) / 20% ≈ 60%
+        let rate = compute_borrow_rate(&market);
+        assert_eq!(market.current_borrow_rate_wad, rate);
+        assert!(rate.abs_diff(600_000_000_000_000_000) < WAD / 1_000);
+
+        // The next day accrues on the new curve
+        let before = market.total_borrow_assets;
+        accrue_interest_at(&mut market, start + 2 * 86_400).unwrap();
+        let growth = w_taylor_compounded(rate / SECONDS_PER_YEAR, 86_400).unwrap();
+        let expected = before as u128 * growth / WAD;
+        assert_eq!((market.total_borrow_assets - before) as u128, expected);
+        assert!(expected > before as u128 * FIXED_ANNUAL_RATE_WAD / WAD / 365 * 10);
+    }
+
+    #[test]
+    fn test_irm_parameters_validated() {
+        assert!(validate_irm(&CURVE).is_ok());
+        assert!(validate_irm(&IrmParams::default()).is_ok());
+
+        let invalid = [
+            // Rates without a kink
+            IrmParams { kink_utilization_bps: 0, ..CURVE },
+            // Kink past 100%
+            IrmParams { kink_utilization_bps: 10_001, ..CURVE },
+            // Flatter above the kink than below
+            IrmParams { slope1_wad: CURVE.slope2_wad + 1, ..CURVE },
+            // Above the rate ceiling at full utilization
+            IrmParams { slope2_wad: MAX_IRM_RATE_WAD as u64, ..CURVE },
+        ];
+        for params in invalid {
+            assert_eq!(
+                validate_irm(&params).unwrap_err(),
+                PelagoError::InvalidParameter.into()
+            );
+        }
+    }
+}
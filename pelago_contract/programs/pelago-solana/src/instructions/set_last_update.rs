@@ -0,0 +1,57 @@
+//! Set Last Update Instruction (test-utils only)
+//!
+//! Warps `market.last_update` to an arbitrary timestamp so tests can simulate
+//! elapsed time deterministically instead of waiting on the validator clock.
+//!
+//! **Feature Gate:** Only compiled with `--features test-utils`. The module,
+//! accounts struct and program entrypoint are all absent from default builds,
+//! so production deployments cannot expose it.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Warp a market's accrual clock (test-utils only)
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetLastUpdate<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_last_update instruction
+///
+/// **State Changes:**
+/// - market.last_update = `last_update` (no interest is accrued)
+///
+/// **Usage:** Set `last_update = now - N` so the next operation accrues
+/// exactly N seconds of interest.
+pub fn handler(ctx: Context<SetLastUpdate>, last_update: i64) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    let previous = market.last_update;
+    market.last_update = last_update;
+
+    msg!(
+        "TEST ONLY last_update warped: market={}, previous={}, new={}",
+        market.key(),
+        previous,
+        last_update
+    );
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Configure the maximum price age accepted by liquidations
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetLiquidationStaleness<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_liquidation_staleness instruction
+///
+/// **State Changes:**
+/// - market.liquidation_max_staleness_secs = `liquidation_max_staleness_secs` (0 = off)
+pub fn handler(
+    ctx: Context<SetLiquidationStaleness>,
+    liquidation_max_staleness_secs: u32,
+) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.liquidation_max_staleness_secs = liquidation_max_staleness_secs;
+
+    msg!(
+        "Liquidation staleness updated: market={}, liquidation_max_staleness_secs={}",
+        market.key(),
+        liquidation_max_staleness_secs
+    );
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Appoint or remove a market's manager
+///
+/// The manager may pause the market, set borrow caps and update the IRM.
+/// Fees, oracle prices and the manager itself stay with the authority.
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetManager<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_manager instruction
+///
+/// **State Changes:**
+/// - market.manager = `manager` (Pubkey::default() = none)
+pub fn handler(ctx: Context<SetManager>, manager: Pubkey) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    let previous = market.manager;
+    market.manager = manager;
+
+    msg!(
+        "Manager updated: market={}, previous={}, new={}",
+        market.key(),
+        previous,
+        manager
+    );
+
+    Ok(())
+}
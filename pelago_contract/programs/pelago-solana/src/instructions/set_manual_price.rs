@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Set a manual collateral price for a market
+///
+/// Intended for closed betas: bridges the gap between the hardcoded
+/// FIXED_ORACLE_PRICE and a full oracle integration.
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetManualPrice<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_manual_price instruction
+///
+/// **Validation:**
+/// - `price` must be non-zero (PRICE_PRECISION scale, see FIXED_ORACLE_PRICE)
+///
+/// **State Changes:**
+/// - market.manual_price = `price`
+/// - market.manual_price_enabled = `enabled`
+/// - market.price_updated_at = now
+pub fn handler(ctx: Context<SetManualPrice>, price: u64, enabled: bool) -> Result<()> {
+    require!(price > 0, PelagoError::InvalidPrice);
+
+    let market = &mut ctx.accounts.market;
+    market.manual_price = price;
+    market.manual_price_enabled = enabled;
+    market.price_updated_at = Clock::get()?.unix_timestamp;
+
+    msg!(
+        "Manual price updated: market={}, price={}, enabled={}",
+        market.key(),
+        price,
+        enabled
+    );
+
+    Ok(())
+}
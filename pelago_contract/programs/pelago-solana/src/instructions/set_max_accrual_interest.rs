@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::BPS_DENOMINATOR;
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::utils::interest::accrue_interest;
+
+/// Configure the per-accrual interest cap
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetMaxAccrualInterest<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_max_accrual_interest instruction
+///
+/// Interest accrued so far is settled under the old cap before switching.
+///
+/// **Validation:**
+/// - `max_accrual_interest_bps` must be <= 10_000 (100% of borrows)
+///
+/// **State Changes:**
+/// - market.max_accrual_interest_bps = `max_accrual_interest_bps` (0 = uncapped)
+pub fn handler(ctx: Context<SetMaxAccrualInterest>, max_accrual_interest_bps: u16) -> Result<()> {
+    require!(
+        (max_accrual_interest_bps as u64) <= BPS_DENOMINATOR,
+        PelagoError::InvalidParameter
+    );
+
+    let market = &mut ctx.accounts.market;
+    accrue_interest(market)?;
+    market.max_accrual_interest_bps = max_accrual_interest_bps;
+
+    msg!(
+        "Accrual interest cap updated: market={}, max_accrual_interest_bps={}",
+        market.key(),
+        max_accrual_interest_bps
+    );
+
+    Ok(())
+}
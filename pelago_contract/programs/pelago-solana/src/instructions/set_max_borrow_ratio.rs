@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::BPS_DENOMINATOR;
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::utils::interest::accrue_interest;
+
+/// Configure the borrow-to-supply ratio ceiling monitored on accrual
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetMaxBorrowRatio<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_max_borrow_ratio instruction
+///
+/// Interest accrued so far is checked against the old ceiling before switching.
+///
+/// **Validation:**
+/// - `max_borrow_ratio_bps` must be <= 10_000 (100% of supply)
+///
+/// **State Changes:**
+/// - market.max_borrow_ratio_bps = `max_borrow_ratio_bps` (0 = off)
+pub fn handler(ctx: Context<SetMaxBorrowRatio>, max_borrow_ratio_bps: u16) -> Result<()> {
+    require!(
+        (max_borrow_ratio_bps as u64) <= BPS_DENOMINATOR,
+        PelagoError::InvalidParameter
+    );
+
+    let market = &mut ctx.accounts.market;
+    accrue_interest(market)?;
+    market.max_borrow_ratio_bps = max_borrow_ratio_bps;
+
+    msg!(
+        "Borrow ratio ceiling updated: market={}, max_borrow_ratio_bps={}",
+        market.key(),
+        max_borrow_ratio_bps
+    );
+
+    Ok(())
+}
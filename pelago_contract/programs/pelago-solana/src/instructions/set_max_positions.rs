@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Configure the maximum number of open positions in a market
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetMaxPositions<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_max_positions instruction
+///
+/// **State Changes:**
+/// - market.max_positions = `max_positions` (0 = unlimited)
+///
+/// Lowering the cap below `open_positions` only blocks new positions;
+/// existing ones are unaffected.
+pub fn handler(ctx: Context<SetMaxPositions>, max_positions: u32) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.max_positions = max_positions;
+
+    msg!(
+        "Max positions updated: market={}, max_positions={}, open_positions={}",
+        market.key(),
+        max_positions,
+        market.open_positions
+    );
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::instructions::supply::min_initial_deposit_base_units;
+use crate::state::Market;
+
+/// Configure the smallest first supply into an empty market
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetMinInitialDeposit<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_min_initial_deposit instruction
+///
+/// **State Changes:**
+/// - market.min_initial_deposit = `whole_tokens × 10^loan_decimals` (0 = none)
+///
+/// **Errors:**
+/// - MathOverflow: The base-unit minimum does not fit in u64
+pub fn handler(ctx: Context<SetMinInitialDeposit>, whole_tokens: u64) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.min_initial_deposit = min_initial_deposit_base_units(whole_tokens, market.loan_decimals)?;
+
+    msg!(
+        "Min initial deposit updated: market={}, whole_tokens={}, base_units={}",
+        market.key(),
+        whole_tokens,
+        market.min_initial_deposit
+    );
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Configure the minimum amount a partial liquidation must repay
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetMinLiquidationAssets<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_min_liquidation_assets instruction
+///
+/// **State Changes:**
+/// - market.min_liquidation_assets = `min_liquidation_assets` (0 = no minimum)
+pub fn handler(ctx: Context<SetMinLiquidationAssets>, min_liquidation_assets: u64) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.min_liquidation_assets = min_liquidation_assets;
+
+    msg!(
+        "Min liquidation assets updated: market={}, min_liquidation_assets={}",
+        market.key(),
+        min_liquidation_assets
+    );
+
+    Ok(())
+}
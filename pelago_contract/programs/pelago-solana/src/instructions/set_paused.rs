@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::instructions::guardian_pause::MarketPausedEvent;
+use crate::utils::interest::accrue_interest;
+
+/// Pause or unpause a market
+///
+/// **Access Control:** The market authority or manager
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        constraint = market.is_operator(&authority.key()) @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority or manager (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_paused instruction
+///
+/// Interest is settled up to now under the old pause state, so a market
+/// with `accrue_while_paused` off charges exactly the unpaused periods.
+///
+/// **State Changes:**
+/// - market.paused = `paused`
+pub fn handler(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    accrue_interest(market)?;
+    market.paused = paused;
+
+    msg!("Market pause updated: market={}, paused={}", market.key(), paused);
+
+    emit!(MarketPausedEvent {
+        market: market.key(),
+        by: ctx.accounts.authority.key(),
+        paused,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::{Market, UserPosition};
+
+/// Freeze or unfreeze one position for a compliance hold
+///
+/// Unlike `set_paused`, only the targeted position is affected: it can no
+/// longer borrow or withdraw, but can still repay and supply to reduce risk.
+/// Liquidations still apply.
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetPositionFrozen<'info> {
+    /// Market account
+    #[account(
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Position to freeze or unfreeze
+    #[account(
+        mut,
+        constraint = user_position.market == market.key() @ PelagoError::InvalidParameter,
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_position_frozen instruction
+///
+/// **State Changes:**
+/// - user_position.frozen = `frozen`
+pub fn handler(ctx: Context<SetPositionFrozen>, frozen: bool) -> Result<()> {
+    let user_position = &mut ctx.accounts.user_position;
+    user_position.frozen = frozen;
+
+    msg!(
+        "Position freeze updated: market={}, user={}, frozen={}",
+        ctx.accounts.market.key(),
+        user_position.user,
+        frozen
+    );
+
+    Ok(())
+}
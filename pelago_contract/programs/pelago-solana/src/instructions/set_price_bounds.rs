@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Set the plausible collateral price range of a market
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetPriceBounds<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_price_bounds instruction
+///
+/// Prices outside the band are rejected by `utils::oracle` with
+/// OraclePriceOutOfBounds. A zero bound disables that side.
+///
+/// **State Changes:**
+/// - market.min_sane_price = `min_sane_price`
+/// - market.max_sane_price = `max_sane_price`
+///
+/// **Errors:**
+/// - InvalidParameter: both bounds set and `min_sane_price > max_sane_price`
+pub fn handler(ctx: Context<SetPriceBounds>, min_sane_price: u64, max_sane_price: u64) -> Result<()> {
+    require!(
+        max_sane_price == 0 || min_sane_price <= max_sane_price,
+        PelagoError::InvalidParameter
+    );
+
+    let market = &mut ctx.accounts.market;
+    market.min_sane_price = min_sane_price;
+    market.max_sane_price = max_sane_price;
+
+    msg!(
+        "Price bounds updated: market={}, min={}, max={}",
+        market.key(),
+        min_sane_price,
+        max_sane_price
+    );
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{MAX_FEE_BPS, MAX_LLTV};
+use crate::error::PelagoError;
+use crate::state::{Config, ProtocolConfig};
+
+/// Create or update the protocol-wide market defaults
+///
+/// **Access Control:** Only the config admin
+#[derive(Accounts)]
+pub struct SetProtocolConfig<'info> {
+    /// Config PDA (source of the admin key)
+    #[account(
+        seeds = [Config::SEED_PREFIX],
+        bump = config.bump,
+        has_one = admin @ PelagoError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// ProtocolConfig PDA (created on first call)
+    /// Seeds: ["protocol-config"]
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = ProtocolConfig::LEN,
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Protocol admin (signer, pays for the account on first call)
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// Solana system program
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for set_protocol_config instruction
+///
+/// Existing markets are unaffected; the values only apply to markets
+/// initialized afterwards with the config passed.
+///
+/// **Validation:**
+/// - `default_fee_bps` must be <= MAX_FEE_BPS
+/// - `max_lltv` must be > 0 and <= MAX_LLTV
+/// - `min_rate_wad` must be <= `max_rate_wad`
+///
+/// **State Changes:**
+/// - protocol_config fields = parameters
+pub fn handler(
+    ctx: Context<SetProtocolConfig>,
+    default_fee_bps: u16,
+    fee_recipient: Pubkey,
+    max_lltv: u64,
+    min_rate_wad: u128,
+    max_rate_wad: u128,
+) -> Result<()> {
+    require!(default_fee_bps <= MAX_FEE_BPS, PelagoError::InvalidParameter);
+    require!(max_lltv > 0 && max_lltv <= MAX_LLTV, PelagoError::InvalidLltv);
+    require!(min_rate_wad <= max_rate_wad, PelagoError::InvalidParameter);
+
+    let protocol_config = &mut ctx.accounts.protocol_config;
+    protocol_config.default_fee_bps = default_fee_bps;
+    protocol_config.fee_recipient = fee_recipient;
+    protocol_config.max_lltv = max_lltv;
+    protocol_config.min_rate_wad = min_rate_wad;
+    protocol_config.max_rate_wad = max_rate_wad;
+    protocol_config.bump = ctx.bumps.protocol_config;
+
+    msg!(
+        "Protocol config updated: default_fee_bps={}, fee_recipient={}, max_lltv={}, rate_wad=[{}, {}]",
+        default_fee_bps,
+        fee_recipient,
+        max_lltv,
+        min_rate_wad,
+        max_rate_wad
+    );
+
+    Ok(())
+}
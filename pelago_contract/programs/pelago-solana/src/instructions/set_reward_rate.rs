@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_REWARD_RATE_PER_SECOND;
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::utils::interest::accrue_interest;
+
+/// Change the supply reward emission rate
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetRewardRate<'info> {
+    /// Market account (rewards must be initialized)
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_reward_rate instruction
+///
+/// Accrues first, so the old rate applies up to now and the new one from now on.
+///
+/// **State Changes:**
+/// - market.reward_rate_per_second = `reward_rate_per_second` (0 = stop emission)
+///
+/// **Errors:**
+/// - InvalidParameter: Rewards are not initialized, or the rate exceeds
+///   MAX_REWARD_RATE_PER_SECOND
+pub fn handler(ctx: Context<SetRewardRate>, reward_rate_per_second: u64) -> Result<()> {
+    require!(
+        reward_rate_per_second <= MAX_REWARD_RATE_PER_SECOND,
+        PelagoError::InvalidParameter
+    );
+
+    let market = &mut ctx.accounts.market;
+    require!(market.reward_vault != Pubkey::default(), PelagoError::InvalidParameter);
+
+    accrue_interest(market)?;
+    market.reward_rate_per_second = reward_rate_per_second;
+
+    msg!(
+        "Reward rate updated: market={}, rate={}/s",
+        market.key(),
+        reward_rate_per_second
+    );
+
+    Ok(())
+}
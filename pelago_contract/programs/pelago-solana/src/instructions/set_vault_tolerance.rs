@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Configure how far the loan vault may fall short of market accounting
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SetVaultTolerance<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for set_vault_tolerance instruction
+///
+/// **State Changes:**
+/// - market.vault_tolerance = `vault_tolerance` (loan base units; 0 = exact)
+pub fn handler(ctx: Context<SetVaultTolerance>, vault_tolerance: u64) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.vault_tolerance = vault_tolerance;
+
+    msg!(
+        "Vault tolerance updated: market={}, vault_tolerance={}",
+        market.key(),
+        vault_tolerance
+    );
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+//! Skim Vault Instruction
+//!
+//! Reconciles a loan vault holding more than market accounting expects.
+//! Tokens sent straight to the vault (donations, mistaken transfers) are
+//! never credited to `total_supply_assets`, so they belong to no supplier;
+//! the market authority can move them out.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::error::PelagoError;
+use crate::instructions::supply::expected_vault_balance;
+use crate::state::Market;
+use crate::utils::interest::accrue_interest;
+use crate::utils::vault_snapshot::snapshot_balance_delta;
+
+/// Transfer the loan vault's surplus over market accounting
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SkimVault<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market's loan token vault (source of the surplus)
+    #[account(
+        mut,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
+    )]
+    pub loan_vault: Account<'info, TokenAccount>,
+
+    /// Receiver loan token account
+    #[account(
+        mut,
+        constraint = receiver_token_account.mint == market.loan_token_mint @ PelagoError::UninitializedMarket,
+    )]
+    pub receiver_token_account: Account<'info, TokenAccount>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler for skim_vault instruction
+///
+/// Interest is accrued first, so the surplus is measured against current
+/// accounting. Market state is unchanged; only the vault balance drops back
+/// to `expected_vault_balance`.
+///
+/// **Errors:**
+/// - Unauthorized: Signer is not the market authority
+/// - ZeroAmount: The vault holds no surplus
+/// - MathOverflow: Calculation overflow
+pub fn handler(ctx: Context<SkimVault>) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    accrue_interest(market)?;
+
+    let surplus = vault_surplus(market, ctx.accounts.loan_vault.amount)?;
+    require!(surplus > 0, PelagoError::ZeroAmount);
+
+    let seeds = &[
+        Market::SEED_PREFIX,
+        market.loan_token_mint.as_ref(),
+        market.collateral_token_mint.as_ref(),
+        &[market.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.loan_vault.to_account_info(),
+            to: ctx.accounts.receiver_token_account.to_account_info(),
+            authority: market.to_account_info(),
+        },
+        signer_seeds,
+    );
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || {
+        token::transfer(cpi_ctx, surplus)
+    })?
+    .require_decrease(surplus)?;
+
+    msg!(
+        "Vault skimmed: market={}, surplus={}, receiver={}",
+        market.key(),
+        surplus,
+        ctx.accounts.receiver_token_account.key()
+    );
+
+    Ok(())
+}
+
+/// Loan tokens in the vault beyond what market accounting expects
+///
+/// **Errors:**
+/// - MathOverflow: Calculation overflow
+pub fn vault_surplus(market: &Market, vault_amount: u64) -> Result<u64> {
+    Ok(vault_amount.saturating_sub(expected_vault_balance(market)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::supply::check_vault_accounting;
+
+    #[test]
+    fn test_skim_restores_expected_balance() {
+        // 1000 USDC supplied, 400 borrowed, 2 USDC of reserves
+        let market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_borrow_assets: 400_000_000,
+            reserves: 2_000_000,
+            ..Default::default()
+        };
+
+        // A 10,000 USDC donation is the surplus; skimming it leaves the
+        // vault exactly at the expected balance
+        let vault = 602_000_000 + 10_000_000_000;
+        let surplus = vault_surplus(&market, vault).unwrap();
+        assert_eq!(surplus, 10_000_000_000);
+        assert!(check_vault_accounting(vault - surplus, &market).is_ok());
+
+        // Nothing to skim from a balanced or short vault
+        assert_eq!(vault_surplus(&market, 602_000_000).unwrap(), 0);
+        assert_eq!(vault_surplus(&market, 1).unwrap(), 0);
+    }
+}
@@ -2,9 +2,12 @@ use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
 use crate::error::PelagoError;
-use crate::state::{Market, UserPosition};
-use crate::utils::shares_math::{to_shares_down, to_assets_up};
+use crate::state::{Market, UserPosition, UserRegistry};
+use crate::utils::shares_math::{to_shares_down, to_shares_up, to_assets_up, VIRTUAL_SHARES};
 use crate::utils::interest::accrue_interest;
+use crate::utils::invariants::check_recovery_invariants;
+use crate::utils::rewards::settle_rewards;
+use crate::utils::vault_snapshot::snapshot_balance_delta;
 
 /// Supply loan assets to the market
 ///
@@ -47,10 +50,23 @@ pub struct Supply<'info> {
     )]
     pub user_position: Account<'info, UserPosition>,
 
+    /// User's market registry PDA (created on the user's first position)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserRegistry::LEN,
+        seeds = [
+            UserRegistry::SEED_PREFIX,
+            user.key().as_ref(),
+        ],
+        bump
+    )]
+    pub user_registry: Account<'info, UserRegistry>,
+
     /// Market's loan token vault (receives the deposit)
     #[account(
         mut,
-        constraint = loan_vault.key() == market.loan_vault @ PelagoError::UninitializedMarket,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
     )]
     pub loan_vault: Account<'info, TokenAccount>,
 
@@ -90,23 +106,36 @@ pub struct Supply<'info> {
 /// **Share Calculation (P1):**
 /// - If assets provided: `shares = toSharesDown(assets, totalAssets, totalShares)`
 ///   - Rounding DOWN: User receives slightly fewer shares → favors protocol
+///   - `market.favor_user_on_supply` switches to `toSharesUp` (see
+///     `supply_shares_for_assets`)
 /// - If shares provided: `assets = toAssetsUp(shares, totalAssets, totalShares)`
 ///   - Rounding UP: User pays slightly more assets → favors protocol
+///   - `max_assets_in > 0` bounds the computed assets (slippage protection
+///     against interest accrued between simulation and execution)
 ///
 /// **State Changes:**
 /// - user_position.supply_shares += calculated_shares
 /// - market.total_supply_assets += calculated_assets
-/// - market.total_supply_shares += calculated_shares
+/// - market.total_supply_shares += calculated_shares (+ dead shares on the
+///   first supply, see `first_supply_split`)
 /// - loan_vault.amount += calculated_assets (via token transfer)
 ///
 /// **Error Cases:**
 /// - InconsistentInput: Both or neither of (assets, shares) are non-zero
+/// - UninitializedMarket: Market fields were never set by `initialize_market`
+///   (a market PDA that was never created fails earlier, in Anchor's
+///   account deserialization, with `AccountNotInitialized`)
+/// - VaultAccountingMismatch: loan vault holds less than market accounting expects
+/// - SlippageExceeded: Shares mode requires more than `max_assets_in` assets
+/// - ZeroAmount: The conversion rounded the assets or the shares to zero
+/// - InitialDepositTooSmall: First supply below `market.min_initial_deposit`
 /// - MathOverflow: Share calculation overflow
 /// - Insufficient user balance (handled by token program)
 pub fn handler(
     ctx: Context<Supply>,
     assets: u64,
     shares: u64,
+    max_assets_in: u64,
 ) -> Result<()> {
     // Step 1: Validate input mutual exclusivity
     // Exactly one of (assets, shares) must be non-zero (Pelago: exactlyOneZero)
@@ -116,44 +145,58 @@ pub fn handler(
     );
 
     let market = &mut ctx.accounts.market;
+    market.require_initialized()?;
+    require!(!market.paused, PelagoError::MarketPaused);
     let user_position = &mut ctx.accounts.user_position;
 
     // Step 2: Accrue interest before any calculation (P1)
     // This ensures share conversion uses up-to-date totalSupplyAssets
     accrue_interest(market)?;
+    let borrow_assets_before = market.total_borrow_assets;
+
+    // Verify the vault still covers accounting before crediting new shares
+    // A shortfall means assets left the vault without being accounted for
+    check_vault_accounting(ctx.accounts.loan_vault.amount, market)?;
 
     // Step 3: Initialize user position fields if this is first interaction
     // (init_if_needed creates account but doesn't initialize fields)
     if user_position.user == Pubkey::default() {
-        user_position.user = ctx.accounts.user.key();
-        user_position.market = market.key();
-        user_position.supply_shares = 0;
-        user_position.borrow_shares = 0;
-        user_position.collateral_amount = 0;
-        user_position.bump = ctx.bumps.user_position;
+        user_position.open(ctx.accounts.user.key(), market.key(), ctx.bumps.user_position)?;
+        market.register_position()?;
+
+        let user_registry = &mut ctx.accounts.user_registry;
+        if user_registry.user == Pubkey::default() {
+            user_registry.user = ctx.accounts.user.key();
+            user_registry.bump = ctx.bumps.user_registry;
+        }
+        user_registry.record_market(market.key())?;
     }
 
     // Step 4: Convert between assets and shares using virtual shares (P1)
     // Dual-parameter mode following Pelago design
-    let (final_assets, final_shares) = if assets > 0 {
+    let (final_assets, minted_shares) = if assets > 0 {
         // Mode 1: User specifies assets, calculate shares
-        // Rounding DOWN: User receives fewer shares → favors protocol
-        let s = to_shares_down(
-            assets,
-            market.total_supply_assets,
-            market.total_supply_shares,
-        )?;
+        // Rounding DOWN by default: User receives fewer shares → favors protocol
+        let s = supply_shares_for_assets(market, assets)?;
         (assets, s)
     } else {
         // Mode 2: User specifies shares, calculate assets
         // Rounding UP: User pays more assets → favors protocol
+        // The first supply also pays for the dead shares locked out of it
+        let minted = shares
+            .checked_add(first_supply_dead_shares(market))
+            .ok_or(PelagoError::MathOverflow)?;
         let a = to_assets_up(
-            shares,
+            minted,
             market.total_supply_assets,
             market.total_supply_shares,
         )?;
-        (a, shares)
+        check_max_assets_in(a, max_assets_in)?;
+        (a, minted)
     };
+    check_initial_deposit(market, final_assets)?;
+    let (final_shares, dead_shares) = first_supply_split(market, minted_shares)?;
+    check_supply_amounts(final_assets, final_shares)?;
 
     msg!(
         "Supply calculation: assets={}, shares={}, total_assets={}, total_shares={}",
@@ -173,9 +216,11 @@ pub fn handler(
         ctx.accounts.token_program.to_account_info(),
         transfer_accounts,
     );
-    token::transfer(cpi_ctx, final_assets)?;
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || token::transfer(cpi_ctx, final_assets))?
+        .require_increase(final_assets)?;
 
     // Step 6: Update user position
+    settle_rewards(market, user_position)?;
     user_position.supply_shares = user_position
         .supply_shares
         .checked_add(final_shares)
@@ -189,8 +234,16 @@ pub fn handler(
 
     market.total_supply_shares = market
         .total_supply_shares
-        .checked_add(final_shares)
+        .checked_add(minted_shares)
         .ok_or(PelagoError::MathOverflow)?;
+    if dead_shares > 0 {
+        market.dead_shares = market
+            .dead_shares
+            .checked_add(dead_shares)
+            .ok_or(PelagoError::MathOverflow)?;
+        msg!("First supply: dead_shares={}", dead_shares);
+    }
+    check_recovery_invariants(market, borrow_assets_before)?;
 
     msg!(
         "Supply success: user={}, assets={}, shares={}, user_total_shares={}, market_total_supply={}",
@@ -201,8 +254,12 @@ pub fn handler(
         market.total_supply_assets
     );
 
+    // Record position activity for dormancy tracking
+    user_position.last_activity = Clock::get()?.unix_timestamp;
+
     // Emit event for off-chain tracking
     emit!(SupplyEvent {
+        market: market.key(),
         user: ctx.accounts.user.key(),
         assets: final_assets,
         shares: final_shares,
@@ -213,16 +270,199 @@ pub fn handler(
     Ok(())
 }
 
+/// Verifies the loan vault holds at least what market accounting expects
+///
+/// **Expected Balance:**
+/// ```text
+/// expected = total_supply_assets + reserves - total_borrow_assets
+/// ```
+///
+/// Reserves are debt owed to the protocol (e.g. origination fees), so they
+/// sit in `total_borrow_assets` without having left the vault. On an
+/// impaired market the debt can exceed `total_supply_assets + reserves`;
+/// the expected balance then floors at zero.
+///
+/// Only shortfalls are rejected: a surplus (a direct donation) is never
+/// credited to `total_supply_assets`, so it cannot move the share price,
+/// and the authority can remove it with `skim_vault`.
+///
+/// **Validation:**
+/// - `vault_amount + market.vault_tolerance >= expected`
+///
+/// **Errors:**
+/// - VaultAccountingMismatch: Shortfall exceeds the market's tolerance
+/// - MathOverflow: total_supply_assets + reserves overflows
+pub fn check_vault_accounting(vault_amount: u64, market: &Market) -> Result<()> {
+    let expected = expected_vault_balance(market)?;
+
+    let shortfall = expected.saturating_sub(vault_amount);
+    if shortfall > market.vault_tolerance {
+        msg!(
+            "Vault accounting mismatch: vault={}, expected={}, shortfall={}",
+            vault_amount,
+            expected,
+            shortfall
+        );
+        return err!(PelagoError::VaultAccountingMismatch);
+    }
+
+    Ok(())
+}
+
+/// Loan vault balance implied by market accounting
+///
+/// `total_supply_assets + reserves - total_borrow_assets`, floored at zero.
+///
+/// **Errors:**
+/// - MathOverflow: total_supply_assets + reserves overflows
+pub fn expected_vault_balance(market: &Market) -> Result<u64> {
+    Ok(market
+        .total_supply_assets
+        .checked_add(market.reserves)
+        .ok_or(PelagoError::MathOverflow)?
+        .saturating_sub(market.total_borrow_assets))
+}
+
+/// Supply shares minted for `assets` in asset mode
+///
+/// Rounds down by default. With `market.favor_user_on_supply` the shares are
+/// rounded up instead, handing the sub-share dust to the supplier. That dust
+/// is paid by existing suppliers, and rounding against the protocol is what
+/// inflation attacks exploit: only enable it on markets whose virtual offsets
+/// and deposit sizes make the per-supply gain negligible (internal or
+/// promotional markets).
+pub fn supply_shares_for_assets(market: &Market, assets: u64) -> Result<u64> {
+    if market.favor_user_on_supply {
+        to_shares_up(assets, market.total_supply_assets, market.total_supply_shares)
+    } else {
+        to_shares_down(assets, market.total_supply_assets, market.total_supply_shares)
+    }
+}
+
+/// Rejects a shares-mode supply whose computed assets exceed the caller's bound
+///
+/// `max_assets_in == 0` disables the check.
+pub fn check_max_assets_in(assets: u64, max_assets_in: u64) -> Result<()> {
+    require!(
+        max_assets_in == 0 || assets <= max_assets_in,
+        PelagoError::SlippageExceeded
+    );
+    Ok(())
+}
+
+/// Dead shares owed by a supply into `market` right now
+///
+/// Non-zero only while the market has no supply shares at all (nobody has
+/// supplied yet, and `seed_market` was not used).
+pub fn first_supply_dead_shares(market: &Market) -> u64 {
+    if market.total_supply_shares == 0 {
+        market.first_supply_dead_shares
+    } else {
+        0
+    }
+}
+
+/// Splits the shares minted by a supply into `(user_shares, dead_shares)`
+///
+/// On the first supply into an empty market, `market.first_supply_dead_shares`
+/// are kept out of the supplier's position. They count towards
+/// `total_supply_shares` (and `market.dead_shares`) but no position owns
+/// them, so the share supply can never drain back to the near-zero state a
+/// first-depositor inflation attack needs.
+///
+/// **Errors:**
+/// - ZeroAmount: The first supply does not exceed the dead shares
+pub fn first_supply_split(market: &Market, minted_shares: u64) -> Result<(u64, u64)> {
+    let dead_shares = first_supply_dead_shares(market);
+    require!(minted_shares > dead_shares, PelagoError::ZeroAmount);
+    Ok((minted_shares - dead_shares, dead_shares))
+}
+
+/// Recommended `first_supply_dead_shares` for a loan token with `loan_decimals`
+///
+/// `VIRTUAL_SHARES` alone is sized for share-price rounding, not for
+/// counted donations: against a 1e9-token donation (MAX_MARKET_TOKENS) the
+/// virtual shares are worth half a token each, and a 1,000-token victim can
+/// lose about half of the deposit. Dead shares raise the share supply the
+/// donation is spread over.
+///
+/// The recommendation locks 0.001 whole tokens at the empty-market price,
+/// `10^loan_decimals × VIRTUAL_SHARES / 1000` shares, so the first supplier
+/// pays the same token amount on every mint:
+///
+/// | decimals | dead shares | victim loss (1e9-token donation, 1,000-token deposit) |
+/// |----------|-------------|--------------------------------------------------------|
+/// | 6        | 1e9         | < 0.1%                                                 |
+/// | 8        | 1e11        | < 0.001%                                               |
+/// | 9        | 1e12        | < 0.0001%                                              |
+///
+/// Either way the attacker forfeits nearly all of the donation to the
+/// dead shares. Saturates at u64::MAX for mints whose virtual offsets
+/// `initialize_market` would reject anyway.
+pub fn recommended_first_supply_dead_shares(loan_decimals: u8) -> u64 {
+    10u128
+        .checked_pow(loan_decimals as u32)
+        .and_then(|unit| unit.checked_mul(VIRTUAL_SHARES))
+        .and_then(|shares| u64::try_from(shares / 1_000).ok())
+        .unwrap_or(u64::MAX)
+}
+
+/// Converts a minimum initial deposit in whole tokens to loan base units
+///
+/// The same whole-token minimum maps to a larger base-unit threshold on a
+/// mint with more decimals, keeping it economically equal across markets.
+///
+/// **Errors:**
+/// - MathOverflow: `whole_tokens × 10^loan_decimals` does not fit in u64
+pub fn min_initial_deposit_base_units(whole_tokens: u64, loan_decimals: u8) -> Result<u64> {
+    10u64
+        .checked_pow(loan_decimals as u32)
+        .and_then(|unit| whole_tokens.checked_mul(unit))
+        .ok_or(PelagoError::MathOverflow.into())
+}
+
+/// Rejects a first supply below the market's minimum initial deposit
+///
+/// Applies only while the market has no supply shares; a dust first
+/// deposit is what makes the share price cheap to inflate.
+///
+/// **Errors:**
+/// - InitialDepositTooSmall: `assets < market.min_initial_deposit`
+pub fn check_initial_deposit(market: &Market, assets: u64) -> Result<()> {
+    require!(
+        market.total_supply_shares > 0 || assets >= market.min_initial_deposit,
+        PelagoError::InitialDepositTooSmall
+    );
+    Ok(())
+}
+
+/// Rejects a supply whose conversion rounded either side to zero
+///
+/// Zero shares would swallow the user's assets; zero assets would mint
+/// shares for free. Neither happens with the default rounding on sane
+/// totals, but a heavily inflated share price or a user-favoring rounding
+/// mode can get there.
+///
+/// **Errors:**
+/// - ZeroAmount: `assets == 0` or `shares == 0`
+pub fn check_supply_amounts(assets: u64, shares: u64) -> Result<()> {
+    require!(assets > 0 && shares > 0, PelagoError::ZeroAmount);
+    Ok(())
+}
+
 /// Event emitted on successful supply
 #[event]
 pub struct SupplyEvent {
+    /// Market public key
+    pub market: Pubkey,
+
     /// User public key (supplier)
     pub user: Pubkey,
 
     /// Assets supplied
     pub assets: u64,
 
-    /// Shares received
+    /// Shares received (excludes first-supply dead shares)
     pub shares: u64,
 
     /// Total supply shares in market
@@ -231,3 +471,349 @@ pub struct SupplyEvent {
     /// Total supply assets in market
     pub total_supply_assets: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market_with_totals(supply_assets: u64, borrow_assets: u64) -> Market {
+        Market {
+            total_supply_assets: supply_assets,
+            total_borrow_assets: borrow_assets,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_min_initial_deposit_scales_with_decimals() {
+        // One whole token minimum on a 6-decimal and a 9-decimal loan mint
+        let usdc = Market {
+            loan_decimals: 6,
+            min_initial_deposit: min_initial_deposit_base_units(1, 6).unwrap(),
+            ..Default::default()
+        };
+        let sol = Market {
+            loan_decimals: 9,
+            min_initial_deposit: min_initial_deposit_base_units(1, 9).unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(usdc.min_initial_deposit, 1_000_000);
+        assert_eq!(sol.min_initial_deposit, 1_000_000_000);
+
+        // 1 USDC clears the USDC market; the same base units are 0.001 SOL
+        assert!(check_initial_deposit(&usdc, 1_000_000).is_ok());
+        assert_eq!(
+            check_initial_deposit(&sol, 1_000_000).unwrap_err(),
+            PelagoError::InitialDepositTooSmall.into()
+        );
+        assert!(check_initial_deposit(&sol, 1_000_000_000).is_ok());
+
+        // Only the first supply is bound
+        let supplied = Market {
+            total_supply_shares: 1,
+            ..sol
+        };
+        assert!(check_initial_deposit(&supplied, 1).is_ok());
+
+        assert_eq!(
+            min_initial_deposit_base_units(u64::MAX, 6).unwrap_err(),
+            PelagoError::MathOverflow.into()
+        );
+    }
+
+    #[test]
+    fn test_first_supply_locks_dead_shares() {
+        let mut market = Market {
+            first_supply_dead_shares: 1_000_000_000,
+            ..Default::default()
+        };
+
+        // A first supply that does not cover the dead shares is rejected
+        let minted = supply_shares_for_assets(&market, 1_000).unwrap();
+        assert_eq!(
+            first_supply_split(&market, minted).unwrap_err(),
+            PelagoError::ZeroAmount.into()
+        );
+
+        // 1 USDC first supply: 1e9 of its 1e12 shares are locked
+        let minted = supply_shares_for_assets(&market, 1_000_000).unwrap();
+        let (user, dead) = first_supply_split(&market, minted).unwrap();
+        assert_eq!(dead, 1_000_000_000);
+        assert_eq!(user + dead, minted);
+        market.total_supply_assets = 1_000_000;
+        market.total_supply_shares = minted;
+
+        // Later supplies are untouched, and the user can never redeem the
+        // dead shares, so the share supply stays above them
+        assert_eq!(first_supply_split(&market, 5).unwrap(), (5, 0));
+        assert!(market.total_supply_shares - user >= dead);
+    }
+
+    #[test]
+    fn test_degenerate_supply_conversions_rejected() {
+        // Share price inflated far above one asset per share
+        let inflated = Market {
+            total_supply_assets: 1_000_000_000_000,
+            total_supply_shares: 1_000,
+            ..Default::default()
+        };
+
+        // A dust supply in assets mode would mint no shares
+        let shares = supply_shares_for_assets(&inflated, 1).unwrap();
+        assert_eq!(shares, 0);
+        assert_eq!(
+            check_supply_amounts(1, shares).unwrap_err(),
+            PelagoError::ZeroAmount.into()
+        );
+
+        // Shares mode rounds the assets up, so a single share still costs
+        // something; zero assets for shares would be free shares
+        let assets = to_assets_up(1, inflated.total_supply_assets, inflated.total_supply_shares)
+            .unwrap();
+        assert!(assets > 0);
+        assert!(check_supply_amounts(assets, 1).is_ok());
+        assert_eq!(
+            check_supply_amounts(0, 1).unwrap_err(),
+            PelagoError::ZeroAmount.into()
+        );
+    }
+
+    #[test]
+    fn test_favor_user_on_supply_rounds_shares_up() {
+        // Interest-grown market: 1 asset is worth a non-integer number of shares
+        let protocol_favoring = Market {
+            total_supply_assets: 1_051_267,
+            total_supply_shares: 1_000_000_000_000,
+            ..Default::default()
+        };
+        let user_favoring = Market {
+            favor_user_on_supply: true,
+            ..protocol_favoring.clone()
+        };
+
+        for assets in [1, 7, 999, 1_000_000] {
+            let down = supply_shares_for_assets(&protocol_favoring, assets).unwrap();
+            let up = supply_shares_for_assets(&user_favoring, assets).unwrap();
+            assert!(up >= down);
+            assert!(up - down <= 1);
+        }
+    }
+
+    #[test]
+    fn test_vault_accounting_matches() {
+        let mut market = market_with_totals(1_000_000_000, 400_000_000);
+        assert!(check_vault_accounting(600_000_000, &market).is_ok());
+
+        // A shortfall within the market's tolerance is accepted
+        market.vault_tolerance = 1_000_000;
+        assert!(check_vault_accounting(599_000_000, &market).is_ok());
+        assert_eq!(
+            check_vault_accounting(598_999_999, &market).unwrap_err(),
+            PelagoError::VaultAccountingMismatch.into()
+        );
+    }
+
+    #[test]
+    fn test_vault_donation_passes_guard() {
+        // Attacker supplied 1 unit, then donated 10,000 USDC directly to the vault
+        let market = market_with_totals(1, 0);
+        assert!(check_vault_accounting(1 + 10_000_000_000, &market).is_ok());
+
+        // ...but losing a single unit past the tolerance is caught
+        assert_eq!(
+            check_vault_accounting(0, &market).unwrap_err(),
+            PelagoError::VaultAccountingMismatch.into()
+        );
+    }
+
+    #[test]
+    fn test_accrued_interest_exceeds_max_assets_in() {
+        use crate::utils::interest::accrue_interest_at;
+
+        let start = 1_700_000_000;
+        let mut market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_supply_shares: 1_000_000_000 * 1_000_000,
+            total_borrow_assets: 800_000_000,
+            total_borrow_shares: 800_000_000 * 1_000_000,
+            last_update: start,
+            ..Default::default()
+        };
+        let shares = 100_000_000 * 1_000_000; // ~100 USDC worth
+
+        // Client simulates at `start` and uses the quote as its bound
+        let quoted = to_assets_up(shares, market.total_supply_assets, market.total_supply_shares)
+            .unwrap();
+        assert!(check_max_assets_in(quoted, quoted).is_ok());
+
+        // Transaction lands a day later: the same shares cost more
+        accrue_interest_at(&mut market, start + 86_400).unwrap();
+        let required = to_assets_up(shares, market.total_supply_assets, market.total_supply_shares)
+            .unwrap();
+        assert!(required > quoted);
+        assert_eq!(
+            check_max_assets_in(required, quoted).unwrap_err(),
+            PelagoError::SlippageExceeded.into()
+        );
+
+        // Zero disables the bound
+        assert!(check_max_assets_in(required, 0).is_ok());
+    }
+
+    /// Classic ERC4626-style inflation attack, replayed through the same
+    /// conversions and guards the supply/withdraw handlers use
+    mod inflation_attack {
+        use super::*;
+        use crate::utils::shares_math::{to_assets_down, to_shares_down};
+
+        /// Mirrors supply Mode 1: credit `to_shares_down(assets)` and grow totals
+        fn supply_assets(market: &mut Market, vault: &mut u64, assets: u64) -> Result<u64> {
+            check_vault_accounting(*vault, market)?;
+            let shares = to_shares_down(assets, market.total_supply_assets, market.total_supply_shares)?;
+            market.total_supply_assets += assets;
+            market.total_supply_shares += shares;
+            *vault += assets;
+            Ok(shares)
+        }
+
+        /// Mirrors withdraw Mode 2: redeem all shares at `to_assets_down`
+        fn redeemable(market: &Market, shares: u64) -> u64 {
+            to_assets_down(shares, market.total_supply_assets, market.total_supply_shares).unwrap()
+        }
+
+        const VICTIM_DEPOSIT: u64 = 1_000_000_000; // 1000 USDC
+
+        #[test]
+        fn test_donation_does_not_move_share_price() {
+            let mut market = Market::default();
+            let mut vault = 0;
+
+            // Attacker supplies the minimum, then donates 10,000 USDC
+            let attacker_shares = supply_assets(&mut market, &mut vault, 1).unwrap();
+            vault += 10_000_000_000;
+
+            // Donations never reach total_supply_assets, so the price is unchanged
+            let victim_shares = supply_assets(&mut market, &mut vault, VICTIM_DEPOSIT).unwrap();
+            assert!(redeemable(&market, victim_shares) >= VICTIM_DEPOSIT - 1);
+            assert!(redeemable(&market, attacker_shares) <= 1);
+        }
+
+        #[test]
+        fn test_dead_shares_make_inflation_uneconomical() {
+            // Same worst case as below, but the market locks dead shares
+            // out of the first supply (1,000x the virtual shares)
+            let donation = 1_000_000_000; // 1000 USDC
+            let mut market = Market {
+                first_supply_dead_shares: 1_000_000_000,
+                ..Default::default()
+            };
+            let mut vault = 0;
+
+            // Attacker supplies just enough to clear the dead shares, then
+            // inflates the share price with a counted donation
+            let minted = to_shares_down(1_001, 0, 0).unwrap();
+            let (attacker_shares, dead) = first_supply_split(&market, minted).unwrap();
+            market.total_supply_assets += 1_001;
+            market.total_supply_shares += minted;
+            vault += 1_001;
+            market.total_supply_assets += donation;
+            vault += donation;
+
+            let victim_shares = supply_assets(&mut market, &mut vault, VICTIM_DEPOSIT).unwrap();
+            assert!(victim_shares > 0);
+            assert!(redeemable(&market, victim_shares) >= VICTIM_DEPOSIT - 1);
+
+            // The dead shares outweigh the attacker's 1e6: the donation is
+            // almost entirely lost to them
+            assert_eq!(dead, 1_000 * attacker_shares);
+            assert!(redeemable(&market, attacker_shares) < donation / 1_000);
+        }
+
+        /// Counted-donation attack against `dead_shares`: the attacker makes
+        /// the smallest first supply that clears them, donates `donation`,
+        /// and the victim supplies `deposit`
+        ///
+        /// **Returns:** `(attacker_cost, attacker_value, victim_value)`
+        fn counted_donation_attack(dead_shares: u64, donation: u64, deposit: u64) -> (u64, u64, u64) {
+            let mut market = Market {
+                first_supply_dead_shares: dead_shares,
+                ..Default::default()
+            };
+            let mut vault = 0;
+
+            let first = dead_shares / VIRTUAL_SHARES as u64 + 1;
+            let minted = to_shares_down(first, 0, 0).unwrap();
+            let (attacker_shares, _) = first_supply_split(&market, minted).unwrap();
+            market.total_supply_assets += first + donation;
+            market.total_supply_shares += minted;
+            vault += first + donation;
+
+            let victim_shares = supply_assets(&mut market, &mut vault, deposit).unwrap();
+            (
+                first + donation,
+                redeemable(&market, attacker_shares),
+                redeemable(&market, victim_shares),
+            )
+        }
+
+        #[test]
+        fn test_counted_donation_bounded_across_decimals() {
+            for decimals in [6u8, 8, 9] {
+                let unit = 10u64.pow(decimals as u32);
+                let deposit = 1_000 * unit;
+                let dead_shares = recommended_first_supply_dead_shares(decimals);
+                // 0.1% at 6 decimals, 10x tighter per extra decimal
+                let max_loss = deposit / 10u64.pow(decimals as u32 - 3);
+
+                // Donations from 1 token up to MAX_MARKET_TOKENS
+                for donation in [1, 1_000, 1_000_000, 1_000_000_000].map(|tokens| tokens * unit) {
+                    let (cost, attacker_value, victim_value) =
+                        counted_donation_attack(dead_shares, donation, deposit);
+                    assert!(
+                        victim_value > deposit - max_loss,
+                        "decimals={decimals} donation={donation} victim={victim_value}"
+                    );
+                    assert!(attacker_value < cost);
+                    assert!(attacker_value + victim_value <= cost + deposit);
+                }
+
+                // Bare virtual shares: the largest donation takes about half the deposit
+                let (cost, attacker_value, victim_value) =
+                    counted_donation_attack(0, 1_000_000_000 * unit, deposit);
+                assert!(victim_value < deposit * 51 / 100);
+                assert!(attacker_value < cost);
+            }
+
+            assert_eq!(recommended_first_supply_dead_shares(6), 1_000_000_000);
+            assert_eq!(recommended_first_supply_dead_shares(9), 1_000_000_000_000);
+            assert_eq!(recommended_first_supply_dead_shares(30), u64::MAX);
+        }
+
+        #[test]
+        fn test_virtual_shares_bound_loss_even_if_donation_counted() {
+            // Worst case: the donation is somehow reflected in total_supply_assets
+            // (e.g. a future sync path). Virtual shares still bound the victim's
+            // loss to a rounding error while the attacker forfeits part of the donation.
+            let donation = 1_000_000_000_000; // 1M USDC
+            let mut market = Market::default();
+            let mut vault = 0;
+
+            let attacker_shares = supply_assets(&mut market, &mut vault, 1).unwrap();
+            market.total_supply_assets += donation;
+            vault += donation;
+
+            let victim_shares = supply_assets(&mut market, &mut vault, VICTIM_DEPOSIT).unwrap();
+            assert!(victim_shares > 0);
+
+            // Victim loses at most 0.1% of the deposit
+            let victim_value = redeemable(&market, victim_shares);
+            assert!(victim_value >= VICTIM_DEPOSIT - VICTIM_DEPOSIT / 1_000);
+
+            // Attacker's 1e6 shares match the 1e6 virtual shares, so about half
+            // of the donation is forfeited: the attack is strictly unprofitable
+            let attacker_value = redeemable(&market, attacker_shares);
+            assert!(attacker_value + victim_value < donation + VICTIM_DEPOSIT);
+            assert!(attacker_value < donation * 51 / 100);
+        }
+    }
+}
@@ -2,7 +2,8 @@ use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
 use crate::error::PelagoError;
-use crate::state::{Market, UserPosition};
+use crate::state::{Market, UserPosition, UserRegistry};
+use crate::utils::vault_snapshot::snapshot_balance_delta;
 
 /// Supply collateral assets to the market
 ///
@@ -44,10 +45,23 @@ pub struct SupplyCollateral<'info> {
     )]
     pub user_position: Account<'info, UserPosition>,
 
+    /// User's market registry PDA (created on the user's first position)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserRegistry::LEN,
+        seeds = [
+            UserRegistry::SEED_PREFIX,
+            user.key().as_ref(),
+        ],
+        bump
+    )]
+    pub user_registry: Account<'info, UserRegistry>,
+
     /// Market's collateral token vault (receives the deposit)
     #[account(
         mut,
-        constraint = collateral_vault.key() == market.collateral_vault @ PelagoError::UninitializedMarket,
+        constraint = collateral_vault.key() == market.collateral_vault @ PelagoError::WrongCollateralVault,
     )]
     pub collateral_vault: Account<'info, TokenAccount>,
 
@@ -79,31 +93,35 @@ pub struct SupplyCollateral<'info> {
 ///
 /// **State Changes:**
 /// - user_position.collateral_amount += amount
+/// - market.total_collateral += amount
 /// - collateral_vault.amount += amount (via token transfer)
 ///
-/// **Note:** Unlike supply/borrow, collateral doesn't affect market totals
-/// because it's tracked per-user only. The market doesn't need to know
-/// total collateral for P0 operations.
+/// **Note:** `market.total_collateral` is an aggregate for monitoring only;
+/// health checks always use the per-user `collateral_amount`.
 ///
 /// **Error Cases:**
 /// - ZeroAmount: amount == 0
+/// - CollateralCapExceeded: total_collateral would exceed the market's collateral_cap
 /// - Insufficient user balance (handled by token program)
 pub fn handler(ctx: Context<SupplyCollateral>, amount: u64) -> Result<()> {
     // Validate amount
     require!(amount > 0, PelagoError::ZeroAmount);
 
-    let market = &ctx.accounts.market;
+    let market = &mut ctx.accounts.market;
     let user_position = &mut ctx.accounts.user_position;
 
     // Initialize user position fields if this is first interaction
     // (init_if_needed creates account but doesn't initialize fields)
     if user_position.user == Pubkey::default() {
-        user_position.user = ctx.accounts.user.key();
-        user_position.market = market.key();
-        user_position.supply_shares = 0;
-        user_position.borrow_shares = 0;
-        user_position.collateral_amount = 0;
-        user_position.bump = ctx.bumps.user_position;
+        user_position.open(ctx.accounts.user.key(), market.key(), ctx.bumps.user_position)?;
+        market.register_position()?;
+
+        let user_registry = &mut ctx.accounts.user_registry;
+        if user_registry.user == Pubkey::default() {
+            user_registry.user = ctx.accounts.user.key();
+            user_registry.bump = ctx.bumps.user_registry;
+        }
+        user_registry.record_market(market.key())?;
     }
 
     // Transfer collateral tokens from user to market vault
@@ -116,13 +134,22 @@ pub fn handler(ctx: Context<SupplyCollateral>, amount: u64) -> Result<()> {
         ctx.accounts.token_program.to_account_info(),
         transfer_accounts,
     );
-    token::transfer(cpi_ctx, amount)?;
+    snapshot_balance_delta(&mut ctx.accounts.collateral_vault, || token::transfer(cpi_ctx, amount))?
+        .require_increase(amount)?;
 
     // Update user position collateral
     user_position.collateral_amount = user_position
         .collateral_amount
         .checked_add(amount)
         .ok_or(PelagoError::MathOverflow)?;
+    market.total_collateral = market
+        .total_collateral
+        .checked_add(amount)
+        .ok_or(PelagoError::MathOverflow)?;
+    check_collateral_cap(market)?;
+
+    // Record position activity for dormancy tracking
+    user_position.last_activity = Clock::get()?.unix_timestamp;
 
     msg!(
         "SupplyCollateral: user={}, amount={}, total_collateral={}",
@@ -131,5 +158,78 @@ pub fn handler(ctx: Context<SupplyCollateral>, amount: u64) -> Result<()> {
         user_position.collateral_amount
     );
 
+    emit!(SupplyCollateralEvent {
+        market: market.key(),
+        user: user_position.user,
+        amount,
+        collateral_amount: user_position.collateral_amount,
+        total_collateral: market.total_collateral,
+    });
+
+    Ok(())
+}
+
+/// Validates total_collateral against the market's collateral cap
+///
+/// Withdrawals and liquidations only lower total_collateral, so a cap set
+/// below the current total just blocks new deposits.
+///
+/// **Errors:**
+/// - CollateralCapExceeded: total_collateral > collateral_cap (when non-zero)
+pub fn check_collateral_cap(market: &Market) -> Result<()> {
+    require!(
+        market.collateral_cap == 0 || market.total_collateral <= market.collateral_cap,
+        PelagoError::CollateralCapExceeded
+    );
     Ok(())
 }
+
+/// Event emitted on successful collateral supply
+#[event]
+pub struct SupplyCollateralEvent {
+    /// Market public key
+    pub market: Pubkey,
+
+    /// User public key (depositor)
+    pub user: Pubkey,
+
+    /// Collateral deposited
+    pub amount: u64,
+
+    /// User's collateral after the deposit
+    pub collateral_amount: u64,
+
+    /// Total collateral in market
+    pub total_collateral: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market_with_collateral(total_collateral: u64, collateral_cap: u64) -> Market {
+        Market {
+            total_collateral,
+            collateral_cap,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_collateral_cap_boundary() {
+        // Filling the cap exactly is allowed, one unit more is not
+        let full = market_with_collateral(10_000_000_000, 10_000_000_000);
+        assert!(check_collateral_cap(&full).is_ok());
+
+        let over = market_with_collateral(10_000_000_001, 10_000_000_000);
+        assert_eq!(
+            check_collateral_cap(&over).unwrap_err(),
+            PelagoError::CollateralCapExceeded.into()
+        );
+    }
+
+    #[test]
+    fn test_zero_collateral_cap_is_unlimited() {
+        assert!(check_collateral_cap(&market_with_collateral(u64::MAX, 0)).is_ok());
+    }
+}
@@ -0,0 +1,68 @@
+//! Sweep Dust Instruction
+//!
+//! Teardown helper for a market whose users have all left. Rounding can
+//! leave a few base units in `total_supply_assets` or `total_borrow_assets`
+//! after the last share is burned; this moves them into `reserves` so the
+//! market's totals read zero.
+//!
+//! Only residue up to `MAX_DUST_SWEEP_ASSETS` is swept, so this can't be
+//! used to take over a market that still holds real balances.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::utils::interest::accrue_interest;
+use crate::utils::invariants::sweep_dust;
+
+/// Sweep rounding residue of an emptied market into reserves
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct SweepDust<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+}
+
+/// Handler for sweep_dust instruction
+///
+/// **Validation:**
+/// - `total_supply_shares == 0 && total_borrow_shares == 0`
+/// - Both residues ≤ MAX_DUST_SWEEP_ASSETS
+///
+/// **State Changes:**
+/// - market.reserves += total_supply_assets - total_borrow_assets
+/// - market.total_supply_assets = 0
+/// - market.total_borrow_assets = 0
+///
+/// **Errors:**
+/// - InvalidParameter: Shares remain or a residue is above the dust threshold
+pub fn handler(ctx: Context<SweepDust>) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    accrue_interest(market)?;
+
+    let (swept_supply, swept_borrow) = sweep_dust(market)?;
+
+    msg!(
+        "Dust swept: market={}, supply_assets={}, borrow_assets={}, reserves={}",
+        market.key(),
+        swept_supply,
+        swept_borrow,
+        market.reserves
+    );
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! Update Price History Instruction
+//!
+//! Permissionless keeper crank that records the current spot price into the
+//! market's ring buffer and refreshes the cached TWAP on the market.
+//!
+//! The recorded price is always `market.spot_price()`, so callers can't
+//! inject arbitrary prices; they only choose when samples are taken.
+//! Samples are at least `min_sample_spacing` apart, so cranking quickly
+//! can't flush the buffer and leave it covering less than the window.
+
+use anchor_lang::prelude::*;
+
+use crate::state::{Market, PriceHistory, PriceSample};
+use crate::utils::oracle::check_price_bounds;
+use crate::utils::twap::{min_sample_spacing, twap};
+
+/// Record the current spot price and refresh the market TWAP
+///
+/// **Access Control:** Permissionless (keeper crank)
+#[derive(Accounts)]
+pub struct UpdatePriceHistory<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Price history PDA of this market
+    #[account(
+        mut,
+        seeds = [PriceHistory::SEED_PREFIX, market.key().as_ref()],
+        bump = price_history.bump,
+    )]
+    pub price_history: Account<'info, PriceHistory>,
+}
+
+/// Handler for update_price_history instruction
+///
+/// **Processing Steps:**
+/// 1. Push `market.spot_price()` at the current timestamp, unless the latest
+///    sample is less than `min_sample_spacing` old
+/// 2. Recompute the TWAP over `market.twap_window`
+/// 3. Cache it in `market.twap_price` (0 while warming up → spot fallback)
+///    and stamp `market.twap_updated_at`
+pub fn handler(ctx: Context<UpdatePriceHistory>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let market = &mut ctx.accounts.market;
+    let price_history = &mut ctx.accounts.price_history;
+
+    let spot = check_price_bounds(market, market.spot_price())?;
+    record_sample(price_history, spot, now, market.twap_window);
+
+    market.twap_price = twap(&price_history.chronological(), now, market.twap_window).unwrap_or(0);
+    market.twap_updated_at = now;
+
+    msg!(
+        "Price history updated: market={}, spot={}, twap={}, samples={}",
+        market.key(),
+        spot,
+        market.twap_price,
+        price_history.count
+    );
+
+    Ok(())
+}
+
+/// Pushes `price` at `now` if the latest sample is at least
+/// `min_sample_spacing(twap_window)` old
+///
+/// **Returns:** Whether a sample was recorded
+pub fn record_sample(
+    price_history: &mut PriceHistory,
+    price: u64,
+    now: i64,
+    twap_window: u32,
+) -> bool {
+    let latest = price_history.chronological().last().copied();
+    if let Some(sample) = latest {
+        if now - sample.timestamp < min_sample_spacing(twap_window) {
+            return false;
+        }
+    }
+    price_history.push(PriceSample {
+        price,
+        timestamp: now,
+    });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rapid_cranks_cannot_flush_history() {
+        // 160s window over 16 samples: at most one sample every 10s
+        let mut history = PriceHistory::default();
+        assert!(record_sample(&mut history, 100_000, 0, 160));
+
+        // A burst of cranks within the spacing records nothing
+        for now in 1..10 {
+            assert!(!record_sample(&mut history, 200_000, now, 160));
+        }
+        assert_eq!(history.count, 1);
+
+        // Even a full buffer still covers the window
+        for i in 1..PriceHistory::CAPACITY as i64 {
+            assert!(record_sample(&mut history, 100_000, i * 10, 160));
+        }
+        let samples = history.chronological();
+        assert_eq!(samples.len(), PriceHistory::CAPACITY);
+        assert!(twap(&samples, 160, 160).is_some());
+    }
+}
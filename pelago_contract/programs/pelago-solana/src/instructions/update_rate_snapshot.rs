@@ -0,0 +1,136 @@
+//! Update Rate Snapshot Instruction
+//!
+//! Permissionless keeper crank that accrues interest and records the
+//! market's share prices into its rate snapshot ring buffer.
+//!
+//! Samples always come from the market's own totals right after accrual,
+//! so callers can't inject arbitrary indices; they only choose when
+//! snapshots are taken. Consecutive samples are kept at least
+//! `RateSnapshot::MIN_SAMPLE_SPACING` apart: a crank inside the spacing
+//! refreshes the latest sample instead of pushing, so a burst of cranks
+//! can't flush the history.
+
+use anchor_lang::prelude::*;
+
+use crate::state::{Market, RateSample, RateSnapshot};
+use crate::utils::interest::accrue_interest;
+use crate::utils::rate_history::rate_sample;
+
+/// Accrue interest and record the market's share prices
+///
+/// **Access Control:** Permissionless (keeper crank)
+#[derive(Accounts)]
+pub struct UpdateRateSnapshot<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Rate snapshot PDA of this market
+    #[account(
+        mut,
+        seeds = [RateSnapshot::SEED_PREFIX, market.key().as_ref()],
+        bump = rate_snapshot.bump,
+    )]
+    pub rate_snapshot: Account<'info, RateSnapshot>,
+}
+
+/// Handler for update_rate_snapshot instruction
+///
+/// **Processing Steps:**
+/// 1. Accrue interest
+/// 2. Record the borrow and supply share prices at `market.last_update`
+///    (refreshing the latest sample if it is within the minimum spacing)
+pub fn handler(ctx: Context<UpdateRateSnapshot>) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    let rate_snapshot = &mut ctx.accounts.rate_snapshot;
+
+    accrue_interest(market)?;
+    let sample = rate_sample(market, market.last_update)?;
+    record_sample(rate_snapshot, sample);
+
+    msg!(
+        "Rate snapshot updated: market={}, borrow_index={}, supply_index={}, samples={}",
+        market.key(),
+        sample.borrow_index,
+        sample.supply_index,
+        rate_snapshot.count
+    );
+
+    Ok(())
+}
+
+/// Records `sample`, keeping committed samples at least
+/// `RateSnapshot::MIN_SAMPLE_SPACING` apart
+///
+/// The latest sample stays open until it is a full spacing past the one
+/// before it: until then each crank overwrites it in place, afterwards the
+/// next crank pushes a new sample.
+///
+/// **Returns:** Whether a new sample was pushed
+pub fn record_sample(rate_snapshot: &mut RateSnapshot, sample: RateSample) -> bool {
+    let samples = rate_snapshot.chronological();
+    if let [.., previous, latest] = samples.as_slice() {
+        if latest.timestamp - previous.timestamp < RateSnapshot::MIN_SAMPLE_SPACING {
+            let index =
+                (rate_snapshot.head as usize + RateSnapshot::CAPACITY - 1) % RateSnapshot::CAPACITY;
+            rate_snapshot.samples[index] = sample;
+            return false;
+        }
+    }
+    rate_snapshot.push(sample);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: i64) -> RateSample {
+        RateSample {
+            timestamp,
+            borrow_index: 1_000_000 + timestamp as u128,
+            supply_index: 1_000_000 + timestamp as u128,
+        }
+    }
+
+    #[test]
+    fn test_crank_burst_cannot_flush_history() {
+        let spacing = RateSnapshot::MIN_SAMPLE_SPACING;
+        let mut snapshot = RateSnapshot::default();
+        assert!(record_sample(&mut snapshot, sample(0)));
+        assert!(record_sample(&mut snapshot, sample(1)));
+
+        // A burst of cranks inside the spacing only refreshes the latest sample
+        for now in 2..spacing {
+            assert!(!record_sample(&mut snapshot, sample(now)));
+        }
+        assert_eq!(snapshot.count, 2);
+        assert_eq!(
+            snapshot.chronological(),
+            vec![sample(0), sample(spacing - 1)]
+        );
+
+        // Once the latest sample is a full spacing past the previous one,
+        // the next crank opens a new sample
+        assert!(!record_sample(&mut snapshot, sample(spacing)));
+        assert!(record_sample(&mut snapshot, sample(spacing + 1)));
+        assert_eq!(snapshot.count, 3);
+
+        // Further cranks refresh the new sample and leave the committed ones
+        for now in spacing + 2..spacing * 2 {
+            assert!(!record_sample(&mut snapshot, sample(now)));
+        }
+        assert_eq!(
+            snapshot.chronological(),
+            vec![sample(0), sample(spacing), sample(spacing * 2 - 1)]
+        );
+    }
+}
@@ -19,6 +19,9 @@ use crate::error::PelagoError;
 use crate::state::{Market, UserPosition};
 use crate::utils::shares_math::{to_shares_up, to_assets_down};
 use crate::utils::interest::accrue_interest;
+use crate::utils::invariants::{check_empty_supply, check_market_invariants, sweep_orphaned_supply};
+use crate::utils::rewards::settle_rewards;
+use crate::utils::vault_snapshot::snapshot_balance_delta;
 
 /// Withdraw loan assets from the market
 ///
@@ -34,6 +37,8 @@ use crate::utils::interest::accrue_interest;
 /// - `market.total_supply_shares` -= calculated_shares
 /// - `market.total_supply_assets` -= calculated_assets
 /// - `loan_vault.amount` -= calculated_assets (via transfer)
+/// - If the last supply share was burned: leftover supply assets move to
+///   `market.reserves` (see `sweep_orphaned_supply`)
 ///
 /// **Validation:**
 /// - Exactly one of (assets, shares) must be non-zero
@@ -78,7 +83,7 @@ pub struct Withdraw<'info> {
     /// Market's loan token vault (source of withdrawal)
     #[account(
         mut,
-        constraint = loan_vault.key() == market.loan_vault @ PelagoError::InvalidVault,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
     )]
     pub loan_vault: Account<'info, TokenAccount>,
 
@@ -101,11 +106,16 @@ pub struct Withdraw<'info> {
 ///   - Rounding UP: User burns more shares → favors protocol
 /// - If shares provided: `assets = to_assets_down(shares, totalSupplyAssets, totalSupplyShares)`
 ///   - Rounding DOWN: User receives fewer assets → favors protocol
+/// - Supplying X assets and withdrawing X right away burns at most 1 share
+///   more than the supply minted, so the round trip loses at most 1 share's
+///   worth of assets
 ///
 /// **Errors:**
 /// - InconsistentInput: Both or neither of (assets, shares) are non-zero
+/// - PositionFrozen: Position is under a compliance hold
 /// - InsufficientSupply: User doesn't have enough supply shares
-/// - InsufficientLiquidity: Withdrawal would violate totalBorrow ≤ totalSupply
+/// - WithdrawBreaksLiquidity: Withdrawal would violate totalBorrow ≤ totalSupply
+/// - InvariantViolation: Last shares burned while debt is still outstanding
 /// - MathOverflow: Calculation overflow
 pub fn handler(
     ctx: Context<Withdraw>,
@@ -119,7 +129,9 @@ pub fn handler(
     );
 
     let market = &mut ctx.accounts.market;
+    require!(!market.paused, PelagoError::MarketPaused);
     let user_position = &mut ctx.accounts.user_position;
+    user_position.check_not_frozen()?;
 
     // Step 2: Accrue interest before any calculation
     accrue_interest(market)?;
@@ -153,6 +165,7 @@ pub fn handler(
     );
 
     // Step 4: Update user position and market totals
+    settle_rewards(market, user_position)?;
     user_position.supply_shares = user_position
         .supply_shares
         .checked_sub(final_shares)
@@ -169,12 +182,13 @@ pub fn handler(
         .ok_or(PelagoError::MathOverflow)?;
 
     // Step 5: Validate liquidity constraint
-    // Invariant: totalBorrowAssets ≤ totalSupplyAssets
-    // This ensures there's always enough liquidity to cover all borrows
-    require!(
-        market.total_borrow_assets <= market.total_supply_assets,
-        PelagoError::InsufficientLiquidity
-    );
+    check_withdraw_liquidity(market)?;
+    let swept = sweep_orphaned_supply(market)?;
+    if swept > 0 {
+        msg!("Swept orphaned supply to reserves: assets={}", swept);
+    }
+    check_empty_supply(market)?;
+    check_market_invariants(market)?;
 
     // Step 6: Transfer tokens from vault to receiver
     // Use PDA signer (market authority) to authorize transfer from vault
@@ -202,7 +216,8 @@ pub fn handler(
         signer_seeds,
     );
 
-    token::transfer(cpi_ctx, final_assets)?;
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || token::transfer(cpi_ctx, final_assets))?
+        .require_decrease(final_assets)?;
 
     msg!(
         "Withdraw success: user={}, assets={}, shares={}, remaining_shares={}, new_total_supply={}",
@@ -213,6 +228,9 @@ pub fn handler(
         market.total_supply_assets
     );
 
+    // Record position activity for dormancy tracking
+    user_position.last_activity = Clock::get()?.unix_timestamp;
+
     // Emit event for off-chain tracking
     emit!(WithdrawEvent {
         market: market.key(),
@@ -251,3 +269,115 @@ pub struct WithdrawEvent {
     /// Remaining total supply shares in market
     pub total_supply_shares: u64,
 }
+
+/// Validates the liquidity invariant after a withdrawal has been applied
+///
+/// Invariant: totalBorrowAssets ≤ totalSupplyAssets
+/// This ensures there's always enough liquidity to cover all borrows
+///
+/// **Errors:**
+/// - WithdrawBreaksLiquidity: Remaining supply no longer covers outstanding borrows
+pub fn check_withdraw_liquidity(market: &Market) -> Result<()> {
+    require!(
+        market.total_borrow_assets <= market.total_supply_assets,
+        PelagoError::WithdrawBreaksLiquidity
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_withdraw_into_borrowed_liquidity_fails() {
+        // 1000 USDC supplied, 900 USDC drained by borrows
+        let mut market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_borrow_assets: 900_000_000,
+            ..Default::default()
+        };
+
+        // Withdrawing the idle 100 USDC keeps the invariant
+        market.total_supply_assets -= 100_000_000;
+        assert!(check_withdraw_liquidity(&market).is_ok());
+
+        // One more unit would dip into borrowed liquidity
+        market.total_supply_assets -= 1;
+        assert_eq!(
+            check_withdraw_liquidity(&market).unwrap_err(),
+            PelagoError::WithdrawBreaksLiquidity.into()
+        );
+    }
+
+    #[test]
+    fn test_randomized_supply_withdraw_cycles_leave_no_orphaned_assets() {
+        use crate::instructions::supply::supply_shares_for_assets;
+        use crate::utils::interest::accrue_interest_at;
+        use crate::utils::shares_math::to_assets_down;
+        use crate::utils::test_rng::next;
+
+        for round in 0..20u64 {
+            let mut seed = round + 1;
+            let mut market = Market {
+                last_update: 1_700_000_000,
+                ..Default::default()
+            };
+            let mut users = [0u64; 4];
+            let mut now = market.last_update;
+
+            for _ in 0..200 {
+                let user = (next(&mut seed) % 4) as usize;
+                match next(&mut seed) % 3 {
+                    0 => {
+                        let assets = 1 + next(&mut seed) % 1_000_000_000;
+                        let shares = supply_shares_for_assets(&market, assets).unwrap();
+                        users[user] += shares;
+                        market.total_supply_assets += assets;
+                        market.total_supply_shares += shares;
+                    }
+                    1 if users[user] > 0 => {
+                        // Partial withdraw by shares
+                        let shares = 1 + next(&mut seed) % users[user];
+                        let assets = to_assets_down(
+                            shares,
+                            market.total_supply_assets,
+                            market.total_supply_shares,
+                        )
+                        .unwrap();
+                        users[user] -= shares;
+                        market.total_supply_shares -= shares;
+                        market.total_supply_assets -= assets;
+                        sweep_orphaned_supply(&mut market).unwrap();
+                    }
+                    _ => {
+                        // A short stretch with a small loan outstanding adds interest
+                        now += (next(&mut seed) % 86_400) as i64;
+                        market.total_borrow_assets = market.total_supply_assets / 2;
+                        accrue_interest_at(&mut market, now).unwrap();
+                        market.total_borrow_assets = 0;
+                    }
+                }
+                assert!(check_empty_supply(&market).is_ok());
+            }
+
+            // Everyone exits
+            for shares in users.iter_mut().filter(|s| **s > 0) {
+                let assets = to_assets_down(
+                    *shares,
+                    market.total_supply_assets,
+                    market.total_supply_shares,
+                )
+                .unwrap();
+                market.total_supply_shares -= *shares;
+                market.total_supply_assets -= assets;
+                *shares = 0;
+                sweep_orphaned_supply(&mut market).unwrap();
+            }
+
+            assert_eq!(market.total_supply_shares, 0);
+            assert_eq!(market.total_supply_assets, 0);
+            assert!(check_empty_supply(&market).is_ok());
+        }
+    }
+}
@@ -14,9 +14,11 @@ use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
 use crate::error::PelagoError;
 use crate::state::{Market, UserPosition};
-use crate::utils::interest::accrue_interest;
-use crate::utils::shares_math::to_assets_up;
-use crate::constants::{FIXED_ORACLE_PRICE, LLTV_PRECISION, PRICE_PRECISION};
+use crate::utils::interest::accrue_interest_at;
+use crate::utils::liquidation::{
+    position_health, require_healthy, PositionHealth, PositionHealthEvent,
+};
+use crate::utils::vault_snapshot::snapshot_balance_delta;
 
 /// Withdraw collateral assets from user position
 ///
@@ -25,6 +27,7 @@ use crate::constants::{FIXED_ORACLE_PRICE, LLTV_PRECISION, PRICE_PRECISION};
 ///
 /// **State Changes:**
 /// - `user_position.collateral_amount` -= assets
+/// - `market.total_collateral` -= assets
 /// - `collateral_vault.amount` -= assets (via transfer)
 ///
 /// **Validation:**
@@ -69,7 +72,7 @@ pub struct WithdrawCollateral<'info> {
     /// Market's collateral token vault (source of withdrawal)
     #[account(
         mut,
-        constraint = collateral_vault.key() == market.collateral_vault @ PelagoError::InvalidVault,
+        constraint = collateral_vault.key() == market.collateral_vault @ PelagoError::WrongCollateralVault,
     )]
     pub collateral_vault: Account<'info, TokenAccount>,
 
@@ -93,7 +96,9 @@ pub struct WithdrawCollateral<'info> {
 ///
 /// **Errors:**
 /// - ZeroAmount: assets == 0
+/// - PositionFrozen: Position is under a compliance hold
 /// - InsufficientCollateral: User doesn't have enough collateral OR health check fails
+///   (the collateral shortfall is written as return data)
 /// - MathOverflow: Calculation overflow
 pub fn handler(
     ctx: Context<WithdrawCollateral>,
@@ -103,11 +108,9 @@ pub fn handler(
     require!(assets > 0, PelagoError::ZeroAmount);
 
     let market = &mut ctx.accounts.market;
+    require!(!market.paused, PelagoError::MarketPaused);
     let user_position = &mut ctx.accounts.user_position;
-
-    // Step 2: Accrue interest before health check
-    // This ensures borrow amounts are up-to-date for accurate health calculation
-    accrue_interest(market)?;
+    user_position.check_not_frozen()?;
 
     msg!(
         "Withdraw collateral: user={}, amount={}, current_collateral={}",
@@ -116,15 +119,13 @@ pub fn handler(
         user_position.collateral_amount
     );
 
-    // Step 3: Update user collateral amount
-    user_position.collateral_amount = user_position
-        .collateral_amount
-        .checked_sub(assets)
-        .ok_or(PelagoError::InsufficientCollateral)?;
-
-    // Step 4: Health check with new collateral amount
-    // P1: Uses virtual shares to calculate actual borrow assets
-    check_health_p1(market, user_position)?;
+    // Steps 2-4: Accrue, debit collateral, check health
+    let health = withdraw_collateral_at(
+        market,
+        user_position,
+        assets,
+        Clock::get()?.unix_timestamp,
+    )?;
 
     // Step 5: Transfer collateral tokens from vault to receiver
     let loan_token_mint = market.loan_token_mint;
@@ -151,13 +152,17 @@ pub fn handler(
         signer_seeds,
     );
 
-    token::transfer(cpi_ctx, assets)?;
+    snapshot_balance_delta(&mut ctx.accounts.collateral_vault, || token::transfer(cpi_ctx, assets))?
+        .require_decrease(assets)?;
 
     msg!(
         "Withdraw collateral success: remaining_collateral={}",
         user_position.collateral_amount
     );
 
+    // Record position activity for dormancy tracking
+    user_position.last_activity = Clock::get()?.unix_timestamp;
+
     // Emit event
     emit!(WithdrawCollateralEvent {
         market: market.key(),
@@ -166,77 +171,93 @@ pub fn handler(
         assets,
         remaining_collateral: user_position.collateral_amount,
     });
+    emit!(PositionHealthEvent {
+        market: market.key(),
+        user: user_position.user,
+        health_factor: health.health_factor,
+        collateral_value: health.collateral_value,
+        debt_value: health.debt_value,
+    });
 
     Ok(())
 }
 
+/// Accrues interest, debits `assets` of collateral and checks health
+///
+/// Accrual always runs first, even for debt-free positions that skip the
+/// health check, so the market is settled before any collateral leaves.
+///
+/// **Errors:**
+/// - InsufficientCollateral: Not enough collateral, or the position would be unhealthy
+/// - MathOverflow: Calculation overflow
+pub fn withdraw_collateral_at(
+    market: &mut Market,
+    user_position: &mut UserPosition,
+    assets: u64,
+    now: i64,
+) -> Result<PositionHealth> {
+    accrue_interest_at(market, now)?;
+
+    user_position.collateral_amount = user_position
+        .collateral_amount
+        .checked_sub(assets)
+        .ok_or(PelagoError::InsufficientCollateral)?;
+    market.total_collateral = market
+        .total_collateral
+        .checked_sub(assets)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    // Uses virtual shares to calculate actual borrow assets
+    check_health_p1(market, user_position)
+}
+
 /// P1 Health check using virtual shares
 ///
 /// Validates that user position remains healthy after collateral withdrawal.
 /// Uses `to_assets_up` to convert borrow shares to assets (conservative rounding).
 ///
 /// **Formula:**
-/// ```
+/// ```text
 /// collateral_value_usd = collateral_amount × oracle_price / price_precision
 /// borrow_value_usd = to_assets_up(borrow_shares) (already in USDC)
 /// healthy = collateral_value_usd × lltv ≥ borrow_value_usd × LLTV_PRECISION
 /// ```
 ///
+/// Borrow shares at or below `market.dust_shares_threshold` count as no
+/// debt, so rounding dust that `to_assets_up` would turn into a whole unit
+/// can't pin the last collateral. The dust stays owed until repaid.
+///
 /// **Parameters:**
 /// - `market`: Market account (for oracle price, lltv, and total borrow state)
 /// - `user_position`: User position (for collateral and borrow shares)
 ///
 /// **Returns:**
-/// - Ok(()) if position is healthy
-/// - Err(InsufficientCollateral) if position is undercollateralized
+/// - Ok(health) if position is healthy (see `position_health`)
+/// - Err(InsufficientCollateral) if position is undercollateralized, with the
+///   collateral shortfall as return data (see `require_healthy`)
 pub fn check_health_p1(
     market: &Market,
     user_position: &UserPosition,
-) -> Result<()> {
-    // If user has no borrows, they are always healthy
-    if user_position.borrow_shares == 0 {
-        return Ok(());
-    }
-
-    // Calculate actual borrow assets using virtual shares (P1)
-    // Rounding UP to be conservative (favors protocol safety)
-    let borrow_value_usdc = to_assets_up(
-        user_position.borrow_shares,
-        market.total_borrow_assets,
-        market.total_borrow_shares,
-    )?;
-
-    // Calculate collateral value in USDC
-    // collateral_value = (collateral_amount × price) / price_precision
-    let collateral_value_usd = (user_position.collateral_amount as u128)
-        .checked_mul(FIXED_ORACLE_PRICE as u128)
-        .ok_or(PelagoError::MathOverflow)?
-        .checked_div(PRICE_PRECISION as u128)
-        .ok_or(PelagoError::MathOverflow)?;
+) -> Result<PositionHealth> {
+    let health = position_health(market, user_position)?;
 
-    // Calculate max allowed borrow value
-    // max_borrow = (collateral_value × lltv) / LLTV_PRECISION
-    let max_borrow_value = collateral_value_usd
-        .checked_mul(market.lltv as u128)
-        .ok_or(PelagoError::MathOverflow)?
-        .checked_div(LLTV_PRECISION as u128)
-        .ok_or(PelagoError::MathOverflow)?;
+    // If user has no borrows (beyond dust), they are always healthy
+    if user_position.borrow_shares <= market.dust_shares_threshold {
+        return Ok(health);
+    }
 
     msg!(
         "Health check: collateral_value={}, borrow_value={}, max_borrow={}, lltv={}",
-        collateral_value_usd,
-        borrow_value_usdc,
-        max_borrow_value,
+        health.collateral_value,
+        health.debt_value,
+        health.max_borrow,
         market.lltv
     );
 
-    // Validate health: borrow_value <= max_borrow_value
-    require!(
-        (borrow_value_usdc as u128) <= max_borrow_value,
-        PelagoError::InsufficientCollateral
-    );
+    // Validate health: borrow_value <= max_borrow_value (reports the shortfall)
+    require_healthy(market, &health)?;
 
-    Ok(())
+    Ok(health)
 }
 
 /// Event emitted on successful collateral withdrawal
@@ -257,3 +278,172 @@ pub struct WithdrawCollateralEvent {
     /// Remaining collateral in position
     pub remaining_collateral: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PelagoError;
+    use crate::utils::shares_math::to_shares_up;
+
+    const START: i64 = 1_700_000_000;
+
+    /// 10 SOL of collateral (1000 USDC at the fixed price) and a borrow of
+    /// 799.999 USDC, just under the 80% LLTV limit of 800 USDC.
+    fn near_limit_position() -> (Market, UserPosition) {
+        let borrow_assets = 799_999_000;
+        let borrow_shares = to_shares_up(borrow_assets, 0, 0).unwrap();
+
+        let market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_supply_shares: 1_000_000_000 * 1_000_000,
+            total_borrow_assets: borrow_assets,
+            total_borrow_shares: borrow_shares,
+            lltv: 80_000_000,
+            last_update: START,
+            ..Default::default()
+        };
+        let position = UserPosition {
+            borrow_shares,
+            collateral_amount: 10_000_000_000,
+            ..Default::default()
+        };
+        (market, position)
+    }
+
+    #[test]
+    fn test_small_withdrawal_allowed_before_interest() {
+        let (market, mut position) = near_limit_position();
+
+        // Withdrawing 10 lamports lowers the max borrow by 1 unit only
+        position.collateral_amount -= 10;
+        assert!(check_health_p1(&market, &position).is_ok());
+    }
+
+    #[test]
+    fn test_accrued_interest_makes_small_withdrawal_unhealthy() {
+        let (mut market, mut position) = near_limit_position();
+
+        // One day of interest on ~800 USDC is ~0.11 USDC, enough to cross the limit
+        accrue_interest_at(&mut market, START + 86_400).unwrap();
+        assert!(market.total_borrow_assets > 800_000_000);
+
+        position.collateral_amount -= 10;
+        assert_eq!(
+            check_health_p1(&market, &position).unwrap_err(),
+            PelagoError::InsufficientCollateral.into()
+        );
+    }
+
+    #[test]
+    fn test_manual_price_drives_health_check() {
+        let (mut market, position) = near_limit_position();
+        assert!(check_health_p1(&market, &position).is_ok());
+
+        // Price drops from 100 to 90 USDC/SOL: max borrow falls to 720 USDC
+        market.manual_price = 90_000;
+        market.manual_price_enabled = true;
+        assert_eq!(
+            check_health_p1(&market, &position).unwrap_err(),
+            PelagoError::InsufficientCollateral.into()
+        );
+
+        // Price rises to 110 USDC/SOL: max borrow 880 USDC
+        market.manual_price = 110_000;
+        assert!(check_health_p1(&market, &position).is_ok());
+
+        // Disabling falls back to FIXED_ORACLE_PRICE, ignoring the stored price
+        market.manual_price = 1;
+        market.manual_price_enabled = false;
+        assert!(check_health_p1(&market, &position).is_ok());
+    }
+
+    #[test]
+    fn test_fixed_price_sets_borrowing_power() {
+        let (market, position) = near_limit_position();
+
+        let cheap = Market {
+            fixed_price: 50_000,
+            ..market.clone()
+        };
+        let dear = Market {
+            fixed_price: 200_000,
+            ..market
+        };
+
+        // ~800 USDC of debt: fine at 200 USDC/SOL, far over the limit at 50
+        assert!(check_health_p1(&dear, &position).is_ok());
+        assert_eq!(
+            check_health_p1(&cheap, &position).unwrap_err(),
+            PelagoError::InsufficientCollateral.into()
+        );
+    }
+
+    #[test]
+    fn test_dust_shares_do_not_block_collateral_withdrawal() {
+        let (mut market, mut position) = near_limit_position();
+        market.total_collateral = position.collateral_amount;
+
+        // Repaid down to 2 shares of rounding dust, worth 1 unit rounded up
+        position.borrow_shares = 2;
+        let all = position.collateral_amount;
+        assert_eq!(
+            withdraw_collateral_at(&mut market.clone(), &mut position.clone(), all, START)
+                .unwrap_err(),
+            PelagoError::InsufficientCollateral.into()
+        );
+
+        // With a dust threshold the full withdrawal goes through, but the
+        // shares stay on the books until repaid
+        market.dust_shares_threshold = 2;
+        withdraw_collateral_at(&mut market, &mut position, all, START).unwrap();
+        assert_eq!(position.collateral_amount, 0);
+        assert_eq!(position.borrow_shares, 2);
+
+        // Real debt above the threshold is still checked
+        position.borrow_shares = 3;
+        assert_eq!(
+            check_health_p1(&market, &position).unwrap_err(),
+            PelagoError::InsufficientCollateral.into()
+        );
+    }
+
+    #[test]
+    fn test_repaid_position_withdraws_all_after_accrual() {
+        let (mut market, mut position) = near_limit_position();
+        market.total_collateral = position.collateral_amount;
+
+        // Another borrower keeps the market's debt outstanding once this
+        // position has repaid to zero shares
+        position.borrow_shares = 0;
+        let borrow_before = market.total_borrow_assets;
+
+        let health = withdraw_collateral_at(
+            &mut market,
+            &mut position,
+            10_000_000_000,
+            START + 86_400,
+        )
+        .unwrap();
+        assert_eq!(health.health_factor, u64::MAX);
+        assert_eq!(position.collateral_amount, 0);
+        assert_eq!(market.total_collateral, 0);
+
+        // The zero-debt shortcut did not skip accrual
+        assert_eq!(market.last_update, START + 86_400);
+        assert!(market.total_borrow_assets > borrow_before);
+    }
+
+    #[test]
+    fn test_residual_share_blocks_full_withdrawal() {
+        let (mut market, mut position) = near_limit_position();
+        market.total_collateral = position.collateral_amount;
+
+        // A single borrow share left after a partial repay
+        position.borrow_shares = 1;
+        assert_eq!(
+            withdraw_collateral_at(&mut market, &mut position, 10_000_000_000, START + 86_400)
+                .unwrap_err(),
+            PelagoError::InsufficientCollateral.into()
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Withdraw Reserves Instruction
+//!
+//! Lets the market authority take out `market.reserves`: origination fees
+//! and the supply residue swept in by `withdraw`, `claim_fees` and
+//! `sweep_dust`. Reserves are not supplier funds, so paying them out leaves
+//! the suppliers' idle liquidity (`total_supply_assets - total_borrow_assets`)
+//! in the vault.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::error::PelagoError;
+use crate::state::Market;
+use crate::utils::interest::accrue_interest;
+use crate::utils::vault_snapshot::snapshot_balance_delta;
+
+/// Withdraw protocol reserves from a market's loan vault
+///
+/// **Access Control:** Only the market authority
+#[derive(Accounts)]
+pub struct WithdrawReserves<'info> {
+    /// Market account
+    #[account(
+        mut,
+        seeds = [
+            Market::SEED_PREFIX,
+            market.loan_token_mint.as_ref(),
+            market.collateral_token_mint.as_ref(),
+        ],
+        bump = market.bump,
+        has_one = authority @ PelagoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// Market's loan token vault (source of the payout)
+    #[account(
+        mut,
+        constraint = loan_vault.key() == market.loan_vault @ PelagoError::WrongLoanVault,
+    )]
+    pub loan_vault: Account<'info, TokenAccount>,
+
+    /// Receiver loan token account
+    #[account(
+        mut,
+        constraint = receiver_token_account.mint == market.loan_token_mint @ PelagoError::UninitializedMarket,
+    )]
+    pub receiver_token_account: Account<'info, TokenAccount>,
+
+    /// Market authority (signer)
+    pub authority: Signer<'info>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler for withdraw_reserves instruction
+///
+/// **State Changes:**
+/// - market.reserves -= assets
+///
+/// **Errors:**
+/// - Unauthorized: Signer is not the market authority
+/// - ZeroAmount: `assets == 0`
+/// - InsufficientLiquidity: `assets` exceeds `withdrawable_reserves`
+/// - MathOverflow: Calculation overflow
+pub fn handler(ctx: Context<WithdrawReserves>, assets: u64) -> Result<()> {
+    require!(assets > 0, PelagoError::ZeroAmount);
+
+    let market = &mut ctx.accounts.market;
+    accrue_interest(market)?;
+
+    let withdrawable = withdrawable_reserves(market, ctx.accounts.loan_vault.amount);
+    require!(assets <= withdrawable, PelagoError::InsufficientLiquidity);
+    market.reserves -= assets;
+
+    let seeds = &[
+        Market::SEED_PREFIX,
+        market.loan_token_mint.as_ref(),
+        market.collateral_token_mint.as_ref(),
+        &[market.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.loan_vault.to_account_info(),
+            to: ctx.accounts.receiver_token_account.to_account_info(),
+            authority: market.to_account_info(),
+        },
+        signer_seeds,
+    );
+    snapshot_balance_delta(&mut ctx.accounts.loan_vault, || {
+        token::transfer(cpi_ctx, assets)
+    })?
+    .require_decrease(assets)?;
+
+    msg!(
+        "Reserves withdrawn: market={}, assets={}, remaining_reserves={}",
+        market.key(),
+        assets,
+        market.reserves
+    );
+
+    Ok(())
+}
+
+/// Reserves that can leave the vault right now
+///
+/// At most `market.reserves`, and never the suppliers' idle liquidity: the
+/// vault keeps at least `total_supply_assets - total_borrow_assets` after
+/// the payout. A vault running short of the accounting identity caps the
+/// payout rather than the suppliers' withdrawals.
+pub fn withdrawable_reserves(market: &Market, vault_amount: u64) -> u64 {
+    let idle_supply = market
+        .total_supply_assets
+        .saturating_sub(market.total_borrow_assets);
+    market
+        .reserves
+        .min(vault_amount.saturating_sub(idle_supply))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_withdrawable_reserves_capped_by_vault() {
+        // 1000 USDC supplied, 500 borrowed with a 5 USDC origination fee
+        let market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_borrow_assets: 505_000_000,
+            reserves: 5_000_000,
+            ..Default::default()
+        };
+
+        // Vault matches accounting (495 idle + 5 reserves): all withdrawable
+        assert_eq!(withdrawable_reserves(&market, 500_000_000), 5_000_000);
+
+        // Short vault: never dips into the suppliers' 495 USDC idle liquidity
+        assert_eq!(withdrawable_reserves(&market, 497_000_000), 2_000_000);
+        assert_eq!(withdrawable_reserves(&market, 400_000_000), 0);
+
+        // Swept residue on an emptied market is all withdrawable
+        let emptied = Market {
+            reserves: 3,
+            ..Default::default()
+        };
+        assert_eq!(withdrawable_reserves(&emptied, 3), 3);
+    }
+}
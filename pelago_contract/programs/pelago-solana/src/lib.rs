@@ -20,14 +20,31 @@ declare_id!("5Y6KqLPs2DGRBzg4ybG9KfkyM5vTt8ZDELy9YwF8rGJq");
 /// - Collateral asset supply/withdraw
 /// - Borrowing/repayment with health factor validation
 /// - Virtual shares mechanism (防止通胀攻击)
-/// - Interest accrual (简化版线性利息)
+/// - Interest accrual (泰勒级数复利)
+/// - Kinked utilization-based interest rate model (`set_irm`)
 ///
-/// **P1 Simplifications:**
-/// - Fixed oracle price (100 USDC/SOL)
-/// - Fixed annual rate (5%)
-/// - Linear interest (not compound)
-/// - No liquidation mechanism (延迟到P2)
-/// - No authorization/callback systems (延迟到P2)
+/// **Pricing:**
+/// - No external oracle feed: collateral is priced at the market's
+///   `fixed_price` (FIXED_ORACLE_PRICE if unset), or at `manual_price`
+///   while the authority enables it (`set_manual_price`)
+/// - With a `twap_window`, health checks use the TWAP cranked by
+///   `update_price_history`, falling back to spot once it is older than the
+///   window
+/// - Every price must lie within the market's sanity band
+///   (`set_price_bounds`)
+/// - Liquidations reject a manual price older than
+///   `liquidation_max_staleness_secs`; fixed prices never go stale
+///
+/// **Trust Model:**
+/// - The market authority is trusted for prices, fees and risk parameters;
+///   a manager (`set_manager`) may only run operational settings. LLTVs and
+///   rate models are bounded by `ProtocolConfig`, whose admin must be the
+///   program's upgrade authority
+/// - `leverage` calls an arbitrary user-chosen swap program. It is not
+///   trusted: only the collateral actually received is credited, health is
+///   checked afterwards, and the runtime rejects reentrancy into Pelago
+/// - Liquidation via `liquidate` / `batch_liquidate` (close factor + fixed
+///   bonus) or `liquidate_to_target` is permissionless
 #[program]
 pub mod pelago_solana {
     use super::*;
@@ -41,10 +58,18 @@ pub mod pelago_solana {
     /// - `lltv`: Liquidation Loan-to-Value ratio (precision: 1e8)
     ///   - Example: 80% → 80_000_000
     ///   - Valid range: 0 < lltv <= 100_000_000
+    /// - `fixed_price`: Collateral price (precision: PRICE_PRECISION)
+    ///   - Example: 100 USDC/SOL → 100_000
+    ///   - 0 selects 100 loan tokens per collateral token, scaled to the mint decimals
+    /// - `lltv_timelock`: Delay in seconds between `propose_lltv` and `apply_lltv`
+    /// - `fee_bps`: Interest fee in bps (max 2_500)
+    ///   - USE_PROTOCOL_DEFAULT_FEE (u16::MAX) inherits `protocol_config.default_fee_bps`
+    /// - `irm`: Kinked rate model (see `set_irm`)
+    ///   - All zero keeps the fixed 5% rate
     ///
     /// **Accounts:**
     /// - `market`: Market PDA account (to be initialized)
-    /// - `loan_token_mint`: SPL token mint for loan asset (e.g., USDC)
+    /// - `loan_token_mint`: SPL token mint for loan asset (e.g., USDC, at most 10 decimals)
     /// - `collateral_token_mint`: SPL token mint for collateral asset (e.g., SOL)
     /// - `loan_vault`: Token account for holding loan assets (to be created)
     /// - `collateral_vault`: Token account for holding collateral assets (to be created)
@@ -52,8 +77,27 @@ pub mod pelago_solana {
     /// - `system_program`: Solana system program
     /// - `token_program`: SPL token program
     /// - `rent`: Rent sysvar
-    pub fn initialize_market(ctx: Context<InitializeMarket>, lltv: u64) -> Result<()> {
-        instructions::initialize_market::handler(ctx, lltv)
+    /// - `protocol_config`: ProtocolConfig PDA (enforces max LLTV, the enabled
+    ///   LLTV set and rate bounds; resolves the sentinel fee)
+    pub fn initialize_market(
+        ctx: Context<InitializeMarket>,
+        lltv: u64,
+        fixed_price: u64,
+        lltv_timelock: i64,
+        fee_bps: u16,
+        irm: IrmParams,
+    ) -> Result<()> {
+        profiled!(
+            "initialize_market",
+            instructions::initialize_market::handler(
+                ctx,
+                lltv,
+                fixed_price,
+                lltv_timelock,
+                fee_bps,
+                irm,
+            )
+        )
     }
 
     /// Supply loan assets to the market
@@ -68,6 +112,7 @@ pub mod pelago_solana {
     /// **Accounts:**
     /// - `market`: Market account
     /// - `user_position`: User position PDA (created if first supply)
+    /// - `user_registry`: User's market registry PDA (created if needed)
     /// - `loan_vault`: Market's loan token vault
     /// - `user_token_account`: User's loan token account (source)
     /// - `user`: User wallet (signer)
@@ -77,8 +122,14 @@ pub mod pelago_solana {
     /// **P1: Dual-parameter mode** (Pelago compatibility)
     /// - `assets > 0, shares = 0`: Supply exact assets, calculate shares
     /// - `assets = 0, shares > 0`: Burn exact shares, calculate assets
-    pub fn supply(ctx: Context<Supply>, assets: u64, shares: u64) -> Result<()> {
-        instructions::supply::handler(ctx, assets, shares)
+    /// - `max_assets_in`: Upper bound on assets pulled in shares mode (0 = no limit)
+    pub fn supply(
+        ctx: Context<Supply>,
+        assets: u64,
+        shares: u64,
+        max_assets_in: u64,
+    ) -> Result<()> {
+        profiled!("supply", instructions::supply::handler(ctx, assets, shares, max_assets_in))
     }
 
     /// Supply collateral assets to the market
@@ -93,13 +144,14 @@ pub mod pelago_solana {
     /// **Accounts:**
     /// - `market`: Market account
     /// - `user_position`: User position PDA (created if doesn't exist)
+    /// - `user_registry`: User's market registry PDA (created if needed)
     /// - `collateral_vault`: Market's collateral token vault
     /// - `user_collateral_account`: User's collateral token account (source)
     /// - `user`: User wallet (signer)
     /// - `system_program`: Solana system program
     /// - `token_program`: SPL token program
     pub fn supply_collateral(ctx: Context<SupplyCollateral>, amount: u64) -> Result<()> {
-        instructions::supply_collateral::handler(ctx, amount)
+        profiled!("supply_collateral", instructions::supply_collateral::handler(ctx, amount))
     }
 
     /// Borrow loan assets from the market
@@ -128,8 +180,21 @@ pub mod pelago_solana {
     /// **P1: Dual-parameter mode** (Pelago compatibility)
     /// - `assets > 0, shares = 0`: Borrow exact assets, calculate shares
     /// - `assets = 0, shares > 0`: Incur exact debt shares, calculate assets
-    pub fn borrow(ctx: Context<Borrow>, assets: u64, shares: u64) -> Result<()> {
-        instructions::borrow::handler(ctx, assets, shares)
+    ///
+    /// **Liquidation Buffer Preview:**
+    /// - `preview_liquidation_buffer = true`: Returns (via return data) the
+    ///   adverse collateral price move in bps the position can withstand
+    ///   before crossing the liquidation threshold, as a little-endian u64
+    pub fn borrow(
+        ctx: Context<Borrow>,
+        assets: u64,
+        shares: u64,
+        preview_liquidation_buffer: bool,
+    ) -> Result<()> {
+        profiled!(
+            "borrow",
+            instructions::borrow::handler(ctx, assets, shares, preview_liquidation_buffer)
+        )
     }
 
     /// Withdraw loan assets from the market
@@ -155,7 +220,7 @@ pub mod pelago_solana {
     /// - `loan_vault`: Market's loan token vault (source)
     /// - `token_program`: SPL token program
     pub fn withdraw(ctx: Context<Withdraw>, assets: u64, shares: u64) -> Result<()> {
-        instructions::withdraw::handler(ctx, assets, shares)
+        profiled!("withdraw", instructions::withdraw::handler(ctx, assets, shares))
     }
 
     /// Withdraw collateral assets from user position
@@ -179,7 +244,7 @@ pub mod pelago_solana {
     /// - `collateral_vault`: Market's collateral token vault (source)
     /// - `token_program`: SPL token program
     pub fn withdraw_collateral(ctx: Context<WithdrawCollateral>, assets: u64) -> Result<()> {
-        instructions::withdraw_collateral::handler(ctx, assets)
+        profiled!("withdraw_collateral", instructions::withdraw_collateral::handler(ctx, assets))
     }
 
     /// Repay borrowed loan assets
@@ -191,6 +256,8 @@ pub mod pelago_solana {
     /// - `assets`: Amount of loan tokens to repay (mutually exclusive with shares)
     /// - `shares`: Amount of borrow shares to burn (mutually exclusive with assets)
     ///   - Exactly one must be > 0, the other must be 0
+    /// - `supply_excess`: In asset mode, supply any amount beyond the full
+    ///   debt for the payer instead of absorbing it
     ///
     /// **P1 Enhancements:**
     /// - Virtual shares calculation
@@ -200,12 +267,1148 @@ pub mod pelago_solana {
     /// **Accounts:**
     /// - `market`: Market account
     /// - `borrower_position`: Borrower's position PDA
+    /// - `payer_position`: Payer's position PDA (optional; receives the excess
+    ///   supply when the payer is not the borrower)
     /// - `payer`: Payer wallet (signer, can be different from borrower)
     /// - `borrower`: Borrower wallet (whose debt is being repaid)
     /// - `payer_token_account`: Payer's loan token account (source)
     /// - `loan_vault`: Market's loan token vault (destination)
     /// - `token_program`: SPL token program
-    pub fn repay(ctx: Context<Repay>, assets: u64, shares: u64) -> Result<()> {
-        instructions::repay::handler(ctx, assets, shares)
+    pub fn repay(ctx: Context<Repay>, assets: u64, shares: u64, supply_excess: bool) -> Result<()> {
+        profiled!("repay", instructions::repay::handler(ctx, assets, shares, supply_excess))
+    }
+
+    /// Open or increase a leveraged position atomically
+    ///
+    /// Borrows loan tokens, swaps them to collateral through a caller-provided
+    /// swap program, deposits the received collateral, and checks health once.
+    ///
+    /// **Parameters:**
+    /// - `borrow_assets`: Loan tokens to borrow and swap
+    ///   - Must be > 0
+    /// - `min_collateral_out`: Minimum collateral the swap must deliver
+    /// - `swap_data`: Instruction data forwarded to the swap program
+    ///
+    /// **Max Leverage:** `1 / (1 - lltv)` (e.g. 5x at 80% LLTV)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `user_position`: User position PDA (must exist)
+    /// - `loan_vault`: Market's loan token vault (source)
+    /// - `collateral_vault`: Market's collateral token vault (destination)
+    /// - `user_loan_account`: User's loan token account (swap input)
+    /// - `user_collateral_account`: User's collateral token account (swap output)
+    /// - `user`: User wallet (signer)
+    /// - `swap_program`: Swap program invoked as the callback
+    /// - `token_program`: SPL token program
+    /// - `remaining_accounts`: Accounts forwarded to the swap program
+    pub fn leverage<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Leverage<'info>>,
+        borrow_assets: u64,
+        min_collateral_out: u64,
+        swap_data: Vec<u8>,
+    ) -> Result<()> {
+        profiled!(
+            "leverage",
+            instructions::leverage::handler(ctx, borrow_assets, min_collateral_out, swap_data)
+        )
+    }
+
+    /// Read the last-activity timestamp of a position (view)
+    ///
+    /// Returns the Unix timestamp of the position's last supply, borrow,
+    /// repay, withdraw or collateral action via return data.
+    ///
+    /// **Accounts:**
+    /// - `user_position`: User position PDA
+    pub fn get_last_activity(ctx: Context<GetLastActivity>) -> Result<i64> {
+        profiled!("get_last_activity", instructions::get_last_activity::handler(ctx))
+    }
+
+    /// Initialize the protocol config singleton
+    ///
+    /// Records the admin (signer) and the emergency guardian. Only the
+    /// program's upgrade authority may call it.
+    ///
+    /// **Parameters:**
+    /// - `guardian`: Emergency key allowed to pause any market
+    ///
+    /// **Accounts:**
+    /// - `config`: Config PDA (to be initialized)
+    /// - `admin`: Protocol admin (signer, must be the upgrade authority)
+    /// - `program`: This program
+    /// - `program_data`: This program's ProgramData account
+    /// - `system_program`: Solana system program
+    pub fn initialize_config(ctx: Context<InitializeConfig>, guardian: Pubkey) -> Result<()> {
+        profiled!("initialize_config", instructions::initialize_config::handler(ctx, guardian))
+    }
+
+    /// Rotate the protocol emergency guardian
+    ///
+    /// **Parameters:**
+    /// - `guardian`: New guardian key
+    ///
+    /// **Accounts:**
+    /// - `config`: Config PDA
+    /// - `admin`: Protocol admin (signer)
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        profiled!("set_guardian", instructions::set_guardian::handler(ctx, guardian))
+    }
+
+    /// Emergency pause of any market by the protocol guardian
+    ///
+    /// Pause only: the guardian can never unpause a market.
+    ///
+    /// **Accounts:**
+    /// - `config`: Config PDA
+    /// - `market`: Market to pause
+    /// - `guardian`: Protocol guardian (signer)
+    pub fn guardian_pause(ctx: Context<GuardianPause>) -> Result<()> {
+        profiled!("guardian_pause", instructions::guardian_pause::handler(ctx))
+    }
+
+    /// Pause or unpause a market
+    ///
+    /// **Parameters:**
+    /// - `paused`: New pause flag
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority or manager (signer)
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        profiled!("set_paused", instructions::set_paused::handler(ctx, paused))
+    }
+
+    /// Configure a market's borrow caps
+    ///
+    /// **Parameters:**
+    /// - `borrow_cap`: Absolute cap on total borrow assets (0 = unlimited)
+    /// - `borrow_cap_ratio_bps`: Cap relative to total supply in bps (0 = disabled)
+    ///   - Valid range: 0 <= ratio <= 10_000
+    ///   - The tighter of the two caps applies
+    /// - `max_borrow_per_position`: Cap on a single position's debt (0 = unlimited)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority or manager (signer)
+    pub fn set_borrow_caps(
+        ctx: Context<SetBorrowCaps>,
+        borrow_cap: u64,
+        borrow_cap_ratio_bps: u16,
+        max_borrow_per_position: u64,
+    ) -> Result<()> {
+        profiled!(
+            "set_borrow_caps",
+            instructions::set_borrow_caps::handler(
+                ctx,
+                borrow_cap,
+                borrow_cap_ratio_bps,
+                max_borrow_per_position,
+            )
+        )
+    }
+
+    /// Choose how accrued interest is rounded
+    ///
+    /// **Parameters:**
+    /// - `round_up`: Round interest up (suppliers keep the dust) instead of down
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_interest_rounding(ctx: Context<SetInterestRounding>, round_up: bool) -> Result<()> {
+        profiled!(
+            "set_interest_rounding",
+            instructions::set_interest_rounding::handler(ctx, round_up)
+        )
+    }
+
+    /// Quote the loan tokens needed to fully repay a position
+    ///
+    /// **Parameters:**
+    /// - `buffer_seconds`: Forward window for the buffered estimate
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `user_position`: Borrower's position
+    ///
+    /// **Returns:** `RepayQuote { assets, assets_with_buffer }`
+    pub fn get_repay_amount(ctx: Context<GetRepayAmount>, buffer_seconds: u32) -> Result<RepayQuote> {
+        profiled!("get_repay_amount", instructions::get_repay_amount::handler(ctx, buffer_seconds))
+    }
+
+    /// Set a manual collateral price used by health checks
+    ///
+    /// **Parameters:**
+    /// - `price`: Collateral price (PRICE_PRECISION scale, must be non-zero)
+    /// - `enabled`: Use `price` instead of FIXED_ORACLE_PRICE
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_manual_price(ctx: Context<SetManualPrice>, price: u64, enabled: bool) -> Result<()> {
+        profiled!("set_manual_price", instructions::set_manual_price::handler(ctx, price, enabled))
+    }
+
+    /// Configure the maximum number of open positions
+    ///
+    /// **Parameters:**
+    /// - `max_positions`: Position cap (0 = unlimited)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_max_positions(ctx: Context<SetMaxPositions>, max_positions: u32) -> Result<()> {
+        profiled!("set_max_positions", instructions::set_max_positions::handler(ctx, max_positions))
+    }
+
+    /// Set the protocol fees
+    ///
+    /// **Parameters:**
+    /// - `fee_bps`: Fee on accrued interest in basis points
+    ///   - Valid range: 0 <= fee_bps <= 2_500 (25%)
+    /// - `origination_fee_bps`: One-time fee on borrowed assets in basis points
+    ///   - Valid range: 0 <= origination_fee_bps <= 500 (5%)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_fee(ctx: Context<SetFee>, fee_bps: u16, origination_fee_bps: u16) -> Result<()> {
+        profiled!("set_fee", instructions::set_fee::handler(ctx, fee_bps, origination_fee_bps))
+    }
+
+    /// Recompute total collateral from all open positions
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    /// - `remaining_accounts`: Every open user position of the market
+    ///   (at most MAX_BATCH_POSITION_READS; larger markets can't be resynced)
+    pub fn resync_total_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ResyncTotalCollateral<'info>>,
+    ) -> Result<()> {
+        profiled!("resync_total_collateral", instructions::resync_total_collateral::handler(ctx))
+    }
+
+    /// Create the collateral price history used for TWAP pricing
+    ///
+    /// **Parameters:**
+    /// - `twap_window`: Averaging window in seconds (0 = spot only)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `price_history`: Price history PDA (initialized here)
+    /// - `authority`: Market authority (signer, payer)
+    /// - `system_program`: Solana system program
+    pub fn initialize_price_history(
+        ctx: Context<InitializePriceHistory>,
+        twap_window: u32,
+    ) -> Result<()> {
+        profiled!(
+            "initialize_price_history",
+            instructions::initialize_price_history::handler(ctx, twap_window)
+        )
+    }
+
+    /// Record the spot price and refresh the market TWAP (keeper crank)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `price_history`: Price history PDA of the market
+    pub fn update_price_history(ctx: Context<UpdatePriceHistory>) -> Result<()> {
+        profiled!("update_price_history", instructions::update_price_history::handler(ctx))
+    }
+
+    /// Supply collateral and borrow against it in one transaction
+    ///
+    /// **Parameters:**
+    /// - `collateral_amount`: Collateral to deposit (collateral token base units)
+    /// - `borrow_assets`: Loan assets to borrow (loan token base units)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `user_position`: User position PDA (created if needed)
+    /// - `user_registry`: User's market registry PDA (created if needed)
+    /// - `collateral_vault`: Market's collateral vault
+    /// - `loan_vault`: Market's loan vault
+    /// - `user_collateral_account`: User's collateral token account
+    /// - `user_loan_account`: User's loan token account
+    /// - `user`: User wallet (signer, payer)
+    /// - `system_program`: Solana system program
+    /// - `token_program`: SPL token program
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        collateral_amount: u64,
+        borrow_assets: u64,
+    ) -> Result<()> {
+        profiled!(
+            "open_position",
+            instructions::open_position::handler(ctx, collateral_amount, borrow_assets)
+        )
+    }
+
+    /// Reset a future-dated accrual clock to now (no interest accrued)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account (last_update must be in the future)
+    /// - `authority`: Market authority (signer)
+    pub fn reset_accrual_clock(ctx: Context<ResetAccrualClock>) -> Result<()> {
+        profiled!("reset_accrual_clock", instructions::reset_accrual_clock::handler(ctx))
+    }
+
+    /// Read a versioned summary of a market
+    ///
+    /// **Accounts:**
+    /// - `market`: Market PDA for the mint pair (may be uninitialized)
+    /// - `loan_token_mint`: Loan token mint
+    /// - `collateral_token_mint`: Collateral token mint
+    ///
+    /// **Returns:** `MarketInfo` (`initialized = false` if the market does not exist)
+    pub fn get_market_info(ctx: Context<GetMarketInfo>) -> Result<MarketInfo> {
+        profiled!("get_market_info", instructions::get_market_info::handler(ctx))
+    }
+
+    /// Report potential bad debt across user positions
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `remaining_accounts`: User positions of this market to inspect
+    ///   (at most MAX_BATCH_POSITION_READS)
+    ///
+    /// **Returns:** Total debt exceeding collateral value, in loan token units
+    pub fn check_bad_debt<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CheckBadDebt<'info>>,
+    ) -> Result<u64> {
+        profiled!("check_bad_debt", instructions::check_bad_debt::handler(ctx))
+    }
+
+    /// Propose a new LLTV, applicable after the market's timelock
+    ///
+    /// **Parameters:**
+    /// - `lltv`: Proposed LLTV (precision: 1e8, 0 < lltv <= 100_000_000)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn propose_lltv(ctx: Context<ProposeLltv>, lltv: u64) -> Result<()> {
+        profiled!("propose_lltv", instructions::propose_lltv::handler(ctx, lltv))
+    }
+
+    /// Apply the pending LLTV once its timelock has elapsed
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn apply_lltv(ctx: Context<ApplyLltv>) -> Result<()> {
+        profiled!("apply_lltv", instructions::apply_lltv::handler(ctx))
+    }
+
+    /// Configure the utilization watermark above which new borrows are refused
+    ///
+    /// **Parameters:**
+    /// - `auto_pause_utilization_bps`: Watermark in bps (0 = disabled, max 10_000)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_auto_pause_utilization(
+        ctx: Context<SetAutoPauseUtilization>,
+        auto_pause_utilization_bps: u16,
+    ) -> Result<()> {
+        profiled!(
+            "set_auto_pause_utilization",
+            instructions::set_auto_pause_utilization::handler(ctx, auto_pause_utilization_bps)
+        )
+    }
+
+    /// Configure the minimum time between borrows of one position
+    ///
+    /// **Parameters:**
+    /// - `borrow_cooldown_secs`: Cooldown in seconds (0 = disabled)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_borrow_cooldown(
+        ctx: Context<SetBorrowCooldown>,
+        borrow_cooldown_secs: u32,
+    ) -> Result<()> {
+        profiled!(
+            "set_borrow_cooldown",
+            instructions::set_borrow_cooldown::handler(ctx, borrow_cooldown_secs)
+        )
+    }
+
+    /// Liquidate several unhealthy positions in one transaction
+    ///
+    /// Healthy positions are skipped. Each liquidation repays up to the
+    /// close factor of the position's debt and seizes collateral worth the
+    /// repaid amount plus the liquidation bonus.
+    ///
+    /// **Parameters:**
+    /// - `min_seize`: Minimum total collateral to seize (0 = no limit)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `loan_vault`: Market's loan token vault
+    /// - `collateral_vault`: Market's collateral token vault
+    /// - `liquidator_loan_account`: Liquidator's loan token account (source)
+    /// - `liquidator_collateral_account`: Liquidator's collateral token account (receiver)
+    /// - `liquidator`: Liquidator wallet (signer)
+    /// - `token_program`: SPL token program
+    /// - `remaining_accounts`: Borrower positions (writable, at most MAX_BATCH_LIQUIDATIONS)
+    pub fn batch_liquidate<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchLiquidate<'info>>,
+        min_seize: u64,
+    ) -> Result<()> {
+        profiled!("batch_liquidate", instructions::batch_liquidate::handler(ctx, min_seize))
+    }
+
+    /// Read the protocol's precision constants
+    ///
+    /// **Returns:** `ProtocolConstants` (virtual offsets, WAD, LLTV and price
+    /// precision, seconds per year)
+    pub fn get_constants(ctx: Context<GetConstants>) -> Result<ProtocolConstants> {
+        profiled!("get_constants", instructions::get_constants::handler(ctx))
+    }
+
+    /// Choose whether interest accrues while the market is paused
+    ///
+    /// **Parameters:**
+    /// - `accrue`: Keep charging interest during pauses (default: true)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_accrue_while_paused(ctx: Context<SetAccrueWhilePaused>, accrue: bool) -> Result<()> {
+        profiled!(
+            "set_accrue_while_paused",
+            instructions::set_accrue_while_paused::handler(ctx, accrue)
+        )
+    }
+
+    /// List the markets a user holds positions in
+    ///
+    /// **Accounts:**
+    /// - `user_registry`: User's market registry PDA (may not exist yet)
+    /// - `user`: User wallet
+    ///
+    /// **Returns:** Market pubkeys in creation order (empty if no registry)
+    pub fn get_user_markets(ctx: Context<GetUserMarkets>) -> Result<Vec<Pubkey>> {
+        profiled!("get_user_markets", instructions::get_user_markets::handler(ctx))
+    }
+
+    /// Choose whether asset-mode supply rounds shares in the user's favor
+    ///
+    /// **Parameters:**
+    /// - `favor_user`: Round supply shares up instead of down (default: false)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_favor_user_on_supply(
+        ctx: Context<SetFavorUserOnSupply>,
+        favor_user: bool,
+    ) -> Result<()> {
+        profiled!(
+            "set_favor_user_on_supply",
+            instructions::set_favor_user_on_supply::handler(ctx, favor_user)
+        )
+    }
+
+    /// Seed the initial liquidity of an empty market
+    ///
+    /// Meant to be bundled with `initialize_market` in one transaction. The
+    /// shares of MIN_SEED_ASSETS are locked forever (`market.dead_shares`);
+    /// the rest go to the authority's position.
+    ///
+    /// **Parameters:**
+    /// - `assets`: Seed amount (loan token base units, >= MIN_SEED_ASSETS)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account (no supply yet)
+    /// - `authority_position`: Authority's position PDA (created if needed)
+    /// - `authority_registry`: Authority's market registry PDA (created if needed)
+    /// - `loan_vault`: Market's loan token vault
+    /// - `authority_token_account`: Authority's loan token account (source)
+    /// - `authority`: Market authority (signer, payer)
+    /// - `system_program`: Solana system program
+    /// - `token_program`: SPL token program
+    pub fn seed_market(ctx: Context<SeedMarket>, assets: u64) -> Result<()> {
+        profiled!("seed_market", instructions::seed_market::handler(ctx, assets))
+    }
+
+    /// Set the plausible collateral price range (oracle sanity band)
+    ///
+    /// **Parameters:**
+    /// - `min_sane_price`: Lowest accepted price (PRICE_PRECISION scale, 0 = no floor)
+    /// - `max_sane_price`: Highest accepted price (PRICE_PRECISION scale, 0 = no ceiling)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_price_bounds(
+        ctx: Context<SetPriceBounds>,
+        min_sane_price: u64,
+        max_sane_price: u64,
+    ) -> Result<()> {
+        profiled!(
+            "set_price_bounds",
+            instructions::set_price_bounds::handler(ctx, min_sane_price, max_sane_price)
+        )
+    }
+
+    /// Create or update the protocol-wide market defaults
+    ///
+    /// **Parameters:**
+    /// - `default_fee_bps`: Fee for markets initialized with the sentinel fee (max 2_500)
+    /// - `fee_recipient`: Recipient of protocol fees
+    /// - `max_lltv`: Highest LLTV a new market may use (precision: 1e8)
+    /// - `min_rate_wad` / `max_rate_wad`: Accepted annual borrow rate range (WAD)
+    ///
+    /// **Accounts:**
+    /// - `config`: Config PDA (source of the admin key)
+    /// - `protocol_config`: ProtocolConfig PDA (created on first call)
+    /// - `admin`: Protocol admin (signer)
+    /// - `system_program`: Solana system program
+    pub fn set_protocol_config(
+        ctx: Context<SetProtocolConfig>,
+        default_fee_bps: u16,
+        fee_recipient: Pubkey,
+        max_lltv: u64,
+        min_rate_wad: u128,
+        max_rate_wad: u128,
+    ) -> Result<()> {
+        profiled!(
+            "set_protocol_config",
+            instructions::set_protocol_config::handler(
+                ctx,
+                default_fee_bps,
+                fee_recipient,
+                max_lltv,
+                min_rate_wad,
+                max_rate_wad,
+            )
+        )
+    }
+
+    /// Configure the per-accrual interest cap (safety rail)
+    ///
+    /// **Parameters:**
+    /// - `max_accrual_interest_bps`: Max interest per accrual in bps of total
+    ///   borrows (max 10_000, 0 = uncapped, default 1_000)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_max_accrual_interest(
+        ctx: Context<SetMaxAccrualInterest>,
+        max_accrual_interest_bps: u16,
+    ) -> Result<()> {
+        profiled!(
+            "set_max_accrual_interest",
+            instructions::set_max_accrual_interest::handler(ctx, max_accrual_interest_bps)
+        )
+    }
+
+    /// Whitelist an LLTV value for new markets
+    ///
+    /// **Parameters:**
+    /// - `lltv`: LLTV to enable (precision: 1e8, 0 < lltv <= 100_000_000)
+    ///
+    /// **Accounts:**
+    /// - `config`: Config PDA (source of the admin key)
+    /// - `protocol_config`: ProtocolConfig PDA
+    /// - `admin`: Protocol admin (signer)
+    pub fn enable_lltv(ctx: Context<EnableLltv>, lltv: u64) -> Result<()> {
+        profiled!("enable_lltv", instructions::enable_lltv::handler(ctx, lltv))
+    }
+
+    /// Remove an LLTV value from the new-market whitelist
+    ///
+    /// **Parameters:**
+    /// - `lltv`: Enabled LLTV to disable
+    ///
+    /// **Accounts:**
+    /// - `config`: Config PDA (source of the admin key)
+    /// - `protocol_config`: ProtocolConfig PDA
+    /// - `admin`: Protocol admin (signer)
+    pub fn disable_lltv(ctx: Context<DisableLltv>, lltv: u64) -> Result<()> {
+        profiled!("disable_lltv", instructions::disable_lltv::handler(ctx, lltv))
+    }
+
+    /// Configure the utilization kink for the interest fee
+    ///
+    /// **Parameters:**
+    /// - `fee_kink_utilization_bps`: Utilization below which no fee is taken
+    ///   (< 10_000, 0 = fee always applies); above it the fee ramps up to
+    ///   `fee_bps` at 100% utilization
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_fee_kink(ctx: Context<SetFeeKink>, fee_kink_utilization_bps: u16) -> Result<()> {
+        profiled!(
+            "set_fee_kink",
+            instructions::set_fee_kink::handler(ctx, fee_kink_utilization_bps)
+        )
+    }
+
+    /// Close an empty position and reclaim its rent
+    ///
+    /// The position can be recreated later by supplying again; it then
+    /// counts against `max_positions` and is re-added to the registry.
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `user_position`: User position PDA (closed, must be empty)
+    /// - `user_registry`: User's market registry PDA
+    /// - `user`: User wallet (signer, receives the rent)
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        profiled!("close_position", instructions::close_position::handler(ctx))
+    }
+
+    /// Configure the minimum amount a partial liquidation must repay
+    ///
+    /// Positions whose close-factor share is below the minimum are closed
+    /// in full by liquidators instead.
+    ///
+    /// **Parameters:**
+    /// - `min_liquidation_assets`: Minimum loan tokens repaid by a partial
+    ///   liquidation (0 = no minimum)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_min_liquidation_assets(
+        ctx: Context<SetMinLiquidationAssets>,
+        min_liquidation_assets: u64,
+    ) -> Result<()> {
+        profiled!(
+            "set_min_liquidation_assets",
+            instructions::set_min_liquidation_assets::handler(ctx, min_liquidation_assets)
+        )
+    }
+
+    /// Create the rate snapshots used for realized APY queries
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `rate_snapshot`: Rate snapshot PDA (initialized here)
+    /// - `authority`: Market authority (signer, payer)
+    /// - `system_program`: Solana system program
+    pub fn initialize_rate_snapshot(ctx: Context<InitializeRateSnapshot>) -> Result<()> {
+        profiled!("initialize_rate_snapshot", instructions::initialize_rate_snapshot::handler(ctx))
+    }
+
+    /// Accrue interest and record the market's share prices (keeper crank)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `rate_snapshot`: Rate snapshot PDA of the market
+    pub fn update_rate_snapshot(ctx: Context<UpdateRateSnapshot>) -> Result<()> {
+        profiled!("update_rate_snapshot", instructions::update_rate_snapshot::handler(ctx))
+    }
+
+    /// Read the realized borrow and supply APY over a historical window
+    ///
+    /// **Parameters:**
+    /// - `window`: Lookback in seconds from the latest snapshot (0 = whole history)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `rate_snapshot`: Rate snapshot PDA of the market
+    ///
+    /// **Returns:** `HistoricalApy` (`available = false` with fewer than two snapshots)
+    pub fn get_historical_apy(ctx: Context<GetHistoricalApy>, window: u32) -> Result<HistoricalApy> {
+        profiled!("get_historical_apy", instructions::get_historical_apy::handler(ctx, window))
+    }
+
+    /// Configure the borrow-to-supply ratio ceiling monitored on accrual
+    ///
+    /// Accruals that leave borrows above the ceiling still succeed but emit
+    /// a `RatioBreachEvent` for operators.
+    ///
+    /// **Parameters:**
+    /// - `max_borrow_ratio_bps`: Ceiling in bps of total supply assets
+    ///   (<= 10_000, 0 = off)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_max_borrow_ratio(
+        ctx: Context<SetMaxBorrowRatio>,
+        max_borrow_ratio_bps: u16,
+    ) -> Result<()> {
+        profiled!(
+            "set_max_borrow_ratio",
+            instructions::set_max_borrow_ratio::handler(ctx, max_borrow_ratio_bps)
+        )
+    }
+
+    /// Sweep rounding residue of an emptied market into reserves
+    ///
+    /// Requires every supply and borrow share to be burned; residue above
+    /// `MAX_DUST_SWEEP_ASSETS` is refused.
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn sweep_dust(ctx: Context<SweepDust>) -> Result<()> {
+        profiled!("sweep_dust", instructions::sweep_dust::handler(ctx))
+    }
+
+    /// Configure the maximum price age accepted by liquidations
+    ///
+    /// Only a manual price ages; liquidations on an older one fail with
+    /// `StaleOracle`.
+    ///
+    /// **Parameters:**
+    /// - `liquidation_max_staleness_secs`: Maximum price age in seconds (0 = off)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_liquidation_staleness(
+        ctx: Context<SetLiquidationStaleness>,
+        liquidation_max_staleness_secs: u32,
+    ) -> Result<()> {
+        profiled!(
+            "set_liquidation_staleness",
+            instructions::set_liquidation_staleness::handler(ctx, liquidation_max_staleness_secs)
+        )
+    }
+
+    /// Configure how often a market's interest compounds
+    ///
+    /// **Parameters:**
+    /// - `compound_period_secs`: Compounding period in seconds (<= 365 days,
+    ///   0 = compound on every accrual); only whole periods accrue
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_compound_period(
+        ctx: Context<SetCompoundPeriod>,
+        compound_period_secs: u32,
+    ) -> Result<()> {
+        profiled!(
+            "set_compound_period",
+            instructions::set_compound_period::handler(ctx, compound_period_secs)
+        )
+    }
+
+    /// Quote the collateral price at which a position becomes liquidatable
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `user_position`: Borrower's position in this market
+    ///
+    /// **Returns:** Lowest collateral price (PRICE_PRECISION scale) at which
+    /// the position is still healthy; 0 if it has no debt
+    pub fn get_liquidation_price(ctx: Context<GetLiquidationPrice>) -> Result<u64> {
+        profiled!("get_liquidation_price", instructions::get_liquidation_price::handler(ctx))
+    }
+
+    /// Configure the supply shares locked out of a market's first supply
+    ///
+    /// **Parameters:**
+    /// - `dead_shares`: Shares kept out of the first supplier's position
+    ///   (0 = disabled, max MAX_FIRST_SUPPLY_DEAD_SHARES or the recommendation
+    ///   for the loan decimals, whichever is larger)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_first_supply_dead_shares(
+        ctx: Context<SetFirstSupplyDeadShares>,
+        dead_shares: u64,
+    ) -> Result<()> {
+        profiled!(
+            "set_first_supply_dead_shares",
+            instructions::set_first_supply_dead_shares::handler(ctx, dead_shares)
+        )
+    }
+
+    /// Create a market's reward vault and start streaming rewards to suppliers
+    ///
+    /// **Parameters:**
+    /// - `reward_rate_per_second`: Reward tokens per second, shared by supply
+    ///   shares (max MAX_REWARD_RATE_PER_SECOND)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `reward_mint`: Reward token mint
+    /// - `reward_vault`: Reward token vault (to be created, owned by market PDA)
+    /// - `authority`: Market authority (signer, payer)
+    /// - `system_program`: Solana system program
+    /// - `token_program`: SPL token program
+    /// - `rent`: Rent sysvar
+    pub fn initialize_rewards(
+        ctx: Context<InitializeRewards>,
+        reward_rate_per_second: u64,
+    ) -> Result<()> {
+        profiled!(
+            "initialize_rewards",
+            instructions::initialize_rewards::handler(ctx, reward_rate_per_second)
+        )
+    }
+
+    /// Change the supply reward emission rate
+    ///
+    /// **Parameters:**
+    /// - `reward_rate_per_second`: Reward tokens per second (0 = stop emission)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_reward_rate(ctx: Context<SetRewardRate>, reward_rate_per_second: u64) -> Result<()> {
+        profiled!(
+            "set_reward_rate",
+            instructions::set_reward_rate::handler(ctx, reward_rate_per_second)
+        )
+    }
+
+    /// Claim the reward tokens earned by a position's supply shares
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `user_position`: User's position PDA
+    /// - `reward_vault`: Market's reward vault
+    /// - `receiver_reward_account`: Receiver reward token account
+    /// - `user`: User wallet (signer)
+    /// - `token_program`: SPL token program
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        profiled!("claim_rewards", instructions::claim_rewards::handler(ctx))
+    }
+
+    /// Query the per-second borrow rate the market's accrual applies
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    ///
+    /// **Returns:** Per-second borrow rate (precision: 1e18)
+    pub fn get_rate_per_second(ctx: Context<GetRatePerSecond>) -> Result<u128> {
+        profiled!("get_rate_per_second", instructions::get_rate_per_second::handler(ctx))
+    }
+
+    /// Configure the smallest first supply into an empty market
+    ///
+    /// **Parameters:**
+    /// - `whole_tokens`: Minimum in whole loan tokens, scaled internally by
+    ///   the loan mint decimals (0 = none)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_min_initial_deposit(ctx: Context<SetMinInitialDeposit>, whole_tokens: u64) -> Result<()> {
+        profiled!(
+            "set_min_initial_deposit",
+            instructions::set_min_initial_deposit::handler(ctx, whole_tokens)
+        )
+    }
+
+    /// Update a market's kinked interest rate model
+    ///
+    /// Accrues on the old curve first, so past interest keeps its rate.
+    ///
+    /// **Parameters:**
+    /// - `params`: Base rate, slopes below/above the kink (WAD) and the kink
+    ///   utilization (bps, 0 = back to the fixed rate)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority or manager (signer)
+    pub fn set_irm(ctx: Context<SetIrm>, params: IrmParams) -> Result<()> {
+        profiled!("set_irm", instructions::set_irm::handler(ctx, params))
+    }
+
+    /// Liquidate one unhealthy position back to a target health factor
+    ///
+    /// Repays just enough debt for the position's health factor to reach
+    /// `target_health`, seizing collateral with the liquidation bonus.
+    /// The repay never exceeds the close factor or the position's collateral.
+    ///
+    /// **Parameters:**
+    /// - `target_health`: Health factor to restore (LLTV_PRECISION scale, above 1.0)
+    /// - `min_seize`: Minimum collateral to seize (0 = no limit)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `user_position`: Borrower's position (writable)
+    /// - `loan_vault`: Market's loan token vault
+    /// - `collateral_vault`: Market's collateral token vault
+    /// - `liquidator_loan_account`: Liquidator's loan token account (source)
+    /// - `liquidator_collateral_account`: Liquidator's collateral token account (receiver)
+    /// - `liquidator`: Liquidator wallet (signer)
+    /// - `token_program`: SPL token program
+    pub fn liquidate_to_target(
+        ctx: Context<LiquidateToTarget>,
+        target_health: u64,
+        min_seize: u64,
+    ) -> Result<()> {
+        profiled!(
+            "liquidate_to_target",
+            instructions::liquidate_to_target::handler(ctx, target_health, min_seize)
+        )
+    }
+
+    /// Freeze or unfreeze one position for a compliance hold
+    ///
+    /// A frozen position can't borrow, withdraw or withdraw collateral;
+    /// repay and supply stay open so the user can reduce risk.
+    ///
+    /// **Parameters:**
+    /// - `frozen`: New freeze state
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `user_position`: Position to freeze (writable)
+    /// - `authority`: Market authority (signer)
+    pub fn set_position_frozen(ctx: Context<SetPositionFrozen>, frozen: bool) -> Result<()> {
+        profiled!("set_position_frozen", instructions::set_position_frozen::handler(ctx, frozen))
+    }
+
+    /// Configure the borrow shares collateral health checks treat as zero debt
+    ///
+    /// **Parameters:**
+    /// - `dust_shares_threshold`: Borrow shares ignored by health checks
+    ///   (0 = disabled, max MAX_DUST_SHARES_THRESHOLD)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_dust_shares_threshold(
+        ctx: Context<SetDustSharesThreshold>,
+        dust_shares_threshold: u64,
+    ) -> Result<()> {
+        profiled!(
+            "set_dust_shares_threshold",
+            instructions::set_dust_shares_threshold::handler(ctx, dust_shares_threshold)
+        )
+    }
+
+    /// Reuse a fully wound-down market with new parameters
+    ///
+    /// Keeps the market PDA, mints and vaults, replaces the LLTV, rate model
+    /// and borrow caps, and bumps `version`. Requires every position closed
+    /// and all totals at zero; the new LLTV and rate curve must pass the
+    /// `ProtocolConfig` policy.
+    ///
+    /// **Parameters:**
+    /// - `params`: New LLTV, rate model and borrow caps
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    /// - `protocol_config`: Protocol policy (LLTV whitelist, rate bounds)
+    pub fn reset_market(ctx: Context<ResetMarket>, params: ResetMarketParams) -> Result<()> {
+        profiled!("reset_market", instructions::reset_market::handler(ctx, params))
+    }
+
+    /// Allow or reject liquidators liquidating their own positions
+    ///
+    /// **Parameters:**
+    /// - `disallow`: Reject liquidate / batch_liquidate / liquidate_to_target
+    ///   on the liquidator's own position (default: false)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_disallow_self_liquidation(
+        ctx: Context<SetDisallowSelfLiquidation>,
+        disallow: bool,
+    ) -> Result<()> {
+        profiled!(
+            "set_disallow_self_liquidation",
+            instructions::set_disallow_self_liquidation::handler(ctx, disallow)
+        )
+    }
+
+    /// Read every market rule a borrow is checked against
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    ///
+    /// **Returns:** `BorrowConstraints` (LLTV, caps, fee, liquidity, oracle
+    /// and pause status, and whether borrowing is currently enabled)
+    pub fn get_borrow_constraints(
+        ctx: Context<GetBorrowConstraints>,
+    ) -> Result<BorrowConstraints> {
+        profiled!("get_borrow_constraints", instructions::get_borrow_constraints::handler(ctx))
+    }
+
+    /// Appoint or remove a market's manager
+    ///
+    /// **Parameters:**
+    /// - `manager`: New manager (Pubkey::default() = none)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_manager(ctx: Context<SetManager>, manager: Pubkey) -> Result<()> {
+        profiled!("set_manager", instructions::set_manager::handler(ctx, manager))
+    }
+
+    /// Quote the shares an asset-mode operation would produce
+    ///
+    /// **Parameters:**
+    /// - `side`: Supply or borrow shares
+    /// - `assets`: Loan token amount to convert
+    /// - `round`: Rounding direction (the handler's: supply down, borrow up)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    ///
+    /// **Returns:** Shares for `assets` after accruing interest to now
+    pub fn quote(
+        ctx: Context<Quote>,
+        side: QuoteSide,
+        assets: u64,
+        round: QuoteRounding,
+    ) -> Result<u64> {
+        profiled!("quote", instructions::quote::handler(ctx, side, assets, round))
+    }
+
+    /// Configure a market's collateral cap
+    ///
+    /// **Parameters:**
+    /// - `collateral_cap`: Cap on total deposited collateral (0 = unlimited)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority or manager (signer)
+    pub fn set_collateral_cap(ctx: Context<SetCollateralCap>, collateral_cap: u64) -> Result<()> {
+        profiled!(
+            "set_collateral_cap",
+            instructions::set_collateral_cap::handler(ctx, collateral_cap)
+        )
+    }
+
+    /// Simulate the outcome of liquidating a position
+    ///
+    /// Runs the full seize, repay and bonus math on local copies; nothing is
+    /// written and no tokens move.
+    ///
+    /// **Parameters:**
+    /// - `repay_assets`: Prospective repay, sized as `liquidate` would
+    ///   (u64::MAX = the full close factor)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `user_position`: Borrower's position in this market
+    ///
+    /// **Returns:** `LiquidationPreview` with the repaid and seized amounts,
+    /// resulting health and liquidator profit; zeroed with
+    /// `liquidatable == false` if the position can't be liquidated
+    pub fn preview_liquidation(
+        ctx: Context<PreviewLiquidation>,
+        repay_assets: u64,
+    ) -> Result<LiquidationPreview> {
+        profiled!(
+            "preview_liquidation",
+            instructions::preview_liquidation::handler(ctx, repay_assets)
+        )
+    }
+
+    /// Liquidate one unhealthy position, repaying up to `repay_assets`
+    ///
+    /// Repays the smaller of `repay_assets` and the close factor, seizing
+    /// the matching collateral plus the liquidation bonus. A repay that
+    /// would leave dust debt closes the whole debt; the seize is capped at
+    /// the position's collateral.
+    ///
+    /// **Parameters:**
+    /// - `repay_assets`: Most loan tokens to repay (u64::MAX = the close factor)
+    /// - `min_seize`: Minimum collateral to seize (0 = no limit)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `user_position`: Borrower's position (writable)
+    /// - `loan_vault`: Market's loan token vault
+    /// - `collateral_vault`: Market's collateral token vault
+    /// - `liquidator_loan_account`: Liquidator's loan token account (source)
+    /// - `liquidator_collateral_account`: Liquidator's collateral token account (receiver)
+    /// - `liquidator`: Liquidator wallet (signer)
+    /// - `token_program`: SPL token program
+    pub fn liquidate(ctx: Context<Liquidate>, repay_assets: u64, min_seize: u64) -> Result<()> {
+        profiled!("liquidate", instructions::liquidate::handler(ctx, repay_assets, min_seize))
+    }
+
+    /// Pay a market's protocol fee shares out to the fee recipient
+    ///
+    /// Burns the fee shares for the assets they are worth, up to the
+    /// market's idle liquidity; the rest stays claimable.
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `protocol_config`: Protocol config PDA (holds `fee_recipient`)
+    /// - `loan_vault`: Market's loan token vault
+    /// - `receiver_token_account`: Receiver loan token account
+    /// - `fee_recipient`: Protocol fee recipient (signer)
+    /// - `token_program`: SPL token program
+    pub fn claim_fees(ctx: Context<ClaimFees>) -> Result<()> {
+        profiled!("claim_fees", instructions::claim_fees::handler(ctx))
+    }
+
+    /// Withdraw protocol reserves (origination fees, swept residue)
+    ///
+    /// Capped at `market.reserves` and at what the vault holds beyond the
+    /// suppliers' idle liquidity.
+    ///
+    /// **Parameters:**
+    /// - `assets`: Loan tokens to withdraw
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `loan_vault`: Market's loan token vault
+    /// - `receiver_token_account`: Receiver loan token account
+    /// - `authority`: Market authority (signer)
+    /// - `token_program`: SPL token program
+    pub fn withdraw_reserves(ctx: Context<WithdrawReserves>, assets: u64) -> Result<()> {
+        profiled!("withdraw_reserves", instructions::withdraw_reserves::handler(ctx, assets))
+    }
+
+    /// Configure how far the loan vault may fall short of market accounting
+    ///
+    /// **Parameters:**
+    /// - `vault_tolerance`: Tolerated shortfall in loan base units (0 = exact)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    pub fn set_vault_tolerance(ctx: Context<SetVaultTolerance>, vault_tolerance: u64) -> Result<()> {
+        profiled!(
+            "set_vault_tolerance",
+            instructions::set_vault_tolerance::handler(ctx, vault_tolerance)
+        )
+    }
+
+    /// Transfer the loan vault's surplus over market accounting (donations)
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `loan_vault`: Market's loan token vault
+    /// - `receiver_token_account`: Receiver loan token account
+    /// - `authority`: Market authority (signer)
+    /// - `token_program`: SPL token program
+    pub fn skim_vault(ctx: Context<SkimVault>) -> Result<()> {
+        profiled!("skim_vault", instructions::skim_vault::handler(ctx))
+    }
+
+    /// Warp a market's accrual clock (test-utils builds only)
+    ///
+    /// Sets `market.last_update` directly so tests can simulate elapsed time.
+    /// Compiled only with `--features test-utils`; absent from production builds.
+    ///
+    /// **Parameters:**
+    /// - `last_update`: New accrual timestamp
+    ///
+    /// **Accounts:**
+    /// - `market`: Market account
+    /// - `authority`: Market authority (signer)
+    #[cfg(feature = "test-utils")]
+    pub fn set_last_update(ctx: Context<SetLastUpdate>, last_update: i64) -> Result<()> {
+        profiled!("set_last_update", instructions::set_last_update::handler(ctx, last_update))
     }
 }
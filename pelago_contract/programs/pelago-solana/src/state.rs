@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
 
+use crate::constants::{FIXED_ORACLE_PRICE, USE_PROTOCOL_DEFAULT_FEE};
+use crate::error::PelagoError;
+
 /// Market account structure representing a lending market
 ///
 /// This structure stores all essential information for a lending market including:
@@ -7,12 +10,11 @@ use anchor_lang::prelude::*;
 /// - Token vault addresses for holding deposited assets
 /// - Supply and borrow totals (assets and shares)
 /// - Liquidation Loan-to-Value ratio (LLTV)
-///
-/// **P0 Simplifications:**
-/// - 1:1 share mapping (total_supply_shares == total_supply_assets)
-/// - No interest accrual (last_update is reserved for future use)
-/// - Fixed price oracle (hardcoded 100 USDC/SOL)
+/// - Interest accrual state and the rate model (see `utils::interest`)
+/// - The collateral price: fixed, manual or TWAP, with its sanity band and
+///   staleness limit (see `utils::oracle`)
 #[account]
+#[derive(Default)]
 pub struct Market {
     /// Market authority (admin who can initialize and manage)
     pub authority: Pubkey,
@@ -56,6 +58,214 @@ pub struct Market {
     /// P0: Reserved for future interest accrual, not used currently
     pub last_update: i64,
 
+    /// Market pause flag
+    /// When true: supply, borrow, withdraw and withdraw_collateral are blocked
+    /// Repay and supply_collateral stay open so users can reduce risk
+    /// Set by the market authority, or by the protocol guardian (pause only)
+    pub paused: bool,
+
+    /// Absolute cap on total_borrow_assets (0 = unlimited)
+    pub borrow_cap: u64,
+
+    /// Borrow cap relative to supply, in bps (0 = disabled)
+    /// Effective cap: total_supply_assets × borrow_cap_ratio_bps / 10_000
+    /// When both caps are set, the tighter one applies
+    pub borrow_cap_ratio_bps: u16,
+
+    /// Round accrued interest up instead of down (default: false = floor)
+    /// When set, sub-unit interest dust goes to suppliers instead of borrowers
+    pub round_interest_up: bool,
+
+    /// Manually set collateral price (PRICE_PRECISION scale, see FIXED_ORACLE_PRICE)
+    /// Only used while `manual_price_enabled` is set (closed betas)
+    pub manual_price: u64,
+
+    /// Use `manual_price` instead of FIXED_ORACLE_PRICE in health checks
+    pub manual_price_enabled: bool,
+
+    /// Maximum number of open user positions (0 = unlimited)
+    /// Limits position spam; checked when a position is first created
+    pub max_positions: u32,
+
+    /// Number of currently open user positions
+    /// Incremented on position creation, decremented when a position is closed
+    pub open_positions: u32,
+
+    /// Protocol fee on accrued interest, in bps (0 = no fee, max MAX_FEE_BPS)
+    pub fee_bps: u16,
+
+    /// Supply shares minted to the protocol from interest fees
+    /// Included in total_supply_shares; they earn interest like any supplier
+    pub fee_shares: u64,
+
+    /// Maximum debt assets per position (0 = unlimited)
+    /// Concentration limit applied regardless of collateral
+    pub max_borrow_per_position: u64,
+
+    /// Total collateral deposited across all positions (collateral token base units)
+    /// Repairable via resync_total_collateral if it ever drifts
+    pub total_collateral: u64,
+
+    /// One-time fee on borrowed assets, in bps (0 = none, max MAX_ORIGINATION_FEE_BPS)
+    /// Added to the borrower's debt and credited to `reserves`
+    pub origination_fee_bps: u16,
+
+    /// Protocol reserves in loan token base units
+    /// Claims on debt owed to the protocol (e.g. origination fees), not to suppliers
+    /// Vault identity: vault = total_supply_assets + reserves - total_borrow_assets
+    pub reserves: u64,
+
+    /// TWAP window in seconds for the collateral price (0 = spot only)
+    pub twap_window: u32,
+
+    /// Cached collateral TWAP over `twap_window` (0 = not warmed up or stale)
+    /// Refreshed by update_price_history; health checks fall back to spot while 0
+    pub twap_price: u64,
+
+    /// Per-market fixed collateral price (PRICE_PRECISION scale)
+    /// Set at init; 0 passed at init stores the decimal-scaled default (see base_unit_price)
+    pub fixed_price: u64,
+
+    /// Delay in seconds between propose_lltv and apply_lltv (set at init)
+    pub lltv_timelock: i64,
+
+    /// LLTV proposed via propose_lltv (0 = no pending change)
+    pub pending_lltv: u64,
+
+    /// Earliest timestamp at which pending_lltv can be applied
+    pub lltv_effective_at: i64,
+
+    /// Utilization (bps) above which new borrows are refused (0 = disabled)
+    /// Evaluated on each borrow after accrual; not a stored pause flag
+    pub auto_pause_utilization_bps: u16,
+
+    /// Minimum seconds between borrows of one position (0 = disabled)
+    pub borrow_cooldown_secs: u32,
+
+    /// Accrue interest while the market is paused (default: true)
+    /// When false, a paused market only advances last_update, so borrowers
+    /// are not charged interest for the pause
+    pub accrue_while_paused: bool,
+
+    /// Round asset-mode supply shares up instead of down (default: false)
+    /// Transfers sub-share dust to suppliers and weakens the inflation-attack
+    /// rounding guard; see `supply_shares_for_assets`
+    pub favor_user_on_supply: bool,
+
+    /// Supply shares minted by seed_market that no position owns
+    /// Locked forever so total_supply_shares never returns to zero
+    pub dead_shares: u64,
+
+    /// Lowest plausible collateral price (PRICE_PRECISION scale, 0 = no floor)
+    pub min_sane_price: u64,
+
+    /// Highest plausible collateral price (PRICE_PRECISION scale, 0 = no ceiling)
+    pub max_sane_price: u64,
+
+    /// Annual borrow rate (WAD) as of last_update (0 until the first accrual)
+    /// Refreshed by accrue_interest from the totals right after accrual
+    pub current_borrow_rate_wad: u128,
+
+    /// Annual supply rate (WAD, net of fee) as of last_update
+    /// borrow rate × utilization × (1 - fee); same refresh as the borrow rate
+    pub current_supply_rate_wad: u128,
+
+    /// Decimals of the loan token mint (recorded at init)
+    pub loan_decimals: u8,
+
+    /// Decimals of the collateral token mint (recorded at init)
+    /// Prices are quoted per base unit, so these only seed the default fixed_price
+    pub collateral_decimals: u8,
+
+    /// Cap on interest per accrual, in bps of total_borrow_assets (0 = uncapped)
+    /// Safety rail against a misconfigured rate; not hit in normal operation
+    pub max_accrual_interest_bps: u16,
+
+    /// Utilization (bps) above which the interest fee applies (0 = always)
+    /// The fee ramps from 0 at the kink to fee_bps at 100% utilization
+    pub fee_kink_utilization_bps: u16,
+
+    /// Minimum loan tokens a partial liquidation must repay (0 = no minimum)
+    /// Positions whose close-factor share is below it are closed in full instead
+    pub min_liquidation_assets: u64,
+
+    /// Ceiling on total_borrow_assets as bps of total_supply_assets (0 = off)
+    /// Monitored after each accrual; a breach emits RatioBreachEvent
+    pub max_borrow_ratio_bps: u16,
+
+    /// Unix timestamp of the last manual price update
+    /// Age of the price while manual_price_enabled (fixed prices never go stale)
+    pub price_updated_at: i64,
+
+    /// Maximum manual price age accepted by liquidations, in seconds (0 = off)
+    pub liquidation_max_staleness_secs: u32,
+
+    /// Interest compounding period in seconds (0 = per accrual)
+    /// Only whole periods accrue; the remainder carries over to the next accrual
+    pub compound_period_secs: u32,
+
+    /// Supply shares locked out of the first supply into an empty market
+    /// (0 = disabled; see supply::first_supply_split)
+    pub first_supply_dead_shares: u64,
+
+    /// Token account paying supply rewards (default = rewards not configured)
+    /// Owned by the market PDA, created by initialize_rewards
+    pub reward_vault: Pubkey,
+
+    /// Reward tokens streamed to suppliers per second (base units)
+    pub reward_rate_per_second: u64,
+
+    /// Cumulative rewards per supply share (precision: 1e18)
+    /// Advanced by accrue_interest alongside last_update
+    pub reward_index: u128,
+
+    /// Smallest first supply into an empty market (loan base units, 0 = none)
+    /// Set in whole tokens by set_min_initial_deposit, scaled by loan_decimals
+    pub min_initial_deposit: u64,
+
+    /// Kinked rate model: annual borrow rate at 0% utilization (precision: 1e18)
+    pub irm_base_rate_wad: u64,
+
+    /// Kinked rate model: rate added from 0% up to the kink (precision: 1e18)
+    pub irm_slope1_wad: u64,
+
+    /// Kinked rate model: rate added from the kink up to 100% (precision: 1e18)
+    pub irm_slope2_wad: u64,
+
+    /// Kinked rate model: utilization where slope2 takes over (bps)
+    /// 0 = model off, the fixed FIXED_ANNUAL_RATE_WAD applies
+    pub irm_kink_utilization_bps: u16,
+
+    /// Borrow shares at or below this count as zero debt in collateral health checks (0 = disabled)
+    /// The shares stay on the books until repaid
+    pub dust_shares_threshold: u64,
+
+    /// Parameter generation, bumped by reset_market each time the emptied market is reused
+    pub version: u32,
+
+    /// Reject liquidations where the liquidator is the borrower (default: false)
+    pub disallow_self_liquidation: bool,
+
+    /// Secondary operator for day-to-day tuning (Pubkey::default() = none)
+    /// May pause, set borrow caps and update the IRM; everything else stays authority-only
+    pub manager: Pubkey,
+
+    /// Supply assets were below borrow assets at the last accrual
+    /// While set, interest accrues to borrows only (see MarketImpairedEvent)
+    pub impaired: bool,
+
+    /// Cap on total_collateral (0 = unlimited)
+    /// Bounds exposure to the collateral asset; only gates new deposits
+    pub collateral_cap: u64,
+
+    /// Tolerated loan vault shortfall against market accounting (base units)
+    /// Defaults to VAULT_ACCOUNTING_TOLERANCE_TOKENS whole tokens; surpluses always pass
+    pub vault_tolerance: u64,
+
+    /// Unix timestamp `twap_price` was computed at (0 = never)
+    /// Accrual drops a cached TWAP older than `twap_window`
+    pub twap_updated_at: i64,
+
     /// PDA bump seed for deterministic address derivation
     pub bump: u8,
 }
@@ -75,12 +285,137 @@ impl Market {
     /// - 8 bytes (total_borrow_shares)
     /// - 8 bytes (lltv)
     /// - 8 bytes (last_update)
+    /// - 1 byte (paused)
+    /// - 8 bytes (borrow_cap)
+    /// - 2 bytes (borrow_cap_ratio_bps)
+    /// - 1 byte (round_interest_up)
+    /// - 8 bytes (manual_price)
+    /// - 1 byte (manual_price_enabled)
+    /// - 4 bytes (max_positions)
+    /// - 4 bytes (open_positions)
+    /// - 2 bytes (fee_bps)
+    /// - 8 bytes (fee_shares)
+    /// - 8 bytes (max_borrow_per_position)
+    /// - 8 bytes (total_collateral)
+    /// - 2 bytes (origination_fee_bps)
+    /// - 8 bytes (reserves)
+    /// - 4 bytes (twap_window)
+    /// - 8 bytes (twap_price)
+    /// - 8 bytes (fixed_price)
+    /// - 8 bytes (lltv_timelock)
+    /// - 8 bytes (pending_lltv)
+    /// - 8 bytes (lltv_effective_at)
+    /// - 2 bytes (auto_pause_utilization_bps)
+    /// - 4 bytes (borrow_cooldown_secs)
+    /// - 1 byte (accrue_while_paused)
+    /// - 1 byte (favor_user_on_supply)
+    /// - 8 bytes (dead_shares)
+    /// - 8 bytes (min_sane_price)
+    /// - 8 bytes (max_sane_price)
+    /// - 16 bytes (current_borrow_rate_wad)
+    /// - 16 bytes (current_supply_rate_wad)
+    /// - 1 byte (loan_decimals)
+    /// - 1 byte (collateral_decimals)
+    /// - 2 bytes (max_accrual_interest_bps)
+    /// - 2 bytes (fee_kink_utilization_bps)
+    /// - 8 bytes (min_liquidation_assets)
+    /// - 2 bytes (max_borrow_ratio_bps)
+    /// - 8 bytes (price_updated_at)
+    /// - 4 bytes (liquidation_max_staleness_secs)
+    /// - 4 bytes (compound_period_secs)
+    /// - 8 bytes (first_supply_dead_shares)
+    /// - 32 bytes (reward_vault)
+    /// - 8 bytes (reward_rate_per_second)
+    /// - 16 bytes (reward_index)
+    /// - 8 bytes (min_initial_deposit)
+    /// - 8 bytes (irm_base_rate_wad)
+    /// - 8 bytes (irm_slope1_wad)
+    /// - 8 bytes (irm_slope2_wad)
+    /// - 2 bytes (irm_kink_utilization_bps)
+    /// - 8 bytes (dust_shares_threshold)
+    /// - 4 bytes (version)
+    /// - 1 byte (disallow_self_liquidation)
+    /// - 32 bytes (manager)
+    /// - 1 byte (impaired)
+    /// - 8 bytes (collateral_cap)
+    /// - 8 bytes (vault_tolerance)
+    /// - 8 bytes (twap_updated_at)
     /// - 1 byte (bump)
-    /// Total: 217 bytes
-    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+    ///
+    /// Total: 590 bytes
+    pub const LEN: usize = 
+        8 + 32 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 2 + 1 + 8 + 1 + 4 + 4 +
+        2 + 8 + 8 + 8 + 2 + 8 + 4 + 8 + 8 + 8 + 8 + 8 + 2 + 4 + 1 + 1 + 8 + 8 + 8 + 16 +
+        16 + 1 + 1 + 2 + 2 + 8 + 2 + 8 + 4 + 4 + 8 + 32 + 8 + 16 + 8 + 8 + 8 + 8 + 2 + 8 +
+        4 + 1 + 32 + 1 + 8 + 8 + 8 + 1;
 
     /// PDA seed prefix for market accounts
     pub const SEED_PREFIX: &'static [u8] = b"market";
+
+    /// Spot collateral price (PRICE_PRECISION scale)
+    ///
+    /// Returns `manual_price` while it is enabled, otherwise the market's
+    /// `fixed_price` (FIXED_ORACLE_PRICE if unset).
+    pub fn spot_price(&self) -> u64 {
+        if self.manual_price_enabled {
+            self.manual_price
+        } else if self.fixed_price > 0 {
+            self.fixed_price
+        } else {
+            FIXED_ORACLE_PRICE
+        }
+    }
+
+    /// Collateral price used by health checks (PRICE_PRECISION scale)
+    ///
+    /// Returns the cached TWAP when a window is configured and the price
+    /// history has warmed up, otherwise the spot price.
+    pub fn collateral_price(&self) -> u64 {
+        if self.twap_window > 0 && self.twap_price > 0 {
+            self.twap_price
+        } else {
+            self.spot_price()
+        }
+    }
+
+    /// True if `key` may run operational instructions (pause, borrow caps,
+    /// IRM): the market authority, or the manager once one is set
+    pub fn is_operator(&self, key: &Pubkey) -> bool {
+        *key == self.authority || (self.manager != Pubkey::default() && *key == self.manager)
+    }
+
+    /// Rejects a market whose fields were never set by `initialize_market`
+    ///
+    /// **Errors:**
+    /// - UninitializedMarket: `loan_vault` is still the default pubkey
+    pub fn require_initialized(&self) -> Result<()> {
+        require!(
+            self.loan_vault != Pubkey::default(),
+            PelagoError::UninitializedMarket
+        );
+        Ok(())
+    }
+
+    /// Counts a newly created user position against `max_positions`
+    ///
+    /// **Errors:**
+    /// - PositionLimitReached: `open_positions` already equals a non-zero `max_positions`
+    pub fn register_position(&mut self) -> Result<()> {
+        require!(
+            self.max_positions == 0 || self.open_positions < self.max_positions,
+            PelagoError::PositionLimitReached
+        );
+        self.open_positions = self
+            .open_positions
+            .checked_add(1)
+            .ok_or(PelagoError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Releases a slot when a user position is closed
+    pub fn release_position(&mut self) {
+        self.open_positions = self.open_positions.saturating_sub(1);
+    }
 }
 
 /// User position account structure representing a user's position in a market
@@ -94,6 +429,7 @@ impl Market {
 /// - 1:1 share mapping (shares == assets)
 /// - No interest accumulation tracking
 #[account]
+#[derive(Default)]
 pub struct UserPosition {
     /// User wallet address
     pub user: Pubkey,
@@ -113,6 +449,24 @@ pub struct UserPosition {
     /// Stored in collateral token's base units (e.g., lamports for SOL)
     pub collateral_amount: u64,
 
+    /// Unix timestamp of the last supply/borrow/repay/withdraw/collateral action
+    /// Used off-chain to find dormant positions (see `is_stale`)
+    pub last_activity: i64,
+
+    /// Unix timestamp of the position's last borrow (0 = never)
+    /// Checked against market.borrow_cooldown_secs
+    pub last_borrow_ts: i64,
+
+    /// market.reward_index when this position's rewards were last settled
+    pub reward_index_checkpoint: u128,
+
+    /// Settled rewards not yet claimed (reward token base units)
+    pub reward_accrued: u64,
+
+    /// Compliance hold set by set_position_frozen
+    /// Blocks borrows and withdrawals; repay and supply stay open
+    pub frozen: bool,
+
     /// PDA bump seed for deterministic address derivation
     pub bump: u8,
 }
@@ -126,10 +480,669 @@ impl UserPosition {
     /// - 8 bytes (supply_shares)
     /// - 8 bytes (borrow_shares)
     /// - 8 bytes (collateral_amount)
+    /// - 8 bytes (last_activity)
+    /// - 8 bytes (last_borrow_ts)
+    /// - 16 bytes (reward_index_checkpoint)
+    /// - 8 bytes (reward_accrued)
+    /// - 1 byte (frozen)
     /// - 1 byte (bump)
-    /// Total: 97 bytes
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1;
+    ///
+    /// Total: 138 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 16 + 8 + 1 + 1;
 
     /// PDA seed prefix for user position accounts
     pub const SEED_PREFIX: &'static [u8] = b"user-position";
+
+    /// Returns true if the position has been inactive for at least `threshold` seconds
+    ///
+    /// Positions that were never touched (`last_activity == 0`) are always stale.
+    pub fn is_stale(&self, now: i64, threshold: i64) -> bool {
+        now.saturating_sub(self.last_activity) >= threshold
+    }
+
+    /// Returns true if the position holds no shares, no collateral and no
+    /// unclaimed rewards
+    pub fn is_empty(&self) -> bool {
+        self.supply_shares == 0
+            && self.borrow_shares == 0
+            && self.collateral_amount == 0
+            && self.reward_accrued == 0
+    }
+
+    /// Rejects actions that remove value from a frozen position
+    ///
+    /// **Errors:**
+    /// - PositionFrozen: the position is under a compliance hold
+    pub fn check_not_frozen(&self) -> Result<()> {
+        require!(!self.frozen, PelagoError::PositionFrozen);
+        Ok(())
+    }
+
+    /// Initializes a freshly created position account
+    ///
+    /// Called in the first-interaction branch of every `init_if_needed`
+    /// creator. A position closed by `close_position` is zeroed, so recreating
+    /// it starts from a clean slate; the balance check guards that assumption.
+    ///
+    /// **Errors:**
+    /// - InvariantViolation: the account still carries balances
+    pub fn open(&mut self, user: Pubkey, market: Pubkey, bump: u8) -> Result<()> {
+        require!(self.is_empty(), PelagoError::InvariantViolation);
+
+        self.user = user;
+        self.market = market;
+        self.last_activity = 0;
+        self.last_borrow_ts = 0;
+        self.bump = bump;
+        Ok(())
+    }
+}
+
+/// Protocol-wide configuration singleton
+///
+/// Holds roles that span all markets:
+/// - `admin`: Manages this config (e.g., rotates the guardian)
+/// - `guardian`: Emergency key that can pause any market (pause only, never unpause)
+///
+/// **Security Council Pattern:** The guardian can react to incidents instantly
+/// without being each market's authority. Unpausing still requires the
+/// market authority, which limits the guardian's power.
+#[account]
+#[derive(Default)]
+pub struct Config {
+    /// Protocol admin (manages this config)
+    pub admin: Pubkey,
+
+    /// Emergency guardian (can pause any market)
+    pub guardian: Pubkey,
+
+    /// PDA bump seed for deterministic address derivation
+    pub bump: u8,
+}
+
+impl Config {
+    /// Space required for Config account
+    /// Calculation breakdown:
+    /// - 8 bytes (anchor discriminator)
+    /// - 32 bytes (admin)
+    /// - 32 bytes (guardian)
+    /// - 1 byte (bump)
+    ///
+    /// Total: 73 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 1;
+
+    /// PDA seed for the config singleton
+    pub const SEED_PREFIX: &'static [u8] = b"config";
+}
+
+/// Protocol-wide defaults for new markets
+///
+/// Consulted by `initialize_market` when the config is passed:
+/// - `default_fee_bps` replaces a `fee_bps` of USE_PROTOCOL_DEFAULT_FEE
+/// - `max_lltv` caps the market's LLTV
+/// - The LLTV must be one of `enabled_lltvs` (governance whitelist)
+/// - The fixed borrow rate must lie within [`min_rate_wad`, `max_rate_wad`]
+///
+/// Created and updated by the Config admin via `set_protocol_config`.
+#[account]
+#[derive(Default)]
+pub struct ProtocolConfig {
+    /// Interest fee (bps) for markets initialized with the sentinel fee
+    pub default_fee_bps: u16,
+
+    /// Recipient of protocol fees (claims each market's fee shares via `claim_fees`)
+    pub fee_recipient: Pubkey,
+
+    /// Highest LLTV a new market may use (LLTV_PRECISION scale)
+    pub max_lltv: u64,
+
+    /// Lowest acceptable annual borrow rate (WAD)
+    pub min_rate_wad: u128,
+
+    /// Highest acceptable annual borrow rate (WAD)
+    pub max_rate_wad: u128,
+
+    /// LLTV values new markets may use (at most MAX_ENABLED_LLTVS)
+    /// Managed via enable_lltv / disable_lltv
+    pub enabled_lltvs: Vec<u64>,
+
+    /// PDA bump seed for deterministic address derivation
+    pub bump: u8,
+}
+
+impl ProtocolConfig {
+    /// Space required for ProtocolConfig account
+    /// Calculation breakdown:
+    /// - 8 bytes (anchor discriminator)
+    /// - 2 bytes (default_fee_bps)
+    /// - 32 bytes (fee_recipient)
+    /// - 8 bytes (max_lltv)
+    /// - 16 + 16 bytes (min_rate_wad, max_rate_wad)
+    /// - 4 + 128 bytes (enabled_lltvs: length prefix + 16 × 8)
+    /// - 1 byte (bump)
+    ///
+    /// Total: 215 bytes
+    pub const LEN: usize = 8 + 2 + 32 + 8 + 16 + 16 + 4 + Self::MAX_ENABLED_LLTVS * 8 + 1;
+
+    /// Maximum number of enabled LLTV values
+    pub const MAX_ENABLED_LLTVS: usize = 16;
+
+    /// PDA seed for the protocol config singleton
+    pub const SEED_PREFIX: &'static [u8] = b"protocol-config";
+
+    /// Resolves a requested market fee, substituting the default for the sentinel
+    pub fn resolve_fee_bps(&self, fee_bps: u16) -> u16 {
+        if fee_bps == USE_PROTOCOL_DEFAULT_FEE {
+            self.default_fee_bps
+        } else {
+            fee_bps
+        }
+    }
+
+    /// Adds `lltv` to the enabled set, ignoring values already enabled
+    ///
+    /// **Errors:**
+    /// - InvalidParameter: MAX_ENABLED_LLTVS values are already enabled
+    pub fn enable_lltv(&mut self, lltv: u64) -> Result<()> {
+        if self.enabled_lltvs.contains(&lltv) {
+            return Ok(());
+        }
+        require!(
+            self.enabled_lltvs.len() < Self::MAX_ENABLED_LLTVS,
+            PelagoError::InvalidParameter
+        );
+        self.enabled_lltvs.push(lltv);
+        Ok(())
+    }
+
+    /// Removes `lltv` from the enabled set
+    ///
+    /// Existing markets using it are unaffected.
+    ///
+    /// **Errors:**
+    /// - LltvNotEnabled: `lltv` is not enabled
+    pub fn disable_lltv(&mut self, lltv: u64) -> Result<()> {
+        let index = self
+            .enabled_lltvs
+            .iter()
+            .position(|&enabled| enabled == lltv)
+            .ok_or(PelagoError::LltvNotEnabled)?;
+        self.enabled_lltvs.swap_remove(index);
+        Ok(())
+    }
+
+    /// Checks a new market's LLTV and annual rate against the protocol policy
+    ///
+    /// **Errors:**
+    /// - InvalidLltv: `lltv` above `max_lltv`
+    /// - LltvNotEnabled: `lltv` not in `enabled_lltvs`
+    /// - InvalidParameter: `rate_wad` outside [`min_rate_wad`, `max_rate_wad`]
+    pub fn check_market_params(&self, lltv: u64, rate_wad: u128) -> Result<()> {
+        require!(lltv <= self.max_lltv, PelagoError::InvalidLltv);
+        require!(self.enabled_lltvs.contains(&lltv), PelagoError::LltvNotEnabled);
+        require!(
+            rate_wad >= self.min_rate_wad && rate_wad <= self.max_rate_wad,
+            PelagoError::InvalidParameter
+        );
+        Ok(())
+    }
+}
+
+/// A single collateral price observation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PriceSample {
+    /// Price at PRICE_PRECISION scale
+    pub price: u64,
+
+    /// Unix timestamp of the observation
+    pub timestamp: i64,
+}
+
+/// Per-market ring buffer of recent collateral prices
+///
+/// Feeds the TWAP used by health checks (see `utils::twap`). Samples are
+/// pushed by `update_price_history`; once full, the oldest is overwritten.
+#[account]
+#[derive(Default)]
+pub struct PriceHistory {
+    /// Market this history belongs to
+    pub market: Pubkey,
+
+    /// Index of the next slot to write
+    pub head: u8,
+
+    /// Number of valid samples (<= CAPACITY)
+    pub count: u8,
+
+    /// Ring buffer of samples
+    pub samples: [PriceSample; PriceHistory::CAPACITY],
+
+    /// PDA bump seed for deterministic address derivation
+    pub bump: u8,
+}
+
+impl PriceHistory {
+    /// Number of samples kept in the ring buffer
+    pub const CAPACITY: usize = 16;
+
+    /// Space required for PriceHistory account
+    /// Calculation breakdown:
+    /// - 8 bytes (anchor discriminator)
+    /// - 32 bytes (market)
+    /// - 1 byte (head)
+    /// - 1 byte (count)
+    /// - 256 bytes (samples: 16 × (8 price + 8 timestamp))
+    /// - 1 byte (bump)
+    ///
+    /// Total: 299 bytes
+    pub const LEN: usize = 8 + 32 + 1 + 1 + Self::CAPACITY * 16 + 1;
+
+    /// PDA seed prefix for price history accounts
+    pub const SEED_PREFIX: &'static [u8] = b"price-history";
+
+    /// Appends a sample, overwriting the oldest once the buffer is full
+    pub fn push(&mut self, sample: PriceSample) {
+        self.samples[self.head as usize] = sample;
+        self.head = ((self.head as usize + 1) % Self::CAPACITY) as u8;
+        if (self.count as usize) < Self::CAPACITY {
+            self.count += 1;
+        }
+    }
+
+    /// Valid samples, oldest first
+    pub fn chronological(&self) -> Vec<PriceSample> {
+        let count = self.count as usize;
+        let start = (self.head as usize + Self::CAPACITY - count) % Self::CAPACITY;
+        (0..count)
+            .map(|i| self.samples[(start + i) % Self::CAPACITY])
+            .collect()
+    }
+}
+
+/// Per-user index of the markets the user holds positions in
+///
+/// Lets frontends enumerate a user's positions without scanning accounts:
+/// each listed market plus the user key derives a UserPosition PDA. A market
+/// is recorded when the user's position in it is first created.
+#[account]
+#[derive(Default)]
+pub struct UserRegistry {
+    /// Owner of the registry
+    pub user: Pubkey,
+
+    /// Markets with a position of `user`, in creation order
+    pub markets: Vec<Pubkey>,
+
+    /// PDA bump seed for deterministic address derivation
+    pub bump: u8,
+}
+
+impl UserRegistry {
+    /// Maximum number of markets tracked per user
+    pub const CAPACITY: usize = 16;
+
+    /// Space required for UserRegistry account
+    /// Calculation breakdown:
+    /// - 8 bytes (anchor discriminator)
+    /// - 32 bytes (user)
+    /// - 4 + 512 bytes (markets: length prefix + 16 × 32)
+    /// - 1 byte (bump)
+    ///
+    /// Total: 557 bytes
+    pub const LEN: usize = 8 + 32 + 4 + Self::CAPACITY * 32 + 1;
+
+    /// PDA seed prefix for user registry accounts
+    pub const SEED_PREFIX: &'static [u8] = b"user-registry";
+
+    /// Records `market`, ignoring markets already listed
+    ///
+    /// **Errors:**
+    /// - UserRegistryFull: CAPACITY markets are already recorded
+    pub fn record_market(&mut self, market: Pubkey) -> Result<()> {
+        if self.markets.contains(&market) {
+            return Ok(());
+        }
+        require!(
+            self.markets.len() < Self::CAPACITY,
+            PelagoError::UserRegistryFull
+        );
+        self.markets.push(market);
+        Ok(())
+    }
+
+    /// Forgets `market` when the user's position in it is closed
+    ///
+    /// Ignores markets that are not listed; order of the rest is kept.
+    pub fn remove_market(&mut self, market: Pubkey) {
+        self.markets.retain(|&listed| listed != market);
+    }
+}
+
+/// A single observation of a market's share prices
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateSample {
+    /// Unix timestamp of the observation
+    pub timestamp: i64,
+
+    /// Borrow assets per borrow share (precision: 1e18)
+    pub borrow_index: u128,
+
+    /// Supply assets per supply share (precision: 1e18)
+    pub supply_index: u128,
+}
+
+/// Per-market ring buffer of share price snapshots
+///
+/// Realized borrow and supply yields are the growth of the share prices
+/// between two snapshots (see `utils::rate_history`). Samples are pushed by
+/// `update_rate_snapshot`; once full, the oldest is overwritten.
+#[account]
+#[derive(Default)]
+pub struct RateSnapshot {
+    /// Market this history belongs to
+    pub market: Pubkey,
+
+    /// Index of the next slot to write
+    pub head: u8,
+
+    /// Number of valid samples (<= CAPACITY)
+    pub count: u8,
+
+    /// Ring buffer of samples
+    pub samples: [RateSample; RateSnapshot::CAPACITY],
+
+    /// PDA bump seed for deterministic address derivation
+    pub bump: u8,
+}
+
+impl RateSnapshot {
+    /// Number of samples kept in the ring buffer
+    pub const CAPACITY: usize = 16;
+
+    /// Space required for RateSnapshot account
+    /// Calculation breakdown:
+    /// - 8 bytes (anchor discriminator)
+    /// - 32 bytes (market)
+    /// - 1 byte (head)
+    /// - 1 byte (count)
+    /// - 640 bytes (samples: 16 × (8 timestamp + 16 borrow_index + 16 supply_index))
+    /// - 1 byte (bump)
+    ///
+    /// Total: 683 bytes
+    pub const LEN: usize = 8 + 32 + 1 + 1 + Self::CAPACITY * 40 + 1;
+
+    /// PDA seed prefix for rate snapshot accounts
+    pub const SEED_PREFIX: &'static [u8] = b"rate-snapshot";
+
+    /// Minimum seconds between two recorded samples
+    ///
+    /// The buffer then spans at least `(CAPACITY - 1) × MIN_SAMPLE_SPACING`
+    /// (15 hours), so rapid cranks can't shrink the realized-APY window.
+    pub const MIN_SAMPLE_SPACING: i64 = 3_600;
+
+    /// Appends a sample, overwriting the oldest once the buffer is full
+    pub fn push(&mut self, sample: RateSample) {
+        self.samples[self.head as usize] = sample;
+        self.head = ((self.head as usize + 1) % Self::CAPACITY) as u8;
+        if (self.count as usize) < Self::CAPACITY {
+            self.count += 1;
+        }
+    }
+
+    /// Valid samples, oldest first
+    pub fn chronological(&self) -> Vec<RateSample> {
+        let count = self.count as usize;
+        let start = (self.head as usize + Self::CAPACITY - count) % Self::CAPACITY;
+        (0..count)
+            .map(|i| self.samples[(start + i) % Self::CAPACITY])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_stale() {
+        let position = UserPosition {
+            last_activity: 1_000,
+            ..Default::default()
+        };
+
+        assert!(!position.is_stale(1_000, 60));
+        assert!(!position.is_stale(1_059, 60));
+        assert!(position.is_stale(1_060, 60));
+        assert!(UserPosition::default().is_stale(1_000, 60));
+    }
+
+    #[test]
+    fn test_check_not_frozen() {
+        assert!(UserPosition::default().check_not_frozen().is_ok());
+
+        let frozen = UserPosition {
+            frozen: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            frozen.check_not_frozen().unwrap_err(),
+            PelagoError::PositionFrozen.into()
+        );
+    }
+
+    #[test]
+    fn test_is_operator() {
+        let authority = Pubkey::new_unique();
+        let manager = Pubkey::new_unique();
+        let mut market = Market {
+            authority,
+            ..Default::default()
+        };
+
+        // No manager: only the authority, and never the default key
+        assert!(market.is_operator(&authority));
+        assert!(!market.is_operator(&manager));
+        assert!(!market.is_operator(&Pubkey::default()));
+
+        market.manager = manager;
+        assert!(market.is_operator(&authority));
+        assert!(market.is_operator(&manager));
+        assert!(!market.is_operator(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn test_require_initialized() {
+        assert_eq!(
+            Market::default().require_initialized().unwrap_err(),
+            PelagoError::UninitializedMarket.into()
+        );
+
+        let market = Market {
+            loan_vault: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        assert!(market.require_initialized().is_ok());
+    }
+
+    #[test]
+    fn test_register_position_up_to_cap() {
+        let mut market = Market {
+            max_positions: 2,
+            ..Default::default()
+        };
+
+        market.register_position().unwrap();
+        market.register_position().unwrap();
+        assert_eq!(
+            market.register_position().unwrap_err(),
+            PelagoError::PositionLimitReached.into()
+        );
+        assert_eq!(market.open_positions, 2);
+
+        // Closing a position frees a slot
+        market.release_position();
+        market.register_position().unwrap();
+        assert_eq!(market.open_positions, 2);
+    }
+
+    #[test]
+    fn test_zero_max_positions_is_unlimited() {
+        let mut market = Market {
+            open_positions: 1_000_000,
+            ..Default::default()
+        };
+        assert!(market.register_position().is_ok());
+    }
+
+    #[test]
+    fn test_user_registry_records_each_market_once() {
+        let mut registry = UserRegistry::default();
+        let first = Pubkey::new_unique();
+
+        registry.record_market(first).unwrap();
+        registry.record_market(first).unwrap();
+        assert_eq!(registry.markets, vec![first]);
+
+        for _ in 1..UserRegistry::CAPACITY {
+            registry.record_market(Pubkey::new_unique()).unwrap();
+        }
+        assert_eq!(
+            registry.record_market(Pubkey::new_unique()).unwrap_err(),
+            PelagoError::UserRegistryFull.into()
+        );
+
+        // Known markets are still accepted when full
+        registry.record_market(first).unwrap();
+        assert_eq!(registry.markets.len(), UserRegistry::CAPACITY);
+    }
+
+    #[test]
+    fn test_price_history_ring_buffer_order() {
+        let mut history = PriceHistory::default();
+        let total = PriceHistory::CAPACITY as i64 + 3;
+        for t in 0..total {
+            history.push(PriceSample {
+                price: t as u64,
+                timestamp: t,
+            });
+        }
+
+        let samples = history.chronological();
+        assert_eq!(samples.len(), PriceHistory::CAPACITY);
+        assert_eq!(samples.first().unwrap().timestamp, 3);
+        assert_eq!(samples.last().unwrap().timestamp, total - 1);
+        assert!(samples.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+    }
+
+    #[test]
+    fn test_protocol_config_defaults_and_policy() {
+        let mut config = ProtocolConfig {
+            default_fee_bps: 1_000,
+            max_lltv: 80_000_000,
+            min_rate_wad: 10_000_000_000_000_000,
+            max_rate_wad: 100_000_000_000_000_000,
+            ..Default::default()
+        };
+
+        // The sentinel inherits the default, explicit fees pass through
+        assert_eq!(config.resolve_fee_bps(USE_PROTOCOL_DEFAULT_FEE), 1_000);
+        assert_eq!(config.resolve_fee_bps(0), 0);
+        assert_eq!(config.resolve_fee_bps(250), 250);
+
+        let rate = 50_000_000_000_000_000;
+        config.enable_lltv(80_000_000).unwrap();
+        config.check_market_params(80_000_000, rate).unwrap();
+        assert_eq!(
+            config.check_market_params(80_000_001, rate).unwrap_err(),
+            PelagoError::InvalidLltv.into()
+        );
+        assert_eq!(
+            config
+                .check_market_params(80_000_000, 200_000_000_000_000_000)
+                .unwrap_err(),
+            PelagoError::InvalidParameter.into()
+        );
+    }
+
+    #[test]
+    fn test_enabled_lltv_whitelist() {
+        let mut config = ProtocolConfig {
+            max_lltv: 90_000_000,
+            max_rate_wad: u128::MAX,
+            ..Default::default()
+        };
+        let rate = 50_000_000_000_000_000;
+
+        // Nothing is enabled initially
+        assert_eq!(
+            config.check_market_params(80_000_000, rate).unwrap_err(),
+            PelagoError::LltvNotEnabled.into()
+        );
+
+        config.enable_lltv(80_000_000).unwrap();
+        config.enable_lltv(80_000_000).unwrap(); // idempotent
+        assert_eq!(config.enabled_lltvs, vec![80_000_000]);
+        config.check_market_params(80_000_000, rate).unwrap();
+        assert_eq!(
+            config.check_market_params(86_000_000, rate).unwrap_err(),
+            PelagoError::LltvNotEnabled.into()
+        );
+
+        config.disable_lltv(80_000_000).unwrap();
+        assert_eq!(
+            config.disable_lltv(80_000_000).unwrap_err(),
+            PelagoError::LltvNotEnabled.into()
+        );
+        assert!(config.check_market_params(80_000_000, rate).is_err());
+
+        // Capacity is bounded
+        for i in 0..ProtocolConfig::MAX_ENABLED_LLTVS as u64 {
+            config.enable_lltv(1 + i).unwrap();
+        }
+        assert_eq!(
+            config.enable_lltv(50_000_000).unwrap_err(),
+            PelagoError::InvalidParameter.into()
+        );
+    }
+
+    #[test]
+    fn test_close_and_recreate_position_lifecycle() {
+        let user = Pubkey::new_unique();
+        let market_key = Pubkey::new_unique();
+        let mut market = Market::default();
+        let mut registry = UserRegistry::default();
+
+        // First creation
+        let mut position = UserPosition::default();
+        position.open(user, market_key, 254).unwrap();
+        market.register_position().unwrap();
+        registry.record_market(market_key).unwrap();
+        assert_eq!(market.open_positions, 1);
+
+        // Close: the account is zeroed, the slot and registry entry released
+        market.release_position();
+        registry.remove_market(market_key);
+        assert_eq!(market.open_positions, 0);
+        assert!(registry.markets.is_empty());
+
+        // Recreate on the zeroed account
+        let mut recreated = UserPosition::default();
+        recreated.open(user, market_key, 254).unwrap();
+        market.register_position().unwrap();
+        registry.record_market(market_key).unwrap();
+        assert_eq!(recreated.user, user);
+        assert_eq!(recreated.market, market_key);
+        assert_eq!(market.open_positions, 1);
+        assert_eq!(registry.markets, vec![market_key]);
+
+        // An account that still carries balances is never reopened
+        let mut stale = UserPosition {
+            collateral_amount: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            stale.open(user, market_key, 254).unwrap_err(),
+            PelagoError::InvariantViolation.into()
+        );
+    }
 }
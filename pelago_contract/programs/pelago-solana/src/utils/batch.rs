@@ -0,0 +1,54 @@
+//! Batch Size Limits
+//!
+//! Instructions that loop over `remaining_accounts` check the count up
+//! front, so an oversized batch fails with `BatchTooLarge` before any work
+//! instead of running out of compute halfway through the loop.
+//!
+//! **Compute Budget:** An instruction gets 200,000 compute units by
+//! default (1,400,000 per transaction with a compute budget request).
+//! Rough per-account costs, including deserialization and logging:
+//! - Liquidating a position (health check, seize math, event, write back):
+//!   ~20,000 CU, plus two token transfers for the whole batch
+//! - Reading a position (bad debt check, collateral resync): ~3,000 CU
+//!
+//! **Limits:**
+//! - `batch_liquidate`: MAX_BATCH_LIQUIDATIONS (8), ~160,000 CU at the limit
+//! - `check_bad_debt`, `resync_total_collateral`: MAX_BATCH_POSITION_READS
+//!   (32), ~100,000 CU at the limit
+//!
+//! A transaction also caps the accounts it can reference (64 locks), so
+//! larger limits would not be usable anyway.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+
+/// Rejects a batch of more than `max` accounts
+///
+/// **Errors:**
+/// - BatchTooLarge: `count > max`
+pub fn check_batch_size(count: usize, max: usize) -> Result<()> {
+    if count > max {
+        msg!("Batch too large: accounts={}, max={}", count, max);
+        return err!(PelagoError::BatchTooLarge);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{MAX_BATCH_LIQUIDATIONS, MAX_BATCH_POSITION_READS};
+
+    #[test]
+    fn test_batch_size_limits() {
+        for max in [MAX_BATCH_LIQUIDATIONS, MAX_BATCH_POSITION_READS] {
+            assert!(check_batch_size(0, max).is_ok());
+            assert!(check_batch_size(max, max).is_ok());
+            assert_eq!(
+                check_batch_size(max + 1, max).unwrap_err(),
+                PelagoError::BatchTooLarge.into()
+            );
+        }
+    }
+}
@@ -1,27 +1,37 @@
 //! Interest Accrual Module
 //!
-//! This module implements simplified interest accrual for P1 phase migration.
-//! Calculates and applies linear interest to borrow and supply assets over time.
+//! Calculates and applies compound interest to borrow and supply assets over time.
 //!
-//! **P1 Simplifications:**
-//! - Fixed annual interest rate: 5% (0.05)
-//! - Linear interest calculation (not compound/Taylor series)
-//! - Simple formula: `interest = principal × rate × time`
-//! - No fee mechanism (all interest goes to suppliers)
+//! **Rate Model:**
+//! - Kinked on utilization (`compute_borrow_rate`): `base + slope1 × u / kink`
+//!   up to the kink, steepening by `slope2` above it. Set at
+//!   `initialize_market` and tuned with `set_irm`
+//! - Markets without a kink charge FIXED_ANNUAL_RATE_WAD (5%)
+//! - The rate is re-read at the start of every accrual checkpoint (see
+//!   `checkpoint_secs`), so it follows utilization as interest accrues
 //!
-//! **P2 Future Enhancements:**
-//! - Dynamic Interest Rate Models (IRM)
-//! - Taylor series compound interest (wTaylorCompounded)
-//! - Fee distribution to protocol treasury
+//! **Compounding:**
+//! - Continuous within a checkpoint, approximated by a three-term Taylor
+//!   series (`w_taylor_compounded`), and across checkpoints when an accrual
+//!   spans more than one day
+//! - Formula: `interest = principal × (e^(rate × time) - 1)`
+//! - Optional protocol fee (`effective_fee_bps`), taken as minted supply shares
+//!
+//! **Future Enhancements:**
 //! - Multiple IRM strategies per market
 //!
 //! **Reference:** Pelago.sol _accrueInterest() (L481-509)
 
 use anchor_lang::prelude::*;
+use crate::constants::BPS_DENOMINATOR;
 use crate::error::PelagoError;
 use crate::state::Market;
+use crate::utils::invariants::check_market_invariants;
+use crate::utils::shares_math::to_shares_down;
+use crate::utils::rewards::accrue_rewards;
+use crate::utils::twap::expire_stale_twap;
 
-/// Fixed annual interest rate for P1 phase
+/// Annual interest rate of markets without a kinked rate model
 ///
 /// Rate: 5% per year = 0.05
 /// Precision: 1e18 (WAD precision matching Solidity)
@@ -44,31 +54,60 @@ pub const SECONDS_PER_YEAR: u128 = 31_557_600;
 ///
 /// **Operation Flow:**
 /// 1. Calculate elapsed time since last_update
-/// 2. If elapsed == 0, return early (no time passed)
-/// 3. Calculate linear interest: `interest = totalBorrow × rate × time`
-/// 4. Update totalBorrowAssets (borrowers owe more)
-/// 5. Update totalSupplyAssets (suppliers earn more)
-/// 6. Update last_update timestamp
+/// 2. If elapsed == 0, return early (no time passed); if the market is
+///    paused and `accrue_while_paused` is off, only advance last_update
+/// 3. Split elapsed into checkpoints (see `checkpoint_secs`)
+/// 4. Per checkpoint: compound interest
+///    `interest = totalBorrow × w_taylor_compounded(rate, step)`,
+///    added to totalBorrowAssets and totalSupplyAssets, clamped so the whole
+///    accrual stays within `max_accrual_interest` (see InterestCappedEvent)
+/// 5. Each checkpoint accrues on the totals left by the previous one
+/// 6. Update last_update timestamp and the stored current rates
 /// 7. Emit AccrueInterestEvent
 ///
-/// **Interest Distribution:**
-/// - All interest goes to suppliers (P1 has no fees)
-/// - totalSupplyAssets increases by same amount as totalBorrowAssets
-/// - This maintains the invariant: `totalBorrowAssets ≤ totalSupplyAssets`
+/// **Impaired Markets:** If `total_supply_assets < total_borrow_assets` on
+/// entry, the market is flagged `impaired` and MarketImpairedEvent is
+/// emitted. Borrowers are still charged, but the interest is not credited
+/// to suppliers or the protocol, and the liquidity invariant is not
+/// re-checked, so repays can still restore the market.
+///
+/// **Interest Distribution (see `InterestSplit`):**
+/// - Borrowers are charged the gross interest (`totalBorrowAssets += gross`)
+/// - Suppliers gain `gross - fee`; the fee is minted as supply shares to the
+///   protocol (`market.fee_shares`, paid out by `claim_fees`), diluting
+///   suppliers by exactly the fee
+///   (Pelago.sol: feeShares minted to feeRecipient)
+/// - totalSupplyAssets therefore grows by `supplier + fee = gross`, the same
+///   amount as totalBorrowAssets: the fee shares back the difference between
+///   what borrowers owe and what suppliers earn, so the invariant
+///   `totalBorrowAssets ≤ totalSupplyAssets` is preserved
+///
+/// **Rounding:**
+/// - Default: interest is floored, so sub-unit dust stays with borrowers
+/// - `market.round_interest_up`: interest is rounded up, so suppliers capture it
+/// - Either way the same rounded amount is added to both totals, so the
+///   liquidity invariant is unaffected; rounding up only means borrowers owe
+///   at most 1 base unit more per checkpoint
 ///
-/// **Linear Interest Formula:**
+/// **Compound Interest Formula:**
 /// ```ignore
 /// rate_per_second = FIXED_ANNUAL_RATE / SECONDS_PER_YEAR
-/// interest = (totalBorrow × rate_per_second × elapsed) / WAD
+/// interest = (totalBorrow × w_taylor_compounded(rate_per_second, elapsed)) / WAD
 /// ```
 ///
 /// **Parameters:**
 /// - `market`: Mutable reference to Market account
 ///
 /// **State Changes:**
-/// - `market.total_borrow_assets` += interest
-/// - `market.total_supply_assets` += interest
-/// - `market.last_update` = current_timestamp
+/// - `market.impaired` = total_supply_assets < total_borrow_assets (on entry)
+/// - `market.total_borrow_assets` += gross
+/// - `market.total_supply_assets` += supplier + fee (= gross; 0 while impaired)
+/// - `market.total_supply_shares` += fee_shares (if fee_bps > 0)
+/// - `market.fee_shares` += fee_shares (if fee_bps > 0)
+/// - `market.last_update` = current_timestamp (or the last completed
+///   compounding period, see `compounding_window`)
+/// - `market.current_borrow_rate_wad` / `current_supply_rate_wad` = rates on
+///   the post-accrual totals (both 0 while a pause skips accrual)
 ///
 /// **Errors:**
 /// - MathOverflow: If interest calculation overflows
@@ -79,16 +118,154 @@ pub const SECONDS_PER_YEAR: u128 = 31_557_600;
 /// - Future: Consider batching or lazy accrual for gas savings
 pub fn accrue_interest(market: &mut Market) -> Result<()> {
     let clock = Clock::get()?;
-    let current_timestamp = clock.unix_timestamp;
+    accrue_interest_at(market, clock.unix_timestamp)
+}
+
+/// Accrues interest for a market up to an explicit timestamp
+///
+/// Same logic as `accrue_interest`, but takes the current timestamp as a
+/// parameter instead of reading the Clock sysvar. This keeps the accrual
+/// math deterministic and usable from unit tests.
+///
+/// Applies `project_interest_at` and emits its events; instructions that
+/// commit the market use this, views use the projection.
+///
+/// **Parameters:**
+/// - `market`: Mutable reference to Market account
+/// - `current_timestamp`: Unix timestamp to accrue up to
+pub fn accrue_interest_at(market: &mut Market, current_timestamp: i64) -> Result<()> {
+    let (borrow_before, supply_before) = (market.total_borrow_assets, market.total_supply_assets);
+    let accrual = project_interest_at(market, current_timestamp)?;
+    if accrual.elapsed == 0 {
+        return Ok(());
+    }
+
+    if market.impaired {
+        msg!(
+            "Market impaired: borrow={}, supply={}",
+            borrow_before,
+            supply_before
+        );
+        emit!(MarketImpairedEvent {
+            total_borrow_assets: borrow_before,
+            total_supply_assets: supply_before,
+            timestamp: current_timestamp,
+        });
+    }
+
+    if accrual.skipped_while_paused {
+        msg!("Interest skipped while paused: elapsed={}s", accrual.elapsed);
+        return Ok(());
+    }
+    if accrual.accrued_secs == 0 {
+        return Ok(());
+    }
+
+    if accrual.ratio_breached {
+        msg!(
+            "Borrow ratio breached: borrow={}, supply={}, max_borrow_ratio_bps={}",
+            market.total_borrow_assets,
+            market.total_supply_assets,
+            market.max_borrow_ratio_bps
+        );
+        emit!(RatioBreachEvent {
+            total_borrow_assets: market.total_borrow_assets,
+            total_supply_assets: market.total_supply_assets,
+            max_borrow_ratio_bps: market.max_borrow_ratio_bps,
+            timestamp: market.last_update,
+        });
+    }
+
+    let split = accrual.interest;
+    if accrual.capped {
+        msg!(
+            "Interest capped: interest={}, max_accrual_interest_bps={}",
+            split.gross,
+            market.max_accrual_interest_bps
+        );
+        emit!(InterestCappedEvent {
+            interest: split.gross,
+            max_accrual_interest_bps: market.max_accrual_interest_bps,
+            elapsed_seconds: accrual.accrued_secs,
+            timestamp: market.last_update,
+        });
+    }
+
+    // Emit event for off-chain tracking
+    // Note: market pubkey is not available here since we only have &mut Market
+    // Off-chain indexers can derive it from the transaction context
+    emit!(AccrueInterestEvent {
+        interest: split.gross,
+        supplier_interest: split.supplier,
+        fee_interest: split.fee,
+        total_borrow_assets: market.total_borrow_assets,
+        total_supply_assets: market.total_supply_assets,
+        elapsed_seconds: accrual.accrued_secs,
+        timestamp: market.last_update,
+    });
+
+    msg!(
+        "Interest accrued: interest={}, fee={}, elapsed={}s, new_borrow={}, new_supply={}",
+        split.gross,
+        split.fee,
+        accrual.accrued_secs,
+        market.total_borrow_assets,
+        market.total_supply_assets
+    );
+
+    Ok(())
+}
+
+/// What `project_interest_at` did to the market
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Accrual {
+    /// Seconds since `last_update` on entry (0: the market was untouched)
+    pub elapsed: i64,
+
+    /// Whether a pause skipped the interest (only `last_update` moved)
+    pub skipped_while_paused: bool,
+
+    /// Seconds of interest applied (whole compounding periods)
+    pub accrued_secs: i64,
+
+    /// Interest applied over `accrued_secs`
+    pub interest: InterestSplit,
+
+    /// Whether `max_accrual_interest_bps` clamped the interest
+    pub capped: bool,
+
+    /// Whether borrows ended above `max_borrow_ratio_bps` of supply
+    pub ratio_breached: bool,
+}
+
+/// Applies the accrual to `market` without emitting events or logging
+///
+/// The state changes are exactly those of `accrue_interest_at`; views run
+/// it on a copy of the market to price positions as of `current_timestamp`
+/// without reporting an accrual that never lands on chain. Also drops a
+/// cached TWAP older than the market's window (see `expire_stale_twap`).
+///
+/// **Returns:** What happened, for the caller to report
+pub fn project_interest_at(market: &mut Market, current_timestamp: i64) -> Result<Accrual> {
+    // Every handler accrues before pricing collateral
+    expire_stale_twap(market, current_timestamp);
 
     // Calculate elapsed time in seconds
     let elapsed = current_timestamp
         .checked_sub(market.last_update)
         .ok_or(PelagoError::InvalidTimestamp)?;
+    let mut accrual = Accrual {
+        elapsed,
+        skipped_while_paused: false,
+        accrued_secs: 0,
+        interest: InterestSplit { gross: 0, supplier: 0, fee: 0 },
+        capped: false,
+        ratio_breached: false,
+    };
 
     // Early return if no time has passed (prevents redundant calculations)
     if elapsed == 0 {
-        return Ok(());
+        return Ok(accrual);
     }
 
     // Ensure elapsed is positive (clock should never go backwards)
@@ -96,65 +273,424 @@ pub fn accrue_interest(market: &mut Market) -> Result<()> {
         return err!(PelagoError::InvalidTimestamp);
     }
 
-    let elapsed_u128 = elapsed as u128;
+    // Supply below borrows means value was already lost (bad debt or a
+    // bug). Crediting suppliers interest on top would compound the
+    // distortion, so borrowers are still charged but suppliers and the
+    // protocol earn nothing until the market is whole again
+    market.impaired = market.total_supply_assets < market.total_borrow_assets;
 
-    // Calculate per-second interest rate
-    // rate_per_second = annual_rate / seconds_per_year
-    let rate_per_second = FIXED_ANNUAL_RATE_WAD
+    // Operators can stop charging borrowers while the market is paused
+    if market.paused && !market.accrue_while_paused {
+        accrue_rewards(market, elapsed)?;
+        market.last_update = current_timestamp;
+        market.current_borrow_rate_wad = 0;
+        market.current_supply_rate_wad = 0;
+        accrual.skipped_while_paused = true;
+        return Ok(accrual);
+    }
+
+    // Discrete compounding: only whole periods accrue, the rest carries over
+    let (elapsed, step_secs) = compounding_window(market, elapsed);
+    if elapsed == 0 {
+        return Ok(accrual);
+    }
+    let accrued_to = market.last_update + elapsed;
+
+    // Long gaps are caught up in checkpoints, each accruing on the balance
+    // left by the previous one, so an idle market ends up where frequent
+    // accrual would have put it
+    let max_gross = max_accrual_interest(market)?;
+    let mut capped = false;
+    let mut remaining = elapsed;
+    let mut total = InterestSplit { gross: 0, supplier: 0, fee: 0 };
+    while remaining > 0 && !capped {
+        let step = remaining.min(step_secs);
+        let mut split = interest_split(market, step)?;
+
+        // Safety rail: clamp to what is left of the per-accrual cap
+        if let Some(max_gross) = max_gross {
+            let allowance = max_gross - total.gross;
+            if split.gross > allowance {
+                let (supplier, fee) = split_interest(allowance, effective_fee_bps(market))?;
+                split = InterestSplit { gross: allowance, supplier, fee };
+                capped = true;
+            }
+        }
+        if market.impaired {
+            split = InterestSplit { gross: split.gross, supplier: 0, fee: 0 };
+        }
+
+        apply_interest_step(market, split)?;
+        total.gross = total.gross.checked_add(split.gross).ok_or(PelagoError::MathOverflow)?;
+        total.supplier = total
+            .supplier
+            .checked_add(split.supplier)
+            .ok_or(PelagoError::MathOverflow)?;
+        total.fee = total.fee.checked_add(split.fee).ok_or(PelagoError::MathOverflow)?;
+        remaining -= step;
+    }
+
+    // An impaired market already breaks the liquidity invariant; failing
+    // here would also block the repays that can restore it
+    if !market.impaired {
+        check_market_invariants(market)?;
+    }
+
+    // Interest grows borrows faster than supply in relative terms, so the
+    // ratio can drift past its ceiling without any new borrow. Borrowers
+    // still owe the interest; operators are alerted instead.
+    if let Some(max_borrow) = max_borrow_for_ratio(market)? {
+        accrual.ratio_breached = market.total_borrow_assets > max_borrow;
+    }
+
+    // Supply rewards stream over exactly the time interest covered
+    accrue_rewards(market, elapsed)?;
+
+    // Update timestamp and the rates as of it
+    market.last_update = accrued_to;
+    market.current_borrow_rate_wad = compute_borrow_rate(market);
+    market.current_supply_rate_wad = if market.impaired {
+        0
+    } else {
+        supply_rate_wad(market)?
+    };
+
+    accrual.accrued_secs = elapsed;
+    accrual.interest = total;
+    accrual.capped = capped;
+    Ok(accrual)
+}
+
+/// Annual borrow rate (WAD) charged by the market
+///
+/// FIXED_ANNUAL_RATE_WAD unless the market has a kinked rate model
+/// (`irm_kink_utilization_bps > 0`, see `set_irm`):
+/// ```text
+/// u <= kink: rate = base + slope1 × u / kink
+/// u >  kink: rate = base + slope1 + slope2 × (u - kink) / (1 - kink)
+/// ```
+/// Utilization at the start of each checkpoint stands in for the
+/// utilization over the whole checkpoint, as for the fee kink.
+pub fn compute_borrow_rate(market: &Market) -> u128 {
+    if market.irm_kink_utilization_bps == 0 {
+        return FIXED_ANNUAL_RATE_WAD;
+    }
+
+    // u64 values × WAD fit in u128; total_borrow_assets ≤ total_supply_assets
+    let utilization = if market.total_supply_assets == 0 {
+        0
+    } else {
+        (market.total_borrow_assets as u128 * WAD / market.total_supply_assets as u128).min(WAD)
+    };
+    let kink = market.irm_kink_utilization_bps as u128 * WAD / BPS_DENOMINATOR as u128;
+    let base = market.irm_base_rate_wad as u128;
+    let slope1 = market.irm_slope1_wad as u128;
+    let slope2 = market.irm_slope2_wad as u128;
+
+    if utilization <= kink {
+        base + slope1 * utilization / kink
+    } else {
+        base + slope1 + slope2 * (utilization - kink) / (WAD - kink)
+    }
+}
+
+/// Per-second borrow rate (WAD) the next accrual applies
+///
+/// **Formula:**
+/// ```text
+/// rate_per_second = borrow_rate / SECONDS_PER_YEAR   (floored)
+/// ```
+///
+/// Returns 0 while the market is paused with `accrue_while_paused` off,
+/// where the accrual charges nothing.
+pub fn rate_per_second(market: &Market) -> Result<u128> {
+    if market.paused && !market.accrue_while_paused {
+        return Ok(0);
+    }
+    compute_borrow_rate(market)
         .checked_div(SECONDS_PER_YEAR)
-        .ok_or(PelagoError::MathOverflow)?;
+        .ok_or(PelagoError::MathOverflow.into())
+}
 
-    // Calculate interest
-    // interest = (total_borrow × rate_per_second × elapsed) / WAD
-    let total_borrow_u128 = market.total_borrow_assets as u128;
+/// Annual supply rate (WAD) earned by suppliers, net of the protocol fee
+///
+/// **Formula:**
+/// ```text
+/// supply_rate = borrow_rate × total_borrow / total_supply × (10_000 - fee) / 10_000
+/// ```
+/// where `fee` is `effective_fee_bps`, the fee accrual actually charges at
+/// the current utilization.
+///
+/// Returns 0 for an empty market.
+pub fn supply_rate_wad(market: &Market) -> Result<u128> {
+    if market.total_supply_assets == 0 {
+        return Ok(0);
+    }
 
-    let interest = total_borrow_u128
-        .checked_mul(rate_per_second)
+    let gross = compute_borrow_rate(market)
+        .checked_mul(market.total_borrow_assets as u128)
         .ok_or(PelagoError::MathOverflow)?
-        .checked_mul(elapsed_u128)
+        / market.total_supply_assets as u128;
+    let net = gross
+        .checked_mul((BPS_DENOMINATOR - effective_fee_bps(market) as u64) as u128)
         .ok_or(PelagoError::MathOverflow)?
-        .checked_div(WAD)
-        .ok_or(PelagoError::MathOverflow)?;
+        / BPS_DENOMINATOR as u128;
 
-    // Convert interest back to u64
-    let interest_u64 = u64::try_from(interest)
-        .map_err(|_| PelagoError::MathOverflow)?;
+    Ok(net)
+}
+
+/// Length of one accrual checkpoint (1 day)
+///
+/// Elapsed time longer than this is accrued in checkpoint-sized steps.
+pub const ACCRUAL_CHECKPOINT_SECS: i64 = 86_400;
+
+/// Maximum number of checkpoints processed by a single accrual
+///
+/// Bounds the compute spent catching up an idle market. Up to 365 days are
+/// compounded daily; beyond that the checkpoint length grows so that the
+/// whole gap still fits in this many steps.
+pub const MAX_ACCRUAL_CHECKPOINTS: i64 = 365;
+
+/// Checkpoint length used to accrue `elapsed` seconds
+///
+/// `max(ACCRUAL_CHECKPOINT_SECS, ceil(elapsed / MAX_ACCRUAL_CHECKPOINTS))`
+pub fn checkpoint_secs(elapsed: i64) -> i64 {
+    let stretched = elapsed / MAX_ACCRUAL_CHECKPOINTS
+        + i64::from(elapsed % MAX_ACCRUAL_CHECKPOINTS != 0);
+    stretched.max(ACCRUAL_CHECKPOINT_SECS)
+}
+
+/// Elapsed time to accrue and the checkpoint length to accrue it in
+///
+/// Without a compounding period (`compound_period_secs == 0`) the whole
+/// `elapsed` accrues in `checkpoint_secs` steps. With one, only completed
+/// periods accrue, compounding once per period; the incomplete period
+/// stays pending because `last_update` only advances to its start. More
+/// than MAX_ACCRUAL_CHECKPOINTS periods are grouped into multi-period steps.
+///
+/// **Returns:** `(accrued_elapsed, step_secs)`; `accrued_elapsed` is 0 while
+/// the first period is incomplete
+pub fn compounding_window(market: &Market, elapsed: i64) -> (i64, i64) {
+    let period = market.compound_period_secs as i64;
+    if period == 0 {
+        return (elapsed, checkpoint_secs(elapsed));
+    }
+
+    let periods = elapsed / period;
+    let periods_per_step = periods / MAX_ACCRUAL_CHECKPOINTS
+        + i64::from(periods % MAX_ACCRUAL_CHECKPOINTS != 0);
+    (periods * period, periods_per_step.max(1) * period)
+}
+
+/// Upper bound on the gross interest of one accrual (`None` = uncapped)
+///
+/// `total_borrow_assets × max_accrual_interest_bps / 10_000`, taken on the
+/// borrows before the accrual.
+///
+/// **Safety rail, not normal operation:** the cap only matters if a rate is
+/// misconfigured (or a market sits idle for years); interest above it is
+/// forgiven rather than charged, and an `InterestCappedEvent` flags it.
+pub fn max_accrual_interest(market: &Market) -> Result<Option<u64>> {
+    if market.max_accrual_interest_bps == 0 {
+        return Ok(None);
+    }
+
+    let max_gross = (market.total_borrow_assets as u128)
+        .checked_mul(market.max_accrual_interest_bps as u128)
+        .ok_or(PelagoError::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    Ok(Some(u64::try_from(max_gross).map_err(|_| PelagoError::MathOverflow)?))
+}
+
+/// Borrow assets allowed by `max_borrow_ratio_bps` at the current supply
+///
+/// **Returns:** `None` if the ratio ceiling is disabled
+pub fn max_borrow_for_ratio(market: &Market) -> Result<Option<u64>> {
+    if market.max_borrow_ratio_bps == 0 {
+        return Ok(None);
+    }
+
+    let max_borrow = (market.total_supply_assets as u128)
+        .checked_mul(market.max_borrow_ratio_bps as u128)
+        .ok_or(PelagoError::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    Ok(Some(u64::try_from(max_borrow).map_err(|_| PelagoError::MathOverflow)?))
+}
+
+/// Applies the interest of one checkpoint to the market totals
+///
+/// Does not touch `last_update`; the caller advances the clock once all
+/// checkpoints are applied.
+fn apply_interest_step(market: &mut Market, split: InterestSplit) -> Result<()> {
 
-    // Update market state
-    // Note: Both borrow and supply assets increase by the same amount
+    // Borrowers owe the gross interest
     market.total_borrow_assets = market
         .total_borrow_assets
-        .checked_add(interest_u64)
+        .checked_add(split.gross)
         .ok_or(PelagoError::MathOverflow)?;
 
+    // Suppliers' claim grows by their share, the protocol's by the fee
     market.total_supply_assets = market
         .total_supply_assets
-        .checked_add(interest_u64)
+        .checked_add(split.supplier)
         .ok_or(PelagoError::MathOverflow)?;
 
-    // Update timestamp
-    market.last_update = current_timestamp;
+    // Mint fee shares to the protocol
+    // Shares are priced against supply assets *excluding* the fee so the
+    // protocol receives exactly `split.fee` worth of shares
+    if split.fee > 0 {
+        let fee_shares = to_shares_down(
+            split.fee,
+            market.total_supply_assets,
+            market.total_supply_shares,
+        )?;
+        market.total_supply_assets = market
+            .total_supply_assets
+            .checked_add(split.fee)
+            .ok_or(PelagoError::MathOverflow)?;
+        market.total_supply_shares = market
+            .total_supply_shares
+            .checked_add(fee_shares)
+            .ok_or(PelagoError::MathOverflow)?;
+        market.fee_shares = market
+            .fee_shares
+            .checked_add(fee_shares)
+            .ok_or(PelagoError::MathOverflow)?;
+    }
 
-    // Emit event for off-chain tracking
-    // Note: market pubkey is not available here since we only have &mut Market
-    // Off-chain indexers can derive it from the transaction context
-    emit!(AccrueInterestEvent {
-        interest: interest_u64,
-        total_borrow_assets: market.total_borrow_assets,
-        total_supply_assets: market.total_supply_assets,
-        elapsed_seconds: elapsed,
-        timestamp: current_timestamp,
-    });
+    Ok(())
+}
 
-    msg!(
-        "Interest accrued: interest={}, elapsed={}s, new_borrow={}, new_supply={}",
-        interest_u64,
-        elapsed,
-        market.total_borrow_assets,
-        market.total_supply_assets
-    );
+/// Three-way split of the interest accrued over one period
+///
+/// `gross = supplier + fee` always holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InterestSplit {
+    /// Interest charged to borrowers
+    pub gross: u64,
 
-    Ok(())
+    /// Portion of `gross` earned by suppliers
+    pub supplier: u64,
+
+    /// Portion of `gross` taken as protocol fee
+    pub fee: u64,
+}
+
+/// Computes the gross interest for `elapsed` seconds and splits it by the
+/// effective fee (see `effective_fee_bps`)
+///
+/// Rounding of the gross amount follows `market.round_interest_up`.
+pub fn interest_split(market: &Market, elapsed: i64) -> Result<InterestSplit> {
+    let elapsed_u128 = u128::try_from(elapsed).map_err(|_| PelagoError::InvalidTimestamp)?;
+
+    // Calculate per-second interest rate
+    // rate_per_second = annual_rate / seconds_per_year
+    let rate_per_second = rate_per_second(market)?;
+
+    // Calculate interest
+    // interest = (total_borrow × w_taylor_compounded(rate_per_second, elapsed)) / WAD
+    let total_borrow_u128 = market.total_borrow_assets as u128;
+
+    let interest_wad = total_borrow_u128
+        .checked_mul(w_taylor_compounded(rate_per_second, elapsed_u128)?)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    let interest = if market.round_interest_up {
+        interest_wad.div_ceil(WAD)
+    } else {
+        interest_wad / WAD
+    };
+
+    // Convert interest back to u64
+    let gross = u64::try_from(interest)
+        .map_err(|_| PelagoError::MathOverflow)?;
+    let (supplier, fee) = split_interest(gross, effective_fee_bps(market))?;
+
+    Ok(InterestSplit { gross, supplier, fee })
+}
+
+/// Compounded growth `e^(rate_per_second × elapsed) - 1` in WAD
+///
+/// Approximated by the first three terms of its Taylor series, as in
+/// Morpho Blue's `wTaylorCompounded`:
+/// ```text
+/// x = rate_per_second × elapsed
+/// growth = x + x² / 2 + x³ / 6
+/// ```
+/// Each term rounds down, so the result never exceeds the true compounded
+/// growth; it is never below the linear growth `x`. The truncation error is
+/// at most `x⁴ / 24 × e^x`, negligible at the rates and checkpoint lengths
+/// used.
+///
+/// **Errors:**
+/// - MathOverflow: `rate_per_second × elapsed` or a higher term overflows
+pub fn w_taylor_compounded(rate_per_second: u128, elapsed: u128) -> Result<u128> {
+    let first_term = rate_per_second
+        .checked_mul(elapsed)
+        .ok_or(PelagoError::MathOverflow)?;
+    let second_term = first_term
+        .checked_mul(first_term)
+        .ok_or(PelagoError::MathOverflow)?
+        / (2 * WAD);
+    let third_term = second_term
+        .checked_mul(first_term)
+        .ok_or(PelagoError::MathOverflow)?
+        / (3 * WAD);
+
+    first_term
+        .checked_add(second_term)
+        .and_then(|sum| sum.checked_add(third_term))
+        .ok_or(PelagoError::MathOverflow.into())
+}
+
+/// Fee rate (bps) applied to interest at the market's current utilization
+///
+/// Without a kink (`fee_kink_utilization_bps == 0`) this is `fee_bps`.
+/// Otherwise no fee is taken up to the kink, and above it the fee scales
+/// with how far utilization runs past the kink:
+/// ```text
+/// fee = fee_bps × (utilization - kink) / (10_000 - kink)
+/// ```
+/// Utilization at the start of each checkpoint stands in for the
+/// utilization over the whole checkpoint.
+pub fn effective_fee_bps(market: &Market) -> u16 {
+    let kink = market.fee_kink_utilization_bps as u128;
+    if kink == 0 || market.fee_bps == 0 {
+        return market.fee_bps;
+    }
+    if market.total_supply_assets == 0 || kink >= BPS_DENOMINATOR as u128 {
+        return 0;
+    }
+
+    // total_borrow_assets ≤ total_supply_assets, so utilization ≤ 10_000
+    let utilization = (market.total_borrow_assets as u128 * BPS_DENOMINATOR as u128
+        / market.total_supply_assets as u128)
+        .min(BPS_DENOMINATOR as u128);
+    if utilization <= kink {
+        return 0;
+    }
+
+    (market.fee_bps as u128 * (utilization - kink) / (BPS_DENOMINATOR as u128 - kink)) as u16
+}
+
+/// Splits accrued interest into the supplier and protocol fee portions
+///
+/// **Formula:**
+/// ```text
+/// fee_interest = interest × fee_bps / 10_000   (rounded down)
+/// supplier_interest = interest - fee_interest
+/// ```
+///
+/// **Returns:** `(supplier_interest, fee_interest)`, always summing to `interest`
+pub fn split_interest(interest: u64, fee_bps: u16) -> Result<(u64, u64)> {
+    let fee_interest = (interest as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(PelagoError::MathOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(PelagoError::MathOverflow)?;
+    let fee_interest = u64::try_from(fee_interest).map_err(|_| PelagoError::MathOverflow)?;
+
+    Ok((interest - fee_interest, fee_interest))
 }
 
 /// Event emitted when interest is accrued
@@ -170,6 +706,12 @@ pub struct AccrueInterestEvent {
     /// Interest amount accrued (in loan token base units)
     pub interest: u64,
 
+    /// Portion of `interest` earned by suppliers
+    pub supplier_interest: u64,
+
+    /// Portion of `interest` taken as protocol fee (minted as fee shares)
+    pub fee_interest: u64,
+
     /// New total borrow assets after accrual
     pub total_borrow_assets: u64,
 
@@ -183,6 +725,60 @@ pub struct AccrueInterestEvent {
     pub timestamp: i64,
 }
 
+/// Warning emitted when an accrual hits `max_accrual_interest_bps`
+///
+/// Not an error: the accrual succeeds with the interest clamped. Seeing this
+/// event means the rate configuration deserves a look.
+#[event]
+pub struct InterestCappedEvent {
+    /// Interest actually accrued (the cap)
+    pub interest: u64,
+
+    /// Cap in effect, in bps of total borrow assets
+    pub max_accrual_interest_bps: u16,
+
+    /// Elapsed time covered by the accrual (seconds)
+    pub elapsed_seconds: i64,
+
+    /// Timestamp of the accrual
+    pub timestamp: i64,
+}
+
+/// Warning emitted when an accrual leaves borrows above `max_borrow_ratio_bps`
+///
+/// Not an error: the interest is still accrued. Seeing this event means
+/// utilization is drifting towards the point where suppliers can't withdraw.
+#[event]
+pub struct RatioBreachEvent {
+    /// Total borrow assets after the accrual
+    pub total_borrow_assets: u64,
+
+    /// Total supply assets after the accrual
+    pub total_supply_assets: u64,
+
+    /// Ceiling in effect, in bps of total supply assets
+    pub max_borrow_ratio_bps: u16,
+
+    /// Timestamp of the accrual
+    pub timestamp: i64,
+}
+
+/// Alert emitted when an accrual finds supply assets below borrow assets
+///
+/// The market is flagged `impaired` and its interest accrues to borrows
+/// only until supply covers borrows again.
+#[event]
+pub struct MarketImpairedEvent {
+    /// Total borrow assets before the accrual
+    pub total_borrow_assets: u64,
+
+    /// Total supply assets before the accrual
+    pub total_supply_assets: u64,
+
+    /// Timestamp of the accrual
+    pub timestamp: i64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -198,6 +794,35 @@ mod tests {
         assert!(rate_per_second < FIXED_ANNUAL_RATE_WAD); // Should be much smaller
     }
 
+    #[test]
+    fn test_rate_per_second_annualizes_to_configured_rate() {
+        let market = Market::default();
+        let rate = rate_per_second(&market).unwrap();
+
+        // Flooring loses less than one unit per second of the year
+        let annual = rate * SECONDS_PER_YEAR;
+        assert!(annual <= FIXED_ANNUAL_RATE_WAD);
+        assert!(FIXED_ANNUAL_RATE_WAD - annual < SECONDS_PER_YEAR);
+
+        // It is the rate the accrual charges: one second on 1e18 of debt
+        // compounds to the rate plus at most a unit
+        let big = Market {
+            total_borrow_assets: WAD as u64,
+            ..Default::default()
+        };
+        let gross = interest_split(&big, 1).unwrap().gross as u128;
+        assert_eq!(gross, w_taylor_compounded(rate, 1).unwrap());
+        assert!(gross - rate <= 1);
+
+        // Paused without accrual: nothing is charged
+        let paused = Market {
+            paused: true,
+            accrue_while_paused: false,
+            ..Default::default()
+        };
+        assert_eq!(rate_per_second(&paused).unwrap(), 0);
+    }
+
     #[test]
     fn test_annual_interest_approximation() {
         // Simulate 1 year of interest on 100,000 tokens
@@ -209,7 +834,529 @@ mod tests {
 
         // Expected: 100,000 × 0.05 = 5,000 tokens
         // Allow small rounding error
-        assert!(interest >= 4_999 && interest <= 5_001);
+        assert!((4_999..=5_001).contains(&interest));
+    }
+
+    #[test]
+    fn test_impaired_market_accrues_to_borrows_only() {
+        // Bad debt left supply below borrows
+        let start = 1_700_000_000;
+        let mut market = Market {
+            total_supply_assets: 500_000_000,
+            total_supply_shares: 500_000_000_000_000,
+            total_borrow_assets: 1_000_000_000,
+            total_borrow_shares: 1_000_000_000_000_000,
+            fee_bps: 1_000,
+            last_update: start,
+            ..Default::default()
+        };
+
+        // Accrues instead of failing on the broken invariant
+        accrue_interest_at(&mut market, start + 86_400).unwrap();
+        assert!(market.impaired);
+        assert!(market.total_borrow_assets > 1_000_000_000, "borrowers still charged");
+        assert_eq!(market.total_supply_assets, 500_000_000);
+        assert_eq!(market.total_supply_shares, 500_000_000_000_000);
+        assert_eq!(market.fee_shares, 0);
+        assert_eq!(market.current_supply_rate_wad, 0);
+        assert_eq!(market.last_update, start + 86_400);
+
+        // Once supply covers borrows again, normal accrual resumes
+        market.total_supply_assets = 2_000_000_000;
+        accrue_interest_at(&mut market, start + 2 * 86_400).unwrap();
+        assert!(!market.impaired);
+        assert!(market.total_supply_assets > 2_000_000_000);
+        assert!(market.fee_shares > 0);
+    }
+
+    #[test]
+    fn test_accrue_interest_at_known_elapsed() {
+        // 1,000,000 USDC borrowed for exactly one year at 5%
+        let start = 1_700_000_000;
+        let mut market = Market {
+            total_supply_assets: 2_000_000_000_000,
+            total_borrow_assets: 1_000_000_000_000,
+            last_update: start,
+            ..Default::default()
+        };
+
+        accrue_interest_at(&mut market, start + SECONDS_PER_YEAR as i64).unwrap();
+
+        // One year is caught up in 365 compounded checkpoints
+        let rate_per_second = FIXED_ANNUAL_RATE_WAD / SECONDS_PER_YEAR;
+        let step = checkpoint_secs(SECONDS_PER_YEAR as i64) as u128;
+        let mut borrow = 1_000_000_000_000u128;
+        let mut remaining = SECONDS_PER_YEAR;
+        while remaining > 0 {
+            let dt = remaining.min(step);
+            borrow += borrow * w_taylor_compounded(rate_per_second, dt).unwrap() / WAD;
+            remaining -= dt;
+        }
+        let expected = (borrow - 1_000_000_000_000) as u64;
+        assert_eq!(market.total_borrow_assets, 1_000_000_000_000 + expected);
+        assert_eq!(market.total_supply_assets, 2_000_000_000_000 + expected);
+        assert_eq!(market.last_update, start + SECONDS_PER_YEAR as i64);
+
+        // ≈ 51,271 USDC (5% compounded continuously: e^0.05 - 1)
+        assert!((51_271_000_000..=51_272_000_000).contains(&expected));
+    }
+
+    #[test]
+    fn test_interest_rounding_modes() {
+        // 1 USDC borrowed for 10s accrues ~0.79 base units: floors to 0, rounds up to 1
+        let start = 1_700_000_000;
+        let floor_market = Market {
+            total_supply_assets: 2_000_000,
+            total_borrow_assets: 1_000_000,
+            last_update: start,
+            ..Default::default()
+        };
+        let mut ceil_market = Market {
+            round_interest_up: true,
+            ..floor_market.clone()
+        };
+        let mut floor_market = floor_market;
+
+        accrue_interest_at(&mut floor_market, start + 10).unwrap();
+        accrue_interest_at(&mut ceil_market, start + 10).unwrap();
+
+        assert_eq!(floor_market.total_borrow_assets, 1_000_000);
+        assert_eq!(floor_market.total_supply_assets, 2_000_000);
+        assert_eq!(ceil_market.total_borrow_assets, 1_000_001);
+        assert_eq!(ceil_market.total_supply_assets, 2_000_001);
+    }
+
+    #[test]
+    fn test_fee_split_sums_to_interest() {
+        for (interest, fee_bps) in [(0, 1_000), (1, 1_000), (9_999, 1_000), (50_000_000_000, 2_500)] {
+            let (supplier, fee) = split_interest(interest, fee_bps).unwrap();
+            assert_eq!(supplier + fee, interest);
+        }
+
+        let (supplier, fee) = split_interest(50_000_000_000, 1_000).unwrap();
+        assert_eq!(fee, 5_000_000_000);
+        assert_eq!(supplier, 45_000_000_000);
+    }
+
+    #[test]
+    fn test_accrue_with_fee_mints_fee_shares() {
+        // 1M USDC borrowed for one year with a 10% fee
+        let start = 1_700_000_000;
+        let mut market = Market {
+            total_supply_assets: 2_000_000_000_000,
+            total_supply_shares: 2_000_000_000_000 * 1_000_000,
+            total_borrow_assets: 1_000_000_000_000,
+            total_borrow_shares: 1_000_000_000_000 * 1_000_000,
+            fee_bps: 1_000,
+            last_update: start,
+            ..Default::default()
+        };
+
+        accrue_interest_at(&mut market, start + SECONDS_PER_YEAR as i64).unwrap();
+
+        let interest = market.total_borrow_assets - 1_000_000_000_000;
+        let (_, fee_interest) = split_interest(interest, 1_000).unwrap();
+        assert_eq!(market.total_supply_assets, 2_000_000_000_000 + interest);
+        assert!(market.fee_shares > 0);
+        assert_eq!(
+            market.total_supply_shares,
+            2_000_000_000_000 * 1_000_000 + market.fee_shares
+        );
+
+        // Fee shares are worth at least the fee (minus per-checkpoint rounding);
+        // shares minted at early checkpoints also earn supplier interest on later ones
+        let fee_value = crate::utils::shares_math::to_assets_down(
+            market.fee_shares,
+            market.total_supply_assets,
+            market.total_supply_shares,
+        )
+        .unwrap();
+        assert!(fee_value + MAX_ACCRUAL_CHECKPOINTS as u64 >= fee_interest);
+        assert!(fee_value <= fee_interest / 100 * 102);
+    }
+
+    #[test]
+    fn test_three_way_split_preserves_invariant() {
+        use crate::utils::shares_math::to_assets_down;
+
+        // Fully utilized market (borrow == supply) with the maximum 25% fee
+        let start = 1_700_000_000;
+        let supplier_shares = 1_000_000_000_000 * 1_000_000;
+        let mut market = Market {
+            total_supply_assets: 1_000_000_000_000,
+            total_supply_shares: supplier_shares,
+            total_borrow_assets: 1_000_000_000_000,
+            total_borrow_shares: 1_000_000_000_000 * 1_000_000,
+            fee_bps: 2_500,
+            last_update: start,
+            ..Default::default()
+        };
+
+        let elapsed = ACCRUAL_CHECKPOINT_SECS;
+        let split = interest_split(&market, elapsed).unwrap();
+        assert_eq!(split.supplier + split.fee, split.gross);
+        assert_eq!(split.fee, split.gross / 4);
+
+        accrue_interest_at(&mut market, start + elapsed).unwrap();
+
+        // Borrowers are charged the gross amount
+        assert_eq!(market.total_borrow_assets, 1_000_000_000_000 + split.gross);
+        // Fee shares back the difference, so supply keeps pace with borrow
+        assert_eq!(market.total_supply_assets, 1_000_000_000_000 + split.gross);
+        assert!(market.total_borrow_assets <= market.total_supply_assets);
+
+        // Suppliers gain gross - fee, the protocol gains the fee
+        let supplier_value =
+            to_assets_down(supplier_shares, market.total_supply_assets, market.total_supply_shares)
+                .unwrap();
+        let fee_value =
+            to_assets_down(market.fee_shares, market.total_supply_assets, market.total_supply_shares)
+                .unwrap();
+        assert!(1_000_000_000_000 + split.supplier - supplier_value <= 1);
+        assert!(split.fee - fee_value <= 1);
+    }
+
+    #[test]
+    fn test_checkpoint_secs_bounds_iterations() {
+        assert_eq!(checkpoint_secs(1), ACCRUAL_CHECKPOINT_SECS);
+        assert_eq!(checkpoint_secs(365 * ACCRUAL_CHECKPOINT_SECS), ACCRUAL_CHECKPOINT_SECS);
+        for elapsed in [366 * ACCRUAL_CHECKPOINT_SECS, 10 * SECONDS_PER_YEAR as i64, i64::MAX] {
+            let step = checkpoint_secs(elapsed);
+            assert!(step > ACCRUAL_CHECKPOINT_SECS);
+            assert!((elapsed - 1) / step < MAX_ACCRUAL_CHECKPOINTS);
+        }
+    }
+
+    #[test]
+    fn test_long_accrual_matches_daily_accrual() {
+        // 1M USDC borrowed, left idle for 365 days vs touched once a day
+        let start = 1_700_000_000;
+        let principal = 1_000_000_000_000u64;
+        let market = Market {
+            total_supply_assets: 2 * principal,
+            total_borrow_assets: principal,
+            last_update: start,
+            ..Default::default()
+        };
+
+        let mut idle = market.clone();
+        accrue_interest_at(&mut idle, start + 365 * ACCRUAL_CHECKPOINT_SECS).unwrap();
+
+        let mut daily = market;
+        for day in 1..=365 {
+            accrue_interest_at(&mut daily, start + day * ACCRUAL_CHECKPOINT_SECS).unwrap();
+        }
+
+        assert_eq!(idle.total_borrow_assets, daily.total_borrow_assets);
+        assert_eq!(idle.total_supply_assets, daily.total_supply_assets);
+
+        // Close to principal × e^(365 × r_day), well above the linear 5%
+        let rate_per_day = (FIXED_ANNUAL_RATE_WAD / SECONDS_PER_YEAR
+            * ACCRUAL_CHECKPOINT_SECS as u128) as f64
+            / WAD as f64;
+        let compounded = principal as f64 * (365.0 * rate_per_day).exp();
+        let actual = idle.total_borrow_assets as f64;
+        assert!((actual - compounded).abs() <= 365.0);
+
+        let linear = principal + principal / 100 * 5 * 365 * 86_400 / SECONDS_PER_YEAR as u64;
+        assert!(idle.total_borrow_assets > linear);
+    }
+
+    #[test]
+    fn test_paused_market_skips_interest_when_configured() {
+        let start = 1_700_000_000;
+        let market = Market {
+            total_supply_assets: 2_000_000_000_000,
+            total_borrow_assets: 1_000_000_000_000,
+            paused: true,
+            last_update: start,
+            ..Default::default()
+        };
+
+        let mut frozen = market.clone();
+        accrue_interest_at(&mut frozen, start + 30 * ACCRUAL_CHECKPOINT_SECS).unwrap();
+        assert_eq!(frozen.total_borrow_assets, 1_000_000_000_000);
+        assert_eq!(frozen.total_supply_assets, 2_000_000_000_000);
+        assert_eq!(frozen.last_update, start + 30 * ACCRUAL_CHECKPOINT_SECS);
+
+        let mut accruing = Market {
+            accrue_while_paused: true,
+            ..market
+        };
+        accrue_interest_at(&mut accruing, start + 30 * ACCRUAL_CHECKPOINT_SECS).unwrap();
+        assert!(accruing.total_borrow_assets > 1_000_000_000_000);
+    }
+
+    #[test]
+    fn test_taylor_compounding_exceeds_linear_but_bounded() {
+        let rate_per_second = FIXED_ANNUAL_RATE_WAD / SECONDS_PER_YEAR;
+
+        for elapsed in [86_400, 30 * 86_400, SECONDS_PER_YEAR] {
+            let linear = rate_per_second * elapsed;
+            let compounded = w_taylor_compounded(rate_per_second, elapsed).unwrap();
+            assert!(compounded > linear, "elapsed={}", elapsed);
+
+            // Never above e^x - 1, and within its x⁴ / 24 × e^x truncation
+            // error (up to f64 precision)
+            let x = linear as f64 / WAD as f64;
+            let exact = x.exp_m1() * WAD as f64;
+            let precision = exact * 1e-12;
+            assert!(compounded as f64 <= exact + precision, "elapsed={}", elapsed);
+            let truncation = x.powi(4) / 24.0 * x.exp() * WAD as f64;
+            assert!(exact - compounded as f64 <= truncation + precision);
+        }
+
+        // One year at ~5%: e^0.05 - 1 ≈ 5.127%, against 5% linear
+        let year = w_taylor_compounded(rate_per_second, SECONDS_PER_YEAR).unwrap();
+        assert!(year.abs_diff(51_271_000_000_000_000) < WAD / 1_000_000);
+
+        // No time, no growth; overflow is reported, not wrapped
+        assert_eq!(w_taylor_compounded(rate_per_second, 0).unwrap(), 0);
+        assert_eq!(
+            w_taylor_compounded(u128::MAX, 2).unwrap_err(),
+            PelagoError::MathOverflow.into()
+        );
+        assert_eq!(
+            w_taylor_compounded(WAD, 1u128 << 64).unwrap_err(),
+            PelagoError::MathOverflow.into()
+        );
+    }
+
+    #[test]
+    fn test_kinked_rate_at_utilization_boundaries() {
+        // 2% base, +8% up to an 80% kink, +100% above
+        let at_utilization = |borrowed: u64| Market {
+            total_supply_assets: 1_000_000_000,
+            total_borrow_assets: borrowed,
+            irm_base_rate_wad: 20_000_000_000_000_000,
+            irm_slope1_wad: 80_000_000_000_000_000,
+            irm_slope2_wad: 1_000_000_000_000_000_000,
+            irm_kink_utilization_bps: 8_000,
+            ..Default::default()
+        };
+
+        // 0%: the base rate alone
+        assert_eq!(compute_borrow_rate(&at_utilization(0)), 20_000_000_000_000_000);
+        // At the kink: base + slope1
+        assert_eq!(compute_borrow_rate(&at_utilization(800_000_000)), 100_000_000_000_000_000);
+        // 100%: base + slope1 + slope2
+        assert_eq!(
+            compute_borrow_rate(&at_utilization(1_000_000_000)),
+            1_100_000_000_000_000_000
+        );
+        // Halfway to the kink: base + slope1 / 2
+        assert_eq!(compute_borrow_rate(&at_utilization(400_000_000)), 60_000_000_000_000_000);
+
+        // An empty market sits at 0% utilization; no kink keeps the fixed rate
+        let empty = Market { total_supply_assets: 0, ..at_utilization(0) };
+        assert_eq!(compute_borrow_rate(&empty), 20_000_000_000_000_000);
+        let fixed = Market { irm_kink_utilization_bps: 0, ..at_utilization(800_000_000) };
+        assert_eq!(compute_borrow_rate(&fixed), FIXED_ANNUAL_RATE_WAD);
+    }
+
+    #[test]
+    fn test_accrue_stores_current_rates() {
+        let start = 1_700_000_000;
+        let mut market = Market {
+            total_supply_assets: 2_000_000_000_000,
+            total_borrow_assets: 1_000_000_000_000, // 50% utilization
+            fee_bps: 1_000,
+            last_update: start,
+            ..Default::default()
+        };
+
+        accrue_interest_at(&mut market, start + 3_600).unwrap();
+
+        assert_eq!(market.current_borrow_rate_wad, compute_borrow_rate(&market));
+        assert_eq!(market.current_borrow_rate_wad, FIXED_ANNUAL_RATE_WAD);
+        assert_eq!(market.current_supply_rate_wad, supply_rate_wad(&market).unwrap());
+
+        // ~5% × 50% × 90% = ~2.25%, slightly above since utilization grew
+        let expected = FIXED_ANNUAL_RATE_WAD / 2 * 9 / 10;
+        assert!(market.current_supply_rate_wad >= expected);
+        assert!(market.current_supply_rate_wad < expected + expected / 10_000);
+    }
+
+    #[test]
+    fn test_accrual_breaches_borrow_ratio() {
+        // 900 of 1000 USDC borrowed against a 90% ceiling: at the limit
+        let start = 1_700_000_000;
+        let mut market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_borrow_assets: 900_000_000,
+            max_borrow_ratio_bps: 9_000,
+            last_update: start,
+            ..Default::default()
+        };
+        assert_eq!(max_borrow_for_ratio(&market).unwrap(), Some(900_000_000));
+
+        // A year of interest adds the same amount to both sides, pushing the
+        // ratio past 90%; the interest is still charged
+        accrue_interest_at(&mut market, start + SECONDS_PER_YEAR as i64).unwrap();
+        let max_borrow = max_borrow_for_ratio(&market).unwrap().unwrap();
+        assert!(market.total_borrow_assets > 945_000_000);
+        assert!(market.total_borrow_assets > max_borrow);
+
+        // Disabled ceiling
+        market.max_borrow_ratio_bps = 0;
+        assert_eq!(max_borrow_for_ratio(&market).unwrap(), None);
+    }
+
+    #[test]
+    fn test_compounding_period_steps_interest() {
+        let start = 1_700_000_000;
+        let market = Market {
+            total_supply_assets: 2_000_000_000_000,
+            total_borrow_assets: 1_000_000_000_000,
+            compound_period_secs: 86_400,
+            last_update: start,
+            ..Default::default()
+        };
+
+        // Five hours into the first day: nothing accrues, the clock stays put
+        let mut partial = market.clone();
+        accrue_interest_at(&mut partial, start + 5 * 3_600).unwrap();
+        assert_eq!(partial.total_borrow_assets, market.total_borrow_assets);
+        assert_eq!(partial.last_update, start);
+
+        // Three days and five hours: three daily compounding steps, the
+        // five hours stay pending
+        let mut stepped = market.clone();
+        accrue_interest_at(&mut stepped, start + 3 * 86_400 + 5 * 3_600).unwrap();
+        assert_eq!(stepped.last_update, start + 3 * 86_400);
+
+        let growth_per_day =
+            w_taylor_compounded(FIXED_ANNUAL_RATE_WAD / SECONDS_PER_YEAR, 86_400).unwrap();
+        let mut expected = market.total_borrow_assets as u128;
+        for _ in 0..3 {
+            expected += expected * growth_per_day / WAD;
+        }
+        assert_eq!(stepped.total_borrow_assets as u128, expected);
+
+        // The pending hours complete the fourth day on the next accrual
+        accrue_interest_at(&mut stepped, start + 4 * 86_400).unwrap();
+        assert_eq!(stepped.last_update, start + 4 * 86_400);
+        expected += expected * growth_per_day / WAD;
+        assert_eq!(stepped.total_borrow_assets as u128, expected);
+
+        // Many periods are grouped into at most MAX_ACCRUAL_CHECKPOINTS steps
+        let hourly = Market { compound_period_secs: 3_600, ..market };
+        assert_eq!(compounding_window(&hourly, 10 * 3_600 + 59), (10 * 3_600, 3_600));
+        assert_eq!(compounding_window(&hourly, 730 * 3_600), (730 * 3_600, 2 * 3_600));
+    }
+
+    #[test]
+    fn test_insane_accrual_is_capped() {
+        // 50 idle years at 5% stand in for an insane rate (the rate itself is
+        // a compile-time constant): uncapped, debt would grow more than tenfold
+        let start = 1_700_000_000;
+        let elapsed = 50 * SECONDS_PER_YEAR as i64;
+        let market = Market {
+            total_supply_assets: 2_000_000_000_000,
+            total_borrow_assets: 1_000_000_000_000,
+            fee_bps: 1_000,
+            last_update: start,
+            ..Default::default()
+        };
+
+        let mut uncapped = market.clone();
+        accrue_interest_at(&mut uncapped, start + elapsed).unwrap();
+        assert!(uncapped.total_borrow_assets > 10_000_000_000_000);
+
+        let mut capped = Market {
+            max_accrual_interest_bps: 1_000,
+            ..market
+        };
+        accrue_interest_at(&mut capped, start + elapsed).unwrap();
+        assert_eq!(capped.total_borrow_assets, 1_100_000_000_000);
+        assert_eq!(capped.total_supply_assets, 2_100_000_000_000);
+        assert_eq!(capped.last_update, start + elapsed);
+
+        // Normal accrual stays under the cap and is untouched
+        let mut day = Market {
+            max_accrual_interest_bps: 1_000,
+            ..market.clone()
+        };
+        let mut reference = market;
+        accrue_interest_at(&mut day, start + 86_400).unwrap();
+        accrue_interest_at(&mut reference, start + 86_400).unwrap();
+        assert_eq!(day.total_borrow_assets, reference.total_borrow_assets);
+    }
+
+    #[test]
+    fn test_fee_kink_only_charges_above_kink() {
+        let start = 1_700_000_000;
+        let with_utilization = |borrow: u64| Market {
+            total_supply_assets: 1_000_000_000_000,
+            total_borrow_assets: borrow,
+            fee_bps: 2_000,
+            fee_kink_utilization_bps: 8_000, // 80%
+            last_update: start,
+            ..Default::default()
+        };
+
+        // 50% utilization: below the kink, no fee
+        let mut low = with_utilization(500_000_000_000);
+        assert_eq!(effective_fee_bps(&low), 0);
+        accrue_interest_at(&mut low, start + 86_400).unwrap();
+        assert_eq!(low.fee_shares, 0);
+        let gross = |market: &Market| {
+            compute_borrow_rate(market) * market.total_borrow_assets as u128
+                / market.total_supply_assets as u128
+        };
+        assert_eq!(low.current_supply_rate_wad, gross(&low), "suppliers keep it all");
+
+        // 90% utilization: halfway from kink to 100%, so half the fee
+        let mut high = with_utilization(900_000_000_000);
+        assert_eq!(effective_fee_bps(&high), 1_000);
+        accrue_interest_at(&mut high, start + 86_400).unwrap();
+        assert!(high.fee_shares > 0);
+        let fee = effective_fee_bps(&high) as u128;
+        assert!(fee < 2_000);
+        assert_eq!(
+            high.current_supply_rate_wad,
+            gross(&high) * (10_000 - fee) / 10_000
+        );
+
+        // No kink: the full fee regardless of utilization
+        let no_kink = Market {
+            fee_kink_utilization_bps: 0,
+            ..with_utilization(500_000_000_000)
+        };
+        assert_eq!(effective_fee_bps(&no_kink), 2_000);
+    }
+
+    #[test]
+    fn test_projection_matches_accrual() {
+        // Capped, breaching the ratio ceiling and charging a fee: every
+        // branch that reports an event
+        let start = 1_700_000_000;
+        let market = Market {
+            total_supply_assets: 1_000_000_000_000,
+            total_borrow_assets: 900_000_000_000,
+            fee_bps: 1_000,
+            max_borrow_ratio_bps: 9_000,
+            max_accrual_interest_bps: 1_000,
+            last_update: start,
+            ..Default::default()
+        };
+        let now = start + 50 * SECONDS_PER_YEAR as i64;
+
+        let mut projected = market.clone();
+        let accrual = project_interest_at(&mut projected, now).unwrap();
+        assert!(accrual.capped && accrual.ratio_breached);
+        assert!(!accrual.skipped_while_paused);
+        assert_eq!(accrual.accrued_secs, now - start);
+        assert_eq!(accrual.interest.gross, 90_000_000_000);
+
+        let mut accrued = market;
+        accrue_interest_at(&mut accrued, now).unwrap();
+        assert_eq!(projected.try_to_vec().unwrap(), accrued.try_to_vec().unwrap());
+
+        // Nothing elapsed: nothing to report
+        let again = project_interest_at(&mut projected, now).unwrap();
+        assert_eq!(again.elapsed, 0);
+        assert_eq!(again.interest.gross, 0);
     }
 
     #[test]
@@ -0,0 +1,463 @@
+//! Market Invariant Checks
+//!
+//! Structural invariants that must hold after every state mutation. The
+//! checks are a few comparisons, so they run in release builds too: an
+//! accounting bug introduced by a future change (fees, liquidation, bad
+//! debt) fails the transaction that causes it instead of surfacing later as
+//! stuck withdrawals.
+
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_DUST_SWEEP_ASSETS;
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Validates the market's accounting invariants
+///
+/// **Invariants:**
+/// - `total_borrow_assets ≤ total_supply_assets`: outstanding debt can't
+///   exceed supplied value (borrow shares are never worth more than supply
+///   shares in aggregate)
+/// - `fee_shares ≤ total_supply_shares`: protocol fee shares are a subset
+///   of all supply shares
+///
+/// **Errors:**
+/// - InvariantViolation: any invariant is broken
+pub fn check_market_invariants(market: &Market) -> Result<()> {
+    if market.total_borrow_assets > market.total_supply_assets
+        || market.fee_shares > market.total_supply_shares
+    {
+        msg!(
+            "Invariant violation: borrow_assets={}, supply_assets={}, fee_shares={}, supply_shares={}",
+            market.total_borrow_assets,
+            market.total_supply_assets,
+            market.fee_shares,
+            market.total_supply_shares
+        );
+        return err!(PelagoError::InvariantViolation);
+    }
+
+    Ok(())
+}
+
+/// Validates the invariants after a handler that can only reduce debt
+///
+/// On a market that accrual flagged `impaired`, `total_borrow_assets ≤
+/// total_supply_assets` is already broken; enforcing it would revert the
+/// repays, liquidations and supplies that restore the market. There, the
+/// handler only has to leave the debt no larger than `borrow_assets_before`
+/// (and fee shares within supply shares). Healthy markets get the full
+/// `check_market_invariants`.
+///
+/// **Errors:**
+/// - InvariantViolation: any invariant is broken, or an impaired market's
+///   debt grew
+pub fn check_recovery_invariants(market: &Market, borrow_assets_before: u64) -> Result<()> {
+    if !market.impaired {
+        return check_market_invariants(market);
+    }
+
+    if market.total_borrow_assets > borrow_assets_before
+        || market.fee_shares > market.total_supply_shares
+    {
+        msg!(
+            "Invariant violation on impaired market: borrow_assets={} (before {}), fee_shares={}, supply_shares={}",
+            market.total_borrow_assets,
+            borrow_assets_before,
+            market.fee_shares,
+            market.total_supply_shares
+        );
+        return err!(PelagoError::InvariantViolation);
+    }
+
+    Ok(())
+}
+
+/// Moves supply assets left without any supply shares into reserves
+///
+/// Once the last supply share is burned, whatever `total_supply_assets`
+/// remains (rounding residue of partial withdrawals) has no owner and would
+/// be handed to the next depositor through the share price. It is swept to
+/// `market.reserves`, which keeps the vault identity
+/// `vault = total_supply_assets + reserves - total_borrow_assets` intact.
+///
+/// Nothing is swept while debt is outstanding: that residue still backs the
+/// loans and `check_empty_supply` rejects the state instead.
+///
+/// **Returns:** The swept amount (0 if nothing was swept)
+pub fn sweep_orphaned_supply(market: &mut Market) -> Result<u64> {
+    if market.total_supply_shares > 0
+        || market.total_supply_assets == 0
+        || market.total_borrow_assets > 0
+    {
+        return Ok(0);
+    }
+
+    let swept = market.total_supply_assets;
+    market.reserves = market
+        .reserves
+        .checked_add(swept)
+        .ok_or(PelagoError::MathOverflow)?;
+    market.total_supply_assets = 0;
+    Ok(swept)
+}
+
+/// Validates that a market without supply shares holds no supply assets
+///
+/// `total_supply_shares == 0` ⇒ `total_supply_assets == 0`. Reserves are
+/// tracked outside `total_supply_assets`, so they are unaffected.
+///
+/// **Errors:**
+/// - InvariantViolation: supply assets without any supply shares
+pub fn check_empty_supply(market: &Market) -> Result<()> {
+    if market.total_supply_shares == 0 && market.total_supply_assets > 0 {
+        msg!(
+            "Invariant violation: supply_assets={} with no supply shares",
+            market.total_supply_assets
+        );
+        return err!(PelagoError::InvariantViolation);
+    }
+
+    Ok(())
+}
+
+/// Clears the rounding residue of a market with no shares left
+///
+/// With every supply and borrow share burned, leftover `total_supply_assets`
+/// and `total_borrow_assets` belong to nobody. Supply residue is credited
+/// to `market.reserves` and borrow residue (debt nobody owes) is written
+/// off against them, which keeps the vault identity
+/// `vault = total_supply_assets + reserves - total_borrow_assets` intact.
+///
+/// **Returns:** `(swept_supply_assets, swept_borrow_assets)`
+///
+/// **Errors:**
+/// - InvalidParameter: shares remain, or a residue exceeds MAX_DUST_SWEEP_ASSETS
+/// - MathOverflow: Calculation overflow
+pub fn sweep_dust(market: &mut Market) -> Result<(u64, u64)> {
+    require!(
+        market.total_supply_shares == 0 && market.total_borrow_shares == 0,
+        PelagoError::InvalidParameter
+    );
+    require!(
+        market.total_supply_assets <= MAX_DUST_SWEEP_ASSETS
+            && market.total_borrow_assets <= MAX_DUST_SWEEP_ASSETS,
+        PelagoError::InvalidParameter
+    );
+
+    let swept_supply = market.total_supply_assets;
+    let swept_borrow = market.total_borrow_assets;
+    market.reserves = market
+        .reserves
+        .checked_add(swept_supply)
+        .ok_or(PelagoError::MathOverflow)?
+        .checked_sub(swept_borrow)
+        .ok_or(PelagoError::MathOverflow)?;
+    market.total_supply_assets = 0;
+    market.total_borrow_assets = 0;
+    Ok((swept_supply, swept_borrow))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::interest::accrue_interest_at;
+    use crate::utils::test_rng::next;
+
+    fn fully_utilized() -> Market {
+        Market {
+            total_supply_assets: 1_000_000_000,
+            total_supply_shares: 1_000_000_000 * 1_000_000,
+            total_borrow_assets: 1_000_000_000,
+            total_borrow_shares: 1_000_000_000 * 1_000_000,
+            fee_bps: 2_500,
+            last_update: 1_700_000_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fully_utilized_market_holds() {
+        let mut market = fully_utilized();
+        assert!(check_market_invariants(&market).is_ok());
+
+        // Interest (with fee) keeps borrow == supply at 100% utilization
+        accrue_interest_at(&mut market, 1_700_000_000 + 365 * 86_400).unwrap();
+        assert_eq!(market.total_borrow_assets, market.total_supply_assets);
+        assert!(check_market_invariants(&market).is_ok());
+    }
+
+    #[test]
+    fn test_one_unit_over_is_rejected() {
+        let mut market = fully_utilized();
+        market.total_borrow_assets += 1;
+        assert_eq!(
+            check_market_invariants(&market).unwrap_err(),
+            PelagoError::InvariantViolation.into()
+        );
+
+        let mut market = fully_utilized();
+        market.fee_shares = market.total_supply_shares + 1;
+        assert_eq!(
+            check_market_invariants(&market).unwrap_err(),
+            PelagoError::InvariantViolation.into()
+        );
+    }
+
+    #[test]
+    fn test_orphaned_supply_swept_to_reserves() {
+        let mut market = Market {
+            total_supply_assets: 3,
+            reserves: 10,
+            ..Default::default()
+        };
+        assert_eq!(
+            check_empty_supply(&market).unwrap_err(),
+            PelagoError::InvariantViolation.into()
+        );
+
+        assert_eq!(sweep_orphaned_supply(&mut market).unwrap(), 3);
+        assert_eq!(market.total_supply_assets, 0);
+        assert_eq!(market.reserves, 13);
+        assert!(check_empty_supply(&market).is_ok());
+
+        // Residue backing outstanding debt is not swept
+        let mut market = Market {
+            total_supply_assets: 3,
+            total_borrow_assets: 2,
+            ..Default::default()
+        };
+        assert_eq!(sweep_orphaned_supply(&mut market).unwrap(), 0);
+        assert!(check_empty_supply(&market).is_err());
+    }
+
+    #[test]
+    fn test_rounding_dust_swept() {
+        use crate::utils::shares_math::{to_assets_down, to_assets_up, to_shares_down};
+
+        // One supplier and one borrower against an awkward share price
+        let mut market = Market {
+            total_supply_assets: 1_000_000_007,
+            total_supply_shares: 999_999_999_999_999,
+            total_borrow_assets: 500_000_003,
+            total_borrow_shares: 499_999_999_999_999,
+            reserves: 10,
+            ..Default::default()
+        };
+
+        // Borrower repays a round amount, then the rest of the shares
+        let (assets, shares) = (market.total_borrow_assets, market.total_borrow_shares);
+        let repaid_shares = to_shares_down(250_000_000, assets, shares).unwrap();
+        market.total_borrow_assets -= 250_000_000;
+        market.total_borrow_shares -= repaid_shares;
+        let (assets, shares) = (market.total_borrow_assets, market.total_borrow_shares);
+        let repaid = to_assets_up(shares, assets, shares).unwrap();
+        market.total_borrow_assets = assets.saturating_sub(repaid);
+        market.total_borrow_shares = 0;
+
+        // Supplier withdraws every share, rounded down
+        let (assets, shares) = (market.total_supply_assets, market.total_supply_shares);
+        market.total_supply_assets -= to_assets_down(shares, assets, shares).unwrap();
+        market.total_supply_shares = 0;
+
+        // Rounding leaves dust on the supply side that blocks teardown
+        assert!(market.total_supply_assets > 0);
+        assert!(check_empty_supply(&market).is_err());
+        let identity = market.total_supply_assets + market.reserves - market.total_borrow_assets;
+
+        let (swept_supply, swept_borrow) = sweep_dust(&mut market).unwrap();
+        assert!(swept_supply > 0 && swept_supply <= MAX_DUST_SWEEP_ASSETS);
+        assert_eq!(market.total_supply_assets, 0);
+        assert_eq!(market.total_borrow_assets, 0);
+        assert_eq!(market.reserves, 10 + swept_supply - swept_borrow);
+        assert_eq!(market.reserves, identity);
+        assert!(check_empty_supply(&market).is_ok());
+
+        // Unused shares or a residue above the threshold are refused
+        let mut market = Market {
+            total_supply_assets: 1,
+            total_supply_shares: 1,
+            ..Default::default()
+        };
+        assert!(sweep_dust(&mut market).is_err());
+        let mut market = Market {
+            total_supply_assets: MAX_DUST_SWEEP_ASSETS + 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            sweep_dust(&mut market).unwrap_err(),
+            PelagoError::InvalidParameter.into()
+        );
+    }
+
+    /// Model of a market, its positions and token vaults
+    ///
+    /// Each operation replays a handler's bookkeeping with the same helpers
+    /// the handler calls (share math, borrow and repay recording, liquidity
+    /// checks), but not the handler itself: account constraints and token
+    /// CPIs are out of scope here and covered by the TS integration suites.
+    /// A drift between a handler and its model step is not caught.
+    #[derive(Clone)]
+    struct Model {
+        market: Market,
+        positions: Vec<crate::state::UserPosition>,
+        loan_vault: u64,
+        collateral_vault: u64,
+        now: i64,
+    }
+
+    impl Model {
+        /// Runs one randomized operation; a rejected operation leaves no trace
+        fn step(&mut self, seed: &mut u64) {
+            let before = self.clone();
+            if self.apply(seed).is_err() {
+                *self = before;
+            }
+        }
+
+        fn apply(&mut self, seed: &mut u64) -> Result<()> {
+            use crate::instructions::borrow::{check_available_liquidity, record_borrow};
+            use crate::instructions::repay::split_repay;
+            use crate::instructions::supply::supply_shares_for_assets;
+            use crate::instructions::withdraw::check_withdraw_liquidity;
+            use crate::instructions::withdraw_collateral::withdraw_collateral_at;
+            use crate::utils::liquidation::position_health;
+            use crate::utils::shares_math::{to_assets_down, to_shares_up};
+
+            let user = (next(seed) % self.positions.len() as u64) as usize;
+            let market = &mut self.market;
+            let position = &mut self.positions[user];
+            accrue_interest_at(market, self.now)?;
+
+            match next(seed) % 7 {
+                // supply
+                0 => {
+                    let assets = 1 + next(seed) % 1_000_000_000;
+                    let shares = supply_shares_for_assets(market, assets)?;
+                    require!(shares > 0, PelagoError::ZeroAmount);
+                    position.supply_shares += shares;
+                    market.total_supply_assets += assets;
+                    market.total_supply_shares += shares;
+                    self.loan_vault += assets;
+                }
+                // withdraw by shares
+                1 => {
+                    require!(position.supply_shares > 0, PelagoError::InsufficientSupply);
+                    let shares = 1 + next(seed) % position.supply_shares;
+                    let assets =
+                        to_assets_down(shares, market.total_supply_assets, market.total_supply_shares)?;
+                    position.supply_shares -= shares;
+                    market.total_supply_shares -= shares;
+                    market.total_supply_assets -= assets;
+                    check_withdraw_liquidity(market)?;
+                    sweep_orphaned_supply(market)?;
+                    check_empty_supply(market)?;
+                    self.loan_vault -= assets;
+                }
+                // supply_collateral
+                2 => {
+                    let assets = 1 + next(seed) % 10_000_000_000;
+                    position.collateral_amount += assets;
+                    market.total_collateral += assets;
+                    self.collateral_vault += assets;
+                }
+                // withdraw_collateral
+                3 => {
+                    require!(position.collateral_amount > 0, PelagoError::InsufficientCollateral);
+                    let assets = 1 + next(seed) % position.collateral_amount;
+                    withdraw_collateral_at(market, position, assets, self.now)?;
+                    self.collateral_vault -= assets;
+                }
+                // borrow
+                4 => {
+                    let assets = 1 + next(seed) % 500_000_000;
+                    let shares =
+                        to_shares_up(assets, market.total_borrow_assets, market.total_borrow_shares)?;
+                    check_available_liquidity(market, assets)?;
+                    record_borrow(market, position, assets, shares)?;
+                    require!(
+                        position_health(market, position)?.is_healthy(),
+                        PelagoError::InsufficientCollateral
+                    );
+                    require!(
+                        market.total_borrow_assets <= market.total_supply_assets,
+                        PelagoError::InsufficientLiquidity
+                    );
+                    self.loan_vault -= assets;
+                }
+                // repay (sometimes the full debt)
+                5 => {
+                    require!(position.borrow_shares > 0, PelagoError::ZeroAmount);
+                    let assets = 1 + next(seed) % 600_000_000;
+                    let split = split_repay(market, position.borrow_shares, assets)?;
+                    position.borrow_shares -= split.repaid_shares;
+                    market.total_borrow_shares =
+                        market.total_borrow_shares.saturating_sub(split.repaid_shares);
+                    market.total_borrow_assets =
+                        market.total_borrow_assets.saturating_sub(split.repaid_assets);
+                    self.loan_vault += split.repaid_assets;
+                }
+                // time passes
+                _ => {
+                    self.now += (next(seed) % (7 * 86_400)) as i64;
+                    accrue_interest_at(market, self.now)?;
+                }
+            }
+
+            check_market_invariants(market)
+        }
+
+        fn check(&self) {
+            let market = &self.market;
+            assert!(check_market_invariants(market).is_ok());
+            assert!(check_empty_supply(market).is_ok());
+
+            // Vaults reconcile with the accounting; rounding only ever
+            // leaves the protocol holding slightly more than it owes
+            let expected = market.total_supply_assets + market.reserves - market.total_borrow_assets;
+            assert!(self.loan_vault >= expected);
+            assert!(
+                crate::instructions::supply::check_vault_accounting(self.loan_vault, market).is_ok()
+            );
+            assert_eq!(self.collateral_vault, market.total_collateral);
+
+            // No position holds more than the market total, and together
+            // they account for every non-fee share
+            let supply_shares: u64 = self.positions.iter().map(|p| p.supply_shares).sum();
+            let borrow_shares: u64 = self.positions.iter().map(|p| p.borrow_shares).sum();
+            let collateral: u64 = self.positions.iter().map(|p| p.collateral_amount).sum();
+            for position in &self.positions {
+                assert!(position.supply_shares <= market.total_supply_shares);
+                assert!(position.borrow_shares <= market.total_borrow_shares);
+            }
+            assert_eq!(supply_shares + market.fee_shares, market.total_supply_shares);
+            assert_eq!(borrow_shares, market.total_borrow_shares);
+            assert_eq!(collateral, market.total_collateral);
+        }
+    }
+
+    #[test]
+    fn test_model_of_randomized_lending_cycles_holds_invariants() {
+        for round in 0..40u64 {
+            let mut seed = round + 1;
+            let mut model = Model {
+                market: Market {
+                    lltv: 80_000_000,
+                    fee_bps: (round % 3 * 1_000) as u16,
+                    origination_fee_bps: (round % 2 * 50) as u16,
+                    last_update: 1_700_000_000,
+                    ..Default::default()
+                },
+                positions: vec![Default::default(); 4],
+                loan_vault: 0,
+                collateral_vault: 0,
+                now: 1_700_000_000,
+            };
+
+            for _ in 0..500 {
+                model.step(&mut seed);
+                model.check();
+            }
+        }
+    }
+}
@@ -0,0 +1,1016 @@
+//! Liquidation Math
+//!
+//! Pure health and seize calculations shared by the liquidation
+//! instructions. A position is liquidatable once its debt exceeds the
+//! borrowing power of its collateral at the market's collateral price.
+//!
+//! **Model:**
+//! - Close factor: at most `CLOSE_FACTOR_BPS` of the borrow shares are
+//!   repaid per liquidation, unless that share is below the market's
+//!   `min_liquidation_assets`, in which case the whole debt is repaid
+//! - Bonus: the liquidator receives collateral worth
+//!   `repaid × (1 + LIQUIDATION_BONUS_BPS)`
+//! - If the position holds less collateral than that, all of it is seized
+//!   and the repaid amount is scaled down to match
+//!
+//! **Rounding:** Seized collateral rounds DOWN and the repaid amount rounds
+//! UP, so neither the borrower nor the protocol is shortchanged.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+
+use crate::constants::{
+    BPS_DENOMINATOR, CLOSE_FACTOR_BPS, LIQUIDATION_BONUS_BPS, LLTV_PRECISION, PRICE_PRECISION,
+};
+use crate::error::PelagoError;
+use crate::state::{Market, UserPosition};
+use crate::utils::shares_math::{to_assets_up, to_shares_down, to_shares_up};
+use crate::utils::oracle::collateral_price;
+
+/// Amounts moved by a single liquidation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Liquidation {
+    /// Borrow shares burned from the position
+    pub repaid_shares: u64,
+
+    /// Loan tokens paid by the liquidator
+    pub repaid_assets: u64,
+
+    /// Collateral transferred to the liquidator
+    pub seized_collateral: u64,
+}
+
+/// Health of a position at the market's current collateral price
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionHealth {
+    /// Collateral value in loan token base units
+    pub collateral_value: u64,
+
+    /// Debt in loan token base units (`to_assets_up(borrow_shares)`)
+    pub debt_value: u64,
+
+    /// Borrowing power: collateral_value × lltv / LLTV_PRECISION
+    pub max_borrow: u128,
+
+    /// collateral_value × lltv / debt_value, scaled by LLTV_PRECISION (1e8)
+    /// Below 1e8 the position is liquidatable; u64::MAX without debt
+    pub health_factor: u64,
+}
+
+impl PositionHealth {
+    /// True if the debt is within the borrowing power
+    pub fn is_healthy(&self) -> bool {
+        self.debt_value as u128 <= self.max_borrow
+    }
+}
+
+/// Computes a position's collateral value, debt and health factor
+///
+/// **Formula:**
+/// ```text
+/// debt = to_assets_up(borrow_shares)
+/// collateral_value = collateral_amount × collateral_price / PRICE_PRECISION
+/// max_borrow = collateral_value × lltv / LLTV_PRECISION
+/// health_factor = collateral_value × lltv / debt
+/// ```
+///
+/// Debt-free positions are priced without the oracle sanity band, so a bad
+/// feed never blocks them; their collateral value is informational only.
+pub fn position_health(market: &Market, position: &UserPosition) -> Result<PositionHealth> {
+    let (debt, price) = if position.borrow_shares == 0 {
+        (0, market.collateral_price())
+    } else {
+        let debt = to_assets_up(
+            position.borrow_shares,
+            market.total_borrow_assets,
+            market.total_borrow_shares,
+        )?;
+        (debt, collateral_price(market)?)
+    };
+
+    // u64 × u64 always fits in u128
+    let collateral_value =
+        position.collateral_amount as u128 * price as u128 / PRICE_PRECISION as u128;
+    let weighted = collateral_value * market.lltv as u128;
+    let health_factor = if debt == 0 {
+        u64::MAX
+    } else {
+        u64::try_from(weighted / debt as u128).unwrap_or(u64::MAX)
+    };
+
+    Ok(PositionHealth {
+        collateral_value: u64::try_from(collateral_value).unwrap_or(u64::MAX),
+        debt_value: debt,
+        max_borrow: weighted / LLTV_PRECISION as u128,
+        health_factor,
+    })
+}
+
+/// Collateral value the position lacks to be within its borrowing power
+///
+/// Adding this much collateral value (in loan token base units) makes the
+/// position exactly healthy under `PositionHealth::is_healthy`:
+/// ```text
+/// shortfall = ceil(debt × LLTV_PRECISION / lltv) - collateral_value
+/// ```
+///
+/// **Returns:** 0 for healthy positions, u64::MAX with a zero LLTV
+pub fn collateral_shortfall(market: &Market, health: &PositionHealth) -> u64 {
+    if health.is_healthy() {
+        return 0;
+    }
+    if market.lltv == 0 {
+        return u64::MAX;
+    }
+    let required =
+        (health.debt_value as u128 * LLTV_PRECISION as u128).div_ceil(market.lltv as u128);
+    u64::try_from(required.saturating_sub(health.collateral_value as u128)).unwrap_or(u64::MAX)
+}
+
+/// Rejects an unhealthy position, reporting how much collateral it lacks
+///
+/// Before failing, the shortfall (see `collateral_shortfall`) is written via
+/// `set_return_data` as a little-endian u64. The failed transaction discards
+/// it, but a simulation returns it, so clients can show how much collateral
+/// to add (or, at the LLTV, how much less to borrow).
+///
+/// **Errors:**
+/// - InsufficientCollateral: The debt exceeds the borrowing power
+pub fn require_healthy(market: &Market, health: &PositionHealth) -> Result<()> {
+    if health.is_healthy() {
+        return Ok(());
+    }
+
+    let shortfall = collateral_shortfall(market, health);
+    msg!("Collateral shortfall: {}", shortfall);
+    set_return_data(&shortfall.to_le_bytes());
+    err!(PelagoError::InsufficientCollateral)
+}
+
+/// Returns true if the position's debt exceeds its borrowing power
+///
+/// **Formula:**
+/// ```text
+/// liquidatable = debt > max_borrow   (see `position_health`)
+/// ```
+pub fn is_liquidatable(market: &Market, position: &UserPosition) -> Result<bool> {
+    if position.borrow_shares == 0 {
+        return Ok(false);
+    }
+
+    Ok(!position_health(market, position)?.is_healthy())
+}
+
+/// Lowest collateral price at which the position is still healthy
+///
+/// The position becomes liquidatable as soon as the collateral price
+/// (`market.collateral_price()`, PRICE_PRECISION scale) drops below it.
+/// Solves `position_health` for the price, following its rounding exactly:
+/// ```text
+/// min_value = ceil(debt × LLTV_PRECISION / lltv)
+/// price = ceil(min_value × PRICE_PRECISION / collateral_amount)
+/// ```
+///
+/// **Returns:** 0 for debt-free positions (never liquidatable), u64::MAX
+/// if no price can make the position healthy (no collateral or zero LLTV)
+pub fn liquidation_price(market: &Market, position: &UserPosition) -> Result<u64> {
+    if position.borrow_shares == 0 {
+        return Ok(0);
+    }
+    if position.collateral_amount == 0 || market.lltv == 0 {
+        return Ok(u64::MAX);
+    }
+
+    let debt = to_assets_up(
+        position.borrow_shares,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+    let min_value = (debt as u128 * LLTV_PRECISION as u128).div_ceil(market.lltv as u128);
+    let price = min_value
+        .checked_mul(PRICE_PRECISION as u128)
+        .ok_or(PelagoError::MathOverflow)?
+        .div_ceil(position.collateral_amount as u128);
+    Ok(u64::try_from(price).unwrap_or(u64::MAX))
+}
+
+/// Health snapshot emitted after borrow, withdraw_collateral and liquidation
+///
+/// Lets indexers track liquidation risk from the event stream alone.
+#[event]
+pub struct PositionHealthEvent {
+    /// Market public key
+    pub market: Pubkey,
+
+    /// Position owner
+    pub user: Pubkey,
+
+    /// Health factor scaled by 1e8 (< 1e8 = liquidatable, u64::MAX = no debt)
+    pub health_factor: u64,
+
+    /// Collateral value in loan token base units
+    pub collateral_value: u64,
+
+    /// Debt in loan token base units
+    pub debt_value: u64,
+}
+
+/// Computes the liquidation of an unhealthy position
+///
+/// **Returns:** `None` if the position is healthy or too small to liquidate
+pub fn compute_liquidation(market: &Market, position: &UserPosition) -> Result<Option<Liquidation>> {
+    if !is_liquidatable(market, position)? {
+        return Ok(None);
+    }
+
+    liquidation_for_shares(market, position, max_repaid_shares(market, position)?)
+}
+
+/// Computes the liquidation of an unhealthy position repaying at most
+/// `repay_assets`
+///
+/// The repay is converted to shares rounding down, then clamped to what
+/// `compute_liquidation` would repay (`repay_assets = u64::MAX` repays that
+/// much). A repay that would leave dust debt behind (see `is_dust_debt`)
+/// closes the whole debt instead. The bonus and collateral cap apply as usual.
+///
+/// **Returns:** `None` if the position is healthy or the repay is too
+/// small to burn a share or seize collateral
+pub fn compute_liquidation_for_repay(
+    market: &Market,
+    position: &UserPosition,
+    repay_assets: u64,
+) -> Result<Option<Liquidation>> {
+    if !is_liquidatable(market, position)? {
+        return Ok(None);
+    }
+
+    // Overflows only for repays far above any debt: the clamp applies
+    let repaid_shares = to_shares_down(
+        repay_assets,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )
+    .unwrap_or(u64::MAX)
+    .min(max_repaid_shares(market, position)?);
+
+    let remaining_shares = position.borrow_shares - repaid_shares;
+    if repaid_shares > 0 && is_dust_debt(market, remaining_shares)? {
+        return liquidation_for_shares(market, position, position.borrow_shares);
+    }
+    liquidation_for_shares(market, position, repaid_shares)
+}
+
+/// True if `remaining_shares` of debt are too small to leave on a position
+/// after a partial liquidation
+///
+/// Dust is at most `dust_shares_threshold` shares, or less debt than a
+/// later partial liquidation could repay (`min_liquidation_assets`).
+fn is_dust_debt(market: &Market, remaining_shares: u64) -> Result<bool> {
+    if remaining_shares == 0 {
+        return Ok(false);
+    }
+    if remaining_shares <= market.dust_shares_threshold {
+        return Ok(true);
+    }
+
+    let remaining_assets = to_assets_up(
+        remaining_shares,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+    Ok(remaining_assets < market.min_liquidation_assets)
+}
+
+/// Part of a liquidation's seized collateral paid as the bonus
+///
+/// The seized collateral minus what the repaid debt is worth in collateral
+/// at the market price, rounded up so the bonus is never overstated.
+pub fn liquidation_bonus(market: &Market, liquidation: &Liquidation) -> Result<u64> {
+    let repaid_collateral = mul_div_up(
+        liquidation.repaid_assets as u128,
+        PRICE_PRECISION as u128,
+        collateral_price(market)? as u128,
+    )?;
+    Ok(liquidation.seized_collateral.saturating_sub(repaid_collateral))
+}
+
+/// Computes the liquidation that brings an unhealthy position back to
+/// `target_health`
+///
+/// Instead of the fixed close factor, repays just enough debt for the
+/// health factor (LLTV_PRECISION scale) to reach the target, accounting
+/// for the collateral seized with the bonus. With `D` the debt, `V` the
+/// collateral value, `L` the LLTV and `b` the bonus multiplier:
+/// ```text
+/// (V - r × b) × L / (D - r) = target
+/// r = (target × D - V × L) / (target - b × L)
+/// ```
+/// `r` rounds up, then is converted to shares rounding up, so the target
+/// is reached rather than missed by a unit. The repay is clamped to the
+/// close factor, and to the collateral like `compute_liquidation`.
+///
+/// **Returns:** `None` if the position is healthy
+///
+/// **Errors:**
+/// - InvalidParameter: `target_health` is not above 1.0 (LLTV_PRECISION),
+///   or not above `b × L`, where repaying more would lower the health
+pub fn compute_liquidation_to_target(
+    market: &Market,
+    position: &UserPosition,
+    target_health: u64,
+) -> Result<Option<Liquidation>> {
+    // Both sides scaled by BPS_DENOMINATOR × LLTV_PRECISION
+    let target_weight = target_health as u128 * BPS_DENOMINATOR as u128;
+    let bonus_weight = (BPS_DENOMINATOR + LIQUIDATION_BONUS_BPS) as u128 * market.lltv as u128;
+    require!(
+        target_health > LLTV_PRECISION && target_weight > bonus_weight,
+        PelagoError::InvalidParameter
+    );
+
+    let health = position_health(market, position)?;
+    if health.is_healthy() {
+        return Ok(None);
+    }
+
+    // The seize rounds up and the remaining value rounds down: budget one
+    // unit of collateral value for it. Unhealthy, so the shortfall is positive
+    let collateral_value = (health.collateral_value as u128).saturating_sub(1);
+    let shortfall = target_health as u128 * health.debt_value as u128
+        - collateral_value * market.lltv as u128;
+    let repay = (shortfall * BPS_DENOMINATOR as u128).div_ceil(target_weight - bonus_weight);
+    let repay = u64::try_from(repay).unwrap_or(u64::MAX);
+
+    let repaid_shares = if repay >= health.debt_value {
+        position.borrow_shares
+    } else {
+        to_shares_up(repay, market.total_borrow_assets, market.total_borrow_shares)?
+            .min(position.borrow_shares)
+    };
+    liquidation_for_shares(market, position, repaid_shares.min(close_factor_shares(position)?))
+}
+
+/// Most borrow shares one liquidation repays: the close factor, or the
+/// whole debt if the close factor share is below `min_liquidation_assets`
+fn max_repaid_shares(market: &Market, position: &UserPosition) -> Result<u64> {
+    let close_factor = close_factor_shares(position)?;
+    let repaid_assets = to_assets_up(
+        close_factor,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+
+    if repaid_assets < market.min_liquidation_assets {
+        Ok(position.borrow_shares)
+    } else {
+        Ok(close_factor)
+    }
+}
+
+/// Borrow shares repaid under the close factor, rounded up so dust
+/// positions can still be closed
+fn close_factor_shares(position: &UserPosition) -> Result<u64> {
+    u64::try_from(
+        (position.borrow_shares as u128 * CLOSE_FACTOR_BPS as u128)
+            .div_ceil(BPS_DENOMINATOR as u128),
+    )
+    .map_err(|_| PelagoError::MathOverflow.into())
+}
+
+/// Prices the repayment of `repaid_shares`: the debt repaid and the
+/// collateral seized with the bonus, capped by the position's collateral
+fn liquidation_for_shares(
+    market: &Market,
+    position: &UserPosition,
+    mut repaid_shares: u64,
+) -> Result<Option<Liquidation>> {
+    let mut repaid_assets = to_assets_up(
+        repaid_shares,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+    let mut seized_collateral = seize_for_repay(market, repaid_assets)?;
+
+    // Not enough collateral for the bonus: seize everything, repay less
+    if seized_collateral > position.collateral_amount {
+        seized_collateral = position.collateral_amount;
+        repaid_assets = repay_for_seize(market, seized_collateral)?;
+        repaid_shares = to_shares_down(
+            repaid_assets,
+            market.total_borrow_assets,
+            market.total_borrow_shares,
+        )?
+        .min(position.borrow_shares);
+    }
+
+    if repaid_shares == 0 || seized_collateral == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(Liquidation {
+        repaid_shares,
+        repaid_assets,
+        seized_collateral,
+    }))
+}
+
+/// Collateral owed to a liquidator repaying `repaid_assets` (rounded DOWN)
+///
+/// **Formula:**
+/// ```text
+/// seized = repaid_assets × (10_000 + bonus_bps) × PRICE_PRECISION / (10_000 × collateral_price)
+/// ```
+pub fn seize_for_repay(market: &Market, repaid_assets: u64) -> Result<u64> {
+    mul_div_down(
+        repaid_assets as u128 * (BPS_DENOMINATOR + LIQUIDATION_BONUS_BPS) as u128,
+        PRICE_PRECISION as u128,
+        BPS_DENOMINATOR as u128 * collateral_price(market)? as u128,
+    )
+}
+
+/// Loan tokens a liquidator must repay to seize `seized_collateral` (rounded UP)
+///
+/// Inverse of `seize_for_repay`.
+pub fn repay_for_seize(market: &Market, seized_collateral: u64) -> Result<u64> {
+    mul_div_up(
+        seized_collateral as u128 * collateral_price(market)? as u128,
+        BPS_DENOMINATOR as u128,
+        PRICE_PRECISION as u128 * (BPS_DENOMINATOR + LIQUIDATION_BONUS_BPS) as u128,
+    )
+}
+
+/// `a × b / denominator`, rounded down, as u64
+fn mul_div_down(a: u128, b: u128, denominator: u128) -> Result<u64> {
+    let product = a.checked_mul(b).ok_or(PelagoError::MathOverflow)?;
+    let quotient = product
+        .checked_div(denominator)
+        .ok_or(PelagoError::MathOverflow)?;
+    u64::try_from(quotient).map_err(|_| PelagoError::MathOverflow.into())
+}
+
+/// `a × b / denominator`, rounded up, as u64
+fn mul_div_up(a: u128, b: u128, denominator: u128) -> Result<u64> {
+    require!(denominator > 0, PelagoError::MathOverflow);
+    let product = a.checked_mul(b).ok_or(PelagoError::MathOverflow)?;
+    u64::try_from(product.div_ceil(denominator)).map_err(|_| PelagoError::MathOverflow.into())
+}
+
+/// Applies a liquidation to the position and market totals
+///
+/// **State Changes:**
+/// - position.borrow_shares -= repaid_shares
+/// - position.collateral_amount -= seized_collateral
+/// - market.total_borrow_shares -= repaid_shares (saturating, like repay)
+/// - market.total_borrow_assets -= repaid_assets (saturating, like repay)
+/// - market.total_collateral -= seized_collateral
+pub fn apply_liquidation(
+    market: &mut Market,
+    position: &mut UserPosition,
+    liquidation: &Liquidation,
+) -> Result<()> {
+    position.borrow_shares = position
+        .borrow_shares
+        .checked_sub(liquidation.repaid_shares)
+        .ok_or(PelagoError::MathOverflow)?;
+    position.collateral_amount = position
+        .collateral_amount
+        .checked_sub(liquidation.seized_collateral)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    market.total_borrow_shares = market
+        .total_borrow_shares
+        .saturating_sub(liquidation.repaid_shares);
+    market.total_borrow_assets = market
+        .total_borrow_assets
+        .saturating_sub(liquidation.repaid_assets);
+    market.total_collateral = market
+        .total_collateral
+        .checked_sub(liquidation.seized_collateral)
+        .ok_or(PelagoError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Rejects a partial liquidation repaying less than `min_liquidation_assets`
+///
+/// A liquidation that clears the position's remaining debt is always
+/// allowed, however small. `min_liquidation_assets == 0` disables the check.
+pub fn check_min_liquidation(
+    market: &Market,
+    position: &UserPosition,
+    liquidation: &Liquidation,
+) -> Result<()> {
+    let full = liquidation.repaid_shares == position.borrow_shares;
+    if !full && liquidation.repaid_assets < market.min_liquidation_assets {
+        msg!(
+            "Liquidation below minimum: repaid={}, min_liquidation_assets={}",
+            liquidation.repaid_assets,
+            market.min_liquidation_assets
+        );
+        return err!(PelagoError::LiquidationTooSmall);
+    }
+    Ok(())
+}
+
+/// Rejects a liquidation that seizes less collateral than the caller's bound
+///
+/// `min_seize == 0` disables the check.
+pub fn check_min_seize(seized_collateral: u64, min_seize: u64) -> Result<()> {
+    if min_seize > 0 && seized_collateral < min_seize {
+        msg!(
+            "Seize below bound: seized={}, min_seize={}",
+            seized_collateral,
+            min_seize
+        );
+        return err!(PelagoError::SlippageExceeded);
+    }
+    Ok(())
+}
+
+/// Rejects a liquidator liquidating their own position when the market
+/// sets `disallow_self_liquidation`
+pub fn check_self_liquidation(
+    market: &Market,
+    position: &UserPosition,
+    liquidator: &Pubkey,
+) -> Result<()> {
+    require!(
+        !(market.disallow_self_liquidation && position.user == *liquidator),
+        PelagoError::SelfLiquidationDisallowed
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::shares_math::to_shares_up;
+
+    /// 10 SOL of collateral at 100 USDC/SOL (1000 USDC), 80% LLTV
+    fn borrower(debt: u64, price: u64) -> (Market, UserPosition) {
+        let borrow_shares = to_shares_up(debt, 0, 0).unwrap();
+        let market = Market {
+            total_supply_assets: 2_000_000_000,
+            total_borrow_assets: debt,
+            total_borrow_shares: borrow_shares,
+            total_collateral: 10_000_000_000,
+            lltv: 80_000_000,
+            fixed_price: price,
+            ..Default::default()
+        };
+        let position = UserPosition {
+            borrow_shares,
+            collateral_amount: 10_000_000_000,
+            ..Default::default()
+        };
+        (market, position)
+    }
+
+    #[test]
+    fn test_position_health_factor() {
+        // 1000 USDC of collateral, 80% LLTV, 500 USDC of debt: HF = 800 / 500 = 1.6
+        let (market, position) = borrower(500_000_000, 100_000);
+        let health = position_health(&market, &position).unwrap();
+        assert_eq!(health.collateral_value, 1_000_000_000);
+        assert_eq!(health.debt_value, 500_000_000);
+        assert_eq!(health.health_factor, 160_000_000);
+        assert!(health.is_healthy());
+
+        // Exactly at the limit: HF = 1.0
+        let (market, position) = borrower(800_000_000, 100_000);
+        assert_eq!(position_health(&market, &position).unwrap().health_factor, 100_000_000);
+
+        // No debt
+        let position = UserPosition { borrow_shares: 0, ..position };
+        assert_eq!(position_health(&market, &position).unwrap().health_factor, u64::MAX);
+    }
+
+    #[test]
+    fn test_liquidation_price_sits_at_threshold() {
+        for debt in [5_000_001, 123_456_789, 700_000_000, 799_999_999] {
+            let (market, position) = borrower(debt, 100_000);
+            let price = liquidation_price(&market, &position).unwrap();
+
+            // Healthy at the returned price, liquidatable one tick below
+            let at = Market { fixed_price: price, ..market.clone() };
+            let below = Market { fixed_price: price - 1, ..market };
+            assert!(!is_liquidatable(&at, &position).unwrap());
+            assert!(is_liquidatable(&below, &position).unwrap());
+        }
+
+        // 700 USDC against 10 SOL at 80% LLTV: 87.5 USDC/SOL
+        let (market, position) = borrower(700_000_000, 100_000);
+        assert_eq!(liquidation_price(&market, &position).unwrap(), 87_500);
+
+        // No debt: never liquidatable
+        let position = UserPosition { borrow_shares: 0, ..position };
+        assert_eq!(liquidation_price(&market, &position).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_healthy_position_not_liquidated() {
+        // 800 USDC of debt is exactly at the limit
+        let (market, position) = borrower(800_000_000, 100_000);
+        assert!(!is_liquidatable(&market, &position).unwrap());
+        assert_eq!(compute_liquidation(&market, &position).unwrap(), None);
+    }
+
+    #[test]
+    fn test_close_factor_and_bonus() {
+        // Price drops to 85 USDC/SOL: max borrow 680 USDC < 700 USDC debt
+        let (mut market, mut position) = borrower(700_000_000, 85_000);
+        assert!(is_liquidatable(&market, &position).unwrap());
+
+        let liquidation = compute_liquidation(&market, &position).unwrap().unwrap();
+        assert_eq!(liquidation.repaid_shares, position.borrow_shares / 2);
+        assert_eq!(liquidation.repaid_assets, 350_000_000);
+        // 350 USDC × 1.05 / 85 USDC/SOL ≈ 4.3235 SOL
+        assert_eq!(liquidation.seized_collateral, 4_323_529_411);
+
+        apply_liquidation(&mut market, &mut position, &liquidation).unwrap();
+        assert_eq!(market.total_borrow_assets, 350_000_000);
+        assert_eq!(market.total_borrow_shares, position.borrow_shares);
+        assert_eq!(position.collateral_amount, 10_000_000_000 - 4_323_529_411);
+        assert_eq!(market.total_collateral, position.collateral_amount);
+    }
+
+    #[test]
+    fn test_liquidation_restores_target_health() {
+        // 85 USDC/SOL: 680 USDC of borrowing power against 700 USDC of debt
+        let (mut market, mut position) = borrower(700_000_000, 85_000);
+        let target = 105_000_000; // HF 1.05
+
+        // (1.05 × 700 - 680) / (1.05 - 1.05 × 0.8) ≈ 261.9 USDC, below the close factor
+        let liquidation = compute_liquidation_to_target(&market, &position, target)
+            .unwrap()
+            .unwrap();
+        assert!(liquidation.repaid_assets < 350_000_000);
+        assert!(liquidation.repaid_assets.abs_diff(261_904_762) <= 10);
+
+        apply_liquidation(&mut market, &mut position, &liquidation).unwrap();
+        let health = position_health(&market, &position).unwrap();
+        assert!(health.health_factor >= target);
+        assert!(health.health_factor - target < target / 100_000);
+    }
+
+    #[test]
+    fn test_liquidation_to_target_clamped_and_validated() {
+        // A far-off target repays no more than the close factor
+        let (market, position) = borrower(700_000_000, 85_000);
+        let clamped = compute_liquidation_to_target(&market, &position, 500_000_000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(clamped, compute_liquidation(&market, &position).unwrap().unwrap());
+
+        // Crashed price: the collateral caps the seize, as with the close factor
+        let (crashed, position) = borrower(700_000_000, 10_000);
+        let capped = compute_liquidation_to_target(&crashed, &position, 105_000_000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(capped.seized_collateral, position.collateral_amount);
+
+        // Healthy positions are left alone
+        let (healthy, position) = borrower(700_000_000, 100_000);
+        assert_eq!(
+            compute_liquidation_to_target(&healthy, &position, 105_000_000).unwrap(),
+            None
+        );
+
+        // Targets at or below 1.0, or below the bonus-weighted LLTV, are meaningless
+        for target in [100_000_000, 50_000_000] {
+            assert_eq!(
+                compute_liquidation_to_target(&market, &position, target).unwrap_err(),
+                PelagoError::InvalidParameter.into()
+            );
+        }
+    }
+
+    #[test]
+    fn test_seize_capped_by_collateral() {
+        // Price crashes to 10 USDC/SOL: 10 SOL = 100 USDC against 700 USDC debt
+        let (market, position) = borrower(700_000_000, 10_000);
+
+        let liquidation = compute_liquidation(&market, &position).unwrap().unwrap();
+        assert_eq!(liquidation.seized_collateral, position.collateral_amount);
+        // 100 USDC / 1.05 ≈ 95.238096 USDC, rounded up
+        assert_eq!(liquidation.repaid_assets, 95_238_096);
+        assert!(liquidation.repaid_shares < position.borrow_shares / 2);
+    }
+
+    #[test]
+    fn test_min_liquidation_assets() {
+        // Close factor share (350 USDC) is below a 400 USDC minimum: the
+        // whole 700 USDC debt is repaid instead
+        let (mut market, mut position) = borrower(700_000_000, 85_000);
+        market.min_liquidation_assets = 400_000_000;
+        let liquidation = compute_liquidation(&market, &position).unwrap().unwrap();
+        assert_eq!(liquidation.repaid_shares, position.borrow_shares);
+        assert_eq!(liquidation.repaid_assets, 700_000_000);
+        assert!(check_min_liquidation(&market, &position, &liquidation).is_ok());
+        apply_liquidation(&mut market, &mut position, &liquidation).unwrap();
+        assert_eq!(position.borrow_shares, 0);
+
+        // Price crash: collateral only covers ~95 USDC, a partial
+        // liquidation below the minimum
+        let (mut market, position) = borrower(700_000_000, 10_000);
+        market.min_liquidation_assets = 400_000_000;
+        let liquidation = compute_liquidation(&market, &position).unwrap().unwrap();
+        assert!(liquidation.repaid_shares < position.borrow_shares);
+        assert_eq!(
+            check_min_liquidation(&market, &position, &liquidation).unwrap_err(),
+            PelagoError::LiquidationTooSmall.into()
+        );
+
+        // Zero disables the minimum
+        market.min_liquidation_assets = 0;
+        assert!(check_min_liquidation(&market, &position, &liquidation).is_ok());
+    }
+
+    #[test]
+    fn test_liquidation_for_repay_clamped_to_close_factor() {
+        let (market, position) = borrower(700_000_000, 85_000);
+        let close_factor = compute_liquidation(&market, &position).unwrap().unwrap();
+
+        // A repay within the close factor is honored, rounding in the protocol's favor
+        let liquidation = compute_liquidation_for_repay(&market, &position, 100_000_000)
+            .unwrap()
+            .unwrap();
+        assert!(liquidation.repaid_shares < close_factor.repaid_shares);
+        assert!((99_999_999..=100_000_000).contains(&liquidation.repaid_assets));
+        assert_eq!(
+            liquidation.seized_collateral,
+            seize_for_repay(&market, liquidation.repaid_assets).unwrap()
+        );
+
+        // Larger repays stop at the close factor
+        for repay in [350_000_000, 600_000_000, u64::MAX] {
+            assert_eq!(
+                compute_liquidation_for_repay(&market, &position, repay).unwrap(),
+                Some(close_factor)
+            );
+        }
+
+        // Healthy positions and repays below one share liquidate nothing
+        let (healthy_market, healthy) = borrower(700_000_000, 100_000);
+        assert_eq!(
+            compute_liquidation_for_repay(&healthy_market, &healthy, u64::MAX).unwrap(),
+            None
+        );
+        assert_eq!(compute_liquidation_for_repay(&market, &position, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_dust_remainder_closes_whole_debt() {
+        // A 400 USDC minimum lifts the cap to the whole debt; repaying all
+        // but 1_000 units would leave unliquidatable dust behind
+        let (mut market, position) = borrower(700_000_000, 85_000);
+        market.min_liquidation_assets = 400_000_000;
+
+        let liquidation = compute_liquidation_for_repay(&market, &position, 699_999_000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(liquidation.repaid_shares, position.borrow_shares);
+        assert_eq!(liquidation.repaid_assets, 700_000_000);
+
+        // A remainder above the minimum stays on the position
+        let liquidation = compute_liquidation_for_repay(&market, &position, 250_000_000)
+            .unwrap()
+            .unwrap();
+        assert!(liquidation.repaid_shares < position.borrow_shares);
+
+        // Remaining shares within dust_shares_threshold count as dust too
+        market.min_liquidation_assets = 0;
+        market.dust_shares_threshold = 1_000_000;
+        assert!(is_dust_debt(&market, 1_000_000).unwrap());
+        assert!(!is_dust_debt(&market, 1_000_001).unwrap());
+        assert!(!is_dust_debt(&market, 0).unwrap());
+    }
+
+    #[test]
+    fn test_liquidation_bonus_is_incentive_share_of_seize() {
+        let (market, position) = borrower(700_000_000, 85_000);
+        let liquidation = compute_liquidation(&market, &position).unwrap().unwrap();
+
+        // 350 USDC at 85 USDC/SOL is worth ~4.1176 SOL; the 5% bonus ~0.2059 SOL
+        let bonus = liquidation_bonus(&market, &liquidation).unwrap();
+        assert_eq!(bonus, 4_323_529_411 - 4_117_647_059);
+        assert_eq!(bonus, (liquidation.seized_collateral - bonus) * 5 / 100);
+    }
+
+    #[test]
+    fn test_min_seize_rejects_worse_execution() {
+        // Keeper simulates at 85 USDC/SOL and bounds the seize at the quote
+        let (market, position) = borrower(700_000_000, 85_000);
+        let quoted = compute_liquidation(&market, &position).unwrap().unwrap().seized_collateral;
+        assert!(check_min_seize(quoted, quoted).is_ok());
+
+        // Price recovers to 86 before execution: still unhealthy, but the
+        // same repayment now buys less collateral
+        let (market, position) = borrower(700_000_000, 86_000);
+        let seized = compute_liquidation(&market, &position).unwrap().unwrap().seized_collateral;
+        assert!(seized < quoted);
+        assert_eq!(
+            check_min_seize(seized, quoted).unwrap_err(),
+            PelagoError::SlippageExceeded.into()
+        );
+
+        // Zero disables the bound
+        assert!(check_min_seize(seized, 0).is_ok());
+    }
+
+    #[test]
+    fn test_collateral_shortfall_restores_health() {
+        // 700 USDC against 850 USDC of SOL at 80%: 875 USDC of value needed
+        let (market, mut position) = borrower(700_000_000, 85_000);
+        let health = position_health(&market, &position).unwrap();
+        let shortfall = collateral_shortfall(&market, &health);
+        assert_eq!(shortfall, 25_000_000);
+        assert_eq!(
+            require_healthy(&market, &health).unwrap_err(),
+            PelagoError::InsufficientCollateral.into()
+        );
+
+        // Topping up by the shortfall (in SOL at 85 USDC) is exactly enough
+        let top_up = (shortfall as u128 * PRICE_PRECISION as u128).div_ceil(85_000) as u64;
+        position.collateral_amount += top_up;
+        let health = position_health(&market, &position).unwrap();
+        assert!(require_healthy(&market, &health).is_ok());
+        assert_eq!(collateral_shortfall(&market, &health), 0);
+
+        position.collateral_amount -= 1;
+        let health = position_health(&market, &position).unwrap();
+        assert_eq!(collateral_shortfall(&market, &health), 1);
+    }
+
+    #[test]
+    fn test_self_liquidation_toggle() {
+        let (mut market, mut position) = borrower(700_000_000, 85_000);
+        let borrower_key = Pubkey::new_unique();
+        position.user = borrower_key;
+
+        // Allowed by default, for the borrower and anyone else
+        assert!(check_self_liquidation(&market, &position, &borrower_key).is_ok());
+
+        market.disallow_self_liquidation = true;
+        assert_eq!(
+            check_self_liquidation(&market, &position, &borrower_key).unwrap_err(),
+            PelagoError::SelfLiquidationDisallowed.into()
+        );
+        assert!(check_self_liquidation(&market, &position, &Pubkey::new_unique()).is_ok());
+    }
+
+    #[test]
+    fn test_rounding_never_favors_liquidator() {
+        let bonus = (BPS_DENOMINATOR + LIQUIDATION_BONUS_BPS) as u128;
+        let mut checked = 0;
+
+        for debt in [1u64, 7, 999, 123_457, 700_000_000, 1_000_000_000_000] {
+            for collateral in [1u64, 13, 1_000_000_007, 10_000_000_000] {
+                for price in [1u64, 3, 85_003, 99_999, 1_000_000_000] {
+                    // Two other borrowers and accrued interest: a borrow
+                    // share is worth 1.07 of its initial value
+                    let total_borrow_assets = debt * 3;
+                    let total_borrow_shares =
+                        (total_borrow_assets as u128 * 1_000_000 * 100 / 107) as u64;
+                    let market = Market {
+                        total_supply_assets: u64::MAX / 2,
+                        total_borrow_assets,
+                        total_borrow_shares,
+                        lltv: 80_000_000,
+                        fixed_price: price,
+                        ..Default::default()
+                    };
+                    let position = UserPosition {
+                        borrow_shares: total_borrow_shares / 3,
+                        collateral_amount: collateral,
+                        ..Default::default()
+                    };
+
+                    let Some(l) = compute_liquidation(&market, &position).unwrap() else {
+                        continue;
+                    };
+                    checked += 1;
+
+                    // Seized value never exceeds repaid × incentive
+                    assert!(
+                        l.seized_collateral as u128 * price as u128 * BPS_DENOMINATOR as u128
+                            <= l.repaid_assets as u128 * bonus * PRICE_PRECISION as u128,
+                        "over-seized: debt={debt}, collateral={collateral}, price={price}"
+                    );
+                    // The burned debt is fully paid for
+                    let burned = to_assets_up(
+                        l.repaid_shares,
+                        market.total_borrow_assets,
+                        market.total_borrow_shares,
+                    )
+                    .unwrap();
+                    assert!(
+                        burned <= l.repaid_assets,
+                        "underpaid: debt={debt}, collateral={collateral}, price={price}"
+                    );
+                    assert!(l.repaid_shares <= position.borrow_shares);
+                    assert!(l.seized_collateral <= position.collateral_amount);
+                }
+            }
+        }
+        assert!(checked > 20, "sweep should cover many liquidations");
+    }
+
+    #[test]
+    fn test_seize_and_repay_round_opposite_ways() {
+        let market = Market {
+            fixed_price: 85_003,
+            ..Default::default()
+        };
+        // 1 unit of debt buys 12.35... units of collateral: seize rounds down
+        assert_eq!(seize_for_repay(&market, 1).unwrap(), 12);
+        // 12 units of collateral cost 0.97... of debt: repay rounds up
+        assert_eq!(repay_for_seize(&market, 12).unwrap(), 1);
+        for seized in [1u64, 12, 13, 1_000_000_007] {
+            let repay = repay_for_seize(&market, seized).unwrap();
+            assert!(seize_for_repay(&market, repay).unwrap() >= seized);
+        }
+    }
+
+    /// Executable spec of the health / liquidation boundary
+    ///
+    /// The loan token is the unit of account (it has no price of its own),
+    /// so the boundary is swept through the collateral price and the debt.
+    /// Borrowing and liquidation must agree exactly: a position is either
+    /// borrowable up to its limit or liquidatable, never both or neither.
+    mod boundaries {
+        use super::*;
+        use crate::instructions::withdraw_collateral::check_health_p1;
+
+        /// At 700 USDC of debt on 10 SOL with 80% LLTV, the threshold price
+        /// is 700 / (10 × 0.8) = 87.5 USDC/SOL
+        const THRESHOLD_PRICE: u64 = 87_500;
+
+        #[test]
+        fn test_borrow_rejected_just_past_lltv() {
+            // 10 SOL at 100 USDC/SOL with 80% LLTV: 800 USDC limit
+            let (market, position) = borrower(800_000_000, 100_000);
+            assert!(check_health_p1(&market, &position).is_ok());
+            assert!(!is_liquidatable(&market, &position).unwrap());
+
+            let (market, position) = borrower(800_000_001, 100_000);
+            assert_eq!(
+                check_health_p1(&market, &position).unwrap_err(),
+                PelagoError::InsufficientCollateral.into()
+            );
+            assert!(is_liquidatable(&market, &position).unwrap());
+        }
+
+        #[test]
+        fn test_price_sweep_crosses_threshold_exactly_once() {
+            for price in (THRESHOLD_PRICE - 2_000..=THRESHOLD_PRICE + 2_000).rev() {
+                let (market, position) = borrower(700_000_000, price);
+                let liquidatable = is_liquidatable(&market, &position).unwrap();
+
+                // Liquidation rejected at and above the threshold, allowed just below
+                assert_eq!(liquidatable, price < THRESHOLD_PRICE, "price {price}");
+                // Borrow health agrees with liquidation at every price
+                assert_eq!(
+                    check_health_p1(&market, &position).is_ok(),
+                    !liquidatable,
+                    "price {price}"
+                );
+                assert_eq!(
+                    compute_liquidation(&market, &position).unwrap().is_some(),
+                    liquidatable
+                );
+            }
+        }
+
+        #[test]
+        fn test_debt_sweep_crosses_limit_exactly_once() {
+            for debt in 799_999_990..=800_000_010u64 {
+                let (market, position) = borrower(debt, 100_000);
+                let liquidatable = is_liquidatable(&market, &position).unwrap();
+                assert_eq!(liquidatable, debt > 800_000_000, "debt {debt}");
+                assert_eq!(check_health_p1(&market, &position).is_ok(), !liquidatable);
+            }
+        }
+
+        #[test]
+        fn test_seize_matches_incentive_formula_at_boundary() {
+            let price = THRESHOLD_PRICE - 1;
+            let (market, position) = borrower(700_000_000, price);
+            let liquidation = compute_liquidation(&market, &position).unwrap().unwrap();
+
+            // Close factor: half the debt
+            assert_eq!(liquidation.repaid_assets, 350_000_000);
+
+            // seized = repaid × 1.05 / price, rounded down
+            let incentive_value = 350_000_000u128 * 10_500 / 10_000;
+            let expected = incentive_value * 1_000_000 / price as u128;
+            assert_eq!(liquidation.seized_collateral as u128, expected);
+
+            // Rounding favors the borrower: the seize is worth at most the
+            // incentive, and one more unit would exceed it
+            let value = |seized: u128| seized * price as u128 / 1_000_000;
+            assert!(value(expected) <= incentive_value);
+            assert!((expected + 1) * price as u128 > incentive_value * 1_000_000);
+        }
+    }
+}
@@ -4,10 +4,33 @@
 //!
 //! **P1 Phase Libraries:**
 //! - `shares_math`: Virtual shares calculation (防止通胀攻击)
-//! - `interest`: Interest accrual mechanism (简化版线性利息)
+//! - `interest`: Interest accrual mechanism (泰勒级数复利)
+//!
+//! **P2 Phase Libraries:**
+//! - `vault_snapshot`: Token balance deltas across CPIs (reload-safe)
+//! - `twap`: Time-weighted collateral price over a ring buffer of samples
+//! - `invariants`: Market accounting invariants checked after mutations
+//! - `liquidation`: Health and seize math for liquidations
+//! - `oracle`: Bounds-checked collateral price reads
+//! - `rate_history`: Realized yields from share price snapshots
+//! - `rewards`: Supply reward index and per-position settlement
+//! - `batch`: Account count limits for remaining_accounts loops
+//! - `profile`: Compute unit logging around handlers (profile feature)
+//! - `test_rng`: Seeded generator for randomized unit tests (tests only)
 
 pub mod shares_math;
 pub mod interest;
+pub mod vault_snapshot;
+pub mod twap;
+pub mod invariants;
+pub mod liquidation;
+pub mod oracle;
+pub mod rate_history;
+pub mod rewards;
+pub mod batch;
+pub mod profile;
+#[cfg(test)]
+pub mod test_rng;
 
 // Re-export commonly used functions for convenience
 pub use shares_math::{
@@ -21,7 +44,11 @@ pub use shares_math::{
 
 pub use interest::{
     accrue_interest,
+    accrue_interest_at,
+    project_interest_at,
     AccrueInterestEvent,
     FIXED_ANNUAL_RATE_WAD,
     WAD,
 };
+
+pub use vault_snapshot::{snapshot_balance_delta, BalanceDelta};
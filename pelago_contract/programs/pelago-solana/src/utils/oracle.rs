@@ -0,0 +1,250 @@
+//! Oracle Price Reads
+//!
+//! Every collateral price used by health, liquidation and price-history
+//! logic goes through this module, which rejects prices outside the
+//! market's sanity band (`min_sane_price`..=`max_sane_price`). The band is
+//! a last line of defense against a corrupted feed: a grossly wrong price
+//! fails the operation instead of being acted on.
+//!
+//! Liquidations additionally require a fresh price (see
+//! `check_price_freshness`), since they seize collateral on it.
+
+use anchor_lang::prelude::*;
+use crate::error::PelagoError;
+use crate::state::Market;
+
+/// Converts a whole-token price into the per-base-unit price used on-chain
+///
+/// `whole_price` is in PRICE_PRECISION loan tokens per whole collateral
+/// token. Health math multiplies collateral base units by the stored price,
+/// so the decimals are folded in here:
+/// ```text
+/// price = whole_price × 10^loan_decimals / 10^collateral_decimals
+/// ```
+/// Zero-decimal mints need no special case: their factor is 10^0 = 1.
+///
+/// **Errors:**
+/// - InvalidParameter: a non-zero price that rounds to 0 base-unit price
+///   (collateral decimals too high for the loan token to express)
+/// - MathOverflow: the scaled price does not fit in u64
+pub fn base_unit_price(whole_price: u64, collateral_decimals: u8, loan_decimals: u8) -> Result<u64> {
+    let pow10 = |decimals: u8| {
+        10u128
+            .checked_pow(decimals as u32)
+            .ok_or(PelagoError::MathOverflow)
+    };
+    let price = (whole_price as u128)
+        .checked_mul(pow10(loan_decimals)?)
+        .ok_or(PelagoError::MathOverflow)?
+        / pow10(collateral_decimals)?;
+
+    require!(price > 0 || whole_price == 0, PelagoError::InvalidParameter);
+    u64::try_from(price).map_err(|_| PelagoError::MathOverflow.into())
+}
+
+/// Collateral price used by health checks, bounds-checked
+///
+/// **Errors:**
+/// - OraclePriceOutOfBounds: `market.collateral_price()` is outside the band
+pub fn collateral_price(market: &Market) -> Result<u64> {
+    check_price_bounds(market, market.collateral_price())
+}
+
+/// Returns `price` if it lies within the market's sanity band
+///
+/// A zero bound disables that side of the band.
+///
+/// **Errors:**
+/// - OraclePriceOutOfBounds: `price < min_sane_price` or `price > max_sane_price`
+pub fn check_price_bounds(market: &Market, price: u64) -> Result<u64> {
+    let below_floor = market.min_sane_price > 0 && price < market.min_sane_price;
+    let above_ceiling = market.max_sane_price > 0 && price > market.max_sane_price;
+    if below_floor || above_ceiling {
+        msg!(
+            "Oracle price out of bounds: price={}, min={}, max={}",
+            price,
+            market.min_sane_price,
+            market.max_sane_price
+        );
+        return err!(PelagoError::OraclePriceOutOfBounds);
+    }
+    Ok(price)
+}
+
+/// Rejects a price older than `max_staleness` seconds at `now`
+///
+/// Only the manual price ages: fixed prices are constants and never go
+/// stale. `max_staleness == 0` disables the check.
+///
+/// **Errors:**
+/// - StaleOracle: manual price set more than `max_staleness` seconds ago
+pub fn check_price_freshness(market: &Market, now: i64, max_staleness: u32) -> Result<()> {
+    if max_staleness == 0 || !market.manual_price_enabled {
+        return Ok(());
+    }
+
+    let age = now.saturating_sub(market.price_updated_at);
+    if age > max_staleness as i64 {
+        msg!(
+            "Stale oracle: age={}s, max_staleness={}s",
+            age,
+            max_staleness
+        );
+        return err!(PelagoError::StaleOracle);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{DEFAULT_WHOLE_TOKEN_PRICE, FIXED_ORACLE_PRICE, LLTV_PRECISION};
+    use crate::state::UserPosition;
+    use crate::utils::liquidation::is_liquidatable;
+    use crate::utils::shares_math::to_shares_up;
+
+    fn banded_market(price: u64) -> Market {
+        Market {
+            fixed_price: price,
+            min_sane_price: 10_000,     // 10 USDC/SOL
+            max_sane_price: 10_000_000, // 10,000 USDC/SOL
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_normal_price_passes() {
+        assert_eq!(collateral_price(&banded_market(100_000)).unwrap(), 100_000);
+        // Band edges are inclusive
+        assert!(collateral_price(&banded_market(10_000)).is_ok());
+        assert!(collateral_price(&banded_market(10_000_000)).is_ok());
+    }
+
+    #[test]
+    fn test_absurd_prices_rejected() {
+        let out_of_bounds = PelagoError::OraclePriceOutOfBounds.into();
+        assert_eq!(collateral_price(&banded_market(1)).unwrap_err(), out_of_bounds);
+        assert_eq!(
+            collateral_price(&banded_market(1_000_000_000_000)).unwrap_err(),
+            out_of_bounds
+        );
+    }
+
+    #[test]
+    fn test_unset_band_accepts_any_price() {
+        let market = Market {
+            fixed_price: 1,
+            ..Default::default()
+        };
+        assert!(collateral_price(&market).is_ok());
+        assert!(check_price_bounds(&market, u64::MAX).is_ok());
+    }
+
+    /// Market with one position holding one whole collateral token at
+    /// 100 loan tokens each, borrowing `debt` at 100% LLTV
+    fn whole_token_position(collateral_decimals: u8, loan_decimals: u8, debt: u64) -> bool {
+        let price = base_unit_price(DEFAULT_WHOLE_TOKEN_PRICE, collateral_decimals, loan_decimals)
+            .unwrap();
+        let borrow_shares = to_shares_up(debt, 0, 0).unwrap();
+        let market = Market {
+            fixed_price: price,
+            lltv: LLTV_PRECISION,
+            total_supply_assets: debt,
+            total_borrow_assets: debt,
+            total_borrow_shares: borrow_shares,
+            ..Default::default()
+        };
+        let position = UserPosition {
+            collateral_amount: 10u64.pow(collateral_decimals as u32),
+            borrow_shares,
+            ..Default::default()
+        };
+        is_liquidatable(&market, &position).unwrap()
+    }
+
+    #[test]
+    fn test_base_unit_price_matches_sol_usdc_default() {
+        assert_eq!(
+            base_unit_price(DEFAULT_WHOLE_TOKEN_PRICE, 9, 6).unwrap(),
+            FIXED_ORACLE_PRICE
+        );
+    }
+
+    #[test]
+    fn test_zero_decimal_pairs_value_sanely() {
+        // One whole collateral token is worth exactly 100 whole loan tokens
+        for (collateral_decimals, loan_decimals) in [(0u8, 6u8), (6, 0), (0, 0)] {
+            let value = 100 * 10u64.pow(loan_decimals as u32);
+            assert!(
+                !whole_token_position(collateral_decimals, loan_decimals, value),
+                "{}/{}: borrowing the full value should be healthy",
+                collateral_decimals,
+                loan_decimals
+            );
+            assert!(
+                whole_token_position(collateral_decimals, loan_decimals, value + 1),
+                "{}/{}: one unit over the value should be liquidatable",
+                collateral_decimals,
+                loan_decimals
+            );
+        }
+
+        assert_eq!(base_unit_price(DEFAULT_WHOLE_TOKEN_PRICE, 0, 6).unwrap(), 100_000_000_000_000);
+        assert_eq!(base_unit_price(DEFAULT_WHOLE_TOKEN_PRICE, 6, 0).unwrap(), 100);
+        assert_eq!(base_unit_price(DEFAULT_WHOLE_TOKEN_PRICE, 0, 0).unwrap(), DEFAULT_WHOLE_TOKEN_PRICE);
+    }
+
+    #[test]
+    fn test_unrepresentable_prices_rejected() {
+        // 18-decimal collateral against a 0-decimal loan token rounds to 0
+        assert_eq!(
+            base_unit_price(DEFAULT_WHOLE_TOKEN_PRICE, 18, 0).unwrap_err(),
+            PelagoError::InvalidParameter.into()
+        );
+        // Too large for u64
+        assert_eq!(
+            base_unit_price(u64::MAX, 0, 6).unwrap_err(),
+            PelagoError::MathOverflow.into()
+        );
+    }
+
+    #[test]
+    fn test_price_fresh_for_borrow_but_stale_for_liquidation() {
+        use crate::utils::liquidation::position_health;
+
+        // Manual price of 100 USDC/SOL set ten minutes ago, five-minute
+        // liquidation limit
+        let now = 1_700_000_600;
+        let market = Market {
+            manual_price: 100_000,
+            manual_price_enabled: true,
+            price_updated_at: now - 600,
+            liquidation_max_staleness_secs: 300,
+            lltv: 80 * LLTV_PRECISION / 100,
+            ..Default::default()
+        };
+        let position = UserPosition {
+            collateral_amount: 10_000_000_000,
+            ..Default::default()
+        };
+
+        // Borrow-side pricing has no age limit
+        assert_eq!(collateral_price(&market).unwrap(), 100_000);
+        assert!(position_health(&market, &position).unwrap().is_healthy());
+
+        // Liquidations refuse it
+        assert_eq!(
+            check_price_freshness(&market, now, market.liquidation_max_staleness_secs).unwrap_err(),
+            PelagoError::StaleOracle.into()
+        );
+        assert!(check_price_freshness(&market, now - 300, 300).is_ok());
+        assert!(check_price_freshness(&market, now, 0).is_ok());
+
+        // Fixed prices never go stale
+        let fixed = Market {
+            manual_price_enabled: false,
+            ..market
+        };
+        assert!(check_price_freshness(&fixed, now, 300).is_ok());
+    }
+}
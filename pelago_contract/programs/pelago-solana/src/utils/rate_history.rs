@@ -0,0 +1,143 @@
+//! Realized Rates
+//!
+//! Annualizes the growth of a market's share prices between two snapshots,
+//! giving borrowers and suppliers the yield they actually realized rather
+//! than the instantaneous rate.
+//!
+//! **Model:** The borrow (supply) index is the value of one borrow (supply)
+//! share, including the virtual offsets. Interest raises it; borrows,
+//! repays, supplies and withdrawals leave it unchanged up to rounding.
+//!
+//! **Annualization:** Growth is scaled linearly to a year, so compounding
+//! shows as a realized rate slightly above the nominal one:
+//! ```text
+//! apy = (end_index / start_index - 1) × SECONDS_PER_YEAR / elapsed
+//! ```
+
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::{Market, RateSample};
+use crate::utils::interest::{SECONDS_PER_YEAR, WAD};
+use crate::utils::shares_math::{VIRTUAL_ASSETS, VIRTUAL_SHARES};
+
+/// Value of one share in assets (precision: 1e18), virtual offsets included
+pub fn share_index(total_assets: u64, total_shares: u64) -> Result<u128> {
+    (total_assets as u128 + VIRTUAL_ASSETS)
+        .checked_mul(WAD)
+        .ok_or(PelagoError::MathOverflow)?
+        .checked_div(total_shares as u128 + VIRTUAL_SHARES)
+        .ok_or(PelagoError::MathOverflow.into())
+}
+
+/// Snapshot of the market's share prices at `timestamp`
+pub fn rate_sample(market: &Market, timestamp: i64) -> Result<RateSample> {
+    Ok(RateSample {
+        timestamp,
+        borrow_index: share_index(market.total_borrow_assets, market.total_borrow_shares)?,
+        supply_index: share_index(market.total_supply_assets, market.total_supply_shares)?,
+    })
+}
+
+/// Annualized growth of an index between two snapshots (precision: 1e18)
+///
+/// **Returns:** 0 if the index did not grow or no time elapsed
+pub fn annualized_growth_wad(start_index: u128, end_index: u128, elapsed: i64) -> Result<u128> {
+    if elapsed <= 0 || start_index == 0 || end_index <= start_index {
+        return Ok(0);
+    }
+    (end_index - start_index)
+        .checked_mul(WAD)
+        .ok_or(PelagoError::MathOverflow)?
+        .checked_div(start_index)
+        .ok_or(PelagoError::MathOverflow)?
+        .checked_mul(SECONDS_PER_YEAR)
+        .ok_or(PelagoError::MathOverflow)?
+        .checked_div(elapsed as u128)
+        .ok_or(PelagoError::MathOverflow.into())
+}
+
+/// Picks the snapshots spanning the last `window` seconds
+///
+/// The end is the latest snapshot; the start is the latest snapshot at
+/// least `window` seconds older, or the oldest one if the history is
+/// shorter than the window. `window == 0` spans the whole history.
+///
+/// **Returns:** `None` with fewer than two snapshots
+pub fn window_bounds(samples: &[RateSample], window: u32) -> Option<(RateSample, RateSample)> {
+    let (end, earlier) = samples.split_last()?;
+    let first = *earlier.first()?;
+    let cutoff = end.timestamp.saturating_sub(window as i64);
+    let start = if window == 0 {
+        first
+    } else {
+        earlier
+            .iter()
+            .rev()
+            .find(|sample| sample.timestamp <= cutoff)
+            .copied()
+            .unwrap_or(first)
+    };
+    Some((start, *end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::interest::{accrue_interest_at, FIXED_ANNUAL_RATE_WAD};
+
+    #[test]
+    fn test_realized_apy_matches_rate() {
+        // 1000 USDC supplied, all of it borrowed, no fee
+        let mut market = Market {
+            total_supply_assets: 1_000_000_000,
+            total_supply_shares: 1_000_000_000_000_000,
+            total_borrow_assets: 1_000_000_000,
+            total_borrow_shares: 1_000_000_000_000_000,
+            last_update: 0,
+            ..Default::default()
+        };
+
+        // Weekly snapshots over ten weeks
+        let mut samples = vec![rate_sample(&market, 0).unwrap()];
+        for week in 1..=10 {
+            let now = week * 7 * 86_400;
+            accrue_interest_at(&mut market, now).unwrap();
+            samples.push(rate_sample(&market, now).unwrap());
+        }
+
+        // Realized borrow APY over the last week is the configured 5%,
+        // plus a hair of compounding from the earlier accruals
+        let (start, end) = window_bounds(&samples, 7 * 86_400).unwrap();
+        assert_eq!(end.timestamp - start.timestamp, 7 * 86_400);
+        let apy = annualized_growth_wad(start.borrow_index, end.borrow_index, 7 * 86_400).unwrap();
+        assert!(apy >= FIXED_ANNUAL_RATE_WAD - FIXED_ANNUAL_RATE_WAD / 1_000);
+        assert!(apy <= FIXED_ANNUAL_RATE_WAD + FIXED_ANNUAL_RATE_WAD / 100);
+
+        // Fully utilized without a fee: suppliers earn what borrowers pay
+        let (start, end) = window_bounds(&samples, 0).unwrap();
+        let elapsed = end.timestamp - start.timestamp;
+        let borrow = annualized_growth_wad(start.borrow_index, end.borrow_index, elapsed).unwrap();
+        let supply = annualized_growth_wad(start.supply_index, end.supply_index, elapsed).unwrap();
+        assert!(borrow.abs_diff(supply) <= FIXED_ANNUAL_RATE_WAD / 1_000);
+    }
+
+    #[test]
+    fn test_window_bounds_needs_two_snapshots() {
+        let sample = |timestamp| RateSample {
+            timestamp,
+            ..Default::default()
+        };
+        assert_eq!(window_bounds(&[], 3_600), None);
+        assert_eq!(window_bounds(&[sample(100)], 3_600), None);
+
+        // History shorter than the window: falls back to the oldest snapshot
+        let samples = [sample(100), sample(200), sample(300)];
+        assert_eq!(window_bounds(&samples, 3_600), Some((sample(100), sample(300))));
+        assert_eq!(window_bounds(&samples, 100), Some((sample(200), sample(300))));
+
+        // No growth or no elapsed time
+        assert_eq!(annualized_growth_wad(WAD, WAD, 3_600).unwrap(), 0);
+        assert_eq!(annualized_growth_wad(WAD, 2 * WAD, 0).unwrap(), 0);
+    }
+}
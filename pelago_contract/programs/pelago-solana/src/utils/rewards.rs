@@ -0,0 +1,128 @@
+//! Supply Rewards
+//!
+//! Streams an incentive token to suppliers on top of interest, in
+//! proportion to supply share-time.
+//!
+//! **Model:** The market's reward index is the cumulative reward per supply
+//! share. Each accrual adds the emission since `last_update`, spread over
+//! the supply shares outstanding:
+//! ```text
+//! reward_index += rate × elapsed × 1e18 / total_supply_shares
+//! ```
+//! A position earns `supply_shares × (reward_index - checkpoint) / 1e18`.
+//! Positions are settled (earned rewards booked, checkpoint moved to the
+//! index) before every change to their supply shares, so shares only earn
+//! for the time they were held.
+//!
+//! **Undistributed emission:** Nothing accrues while the market has no
+//! supply shares, and dead shares earn rewards nobody can claim; both stay
+//! in the reward vault.
+
+use anchor_lang::prelude::*;
+
+use crate::error::PelagoError;
+use crate::state::{Market, UserPosition};
+use crate::utils::interest::WAD;
+
+/// Advances the reward index over `elapsed` seconds
+///
+/// Called by the interest accrual for exactly the time it moves
+/// `last_update` forward.
+pub fn accrue_rewards(market: &mut Market, elapsed: i64) -> Result<()> {
+    if market.reward_rate_per_second == 0 || market.total_supply_shares == 0 || elapsed <= 0 {
+        return Ok(());
+    }
+
+    let delta = (market.reward_rate_per_second as u128)
+        .checked_mul(elapsed as u128)
+        .ok_or(PelagoError::MathOverflow)?
+        .checked_mul(WAD)
+        .ok_or(PelagoError::MathOverflow)?
+        / market.total_supply_shares as u128;
+    market.reward_index = market
+        .reward_index
+        .checked_add(delta)
+        .ok_or(PelagoError::MathOverflow)?;
+    Ok(())
+}
+
+/// Rewards the position could claim at the market's current index
+pub fn pending_rewards(market: &Market, position: &UserPosition) -> Result<u64> {
+    let earned = (position.supply_shares as u128)
+        .checked_mul(market.reward_index.saturating_sub(position.reward_index_checkpoint))
+        .ok_or(PelagoError::MathOverflow)?
+        / WAD;
+    u64::try_from(earned)
+        .ok()
+        .and_then(|earned| position.reward_accrued.checked_add(earned))
+        .ok_or(PelagoError::MathOverflow.into())
+}
+
+/// Books the position's earned rewards and moves its checkpoint to the index
+///
+/// Must run after the market is accrued and before the position's supply
+/// shares change.
+pub fn settle_rewards(market: &Market, position: &mut UserPosition) -> Result<()> {
+    position.reward_accrued = pending_rewards(market, position)?;
+    position.reward_index_checkpoint = market.reward_index;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::interest::accrue_interest_at;
+
+    /// Supplies `shares` for `position` the way the supply handler does
+    fn supply(market: &mut Market, position: &mut UserPosition, shares: u64, now: i64) {
+        accrue_interest_at(market, now).unwrap();
+        settle_rewards(market, position).unwrap();
+        position.supply_shares += shares;
+        market.total_supply_shares += shares;
+        market.total_supply_assets += shares / 1_000_000;
+    }
+
+    /// Books everything earned up to `now`, as claim_rewards does
+    fn claimable(market: &mut Market, position: &mut UserPosition, now: i64) -> u64 {
+        accrue_interest_at(market, now).unwrap();
+        settle_rewards(market, position).unwrap();
+        position.reward_accrued
+    }
+
+    #[test]
+    fn test_rewards_follow_supply_share_time() {
+        let mut market = Market {
+            reward_rate_per_second: 1_000,
+            ..Default::default()
+        };
+        let mut alice = UserPosition::default();
+        let mut bob = UserPosition::default();
+
+        // Alice alone for 100s, then Bob supplies three times her shares
+        supply(&mut market, &mut alice, 100_000_000, 0);
+        supply(&mut market, &mut bob, 300_000_000, 100);
+
+        // Alice: 100s × 1000 + 100s × 1000 / 4; Bob: 100s × 1000 × 3/4
+        assert_eq!(claimable(&mut market, &mut alice, 200), 125_000);
+        assert_eq!(claimable(&mut market, &mut bob, 200), 75_000);
+
+        // Bob joining late earned nothing for the first 100s, and settling
+        // again at the same index books nothing twice
+        assert_eq!(claimable(&mut market, &mut bob, 200), 75_000);
+    }
+
+    #[test]
+    fn test_no_rewards_without_suppliers() {
+        let mut market = Market {
+            reward_rate_per_second: 1_000,
+            ..Default::default()
+        };
+        accrue_interest_at(&mut market, 1_000).unwrap();
+        assert_eq!(market.reward_index, 0);
+
+        // The emission before the first supply is not back-dated
+        let mut alice = UserPosition::default();
+        supply(&mut market, &mut alice, 100_000_000, 1_000);
+        assert_eq!(claimable(&mut market, &mut alice, 1_010), 10_000);
+    }
+}
@@ -13,6 +13,9 @@
 //! 2. Small deposits don't lose value due to rounding errors
 //! 3. Attack cost (≈1B tokens) far exceeds potential gains
 //!
+//! **Audit Feature:** Building with `--features audit-events` makes every conversion
+//! emit a `SharesMathAuditEvent` (inputs, rounding direction, result).
+//!
 //! **Reference:** SharesMathLib.sol (Pelago)
 //! **OpenZeppelin Documentation:** https://docs.openzeppelin.com/contracts/4.x/erc4626#inflation-attack
 
@@ -77,7 +80,12 @@ pub fn to_shares_down(
     let shares = numerator / total_assets_u128; // Rounding down via integer division
 
     // Safely convert back to u64
-    u64::try_from(shares).map_err(|_| PelagoError::MathOverflow.into())
+    let shares = u64::try_from(shares).map_err(|_| PelagoError::MathOverflow)?;
+
+    #[cfg(feature = "audit-events")]
+    emit_audit_event(audit_event(SharesMathOp::ToShares, Rounding::Down, assets, total_assets, total_shares, shares));
+
+    Ok(shares)
 }
 
 /// Converts assets to shares with rounding up
@@ -120,7 +128,12 @@ pub fn to_shares_up(
         .ok_or(PelagoError::MathOverflow)?
         / total_assets_u128;
 
-    u64::try_from(shares).map_err(|_| PelagoError::MathOverflow.into())
+    let shares = u64::try_from(shares).map_err(|_| PelagoError::MathOverflow)?;
+
+    #[cfg(feature = "audit-events")]
+    emit_audit_event(audit_event(SharesMathOp::ToShares, Rounding::Up, assets, total_assets, total_shares, shares));
+
+    Ok(shares)
 }
 
 /// Converts shares to assets with rounding down
@@ -159,7 +172,12 @@ pub fn to_assets_down(
 
     let assets = numerator / total_shares_u128; // Rounding down
 
-    u64::try_from(assets).map_err(|_| PelagoError::MathOverflow.into())
+    let assets = u64::try_from(assets).map_err(|_| PelagoError::MathOverflow)?;
+
+    #[cfg(feature = "audit-events")]
+    emit_audit_event(audit_event(SharesMathOp::ToAssets, Rounding::Down, shares, total_assets, total_shares, assets));
+
+    Ok(assets)
 }
 
 /// Converts shares to assets with rounding up
@@ -202,13 +220,167 @@ pub fn to_assets_up(
         .ok_or(PelagoError::MathOverflow)?
         / total_shares_u128;
 
-    u64::try_from(assets).map_err(|_| PelagoError::MathOverflow.into())
+    let assets = u64::try_from(assets).map_err(|_| PelagoError::MathOverflow)?;
+
+    #[cfg(feature = "audit-events")]
+    emit_audit_event(audit_event(SharesMathOp::ToAssets, Rounding::Up, shares, total_assets, total_shares, assets));
+
+    Ok(assets)
+}
+
+/// Largest market size assumed by the offset overflow analysis, in whole tokens
+///
+/// **Value:** 1,000,000,000 (1e9 whole tokens)
+///
+/// Together with the mint decimals this bounds `total_assets` in
+/// `validate_virtual_offsets`; any larger amount is capped at `u64::MAX`.
+pub const MAX_MARKET_TOKENS: u128 = 1_000_000_000;
+
+/// Validates that virtual offsets cannot overflow the conversion math
+///
+/// Both conversions multiply a u64 amount by an offset total in u128:
+/// ```text
+/// to_shares: assets × (total_shares + virtual_shares)
+/// to_assets: shares × (total_assets + virtual_assets)
+/// ```
+///
+/// **Worst Case Derivation:**
+/// - `max_assets = min(10^decimals × MAX_MARKET_TOKENS, u64::MAX)`: largest
+///   `assets` input and `total_assets` the market is expected to hold
+/// - `max_shares = u64::MAX`: share totals and inputs are stored as u64
+/// - Both products must fit in u128:
+///   `max_assets × (max_shares + virtual_shares) ≤ u128::MAX` and
+///   `max_shares × (max_assets + virtual_assets) ≤ u128::MAX`
+///
+/// With the default offsets (1e6 / 1) this accepts loan tokens with up to
+/// 10 decimals. Every call still uses checked math, so an overflow would
+/// surface as MathOverflow; this check rejects such markets up front
+/// instead of letting them fail once they grow.
+///
+/// **Errors:**
+/// - UnsafeVirtualOffsets: zero offset or a worst-case product overflows
+pub fn validate_virtual_offsets(
+    virtual_shares: u128,
+    virtual_assets: u128,
+    decimals: u8,
+) -> Result<()> {
+    require!(
+        virtual_shares > 0 && virtual_assets > 0,
+        PelagoError::UnsafeVirtualOffsets
+    );
+
+    let max_shares = u64::MAX as u128;
+    let max_assets = 10u128
+        .checked_pow(decimals as u32)
+        .and_then(|unit| unit.checked_mul(MAX_MARKET_TOKENS))
+        .map_or(max_shares, |assets| assets.min(max_shares));
+
+    let to_shares_product = max_shares
+        .checked_add(virtual_shares)
+        .and_then(|shares| shares.checked_mul(max_assets));
+    let to_assets_product = max_assets
+        .checked_add(virtual_assets)
+        .and_then(|assets| assets.checked_mul(max_shares));
+
+    require!(
+        to_shares_product.is_some() && to_assets_product.is_some(),
+        PelagoError::UnsafeVirtualOffsets
+    );
+
+    Ok(())
+}
+
+/// Conversion performed by a shares math call (audit-events feature)
+#[cfg(feature = "audit-events")]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SharesMathOp {
+    /// Assets → shares (`to_shares_down` / `to_shares_up`)
+    ToShares,
+    /// Shares → assets (`to_assets_down` / `to_assets_up`)
+    ToAssets,
+}
+
+/// Rounding direction applied by a shares math call (audit-events feature)
+#[cfg(feature = "audit-events")]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Integer division (floor)
+    Down,
+    /// Ceiling division
+    Up,
+}
+
+/// Event emitted on every shares math conversion (audit-events feature)
+///
+/// Lets auditors trace how much value rounding moved in each operation by
+/// comparing `result` against the exact rational conversion of `input`.
+///
+/// **Feature Gate:** Only compiled with `--features audit-events`.
+/// Production builds contain neither the event nor the emit calls.
+#[cfg(feature = "audit-events")]
+#[event]
+pub struct SharesMathAuditEvent {
+    /// Conversion direction
+    pub op: SharesMathOp,
+
+    /// Rounding direction used
+    pub rounding: Rounding,
+
+    /// Input amount (assets for ToShares, shares for ToAssets)
+    pub input: u64,
+
+    /// Market total assets used in the conversion (before virtual offset)
+    pub total_assets: u64,
+
+    /// Market total shares used in the conversion (before virtual offset)
+    pub total_shares: u64,
+
+    /// Converted amount returned to the caller
+    pub result: u64,
+}
+
+/// Emits a shares math audit event
+///
+/// Test builds also record the logged payload, since `emit!` only writes to
+/// the program log on-chain.
+#[cfg(feature = "audit-events")]
+fn emit_audit_event(event: SharesMathAuditEvent) {
+    #[cfg(test)]
+    tests::AUDIT_LOG.with(|log| log.borrow_mut().push(anchor_lang::Event::data(&event)));
+    emit!(event);
+}
+
+/// Builds the audit event for a shares math conversion
+#[cfg(feature = "audit-events")]
+fn audit_event(
+    op: SharesMathOp,
+    rounding: Rounding,
+    input: u64,
+    total_assets: u64,
+    total_shares: u64,
+    result: u64,
+) -> SharesMathAuditEvent {
+    SharesMathAuditEvent {
+        op,
+        rounding,
+        input,
+        total_assets,
+        total_shares,
+        result,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "audit-events")]
+    thread_local! {
+        /// Audit event payloads logged on this test thread
+        pub static AUDIT_LOG: std::cell::RefCell<Vec<Vec<u8>>> =
+            const { std::cell::RefCell::new(Vec::new()) };
+    }
+
     #[test]
     fn test_to_shares_down_empty_market() {
         // First deposit: 1000 tokens in empty market
@@ -259,4 +431,107 @@ mod tests {
         // assets_up should be >= assets_down
         assert!(assets_up >= assets_down);
     }
+
+    #[test]
+    fn test_supply_withdraw_round_trip_loss_bounded() {
+        // (total_assets, total_shares): empty, 1:1e6, interest-grown,
+        // donation-inflated, share-heavy and large markets
+        let states = [
+            (0u64, 0u64),
+            (1_000_000, 1_000_000_000_000),
+            (1_051_267, 1_000_000_000_000),
+            (1_000_000_000, 1_000_000),
+            (1_000, 7_000_000_000),
+            (2_000_000_000_000, 1_900_000_000_000_000_000),
+        ];
+        let amounts = [1u64, 7, 999, 1_000_000, 123_456_789, 1_000_000_000_000];
+
+        for (total_assets, total_shares) in states {
+            for assets in amounts {
+                // Supply `assets` (shares rounded down)
+                let received = to_shares_down(assets, total_assets, total_shares).unwrap();
+                let assets_after = total_assets + assets;
+                let shares_after = total_shares + received;
+                let one_share = to_assets_up(1, assets_after, shares_after).unwrap();
+
+                // Withdrawing the same assets burns at most 1 share more than received
+                let burned = to_shares_up(assets, assets_after, shares_after).unwrap();
+                assert!(burned <= received + 1, "state ({total_assets}, {total_shares}), assets {assets}");
+
+                // Redeeming every received share returns the deposit minus at most 1 share's worth
+                let redeemed = to_assets_down(received, assets_after, shares_after).unwrap();
+                assert!(redeemed <= assets);
+                assert!(
+                    assets - redeemed <= one_share,
+                    "state ({total_assets}, {total_shares}), assets {assets}"
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "audit-events")]
+    #[test]
+    fn test_conversions_emit_audit_events() {
+        let shares = to_shares_up(7, 10, 15).unwrap();
+        let assets = to_assets_down(shares, 10, 15).unwrap();
+
+        // Decoded from the payloads the conversions logged, discriminator first
+        let events: Vec<SharesMathAuditEvent> = AUDIT_LOG.with(|log| {
+            log.take()
+                .iter()
+                .map(|data| {
+                    assert!(data.starts_with(SharesMathAuditEvent::DISCRIMINATOR));
+                    SharesMathAuditEvent::try_from_slice(&data[8..]).unwrap()
+                })
+                .collect()
+        });
+        assert_eq!(events.len(), 2);
+
+        assert_eq!(events[0].op, SharesMathOp::ToShares);
+        assert_eq!(events[0].rounding, Rounding::Up);
+        assert_eq!(events[0].input, 7);
+        assert_eq!(events[0].total_assets, 10);
+        assert_eq!(events[0].total_shares, 15);
+        assert_eq!(events[0].result, shares);
+
+        assert_eq!(events[1].op, SharesMathOp::ToAssets);
+        assert_eq!(events[1].rounding, Rounding::Down);
+        assert_eq!(events[1].input, shares);
+        assert_eq!(events[1].result, assets);
+    }
+
+    #[test]
+    fn test_default_offsets_safe_for_common_decimals() {
+        // USDC (6), SOL (9) and the 10-decimal limit
+        for decimals in [0, 6, 8, 9, 10] {
+            assert!(validate_virtual_offsets(VIRTUAL_SHARES, VIRTUAL_ASSETS, decimals).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_pathological_offsets_rejected() {
+        let unsafe_offsets = PelagoError::UnsafeVirtualOffsets.into();
+
+        // 18 decimals: max_assets caps at u64::MAX, so u64::MAX × (u64::MAX + 1e6) overflows
+        assert_eq!(
+            validate_virtual_offsets(VIRTUAL_SHARES, VIRTUAL_ASSETS, 18).unwrap_err(),
+            unsafe_offsets
+        );
+
+        // Oversized virtual shares overflow even for a 6-decimal token
+        assert_eq!(
+            validate_virtual_offsets(1u128 << 100, VIRTUAL_ASSETS, 6).unwrap_err(),
+            unsafe_offsets
+        );
+
+        // Zero offsets disable the inflation protection entirely
+        assert_eq!(
+            validate_virtual_offsets(0, VIRTUAL_ASSETS, 6).unwrap_err(),
+            unsafe_offsets
+        );
+        assert_eq!(
+            validate_virtual_offsets(VIRTUAL_SHARES, 0, 6).unwrap_err(),
+            unsafe_offsets
+        );
+    }
 }
@@ -0,0 +1,12 @@
+//! Deterministic Test Randomness
+//!
+//! Tiny linear congruential generator shared by the randomized unit tests,
+//! so they need no extra crates and every failure reproduces from its seed.
+
+/// Advances `seed` and returns the next pseudo-random value (31 bits)
+pub fn next(seed: &mut u64) -> u64 {
+    *seed = seed
+        .wrapping_mul(6_364_136_223_846_793_005)
+        .wrapping_add(1_442_695_040_888_963_407);
+    *seed >> 33
+}
@@ -0,0 +1,122 @@
+//! Time-Weighted Average Price
+//!
+//! Smooths the collateral price over a short window so a single manipulated
+//! observation can't swing health checks within one block.
+//!
+//! **Model:** Each sample's price holds from its timestamp until the next
+//! sample (or `now` for the latest). The TWAP is the time-weighted mean of
+//! that step function over `[now - window, now]`.
+//!
+//! **Warm-up:** If the oldest sample is newer than `now - window`, the
+//! history doesn't cover the window yet and `twap` returns `None`; callers
+//! fall back to the spot price.
+//!
+//! **Staleness:** The TWAP cached on the market is only as fresh as the last
+//! `update_price_history` crank. Accrual drops it once it is older than the
+//! window (`expire_stale_twap`), so health checks never price collateral at
+//! an average that no longer covers the last `window` seconds.
+
+use crate::state::{Market, PriceHistory, PriceSample};
+
+/// Minimum seconds between two recorded samples for `window`
+///
+/// `window / CAPACITY` (at least 1s), so the ring buffer always spans the
+/// whole window and rapid cranks can't overwrite the history.
+pub fn min_sample_spacing(window: u32) -> i64 {
+    (window as i64 / PriceHistory::CAPACITY as i64).max(1)
+}
+
+/// Drops `market.twap_price` if it was computed more than `twap_window`
+/// seconds before `now`; `collateral_price` then falls back to spot
+pub fn expire_stale_twap(market: &mut Market, now: i64) {
+    if market.twap_price > 0
+        && now.saturating_sub(market.twap_updated_at) > market.twap_window as i64
+    {
+        market.twap_price = 0;
+    }
+}
+
+/// Computes the TWAP over `[now - window, now]`
+///
+/// **Parameters:**
+/// - `samples`: Observations, oldest first
+/// - `now`: Current unix timestamp
+/// - `window`: Averaging window in seconds
+///
+/// **Returns:** `None` if `window == 0`, there are no samples, or the
+/// history doesn't cover the full window yet (warm-up)
+pub fn twap(samples: &[PriceSample], now: i64, window: u32) -> Option<u64> {
+    if window == 0 {
+        return None;
+    }
+    let start = now.checked_sub(window as i64)?;
+
+    // Warm-up: the oldest sample must already be in effect at `start`
+    if samples.first()?.timestamp > start {
+        return None;
+    }
+
+    let mut weighted_sum: u128 = 0;
+    for (i, sample) in samples.iter().enumerate() {
+        let segment_end = samples.get(i + 1).map_or(now, |next| next.timestamp).min(now);
+        let segment_start = sample.timestamp.max(start);
+        if segment_end > segment_start {
+            weighted_sum += sample.price as u128 * (segment_end - segment_start) as u128;
+        }
+    }
+
+    u64::try_from(weighted_sum / window as u128).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(price: u64, timestamp: i64) -> PriceSample {
+        PriceSample { price, timestamp }
+    }
+
+    #[test]
+    fn test_twap_lags_sudden_spike() {
+        // Flat at 100 USDC/SOL, then a spike to 200 one minute before `now`
+        let samples = [
+            sample(100_000, 0),
+            sample(100_000, 1_800),
+            sample(200_000, 3_540),
+        ];
+
+        // 540s at 100 + 60s at 200 over a 600s window = 110
+        assert_eq!(twap(&samples, 3_600, 600), Some(110_000));
+    }
+
+    #[test]
+    fn test_stale_cached_twap_expires() {
+        let mut market = Market {
+            twap_window: 600,
+            twap_price: 110_000,
+            twap_updated_at: 3_600,
+            fixed_price: 100_000,
+            ..Default::default()
+        };
+
+        // Within the window the cached TWAP prices collateral
+        expire_stale_twap(&mut market, 4_200);
+        assert_eq!(market.collateral_price(), 110_000);
+
+        // The keeper stopped cranking: spot takes over
+        expire_stale_twap(&mut market, 4_201);
+        assert_eq!(market.twap_price, 0);
+        assert_eq!(market.collateral_price(), 100_000);
+    }
+
+    #[test]
+    fn test_twap_warm_up_falls_back() {
+        let samples = [sample(100_000, 3_300)];
+        assert_eq!(twap(&samples, 3_600, 600), None);
+        assert_eq!(twap(&[], 3_600, 600), None);
+        assert_eq!(twap(&samples, 3_600, 0), None);
+
+        // Once the window is covered the TWAP is available
+        assert_eq!(twap(&samples, 3_900, 600), Some(100_000));
+    }
+}
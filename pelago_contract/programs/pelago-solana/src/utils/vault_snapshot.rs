@@ -0,0 +1,195 @@
+//! Token Balance Snapshots Around CPIs
+//!
+//! Anchor deserializes `Account<TokenAccount>` once, when the instruction
+//! starts. A CPI that moves tokens (swap callback, flash-loan receiver,
+//! liquidation swap) changes the underlying account data but NOT the
+//! deserialized copy, so reading `.amount` after the CPI returns stale data.
+//!
+//! `snapshot_balance_delta` records the balance, runs the callback, then
+//! `reload()`s the account so repayment/slippage checks always use the
+//! post-CPI balance.
+//!
+//! Plain token transfers go through it too: `require_increase` /
+//! `require_decrease` check that a vault moved by exactly the requested
+//! amount, so a mint that skims a fee on transfer fails loudly instead of
+//! leaving the market's accounting ahead of its vault.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+use crate::error::PelagoError;
+
+/// Token account balance before and after a callback
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BalanceDelta {
+    /// Balance before the callback ran
+    pub before: u64,
+
+    /// Balance after the callback, read from the reloaded account
+    pub after: u64,
+}
+
+impl BalanceDelta {
+    /// Tokens gained during the callback (`None` if the balance decreased)
+    pub fn increase(&self) -> Option<u64> {
+        self.after.checked_sub(self.before)
+    }
+
+    /// Tokens lost during the callback (`None` if the balance increased)
+    pub fn decrease(&self) -> Option<u64> {
+        self.before.checked_sub(self.after)
+    }
+
+    /// Requires the balance to have grown by exactly `amount`
+    ///
+    /// **Errors:**
+    /// - TransferAmountMismatch: the account received more or less
+    pub fn require_increase(&self, amount: u64) -> Result<()> {
+        self.require_change(self.increase(), amount)
+    }
+
+    /// Requires the balance to have shrunk by exactly `amount`
+    ///
+    /// **Errors:**
+    /// - TransferAmountMismatch: the account sent more or less
+    pub fn require_decrease(&self, amount: u64) -> Result<()> {
+        self.require_change(self.decrease(), amount)
+    }
+
+    fn require_change(&self, change: Option<u64>, amount: u64) -> Result<()> {
+        if change != Some(amount) {
+            msg!(
+                "Transfer amount mismatch: expected={}, before={}, after={}",
+                amount,
+                self.before,
+                self.after
+            );
+            return err!(PelagoError::TransferAmountMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Runs `callback` and returns how `account`'s balance moved across it
+///
+/// **Parameters:**
+/// - `account`: Token account to measure (reloaded after the callback)
+/// - `callback`: Typically a CPI into a swap or flash-loan receiver program
+///
+/// **Errors:**
+/// - Any error returned by `callback`
+/// - Deserialization errors from `reload()`
+pub fn snapshot_balance_delta<'info, F>(
+    account: &mut Account<'info, TokenAccount>,
+    callback: F,
+) -> Result<BalanceDelta>
+where
+    F: FnOnce() -> Result<()>,
+{
+    let before = account.amount;
+    callback()?;
+    account.reload()?;
+
+    Ok(BalanceDelta {
+        before,
+        after: account.amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_spl::token::spl_token::state::{Account as SplAccount, AccountState};
+
+    fn pack_token_account(amount: u64, data: &mut [u8]) {
+        let state = SplAccount {
+            mint: Pubkey::new_from_array([1; 32]),
+            owner: Pubkey::new_from_array([2; 32]),
+            amount,
+            state: AccountState::Initialized,
+            ..Default::default()
+        };
+        SplAccount::pack(state, data).unwrap();
+    }
+
+    #[test]
+    fn test_delta_reads_post_callback_balance() {
+        let key = Pubkey::new_unique();
+        let owner = anchor_spl::token::ID;
+        let mut lamports = 1_000_000;
+        let mut data = vec![0u8; SplAccount::LEN];
+        pack_token_account(100, &mut data);
+
+        let info = AccountInfo::new(
+            &key, false, true, &mut lamports, &mut data, &owner, false, 0,
+        );
+        let mut account: Account<TokenAccount> = Account::try_from(&info).unwrap();
+
+        // Callback simulates a CPI crediting 250 tokens to the account
+        let delta = snapshot_balance_delta(&mut account, || {
+            pack_token_account(350, &mut info.try_borrow_mut_data()?);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(delta, BalanceDelta { before: 100, after: 350 });
+        assert_eq!(delta.increase(), Some(250));
+        assert_eq!(delta.decrease(), None);
+        assert_eq!(account.amount, 350);
+    }
+
+    #[test]
+    fn test_short_transfer_detected() {
+        let key = Pubkey::new_unique();
+        let owner = anchor_spl::token::ID;
+        let mut lamports = 1_000_000;
+        let mut data = vec![0u8; SplAccount::LEN];
+        pack_token_account(1_000, &mut data);
+
+        let info = AccountInfo::new(
+            &key, false, true, &mut lamports, &mut data, &owner, false, 0,
+        );
+        let mut account: Account<TokenAccount> = Account::try_from(&info).unwrap();
+
+        // A fee-bearing mint delivers 99 of the 100 tokens sent to the vault
+        let delta = snapshot_balance_delta(&mut account, || {
+            pack_token_account(1_099, &mut info.try_borrow_mut_data()?);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            delta.require_increase(100).unwrap_err(),
+            PelagoError::TransferAmountMismatch.into()
+        );
+        assert!(delta.require_increase(99).is_ok());
+
+        // Outgoing transfers must debit exactly the amount as well
+        let sent = BalanceDelta { before: 1_099, after: 999 };
+        assert!(sent.require_decrease(100).is_ok());
+        assert!(sent.require_decrease(101).is_err());
+        assert!(sent.require_increase(100).is_err());
+    }
+
+    #[test]
+    fn test_callback_error_propagates() {
+        let key = Pubkey::new_unique();
+        let owner = anchor_spl::token::ID;
+        let mut lamports = 1_000_000;
+        let mut data = vec![0u8; SplAccount::LEN];
+        pack_token_account(100, &mut data);
+
+        let info = AccountInfo::new(
+            &key, false, true, &mut lamports, &mut data, &owner, false, 0,
+        );
+        let mut account: Account<TokenAccount> = Account::try_from(&info).unwrap();
+
+        let res = snapshot_balance_delta(&mut account, || {
+            err!(crate::error::PelagoError::SlippageExceeded)
+        });
+        assert_eq!(
+            res.unwrap_err(),
+            crate::error::PelagoError::SlippageExceeded.into()
+        );
+    }
+}
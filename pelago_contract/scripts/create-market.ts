@@ -12,6 +12,13 @@ import * as fs from "fs";
 
 // LLTV: 80% (即需要 125% 超额抵押)
 const LLTV = 80_000_000; // 80% in basis points (100_000_000 = 100%)
+// Rate model for initializeMarket: all zero keeps the fixed 5% rate
+const FIXED_RATE_IRM = {
+  baseRateWad: new anchor.BN(0),
+  slope1Wad: new anchor.BN(0),
+  slope2Wad: new anchor.BN(0),
+  kinkUtilizationBps: 0,
+};
 
 async function main() {
   console.log("🚀 开始创建 Pelago Solana 测试市场...\n");
@@ -116,6 +123,12 @@ async function main() {
   );
   console.log(`  ✅ Market PDA: ${marketPda.toBase58()}\n`);
 
+  // ProtocolConfig 必须已存在且启用了该 LLTV (enable_lltv)
+  const [protocolConfigPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("protocol-config")],
+    program.programId
+  );
+
   // Step 4: 生成 Vault Keypairs
   console.log("📦 Step 4: 生成 Vault Keypairs...");
   const loanVault = Keypair.generate();
@@ -127,7 +140,7 @@ async function main() {
   console.log("📦 Step 5: 初始化市场...");
   try {
     const tx = await program.methods
-      .initializeMarket(new anchor.BN(LLTV))
+      .initializeMarket(new anchor.BN(LLTV), new anchor.BN(0), new anchor.BN(0), 0, FIXED_RATE_IRM)
       .accounts({
         market: marketPda,
         loanTokenMint: loanTokenMint,
@@ -138,6 +151,7 @@ async function main() {
         systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        protocolConfig: protocolConfigPda,
       })
       .signers([loanVault, collateralVault])
       .rpc();
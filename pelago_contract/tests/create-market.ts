@@ -11,6 +11,13 @@ import * as fs from "fs";
 
 // LLTV: 80% (即需要 125% 超额抵押)
 const LLTV = 80_000_000;
+// Rate model for initializeMarket: all zero keeps the fixed 5% rate
+const FIXED_RATE_IRM = {
+  baseRateWad: new anchor.BN(0),
+  slope1Wad: new anchor.BN(0),
+  slope2Wad: new anchor.BN(0),
+  kinkUtilizationBps: 0,
+};
 
 describe("Create Market for Testing", () => {
   const provider = anchor.AnchorProvider.env();
@@ -19,6 +26,63 @@ describe("Create Market for Testing", () => {
   const program = anchor.workspace.PelagoSolana as Program<PelagoSolana>;
   const authority = provider.wallet as anchor.Wallet;
 
+  const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const [protocolConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("protocol-config")],
+    program.programId
+  );
+  // initialize_config is restricted to the program's upgrade authority
+  const [programDataPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
+  // initialize_market checks every LLTV against the ProtocolConfig
+  // whitelist: create the config singletons if no earlier suite did, with
+  // permissive bounds, and enable `lltv`
+  async function ensureProtocolConfig(lltv: number) {
+    if (!(await provider.connection.getAccountInfo(configPda))) {
+      await program.methods
+        .initializeConfig(authority.publicKey)
+        .accounts({
+          config: configPda,
+          admin: authority.publicKey,
+          program: program.programId,
+          programData: programDataPda,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+    if (!(await provider.connection.getAccountInfo(protocolConfigPda))) {
+      await program.methods
+        .setProtocolConfig(
+          0,
+          authority.publicKey,
+          new anchor.BN(100_000_000), // MAX_LLTV
+          new anchor.BN(0),
+          new anchor.BN("1000000000000000000") // 100%
+        )
+        .accounts({
+          config: configPda,
+          protocolConfig: protocolConfigPda,
+          admin: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+    await program.methods
+      .enableLltv(new anchor.BN(lltv))
+      .accounts({
+        config: configPda,
+        protocolConfig: protocolConfigPda,
+        admin: authority.publicKey,
+      })
+      .rpc();
+  }
+
   it("创建测试市场", async () => {
     console.log("\n🚀 开始创建 Pelago Solana 测试市场...\n");
 
@@ -120,10 +184,13 @@ describe("Create Market for Testing", () => {
     console.log(`  ✅ Loan Vault: ${loanVault.publicKey.toBase58()}`);
     console.log(`  ✅ Collateral Vault: ${collateralVault.publicKey.toBase58()}\n`);
 
+    // initialize_market 要求 ProtocolConfig 中已启用该 LLTV
+    await ensureProtocolConfig(LLTV);
+
     // Step 5: 初始化市场
     console.log("📦 Step 5: 初始化市场...");
     const tx = await program.methods
-      .initializeMarket(new anchor.BN(LLTV))
+      .initializeMarket(new anchor.BN(LLTV), new anchor.BN(0), new anchor.BN(0), 0, FIXED_RATE_IRM)
       .accounts({
         market: marketPda,
         loanTokenMint: loanTokenMint,
@@ -134,6 +201,7 @@ describe("Create Market for Testing", () => {
         systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        protocolConfig: protocolConfigPda,
       })
       .signers([loanVault, collateralVault])
       .rpc();
@@ -15,7 +15,7 @@ import { assert } from "chai";
  *
  * Tests the new P1 features:
  * - Virtual shares mechanism (inflation attack prevention)
- * - Interest accrual (linear interest at 5% annual rate)
+ * - Interest accrual (5% annual rate, compounded)
  * - Withdraw instruction (with virtual shares)
  * - Repay instruction (with virtual shares and third-party support)
  * - WithdrawCollateral instruction (with health check)
@@ -30,11 +30,75 @@ describe("pelago-solana-p1", () => {
   // Constants
   const LLTV_PRECISION = 100_000_000;
   const LLTV = 0.8 * LLTV_PRECISION; // 80%
+  // Rate model for initializeMarket: all zero keeps the fixed 5% rate
+  const FIXED_RATE_IRM = {
+    baseRateWad: new anchor.BN(0),
+    slope1Wad: new anchor.BN(0),
+    slope2Wad: new anchor.BN(0),
+    kinkUtilizationBps: 0,
+  };
   const USDC_DECIMALS = 6;
   const SOL_DECIMALS = 9;
   const VIRTUAL_SHARES = 1_000_000;
   const FIXED_ORACLE_PRICE = 100; // 100 USDC per SOL
 
+  const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const [protocolConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("protocol-config")],
+    program.programId
+  );
+  // initialize_config is restricted to the program's upgrade authority
+  const [programDataPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
+  // initialize_market checks every LLTV against the ProtocolConfig
+  // whitelist: create the config singletons if no earlier suite did, with
+  // permissive bounds, and enable `lltv`
+  async function ensureProtocolConfig(lltv: number) {
+    if (!(await provider.connection.getAccountInfo(configPda))) {
+      await program.methods
+        .initializeConfig(authority.publicKey)
+        .accounts({
+          config: configPda,
+          admin: authority.publicKey,
+          program: program.programId,
+          programData: programDataPda,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+    if (!(await provider.connection.getAccountInfo(protocolConfigPda))) {
+      await program.methods
+        .setProtocolConfig(
+          0,
+          authority.publicKey,
+          new anchor.BN(100_000_000), // MAX_LLTV
+          new anchor.BN(0),
+          new anchor.BN("1000000000000000000") // 100%
+        )
+        .accounts({
+          config: configPda,
+          protocolConfig: protocolConfigPda,
+          admin: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+    await program.methods
+      .enableLltv(new anchor.BN(lltv))
+      .accounts({
+        config: configPda,
+        protocolConfig: protocolConfigPda,
+        admin: authority.publicKey,
+      })
+      .rpc();
+  }
+
   let loanTokenMint: anchor.web3.PublicKey;
   let collateralTokenMint: anchor.web3.PublicKey;
   let marketPda: anchor.web3.PublicKey;
@@ -74,9 +138,11 @@ describe("pelago-solana-p1", () => {
     loanVault = anchor.web3.Keypair.generate();
     collateralVault = anchor.web3.Keypair.generate();
 
+    await ensureProtocolConfig(LLTV);
+
     // Initialize market
     await program.methods
-      .initializeMarket(new anchor.BN(LLTV))
+      .initializeMarket(new anchor.BN(LLTV), new anchor.BN(0), new anchor.BN(0), 0, FIXED_RATE_IRM)
       .accounts({
         market: marketPda,
         loanTokenMint: loanTokenMint,
@@ -87,6 +153,7 @@ describe("pelago-solana-p1", () => {
         systemProgram: anchor.web3.SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        protocolConfig: protocolConfigPda,
       })
       .signers([loanVault, collateralVault])
       .rpc();
@@ -132,7 +199,7 @@ describe("pelago-solana-p1", () => {
       // Supply 100 USDC
       const supplyAmount = 100_000_000; // 100 USDC
       await program.methods
-        .supply(new anchor.BN(supplyAmount), new anchor.BN(0))
+        .supply(new anchor.BN(supplyAmount), new anchor.BN(0), new anchor.BN(0))
         .accounts({
           market: marketPda,
           userPosition: alicePositionPda,
@@ -203,7 +270,7 @@ describe("pelago-solana-p1", () => {
       // Supply same amount (100 USDC)
       const supplyAmount = 100_000_000;
       await program.methods
-        .supply(new anchor.BN(supplyAmount), new anchor.BN(0))
+        .supply(new anchor.BN(supplyAmount), new anchor.BN(0), new anchor.BN(0))
         .accounts({
           market: marketPda,
           userPosition: bobPositionPda,
@@ -280,7 +347,7 @@ describe("pelago-solana-p1", () => {
 
       // Supply 1000 USDC
       await program.methods
-        .supply(new anchor.BN(1000_000_000), new anchor.BN(0))
+        .supply(new anchor.BN(1000_000_000), new anchor.BN(0), new anchor.BN(0))
         .accounts({
           market: marketPda,
           userPosition: charliePositionPda,
@@ -399,14 +466,14 @@ describe("pelago-solana-p1", () => {
           })
           .signers([charlie])
           .rpc();
-        assert.fail("Should have failed with InsufficientLiquidity or InsufficientSupply");
+        assert.fail("Should have failed with WithdrawBreaksLiquidity or InsufficientSupply");
       } catch (error) {
         const errorStr = error.toString();
-        // Accept either InsufficientLiquidity or InsufficientSupply
+        // Accept either WithdrawBreaksLiquidity or InsufficientSupply
         // (depends on which check fails first)
         assert.isTrue(
-          errorStr.includes("InsufficientLiquidity") || errorStr.includes("InsufficientSupply"),
-          `Expected InsufficientLiquidity or InsufficientSupply, got: ${errorStr}`
+          errorStr.includes("WithdrawBreaksLiquidity") || errorStr.includes("InsufficientSupply"),
+          `Expected WithdrawBreaksLiquidity or InsufficientSupply, got: ${errorStr}`
         );
       }
     });
@@ -472,7 +539,7 @@ describe("pelago-solana-p1", () => {
 
       // Supply 1000 USDC
       await program.methods
-        .supply(new anchor.BN(1000_000_000), new anchor.BN(0))
+        .supply(new anchor.BN(1000_000_000), new anchor.BN(0), new anchor.BN(0))
         .accounts({
           market: marketPda,
           userPosition: davePositionPda,
@@ -502,7 +569,7 @@ describe("pelago-solana-p1", () => {
 
       // Borrow 500 USDC
       await program.methods
-        .borrow(new anchor.BN(500_000_000), new anchor.BN(0))
+        .borrow(new anchor.BN(500_000_000), new anchor.BN(0), false)
         .accounts({
           market: marketPda,
           userPosition: davePositionPda,
@@ -523,10 +590,11 @@ describe("pelago-solana-p1", () => {
       );
 
       await program.methods
-        .repay(new anchor.BN(repayAmount), new anchor.BN(0))
+        .repay(new anchor.BN(repayAmount), new anchor.BN(0), false)
         .accounts({
           market: marketPda,
           borrowerPosition: davePositionPda,
+          payerPosition: null,
           payer: dave.publicKey,
           borrower: dave.publicKey,
           payerTokenAccount: daveLoanAta.address,
@@ -554,10 +622,11 @@ describe("pelago-solana-p1", () => {
       const sharesToBurn = position.borrowShares.divn(2); // Repay half
 
       await program.methods
-        .repay(new anchor.BN(0), sharesToBurn)
+        .repay(new anchor.BN(0), sharesToBurn, false)
         .accounts({
           market: marketPda,
           borrowerPosition: davePositionPda,
+          payerPosition: null,
           payer: dave.publicKey,
           borrower: dave.publicKey,
           payerTokenAccount: daveLoanAta.address,
@@ -620,10 +689,11 @@ describe("pelago-solana-p1", () => {
 
       // Eve repays for Dave
       await program.methods
-        .repay(new anchor.BN(50_000_000), new anchor.BN(0)) // 50 USDC
+        .repay(new anchor.BN(50_000_000), new anchor.BN(0), false) // 50 USDC
         .accounts({
           market: marketPda,
           borrowerPosition: davePositionPda,
+          payerPosition: null,
           payer: eve.publicKey, // Eve is payer
           borrower: dave.publicKey, // Dave is borrower
           payerTokenAccount: eveLoanAta.address,
@@ -653,10 +723,11 @@ describe("pelago-solana-p1", () => {
       const massiveShareRepay = position.borrowShares.muln(10); // 10x current debt
 
       await program.methods
-        .repay(new anchor.BN(0), massiveShareRepay)
+        .repay(new anchor.BN(0), massiveShareRepay, false)
         .accounts({
           market: marketPda,
           borrowerPosition: davePositionPda,
+          payerPosition: null,
           payer: dave.publicKey,
           borrower: dave.publicKey,
           payerTokenAccount: daveLoanAta.address,
@@ -738,7 +809,7 @@ describe("pelago-solana-p1", () => {
 
       // Supply 1000 USDC
       await program.methods
-        .supply(new anchor.BN(1000_000_000), new anchor.BN(0))
+        .supply(new anchor.BN(1000_000_000), new anchor.BN(0), new anchor.BN(0))
         .accounts({
           market: marketPda,
           userPosition: frankPositionPda,
@@ -768,7 +839,7 @@ describe("pelago-solana-p1", () => {
 
       // Borrow 1500 USDC (20 SOL × 100 USDC × 0.8 = 1600 max, so 1500 is safe)
       await program.methods
-        .borrow(new anchor.BN(1500_000_000), new anchor.BN(0))
+        .borrow(new anchor.BN(1500_000_000), new anchor.BN(0), false)
         .accounts({
           market: marketPda,
           userPosition: frankPositionPda,
@@ -852,10 +923,11 @@ describe("pelago-solana-p1", () => {
 
       // Repay by burning ALL shares (not by asset amount)
       await program.methods
-        .repay(new anchor.BN(0), positionBefore.borrowShares) // Use shares path
+        .repay(new anchor.BN(0), positionBefore.borrowShares, false) // Use shares path
         .accounts({
           market: marketPda,
           borrowerPosition: frankPositionPda,
+          payerPosition: null,
           payer: frank.publicKey,
           borrower: frank.publicKey,
           payerTokenAccount: frankLoanAta.address,
@@ -979,7 +1051,7 @@ describe("pelago-solana-p1", () => {
 
       // Supply 2000 USDC
       await program.methods
-        .supply(new anchor.BN(2000_000_000), new anchor.BN(0))
+        .supply(new anchor.BN(2000_000_000), new anchor.BN(0), new anchor.BN(0))
         .accounts({
           market: marketPda,
           userPosition: gracePositionPda,
@@ -1010,7 +1082,7 @@ describe("pelago-solana-p1", () => {
       // Borrow 1000 USDC
       const borrowAmount = 1000_000_000;
       await program.methods
-        .borrow(new anchor.BN(borrowAmount), new anchor.BN(0))
+        .borrow(new anchor.BN(borrowAmount), new anchor.BN(0), false)
         .accounts({
           market: marketPda,
           userPosition: gracePositionPda,
@@ -1033,7 +1105,7 @@ describe("pelago-solana-p1", () => {
 
       // Trigger interest accrual by making any operation (e.g., supply 1 USDC)
       await program.methods
-        .supply(new anchor.BN(1_000_000), new anchor.BN(0))
+        .supply(new anchor.BN(1_000_000), new anchor.BN(0), new anchor.BN(0))
         .accounts({
           market: marketPda,
           userPosition: gracePositionPda,
@@ -0,0 +1,3842 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { PelagoSolana } from "../target/types/pelago_solana";
+import { MockSwap } from "../target/types/mock_swap";
+import {
+  createMint,
+  mintTo,
+  getOrCreateAssociatedTokenAccount,
+  transfer,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { assert } from "chai";
+
+/**
+ * P2 Phase Integration Tests for Pelago Solana
+ *
+ * Instruction-level coverage for features added after P1.
+ * Each describe block creates its own market so tests stay independent.
+ */
+describe("pelago-solana-p2", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PelagoSolana as Program<PelagoSolana>;
+  const mockSwap = anchor.workspace.MockSwap as Program<MockSwap>;
+  const authority = provider.wallet as anchor.Wallet;
+
+  // Constants
+  const LLTV_PRECISION = 100_000_000;
+  const LLTV = 0.8 * LLTV_PRECISION; // 80%
+  // Rate model for initializeMarket: all zero keeps the fixed 5% rate
+  const FIXED_RATE_IRM = {
+    baseRateWad: new anchor.BN(0),
+    slope1Wad: new anchor.BN(0),
+    slope2Wad: new anchor.BN(0),
+    kinkUtilizationBps: 0,
+  };
+  const USDC_DECIMALS = 6;
+  const SOL_DECIMALS = 9;
+
+  interface TestMarket {
+    loanTokenMint: anchor.web3.PublicKey;
+    collateralTokenMint: anchor.web3.PublicKey;
+    marketPda: anchor.web3.PublicKey;
+    loanVault: anchor.web3.Keypair;
+    collateralVault: anchor.web3.Keypair;
+  }
+
+  interface TestUser {
+    keypair: anchor.web3.Keypair;
+    loanAta: anchor.web3.PublicKey;
+    collateralAta: anchor.web3.PublicKey;
+    positionPda: anchor.web3.PublicKey;
+  }
+
+  const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const [protocolConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("protocol-config")],
+    program.programId
+  );
+  // initialize_config is restricted to the program's upgrade authority
+  const [programDataPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
+  // initialize_market checks every LLTV against the ProtocolConfig
+  // whitelist: create the config singletons if no earlier suite did, with
+  // permissive bounds, and enable `lltv`
+  async function ensureProtocolConfig(lltv: number) {
+    if (!(await provider.connection.getAccountInfo(configPda))) {
+      await program.methods
+        .initializeConfig(authority.publicKey)
+        .accounts({
+          config: configPda,
+          admin: authority.publicKey,
+          program: program.programId,
+          programData: programDataPda,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+    if (!(await provider.connection.getAccountInfo(protocolConfigPda))) {
+      await program.methods
+        .setProtocolConfig(
+          0,
+          authority.publicKey,
+          new anchor.BN(100_000_000), // MAX_LLTV
+          new anchor.BN(0),
+          new anchor.BN("1000000000000000000") // 100%
+        )
+        .accounts({
+          config: configPda,
+          protocolConfig: protocolConfigPda,
+          admin: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+    await program.methods
+      .enableLltv(new anchor.BN(lltv))
+      .accounts({
+        config: configPda,
+        protocolConfig: protocolConfigPda,
+        admin: authority.publicKey,
+      })
+      .rpc();
+  }
+
+  before(async () => {
+    await ensureProtocolConfig(LLTV);
+  });
+
+  async function createTestMarket(
+    lltv: number = LLTV,
+    fixedPrice: number = 0,
+    lltvTimelock: number = 0,
+    feeBps: number = 0,
+    loanDecimals: number = USDC_DECIMALS,
+    irm = FIXED_RATE_IRM
+  ): Promise<TestMarket> {
+    const loanTokenMint = await createMint(
+      provider.connection,
+      authority.payer,
+      authority.publicKey,
+      null,
+      loanDecimals
+    );
+    const collateralTokenMint = await createMint(
+      provider.connection,
+      authority.payer,
+      authority.publicKey,
+      null,
+      SOL_DECIMALS
+    );
+
+    const [marketPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("market"),
+        loanTokenMint.toBuffer(),
+        collateralTokenMint.toBuffer(),
+      ],
+      program.programId
+    );
+
+    const loanVault = anchor.web3.Keypair.generate();
+    const collateralVault = anchor.web3.Keypair.generate();
+
+    await program.methods
+      .initializeMarket(
+        new anchor.BN(lltv),
+        new anchor.BN(fixedPrice),
+        new anchor.BN(lltvTimelock),
+        feeBps,
+        irm
+      )
+      .accounts({
+        market: marketPda,
+        loanTokenMint,
+        collateralTokenMint,
+        loanVault: loanVault.publicKey,
+        collateralVault: collateralVault.publicKey,
+        authority: authority.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        protocolConfig: protocolConfigPda,
+      })
+      .signers([loanVault, collateralVault])
+      .rpc();
+
+    return {
+      loanTokenMint,
+      collateralTokenMint,
+      marketPda,
+      loanVault,
+      collateralVault,
+    };
+  }
+
+  async function createTestUser(
+    market: TestMarket,
+    loanAmount: number,
+    collateralAmount: number
+  ): Promise<TestUser> {
+    const keypair = anchor.web3.Keypair.generate();
+
+    const airdrop = await provider.connection.requestAirdrop(
+      keypair.publicKey,
+      2 * anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdrop);
+
+    const loanAta = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority.payer,
+      market.loanTokenMint,
+      keypair.publicKey
+    );
+    const collateralAta = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      authority.payer,
+      market.collateralTokenMint,
+      keypair.publicKey
+    );
+
+    if (loanAmount > 0) {
+      await mintTo(
+        provider.connection,
+        authority.payer,
+        market.loanTokenMint,
+        loanAta.address,
+        authority.publicKey,
+        loanAmount
+      );
+    }
+    if (collateralAmount > 0) {
+      await mintTo(
+        provider.connection,
+        authority.payer,
+        market.collateralTokenMint,
+        collateralAta.address,
+        authority.publicKey,
+        collateralAmount
+      );
+    }
+
+    const [positionPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("user-position"),
+        market.marketPda.toBuffer(),
+        keypair.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+
+    return {
+      keypair,
+      loanAta: loanAta.address,
+      collateralAta: collateralAta.address,
+      positionPda,
+    };
+  }
+
+  async function supply(market: TestMarket, user: TestUser, assets: number) {
+    await program.methods
+      .supply(new anchor.BN(assets), new anchor.BN(0), new anchor.BN(0))
+      .accounts({
+        market: market.marketPda,
+        userPosition: user.positionPda,
+        loanVault: market.loanVault.publicKey,
+        userTokenAccount: user.loanAta,
+        user: user.keypair.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user.keypair])
+      .rpc();
+  }
+
+  async function supplyCollateral(
+    market: TestMarket,
+    user: TestUser,
+    amount: number
+  ) {
+    await program.methods
+      .supplyCollateral(new anchor.BN(amount))
+      .accounts({
+        market: market.marketPda,
+        userPosition: user.positionPda,
+        collateralVault: market.collateralVault.publicKey,
+        userCollateralAccount: user.collateralAta,
+        user: user.keypair.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user.keypair])
+      .rpc();
+  }
+
+  async function borrow(
+    market: TestMarket,
+    user: TestUser,
+    assets: number
+  ): Promise<string> {
+    return program.methods
+      .borrow(new anchor.BN(assets), new anchor.BN(0), false)
+      .accounts({
+        market: market.marketPda,
+        userPosition: user.positionPda,
+        loanVault: market.loanVault.publicKey,
+        userTokenAccount: user.loanAta,
+        user: user.keypair.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user.keypair])
+      .rpc();
+  }
+
+  async function sleep(ms: number) {
+    await new Promise((resolve) => setTimeout(resolve, ms));
+  }
+
+  describe("Position Last Activity", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 2000_000_000, 20_000_000_000);
+    });
+
+    it("Updates last_activity on every position action", async () => {
+      await supply(market, alice, 1000_000_000);
+      const afterSupply = await program.account.userPosition.fetch(
+        alice.positionPda
+      );
+      assert.isTrue(afterSupply.lastActivity.toNumber() > 0);
+
+      await sleep(2000);
+      await supplyCollateral(market, alice, 10_000_000_000);
+      const afterCollateral = await program.account.userPosition.fetch(
+        alice.positionPda
+      );
+      assert.isTrue(
+        afterCollateral.lastActivity.gt(afterSupply.lastActivity),
+        "supply_collateral should update last_activity"
+      );
+
+      await sleep(2000);
+      await borrow(market, alice, 100_000_000);
+      const afterBorrow = await program.account.userPosition.fetch(
+        alice.positionPda
+      );
+      assert.isTrue(
+        afterBorrow.lastActivity.gt(afterCollateral.lastActivity),
+        "borrow should update last_activity"
+      );
+
+      const viewed = await program.methods
+        .getLastActivity()
+        .accounts({ userPosition: alice.positionPda })
+        .view();
+      assert.equal(viewed.toNumber(), afterBorrow.lastActivity.toNumber());
+    });
+  });
+
+  describe("Emergency Guardian", () => {
+    let market: TestMarket;
+    let guardian: anchor.web3.Keypair;
+
+    before(async () => {
+      market = await createTestMarket();
+      guardian = anchor.web3.Keypair.generate();
+
+      await program.methods
+        .setGuardian(guardian.publicKey)
+        .accounts({
+          config: configPda,
+          admin: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Guardian can pause a market it doesn't administer", async () => {
+      await program.methods
+        .guardianPause()
+        .accounts({
+          config: configPda,
+          market: market.marketPda,
+          guardian: guardian.publicKey,
+        })
+        .signers([guardian])
+        .rpc();
+
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.isTrue(marketAccount.paused);
+    });
+
+    it("Guardian cannot unpause", async () => {
+      try {
+        await program.methods
+          .setPaused(false)
+          .accounts({
+            market: market.marketPda,
+            authority: guardian.publicKey,
+          })
+          .signers([guardian])
+          .rpc();
+        assert.fail("Guardian should not be able to unpause");
+      } catch (error) {
+        assert.include(error.toString(), "Unauthorized");
+      }
+
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.isTrue(marketAccount.paused);
+    });
+
+    it("Market authority can unpause", async () => {
+      await program.methods
+        .setPaused(false)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.isFalse(marketAccount.paused);
+    });
+  });
+
+  // Requires a program built with `--features test-utils`
+  // (e.g. `anchor test -- --features test-utils`); skipped otherwise
+  describe("Deterministic Interest (test-utils)", () => {
+    const hasTestUtils = program.idl.instructions.some(
+      (ix) => ix.name === "setLastUpdate"
+    );
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async function () {
+      if (!hasTestUtils) {
+        this.skip();
+      }
+      market = await createTestMarket();
+      alice = await createTestUser(market, 2_000_000_000_000, 20_000_000_000_000);
+      await supply(market, alice, 2_000_000_000_000); // 2M USDC
+      await supplyCollateral(market, alice, 20_000_000_000_000); // 20,000 SOL
+      await borrow(market, alice, 1_000_000_000_000); // 1M USDC
+    });
+
+    it("Accrues exactly one year of interest after warping last_update", async () => {
+      const before = await program.account.market.fetch(market.marketPda);
+      const slot = await provider.connection.getSlot();
+      const now = await provider.connection.getBlockTime(slot);
+      const oneYear = 31_557_600;
+
+      await (program.methods as any)
+        .setLastUpdate(new anchor.BN(now - oneYear))
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      // Any accruing operation triggers the catch-up
+      await supply(market, alice, 1_000_000);
+
+      const after = await program.account.market.fetch(market.marketPda);
+      const interest =
+        after.totalBorrowAssets.toNumber() - before.totalBorrowAssets.toNumber();
+
+      // 5% of 1M USDC compounded continuously ≈ 51,271 USDC
+      // (allow a few seconds of clock drift)
+      assert.approximately(interest, 51_271_000_000, 50_000_000);
+    });
+
+    it("Recovers a market whose last_update is in the future", async () => {
+      const slot = await provider.connection.getSlot();
+      const now = await provider.connection.getBlockTime(slot);
+
+      await (program.methods as any)
+        .setLastUpdate(new anchor.BN(now + 365 * 86_400))
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      try {
+        await supply(market, alice, 1_000_000);
+        assert.fail("Accrual should fail with a future last_update");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidTimestamp");
+      }
+
+      await program.methods
+        .resetAccrualClock()
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      await supply(market, alice, 1_000_000);
+    });
+
+    it("Rejects a shares-mode supply once interest exceeds max_assets_in", async () => {
+      const shares = new anchor.BN(100_000_000).mul(new anchor.BN(1_000_000)); // ~100 USDC
+
+      // Quote at the current state: to_assets_up(shares)
+      const quoted = await program.account.market.fetch(market.marketPda);
+      const totalAssets = quoted.totalSupplyAssets.addn(1);
+      const totalShares = quoted.totalSupplyShares.addn(1_000_000);
+      const maxAssetsIn = shares
+        .mul(totalAssets)
+        .add(totalShares.subn(1))
+        .div(totalShares);
+
+      // A year of interest accrues before the transaction lands
+      const slot = await provider.connection.getSlot();
+      const now = await provider.connection.getBlockTime(slot);
+      await (program.methods as any)
+        .setLastUpdate(new anchor.BN(now - 31_557_600))
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const supplyShares = (max: anchor.BN) =>
+        program.methods
+          .supply(new anchor.BN(0), shares, max)
+          .accounts({
+            market: market.marketPda,
+            userPosition: alice.positionPda,
+            loanVault: market.loanVault.publicKey,
+            userTokenAccount: alice.loanAta,
+            user: alice.keypair.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([alice.keypair])
+          .rpc();
+
+      try {
+        await supplyShares(maxAssetsIn);
+        assert.fail("Supply should exceed max_assets_in");
+      } catch (error) {
+        assert.include(error.toString(), "SlippageExceeded");
+      }
+
+      // Zero disables the bound
+      await supplyShares(new anchor.BN(0));
+    });
+
+    it("Does not charge interest for a pause when accrue_while_paused is off", async () => {
+      await program.methods
+        .setAccrueWhilePaused(false)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+      await program.methods
+        .setPaused(true)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+      const before = await program.account.market.fetch(market.marketPda);
+
+      // Pretend the pause lasted a year
+      await (program.methods as any)
+        .setLastUpdate(before.lastUpdate.sub(new anchor.BN(31_557_600)))
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+      await program.methods
+        .setPaused(false)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const after = await program.account.market.fetch(market.marketPda);
+      assert.equal(
+        after.totalBorrowAssets.toString(),
+        before.totalBorrowAssets.toString()
+      );
+      assert.isFalse(after.paused);
+
+      await program.methods
+        .setAccrueWhilePaused(true)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+  });
+
+  describe("Borrow Caps", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 1000_000_000, 20_000_000_000);
+      await supply(market, alice, 1000_000_000); // 1000 USDC
+      await supplyCollateral(market, alice, 20_000_000_000); // 20 SOL
+    });
+
+    it("Relative cap binds before the absolute cap", async () => {
+      // 30% of 1000 USDC = 300 USDC, tighter than the 500 USDC absolute cap
+      await program.methods
+        .setBorrowCaps(new anchor.BN(500_000_000), 3_000, new anchor.BN(0))
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      await borrow(market, alice, 300_000_000);
+
+      try {
+        await borrow(market, alice, 1_000_000);
+        assert.fail("Borrow above the relative cap should fail");
+      } catch (error) {
+        assert.include(error.toString(), "BorrowCapExceeded");
+      }
+    });
+
+    it("Absolute cap binds before the relative cap", async () => {
+      // 90% of 1000 USDC = 900 USDC, looser than the 350 USDC absolute cap
+      await program.methods
+        .setBorrowCaps(new anchor.BN(350_000_000), 9_000, new anchor.BN(0))
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      await borrow(market, alice, 40_000_000);
+
+      try {
+        await borrow(market, alice, 20_000_000);
+        assert.fail("Borrow above the absolute cap should fail");
+      } catch (error) {
+        assert.include(error.toString(), "BorrowCapExceeded");
+      }
+    });
+
+    it("Rejects a ratio above 100%", async () => {
+      try {
+        await program.methods
+          .setBorrowCaps(new anchor.BN(0), 10_001, new anchor.BN(0))
+          .accounts({
+            market: market.marketPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+        assert.fail("Ratio above 10_000 bps should fail");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidParameter");
+      }
+    });
+  });
+
+  describe("Withdraw Liquidity Invariant", () => {
+    let market: TestMarket;
+    let lender: TestUser;
+    let borrower: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      lender = await createTestUser(market, 1000_000_000, 0);
+      borrower = await createTestUser(market, 0, 20_000_000_000);
+      await supply(market, lender, 1000_000_000); // 1000 USDC
+      await supplyCollateral(market, borrower, 20_000_000_000); // 20 SOL
+      await borrow(market, borrower, 900_000_000); // drain 900 USDC
+    });
+
+    it("Rejects a supplier withdrawal into borrowed liquidity", async () => {
+      try {
+        await program.methods
+          .withdraw(new anchor.BN(200_000_000), new anchor.BN(0))
+          .accounts({
+            market: market.marketPda,
+            userPosition: lender.positionPda,
+            user: lender.keypair.publicKey,
+            receiverTokenAccount: lender.loanAta,
+            loanVault: market.loanVault.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([lender.keypair])
+          .rpc();
+        assert.fail("Withdrawal should break the liquidity invariant");
+      } catch (error) {
+        assert.include(error.toString(), "WithdrawBreaksLiquidity");
+      }
+    });
+  });
+
+  describe("Repay Amount Quote", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 1000_000_000, 20_000_000_000);
+      await supply(market, alice, 900_000_000);
+      await supplyCollateral(market, alice, 20_000_000_000);
+      await borrow(market, alice, 500_000_000);
+    });
+
+    it("Quoted amount matches the cost of a full repay", async () => {
+      await sleep(3000);
+
+      const quote = await program.methods
+        .getRepayAmount(30)
+        .accounts({
+          market: market.marketPda,
+          userPosition: alice.positionPda,
+        })
+        .view();
+      assert.isTrue(quote.assets.gtn(500_000_000), "quote should include interest");
+      assert.isTrue(quote.assetsWithBuffer.gte(quote.assets));
+
+      const balanceBefore = await provider.connection.getTokenAccountBalance(
+        alice.loanAta
+      );
+
+      // Full repay by shares costs what the quote predicted for landing time
+      const before = await program.account.userPosition.fetch(alice.positionPda);
+      await program.methods
+        .repay(new anchor.BN(0), before.borrowShares, false)
+        .accounts({
+          market: market.marketPda,
+          borrowerPosition: alice.positionPda,
+          payerPosition: null,
+          loanVault: market.loanVault.publicKey,
+          payerTokenAccount: alice.loanAta,
+          payer: alice.keypair.publicKey,
+          borrower: alice.keypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([alice.keypair])
+        .rpc();
+
+      const position = await program.account.userPosition.fetch(alice.positionPda);
+      assert.equal(position.borrowShares.toNumber(), 0);
+
+      const balanceAfter = await provider.connection.getTokenAccountBalance(
+        alice.loanAta
+      );
+      const paid =
+        Number(balanceBefore.value.amount) - Number(balanceAfter.value.amount);
+      assert.isTrue(paid >= quote.assets.toNumber());
+      assert.isTrue(paid <= quote.assetsWithBuffer.toNumber());
+    });
+  });
+
+  describe("Borrow Without Collateral", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 1000_000_000, 0);
+      await supply(market, alice, 1000_000_000); // position exists, no collateral
+    });
+
+    it("Rejects early with NoCollateral", async () => {
+      try {
+        await borrow(market, alice, 1_000_000);
+        assert.fail("Borrow without collateral should fail");
+      } catch (error) {
+        assert.include(error.toString(), "NoCollateral");
+      }
+
+      const position = await program.account.userPosition.fetch(alice.positionPda);
+      assert.equal(position.borrowShares.toNumber(), 0);
+    });
+  });
+
+  describe("Manual Price", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    async function setManualPrice(price: number, enabled: boolean) {
+      await program.methods
+        .setManualPrice(new anchor.BN(price), enabled)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    }
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 1000_000_000, 10_000_000_000);
+      await supply(market, alice, 1000_000_000);
+      await supplyCollateral(market, alice, 10_000_000_000); // 10 SOL
+    });
+
+    it("Rejects a zero price", async () => {
+      try {
+        await setManualPrice(0, true);
+        assert.fail("Zero price should fail");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidPrice");
+      }
+    });
+
+    it("Health checks use the manual price when enabled", async () => {
+      // 50 USDC/SOL: 10 SOL × 50 × 80% = 400 USDC max borrow
+      await setManualPrice(50_000, true);
+
+      try {
+        await borrow(market, alice, 500_000_000);
+        assert.fail("Borrow above the manual-price limit should fail");
+      } catch (error) {
+        assert.include(error.toString(), "InsufficientCollateral");
+      }
+
+      await borrow(market, alice, 400_000_000);
+
+      // Back to the fixed price (100 USDC/SOL): 800 USDC max borrow
+      await setManualPrice(50_000, false);
+      await borrow(market, alice, 300_000_000);
+    });
+  });
+
+  describe("Position Limit", () => {
+    let market: TestMarket;
+
+    before(async () => {
+      market = await createTestMarket();
+      await program.methods
+        .setMaxPositions(2)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Rejects new positions beyond the cap", async () => {
+      const users = await Promise.all(
+        [0, 1, 2].map(() => createTestUser(market, 10_000_000, 1_000_000_000))
+      );
+
+      await supply(market, users[0], 10_000_000);
+      await supplyCollateral(market, users[1], 1_000_000_000);
+
+      try {
+        await supply(market, users[2], 10_000_000);
+        assert.fail("Third position should exceed the cap");
+      } catch (error) {
+        assert.include(error.toString(), "PositionLimitReached");
+      }
+
+      // Existing positions can still be topped up
+      await supplyCollateral(market, users[0], 1_000_000_000);
+
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketAccount.openPositions, 2);
+    });
+  });
+
+  describe("Per-Position Borrow Limit", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 10_000_000_000, 100_000_000_000);
+      await supply(market, alice, 10_000_000_000); // 10,000 USDC
+      await supplyCollateral(market, alice, 100_000_000_000); // 100 SOL ($10,000)
+
+      await program.methods
+        .setBorrowCaps(new anchor.BN(0), 0, new anchor.BN(1000_000_000))
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Caps a well-collateralized position", async () => {
+      await borrow(market, alice, 900_000_000);
+
+      try {
+        // Health allows $8,000, but the position limit is $1,000
+        await borrow(market, alice, 200_000_000);
+        assert.fail("Borrow above the position limit should fail");
+      } catch (error) {
+        assert.include(error.toString(), "PositionBorrowLimit");
+      }
+    });
+  });
+
+  // Corrupting total_collateral on-chain isn't possible without a backdoor;
+  // the repair math itself is covered by Rust unit tests
+  describe("Resync Total Collateral", () => {
+    let market: TestMarket;
+    let users: TestUser[];
+
+    before(async () => {
+      market = await createTestMarket();
+      users = await Promise.all(
+        [0, 1].map(() => createTestUser(market, 0, 5_000_000_000))
+      );
+      await supplyCollateral(market, users[0], 3_000_000_000);
+      await supplyCollateral(market, users[1], 2_000_000_000);
+    });
+
+    it("Recomputes the total from all positions", async () => {
+      await program.methods
+        .resyncTotalCollateral()
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .remainingAccounts(
+          users.map((u) => ({
+            pubkey: u.positionPda,
+            isSigner: false,
+            isWritable: false,
+          }))
+        )
+        .rpc();
+
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketAccount.totalCollateral.toNumber(), 5_000_000_000);
+    });
+
+    it("Rejects an incomplete position list", async () => {
+      try {
+        await program.methods
+          .resyncTotalCollateral()
+          .accounts({
+            market: market.marketPda,
+            authority: authority.publicKey,
+          })
+          .remainingAccounts([
+            { pubkey: users[0].positionPda, isSigner: false, isWritable: false },
+          ])
+          .rpc();
+        assert.fail("Partial resync should fail");
+      } catch (error) {
+        assert.include(error.toString(), "IncompleteResync");
+      }
+    });
+  });
+
+  describe("Origination Fee", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 2000_000_000, 20_000_000_000);
+      await supply(market, alice, 2000_000_000);
+      await supplyCollateral(market, alice, 20_000_000_000);
+
+      await program.methods
+        .setFee(0, 100) // 1% origination fee
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Rejects an origination fee above the cap", async () => {
+      try {
+        await program.methods
+          .setFee(0, 501)
+          .accounts({
+            market: market.marketPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+        assert.fail("Fee above 5% should fail");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidParameter");
+      }
+    });
+
+    it("Adds the fee to the borrower's debt and to reserves", async () => {
+      const balanceBefore = await provider.connection.getTokenAccountBalance(
+        alice.loanAta
+      );
+      await borrow(market, alice, 1000_000_000);
+      const balanceAfter = await provider.connection.getTokenAccountBalance(
+        alice.loanAta
+      );
+
+      const received =
+        Number(balanceAfter.value.amount) - Number(balanceBefore.value.amount);
+      assert.equal(received, 1000_000_000, "only the borrowed amount is sent");
+
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketAccount.reserves.toNumber(), 10_000_000);
+
+      const quote = await program.methods
+        .getRepayAmount(0)
+        .accounts({
+          market: market.marketPda,
+          userPosition: alice.positionPda,
+        })
+        .view();
+      assert.isTrue(quote.assets.toNumber() >= 1010_000_000);
+    });
+  });
+
+  describe("Inflation Attack", () => {
+    let market: TestMarket;
+    let attacker: TestUser;
+    let victim: TestUser;
+    const VICTIM_DEPOSIT = 1000_000_000; // 1000 USDC
+    const DONATION = 1_000_000; // 1 USDC
+
+    before(async () => {
+      market = await createTestMarket();
+      attacker = await createTestUser(market, 10_000_000_000, 0);
+      victim = await createTestUser(market, VICTIM_DEPOSIT, 0);
+    });
+
+    it("A small donation doesn't dilute the victim", async () => {
+      await supply(market, attacker, 1);
+      await transfer(
+        provider.connection,
+        attacker.keypair,
+        attacker.loanAta,
+        market.loanVault.publicKey,
+        attacker.keypair,
+        DONATION
+      );
+
+      await supply(market, victim, VICTIM_DEPOSIT);
+      const position = await program.account.userPosition.fetch(victim.positionPda);
+
+      const balanceBefore = await provider.connection.getTokenAccountBalance(
+        victim.loanAta
+      );
+      await program.methods
+        .withdraw(new anchor.BN(0), position.supplyShares)
+        .accounts({
+          market: market.marketPda,
+          userPosition: victim.positionPda,
+          user: victim.keypair.publicKey,
+          receiverTokenAccount: victim.loanAta,
+          loanVault: market.loanVault.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([victim.keypair])
+        .rpc();
+      const balanceAfter = await provider.connection.getTokenAccountBalance(
+        victim.loanAta
+      );
+
+      const redeemed =
+        Number(balanceAfter.value.amount) - Number(balanceBefore.value.amount);
+      assert.isTrue(redeemed >= VICTIM_DEPOSIT - 1, `redeemed ${redeemed}`);
+    });
+
+    it("A large donation neither blocks supply nor is credited", async () => {
+      await transfer(
+        provider.connection,
+        attacker.keypair,
+        attacker.loanAta,
+        market.loanVault.publicKey,
+        attacker.keypair,
+        5_000_000_000
+      );
+
+      // Surpluses pass the vault check; only shortfalls are rejected
+      await supply(market, attacker, 1_000_000);
+      const marketState = await program.account.market.fetch(market.marketPda);
+      assert.isTrue(
+        marketState.totalSupplyAssets.toNumber() < 5_000_000_000,
+        "the donation never reaches total_supply_assets"
+      );
+    });
+
+    it("Skims the donated surplus back out of the vault", async () => {
+      const vaultBefore = await provider.connection.getTokenAccountBalance(
+        market.loanVault.publicKey
+      );
+      await program.methods
+        .skimVault()
+        .accounts({
+          market: market.marketPda,
+          loanVault: market.loanVault.publicKey,
+          receiverTokenAccount: attacker.loanAta,
+          authority: authority.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      const vaultAfter = await provider.connection.getTokenAccountBalance(
+        market.loanVault.publicKey
+      );
+
+      // Both donations leave the vault
+      const skimmed =
+        Number(vaultBefore.value.amount) - Number(vaultAfter.value.amount);
+      assert.equal(skimmed, 5_000_000_000 + DONATION);
+    });
+
+    it("Rejects a vault tolerance change from a non-authority", async () => {
+      try {
+        await program.methods
+          .setVaultTolerance(new anchor.BN(0))
+          .accounts({
+            market: market.marketPda,
+            authority: attacker.keypair.publicKey,
+          })
+          .signers([attacker.keypair])
+          .rpc();
+        assert.fail("Only the market authority can set the tolerance");
+      } catch (error) {
+        assert.include(error.toString(), "Unauthorized");
+      }
+    });
+  });
+
+  describe("TWAP Price", () => {
+    let market: TestMarket;
+    let priceHistoryPda: anchor.web3.PublicKey;
+    const WINDOW = 4;
+
+    async function updatePriceHistory() {
+      await program.methods
+        .updatePriceHistory()
+        .accounts({
+          market: market.marketPda,
+          priceHistory: priceHistoryPda,
+        })
+        .rpc();
+    }
+
+    before(async () => {
+      market = await createTestMarket();
+      [priceHistoryPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("price-history"), market.marketPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializePriceHistory(WINDOW)
+        .accounts({
+          market: market.marketPda,
+          priceHistory: priceHistoryPda,
+          authority: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("Falls back to spot during warm-up, then lags a spike", async () => {
+      await updatePriceHistory();
+      let marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketAccount.twapPrice.toNumber(), 0, "not warmed up yet");
+
+      await sleep((WINDOW + 1) * 1000);
+      await updatePriceHistory();
+      marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketAccount.twapPrice.toNumber(), 100_000);
+
+      // Spot doubles via the manual price
+      await program.methods
+        .setManualPrice(new anchor.BN(200_000), true)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+      await updatePriceHistory();
+      await sleep(1000);
+      await updatePriceHistory();
+
+      marketAccount = await program.account.market.fetch(market.marketPda);
+      const twap = marketAccount.twapPrice.toNumber();
+      assert.isTrue(twap > 100_000 && twap < 200_000, `twap ${twap} should lag`);
+    });
+  });
+
+  describe("Per-Market Fixed Price", () => {
+    it("Markets with different fixed prices give different borrowing power", async () => {
+      const cheap = await createTestMarket(LLTV, 50_000); // 50 USDC/SOL
+      const dear = await createTestMarket(LLTV, 200_000); // 200 USDC/SOL
+
+      const cheapMarket = await program.account.market.fetch(cheap.marketPda);
+      assert.equal(cheapMarket.fixedPrice.toNumber(), 50_000);
+
+      const users = await Promise.all(
+        [cheap, dear].map((m) => createTestUser(m, 2000_000_000, 10_000_000_000))
+      );
+      for (const [i, m] of [cheap, dear].entries()) {
+        await supply(m, users[i], 2000_000_000);
+        await supplyCollateral(m, users[i], 10_000_000_000); // 10 SOL
+      }
+
+      // 10 SOL × 200 × 80% = 1600 USDC
+      await borrow(dear, users[1], 1000_000_000);
+
+      // 10 SOL × 50 × 80% = 400 USDC
+      try {
+        await borrow(cheap, users[0], 1000_000_000);
+        assert.fail("Borrow should exceed the cheaper market's limit");
+      } catch (error) {
+        assert.include(error.toString(), "InsufficientCollateral");
+      }
+    });
+
+    it("Zero selects the default fixed price", async () => {
+      const market = await createTestMarket();
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketAccount.fixedPrice.toNumber(), 100_000);
+    });
+  });
+
+  describe("Open Position", () => {
+    let market: TestMarket;
+    let lender: TestUser;
+    let newcomer: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      lender = await createTestUser(market, 1000_000_000, 0);
+      newcomer = await createTestUser(market, 0, 10_000_000_000);
+      await supply(market, lender, 1000_000_000);
+    });
+
+    it("Supplies collateral and borrows from scratch in one call", async () => {
+      await program.methods
+        .openPosition(new anchor.BN(10_000_000_000), new anchor.BN(500_000_000))
+        .accounts({
+          market: market.marketPda,
+          userPosition: newcomer.positionPda,
+          collateralVault: market.collateralVault.publicKey,
+          loanVault: market.loanVault.publicKey,
+          userCollateralAccount: newcomer.collateralAta,
+          userLoanAccount: newcomer.loanAta,
+          user: newcomer.keypair.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([newcomer.keypair])
+        .rpc();
+
+      const position = await program.account.userPosition.fetch(
+        newcomer.positionPda
+      );
+      assert.equal(position.collateralAmount.toNumber(), 10_000_000_000);
+      assert.isTrue(position.borrowShares.gtn(0));
+
+      const balance = await provider.connection.getTokenAccountBalance(
+        newcomer.loanAta
+      );
+      assert.equal(Number(balance.value.amount), 500_000_000);
+    });
+  });
+
+  describe("Virtual Offset Validation", () => {
+    async function initializeWithLoanDecimals(decimals: number) {
+      const loanTokenMint = await createMint(
+        provider.connection,
+        authority.payer,
+        authority.publicKey,
+        null,
+        decimals
+      );
+      const collateralTokenMint = await createMint(
+        provider.connection,
+        authority.payer,
+        authority.publicKey,
+        null,
+        SOL_DECIMALS
+      );
+      const [marketPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("market"),
+          loanTokenMint.toBuffer(),
+          collateralTokenMint.toBuffer(),
+        ],
+        program.programId
+      );
+      const loanVault = anchor.web3.Keypair.generate();
+      const collateralVault = anchor.web3.Keypair.generate();
+
+      await program.methods
+        .initializeMarket(new anchor.BN(LLTV), new anchor.BN(0), new anchor.BN(0), 0, FIXED_RATE_IRM)
+        .accounts({
+          market: marketPda,
+          loanTokenMint,
+          collateralTokenMint,
+          loanVault: loanVault.publicKey,
+          collateralVault: collateralVault.publicKey,
+          authority: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          protocolConfig: protocolConfigPda,
+        })
+        .signers([loanVault, collateralVault])
+        .rpc();
+    }
+
+    it("Accepts a 9-decimal loan token", async () => {
+      await initializeWithLoanDecimals(9);
+    });
+
+    it("Rejects an 18-decimal loan token", async () => {
+      try {
+        await initializeWithLoanDecimals(18);
+        assert.fail("Initialization should fail with unsafe offsets");
+      } catch (error) {
+        assert.include(error.toString(), "UnsafeVirtualOffsets");
+      }
+    });
+  });
+
+  describe("Market Info", () => {
+    it("Reads back the info of a freshly initialized market", async () => {
+      const market = await createTestMarket();
+
+      const info = await program.methods
+        .getMarketInfo()
+        .accounts({
+          market: market.marketPda,
+          loanTokenMint: market.loanTokenMint,
+          collateralTokenMint: market.collateralTokenMint,
+        })
+        .view();
+
+      assert.equal(info.version, 2);
+      assert.isTrue(info.initialized);
+      assert.isTrue(info.loanTokenMint.equals(market.loanTokenMint));
+      assert.isTrue(info.collateralTokenMint.equals(market.collateralTokenMint));
+      assert.isTrue(info.loanVault.equals(market.loanVault.publicKey));
+      assert.isTrue(info.collateralVault.equals(market.collateralVault.publicKey));
+      assert.equal(info.lltv.toNumber(), LLTV);
+      assert.equal(info.liquidationThreshold.toNumber(), LLTV);
+      assert.equal(info.annualRateWad.toString(), "50000000000000000");
+      assert.equal(info.borrowCap.toNumber(), 0);
+      assert.equal(info.maxPositions, 0);
+      assert.isFalse(info.paused);
+      assert.equal(info.positionCount.toNumber(), 0);
+    });
+
+    it("Counts each position once, on creation", async () => {
+      const market = await createTestMarket();
+      const lender = await createTestUser(market, 2000_000_000, 0);
+      const borrower = await createTestUser(market, 0, 20_000_000_000);
+      const opener = await createTestUser(market, 0, 10_000_000_000);
+
+      const positionCount = async () =>
+        (
+          await program.methods
+            .getMarketInfo()
+            .accounts({
+              market: market.marketPda,
+              loanTokenMint: market.loanTokenMint,
+              collateralTokenMint: market.collateralTokenMint,
+            })
+            .view()
+        ).positionCount.toNumber();
+
+      await supply(market, lender, 1000_000_000);
+      await supplyCollateral(market, borrower, 10_000_000_000);
+      assert.equal(await positionCount(), 2);
+
+      // Top-ups reuse the existing positions
+      await supply(market, lender, 1000_000_000);
+      await supplyCollateral(market, borrower, 10_000_000_000);
+      assert.equal(await positionCount(), 2);
+
+      await program.methods
+        .openPosition(new anchor.BN(10_000_000_000), new anchor.BN(100_000_000))
+        .accounts({
+          market: market.marketPda,
+          userPosition: opener.positionPda,
+          collateralVault: market.collateralVault.publicKey,
+          loanVault: market.loanVault.publicKey,
+          userCollateralAccount: opener.collateralAta,
+          userLoanAccount: opener.loanAta,
+          user: opener.keypair.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([opener.keypair])
+        .rpc();
+      assert.equal(await positionCount(), 3);
+    });
+
+    it("Reports an uninitialized market for an unused mint pair", async () => {
+      const loanTokenMint = anchor.web3.Keypair.generate().publicKey;
+      const collateralTokenMint = anchor.web3.Keypair.generate().publicKey;
+      const [marketPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("market"),
+          loanTokenMint.toBuffer(),
+          collateralTokenMint.toBuffer(),
+        ],
+        program.programId
+      );
+
+      const info = await program.methods
+        .getMarketInfo()
+        .accounts({
+          market: marketPda,
+          loanTokenMint,
+          collateralTokenMint,
+        })
+        .view();
+
+      assert.isFalse(info.initialized);
+      assert.isTrue(info.loanTokenMint.equals(loanTokenMint));
+      assert.equal(info.lltv.toNumber(), 0);
+    });
+  });
+
+  describe("Bad Debt Check", () => {
+    it("Reports the shortfall of an insolvent position", async () => {
+      const market = await createTestMarket();
+      const alice = await createTestUser(market, 1000_000_000, 10_000_000_000);
+      const bob = await createTestUser(market, 0, 10_000_000_000);
+
+      await supply(market, alice, 1000_000_000);
+      await supplyCollateral(market, alice, 10_000_000_000);
+      await supplyCollateral(market, bob, 10_000_000_000);
+      await borrow(market, alice, 700_000_000); // 700 USDC against 1000 USDC of SOL
+      await borrow(market, bob, 100_000_000);
+
+      // 50 USDC/SOL: Alice's 10 SOL are worth 500 USDC against ~700 USDC of debt
+      await program.methods
+        .setManualPrice(new anchor.BN(50_000), true)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const badDebt = await program.methods
+        .checkBadDebt()
+        .accounts({ market: market.marketPda })
+        .remainingAccounts(
+          [alice, bob].map((user) => ({
+            pubkey: user.positionPda,
+            isWritable: false,
+            isSigner: false,
+          }))
+        )
+        .view();
+
+      // Only Alice is underwater; allow a few seconds of accrued interest
+      assert.approximately(badDebt.toNumber(), 200_000_000, 100_000);
+      assert.isTrue(badDebt.toNumber() >= 200_000_000);
+    });
+  });
+
+  describe("LLTV Timelock", () => {
+    const TIMELOCK = 3; // seconds
+    let market: TestMarket;
+
+    const proposeLltv = (lltv: number) =>
+      program.methods
+        .proposeLltv(new anchor.BN(lltv))
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+    const applyLltv = () =>
+      program.methods
+        .applyLltv()
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+    before(async () => {
+      market = await createTestMarket(LLTV, 0, TIMELOCK);
+    });
+
+    it("Rejects applying before the timelock elapses", async () => {
+      await proposeLltv(70_000_000);
+
+      try {
+        await applyLltv();
+        assert.fail("Apply should fail before the timelock");
+      } catch (error) {
+        assert.include(error.toString(), "TimelockNotElapsed");
+      }
+
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketAccount.lltv.toNumber(), LLTV);
+      assert.equal(marketAccount.pendingLltv.toNumber(), 70_000_000);
+    });
+
+    it("Applies the pending LLTV after the timelock", async () => {
+      await sleep((TIMELOCK + 2) * 1000);
+      await applyLltv();
+
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketAccount.lltv.toNumber(), 70_000_000);
+      assert.equal(marketAccount.pendingLltv.toNumber(), 0);
+    });
+  });
+
+  describe("Utilization Auto-Pause", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 1000_000_000, 20_000_000_000);
+      await supply(market, alice, 1000_000_000);
+      await supplyCollateral(market, alice, 20_000_000_000);
+      await program.methods
+        .setAutoPauseUtilization(5_000) // 50%
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Refuses borrows above the watermark and resumes below it", async () => {
+      // 0% utilization before the borrow: allowed, lands at 60%
+      await borrow(market, alice, 600_000_000);
+
+      try {
+        await borrow(market, alice, 1_000_000);
+        assert.fail("Borrow above the watermark should fail");
+      } catch (error) {
+        assert.include(error.toString(), "AutoPaused");
+      }
+
+      // Repay down to ~40% utilization
+      await program.methods
+        .repay(new anchor.BN(200_000_000), new anchor.BN(0), false)
+        .accounts({
+          market: market.marketPda,
+          borrowerPosition: alice.positionPda,
+          payerPosition: null,
+          loanVault: market.loanVault.publicKey,
+          payerTokenAccount: alice.loanAta,
+          payer: alice.keypair.publicKey,
+          borrower: alice.keypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([alice.keypair])
+        .rpc();
+
+      await borrow(market, alice, 1_000_000);
+    });
+  });
+
+  describe("Borrow Cooldown", () => {
+    const COOLDOWN = 3; // seconds
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 1000_000_000, 20_000_000_000);
+      await supply(market, alice, 1000_000_000);
+      await supplyCollateral(market, alice, 20_000_000_000);
+      await program.methods
+        .setBorrowCooldown(COOLDOWN)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Blocks a rapid re-borrow", async () => {
+      await borrow(market, alice, 100_000_000);
+
+      try {
+        await borrow(market, alice, 100_000_000);
+        assert.fail("Re-borrow within the cooldown should fail");
+      } catch (error) {
+        assert.include(error.toString(), "BorrowCooldown");
+      }
+    });
+
+    it("Allows a re-borrow after the cooldown", async () => {
+      await sleep((COOLDOWN + 2) * 1000);
+      await borrow(market, alice, 100_000_000);
+
+      const position = await program.account.userPosition.fetch(alice.positionPda);
+      assert.isTrue(position.lastBorrowTs.gtn(0));
+    });
+  });
+
+  describe("Batch Liquidate", () => {
+    let market: TestMarket;
+    let healthy: TestUser;
+    let bob: TestUser;
+    let carol: TestUser;
+    let liquidator: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      const lender = await createTestUser(market, 3000_000_000, 0);
+      healthy = await createTestUser(market, 0, 10_000_000_000);
+      bob = await createTestUser(market, 0, 10_000_000_000);
+      carol = await createTestUser(market, 0, 10_000_000_000);
+      liquidator = await createTestUser(market, 1000_000_000, 0);
+
+      await supply(market, lender, 3000_000_000);
+      for (const [user, debt] of [
+        [healthy, 300_000_000],
+        [bob, 700_000_000],
+        [carol, 750_000_000],
+      ] as [TestUser, number][]) {
+        await supplyCollateral(market, user, 10_000_000_000); // 10 SOL
+        await borrow(market, user, debt);
+      }
+
+      // 85 USDC/SOL: max borrow 680 USDC per position
+      await program.methods
+        .setManualPrice(new anchor.BN(85_000), true)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    const liquidate = async (users: TestUser[], minSeize: number) =>
+      await program.methods
+        .batchLiquidate(new anchor.BN(minSeize))
+        .accounts({
+          market: market.marketPda,
+          loanVault: market.loanVault.publicKey,
+          collateralVault: market.collateralVault.publicKey,
+          liquidatorLoanAccount: liquidator.loanAta,
+          liquidatorCollateralAccount: liquidator.collateralAta,
+          liquidator: liquidator.keypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          users.map((u) => ({
+            pubkey: u.positionPda,
+            isWritable: true,
+            isSigner: false,
+          }))
+        )
+        .signers([liquidator.keypair])
+        .rpc();
+
+    it("Aborts when the seized collateral is below min_seize", async () => {
+      // 350 USDC × 1.05 / 85 USDC/SOL ≈ 4.32 SOL; demand 5 SOL
+      try {
+        await liquidate([bob], 5_000_000_000);
+        assert.fail("Liquidation should fall short of min_seize");
+      } catch (error) {
+        assert.include(error.toString(), "SlippageExceeded");
+      }
+
+      const position = await program.account.userPosition.fetch(bob.positionPda);
+      assert.equal(position.collateralAmount.toNumber(), 10_000_000_000);
+    });
+
+    it("Liquidates only the unhealthy positions", async () => {
+      const before = await Promise.all(
+        [healthy, bob, carol].map((u) =>
+          program.account.userPosition.fetch(u.positionPda)
+        )
+      );
+
+      await liquidate([healthy, bob, carol], 0);
+
+      const after = await Promise.all(
+        [healthy, bob, carol].map((u) =>
+          program.account.userPosition.fetch(u.positionPda)
+        )
+      );
+
+      // Healthy position untouched
+      assert.equal(after[0].borrowShares.toString(), before[0].borrowShares.toString());
+      assert.equal(after[0].collateralAmount.toNumber(), 10_000_000_000);
+
+      // Unhealthy positions lose half their debt shares (close factor) and some collateral
+      for (const i of [1, 2]) {
+        const expected = before[i].borrowShares.sub(
+          before[i].borrowShares.addn(1).divn(2)
+        );
+        assert.equal(after[i].borrowShares.toString(), expected.toString());
+        assert.isTrue(after[i].collateralAmount.ltn(10_000_000_000));
+      }
+
+      const seized = await provider.connection.getTokenAccountBalance(
+        liquidator.collateralAta
+      );
+      const expectedSeized =
+        20_000_000_000 -
+        after[1].collateralAmount.toNumber() -
+        after[2].collateralAmount.toNumber();
+      assert.equal(Number(seized.value.amount), expectedSeized);
+    });
+  });
+
+  describe("Uninitialized Market", () => {
+    it("Rejects a supply to a market PDA that was never initialized", async () => {
+      const market = await createTestMarket();
+      const user = await createTestUser(market, 1_000_000, 0);
+
+      // Same mints, swapped roles: a valid PDA that was never created
+      const [missingMarket] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("market"),
+          market.collateralTokenMint.toBuffer(),
+          market.loanTokenMint.toBuffer(),
+        ],
+        program.programId
+      );
+      const [missingPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("user-position"),
+          missingMarket.toBuffer(),
+          user.keypair.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .supply(new anchor.BN(1_000_000), new anchor.BN(0), new anchor.BN(0))
+          .accounts({
+            market: missingMarket,
+            userPosition: missingPosition,
+            loanVault: market.loanVault.publicKey,
+            userTokenAccount: user.loanAta,
+            user: user.keypair.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user.keypair])
+          .rpc();
+        assert.fail("Should have rejected the uninitialized market");
+      } catch (error) {
+        assert.include(error.toString(), "AccountNotInitialized");
+      }
+    });
+  });
+
+  describe("Protocol Constants", () => {
+    it("Returns the compiled precision constants", async () => {
+      const constants = await program.methods
+        .getConstants()
+        .accounts({
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .view();
+
+      assert.equal(constants.virtualShares.toString(), "1000000");
+      assert.equal(constants.virtualAssets.toString(), "1");
+      assert.equal(constants.wad.toString(), "1000000000000000000");
+      assert.equal(constants.lltvPrecision.toString(), "100000000");
+      assert.equal(constants.pricePrecision.toString(), "1000000");
+      assert.equal(constants.secondsPerYear.toString(), "31557600");
+    });
+  });
+
+  describe("User Registry", () => {
+    const userMarkets = async (user: anchor.web3.PublicKey) =>
+      await program.methods.getUserMarkets().accounts({ user }).view();
+
+    it("Lists every market the user opened a position in", async () => {
+      const first = await createTestMarket();
+      const second = await createTestMarket();
+      const user = await createTestUser(first, 1_000_000, 0);
+
+      // Same wallet, funded in the second market's loan token
+      const secondLoanAta = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        authority.payer,
+        second.loanTokenMint,
+        user.keypair.publicKey
+      );
+      await mintTo(
+        provider.connection,
+        authority.payer,
+        second.loanTokenMint,
+        secondLoanAta.address,
+        authority.publicKey,
+        1_000_000
+      );
+      const [secondPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("user-position"),
+          second.marketPda.toBuffer(),
+          user.keypair.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      const secondUser: TestUser = {
+        ...user,
+        loanAta: secondLoanAta.address,
+        positionPda: secondPosition,
+      };
+
+      assert.isEmpty(await userMarkets(user.keypair.publicKey));
+
+      await supply(first, user, 500_000);
+      await supply(second, secondUser, 500_000);
+      // A second supply to a known market is not recorded again
+      await supply(first, user, 500_000);
+
+      const markets = await userMarkets(user.keypair.publicKey);
+      assert.equal(markets.length, 2);
+      assert.isTrue(markets[0].equals(first.marketPda));
+      assert.isTrue(markets[1].equals(second.marketPda));
+    });
+  });
+
+  describe("Seeded Market", () => {
+    it("Initializes and seeds in one transaction, locking the dead shares", async () => {
+      const loanTokenMint = await createMint(
+        provider.connection,
+        authority.payer,
+        authority.publicKey,
+        null,
+        USDC_DECIMALS
+      );
+      const collateralTokenMint = await createMint(
+        provider.connection,
+        authority.payer,
+        authority.publicKey,
+        null,
+        SOL_DECIMALS
+      );
+      const [marketPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("market"),
+          loanTokenMint.toBuffer(),
+          collateralTokenMint.toBuffer(),
+        ],
+        program.programId
+      );
+      const [authorityPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("user-position"),
+          marketPda.toBuffer(),
+          authority.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      const loanVault = anchor.web3.Keypair.generate();
+      const collateralVault = anchor.web3.Keypair.generate();
+      const authorityAta = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        authority.payer,
+        loanTokenMint,
+        authority.publicKey
+      );
+      await mintTo(
+        provider.connection,
+        authority.payer,
+        loanTokenMint,
+        authorityAta.address,
+        authority.publicKey,
+        1_000_000
+      );
+
+      const initIx = await program.methods
+        .initializeMarket(new anchor.BN(LLTV), new anchor.BN(0), new anchor.BN(0), 0, FIXED_RATE_IRM)
+        .accounts({
+          market: marketPda,
+          loanTokenMint,
+          collateralTokenMint,
+          loanVault: loanVault.publicKey,
+          collateralVault: collateralVault.publicKey,
+          authority: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          protocolConfig: protocolConfigPda,
+        })
+        .instruction();
+
+      await program.methods
+        .seedMarket(new anchor.BN(1_000_000)) // 1 USDC
+        .accounts({
+          market: marketPda,
+          authorityPosition,
+          loanVault: loanVault.publicKey,
+          authorityTokenAccount: authorityAta.address,
+          authority: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .preInstructions([initIx])
+        .signers([loanVault, collateralVault])
+        .rpc();
+
+      const seeded = await program.account.market.fetch(marketPda);
+      const position = await program.account.userPosition.fetch(authorityPosition);
+      // 0.001 USDC worth of shares is locked
+      assert.equal(seeded.deadShares.toString(), "1000000000");
+      assert.equal(
+        seeded.totalSupplyShares.toString(),
+        position.supplyShares.add(seeded.deadShares).toString()
+      );
+
+      // The authority can withdraw everything it owns, but not the dead shares
+      const withdrawShares = async (shares: anchor.BN) =>
+        await program.methods
+          .withdraw(new anchor.BN(0), shares)
+          .accounts({
+            market: marketPda,
+            userPosition: authorityPosition,
+            user: authority.publicKey,
+            receiverTokenAccount: authorityAta.address,
+            loanVault: loanVault.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+      await withdrawShares(position.supplyShares);
+      try {
+        await withdrawShares(new anchor.BN(1));
+        assert.fail("Dead shares should not be withdrawable");
+      } catch (error) {
+        assert.include(error.toString(), "InsufficientSupply");
+      }
+
+      const drained = await program.account.market.fetch(marketPda);
+      assert.equal(drained.totalSupplyShares.toString(), "1000000000");
+      assert.isTrue(drained.totalSupplyAssets.gtn(0));
+    });
+  });
+
+  describe("Liquidity Errors", () => {
+    it("Tells a fully utilized market from an oversized borrow", async () => {
+      const market = await createTestMarket();
+      const lender = await createTestUser(market, 1000_000_000, 0);
+      const borrower = await createTestUser(market, 0, 100_000_000_000);
+      await supply(market, lender, 1000_000_000); // 1,000 USDC
+      await supplyCollateral(market, borrower, 100_000_000_000); // 100 SOL
+
+      await borrow(market, borrower, 600_000_000);
+      try {
+        await borrow(market, borrower, 500_000_000);
+        assert.fail("Borrow should exceed the remaining liquidity");
+      } catch (error) {
+        assert.include(error.toString(), "InsufficientLiquidity");
+      }
+
+      // Drain the market, allowing for interest accrued since the last borrow
+      const state = await program.account.market.fetch(market.marketPda);
+      await borrow(
+        market,
+        borrower,
+        state.totalSupplyAssets.sub(state.totalBorrowAssets).toNumber()
+      );
+      try {
+        await borrow(market, borrower, 1);
+        assert.fail("Borrow should find no liquidity");
+      } catch (error) {
+        assert.include(error.toString(), "NoLiquidity");
+      }
+    });
+  });
+
+  describe("Oracle Price Bounds", () => {
+    it("Rejects borrows priced outside the sanity band", async () => {
+      const market = await createTestMarket();
+      const lender = await createTestUser(market, 1000_000_000, 0);
+      const borrower = await createTestUser(market, 0, 10_000_000_000);
+      await supply(market, lender, 1000_000_000);
+      await supplyCollateral(market, borrower, 10_000_000_000);
+
+      const setPrice = async (price: number) =>
+        await program.methods
+          .setManualPrice(new anchor.BN(price), true)
+          .accounts({
+            market: market.marketPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+
+      // 10 - 10,000 USDC/SOL
+      await program.methods
+        .setPriceBounds(new anchor.BN(10_000), new anchor.BN(10_000_000))
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      for (const absurd of [1, 1_000_000_000_000]) {
+        await setPrice(absurd);
+        try {
+          await borrow(market, borrower, 100_000_000);
+          assert.fail("Borrow should reject an out-of-band price");
+        } catch (error) {
+          assert.include(error.toString(), "OraclePriceOutOfBounds");
+        }
+      }
+
+      await setPrice(100_000);
+      await borrow(market, borrower, 100_000_000);
+    });
+  });
+
+  describe("Repay Excess As Supply", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 1000_000_000, 20_000_000_000);
+      await supply(market, alice, 500_000_000);
+      await supplyCollateral(market, alice, 20_000_000_000);
+      await borrow(market, alice, 10_000_000); // small 10 USDC loan
+    });
+
+    it("Credits an overpayment as supply shares", async () => {
+      const before = await program.account.userPosition.fetch(alice.positionPda);
+
+      await program.methods
+        .repay(new anchor.BN(60_000_000), new anchor.BN(0), true)
+        .accounts({
+          market: market.marketPda,
+          borrowerPosition: alice.positionPda,
+          payerPosition: null,
+          loanVault: market.loanVault.publicKey,
+          payerTokenAccount: alice.loanAta,
+          payer: alice.keypair.publicKey,
+          borrower: alice.keypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([alice.keypair])
+        .rpc();
+
+      const after = await program.account.userPosition.fetch(alice.positionPda);
+      const marketState = await program.account.market.fetch(market.marketPda);
+      assert.equal(after.borrowShares.toString(), "0");
+
+      // ~50 USDC surplus (the debt carries a little interest) became supply
+      const gained = after.supplyShares.sub(before.supplyShares);
+      assert.isTrue(gained.gtn(0), "Surplus should mint supply shares");
+      const gainedAssets = gained
+        .mul(marketState.totalSupplyAssets.addn(1))
+        .div(marketState.totalSupplyShares.addn(1_000_000));
+      assert.approximately(gainedAssets.toNumber(), 50_000_000, 100_000);
+    });
+  });
+
+  describe("Protocol Config Defaults", () => {
+    const USE_PROTOCOL_DEFAULT_FEE = 65_535;
+
+    before(async () => {
+      await program.methods
+        .setProtocolConfig(
+          1_000, // 10% default fee
+          authority.publicKey,
+          new anchor.BN(LLTV),
+          new anchor.BN("10000000000000000"), // 1%
+          new anchor.BN("100000000000000000") // 10%
+        )
+        .accounts({
+          config: configPda,
+          protocolConfig: protocolConfigPda,
+          admin: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .enableLltv(new anchor.BN(LLTV))
+        .accounts({
+          config: configPda,
+          protocolConfig: protocolConfigPda,
+          admin: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("A market initialized with the sentinel fee inherits the default", async () => {
+      const market = await createTestMarket(LLTV, 0, 0, USE_PROTOCOL_DEFAULT_FEE);
+
+      const marketState = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketState.feeBps, 1_000);
+    });
+
+    it("Rejects an LLTV above the protocol maximum", async () => {
+      try {
+        await createTestMarket(LLTV + 1);
+        assert.fail("LLTV above max_lltv should fail");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidLltv");
+      }
+    });
+  });
+
+  describe("Enabled LLTVs", () => {
+    const OTHER_LLTV = 0.7 * LLTV_PRECISION; // 70%
+
+    async function setLltvEnabled(lltv: number, enabled: boolean) {
+      const method = enabled
+        ? program.methods.enableLltv(new anchor.BN(lltv))
+        : program.methods.disableLltv(new anchor.BN(lltv));
+      await method
+        .accounts({
+          config: configPda,
+          protocolConfig: protocolConfigPda,
+          admin: authority.publicKey,
+        })
+        .rpc();
+    }
+
+    it("Creates a market with an enabled LLTV", async () => {
+      await setLltvEnabled(OTHER_LLTV, true);
+      const market = await createTestMarket(OTHER_LLTV);
+
+      const marketState = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketState.lltv.toNumber(), OTHER_LLTV);
+    });
+
+    it("Rejects a market with a disabled LLTV", async () => {
+      await setLltvEnabled(OTHER_LLTV, false);
+
+      try {
+        await createTestMarket(OTHER_LLTV);
+        assert.fail("Disabled LLTV should be rejected");
+      } catch (error) {
+        assert.include(error.toString(), "LltvNotEnabled");
+      }
+    });
+  });
+
+  describe("Position Health Event", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 1000_000_000, 10_000_000_000);
+      await supply(market, alice, 1000_000_000);
+      await supplyCollateral(market, alice, 10_000_000_000); // 10 SOL = 1000 USDC
+    });
+
+    it("Reports the health factor of the borrowed position", async () => {
+      const signature = await borrow(market, alice, 500_000_000);
+
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const events = [...parser.parseLogs(tx.meta.logMessages)];
+      const health = events.find((e) => e.name === "positionHealthEvent").data;
+
+      // HF = collateral_value × lltv / debt, scaled by 1e8
+      const collateralValue = 1000_000_000;
+      const debt = health.debtValue.toNumber();
+      assert.approximately(debt, 500_000_000, 1);
+      assert.equal(health.collateralValue.toNumber(), collateralValue);
+      const expected = new anchor.BN(collateralValue)
+        .mul(new anchor.BN(LLTV))
+        .div(new anchor.BN(debt));
+      assert.equal(health.healthFactor.toString(), expected.toString());
+      assert.isTrue(health.user.equals(alice.keypair.publicKey));
+    });
+  });
+
+  describe("Close And Recreate Position", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+    let registryPda: anchor.web3.PublicKey;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 1000_000_000, 0);
+      [registryPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("user-registry"), alice.keypair.publicKey.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("Recreates a closed position with clean counters", async () => {
+      await supply(market, alice, 100_000_000);
+      const openBefore = (await program.account.market.fetch(market.marketPda))
+        .openPositions;
+
+      const position = await program.account.userPosition.fetch(alice.positionPda);
+      await program.methods
+        .withdraw(new anchor.BN(0), position.supplyShares)
+        .accounts({
+          market: market.marketPda,
+          userPosition: alice.positionPda,
+          user: alice.keypair.publicKey,
+          receiverTokenAccount: alice.loanAta,
+          loanVault: market.loanVault.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([alice.keypair])
+        .rpc();
+
+      await program.methods
+        .closePosition()
+        .accounts({
+          market: market.marketPda,
+          userPosition: alice.positionPda,
+          userRegistry: registryPda,
+          user: alice.keypair.publicKey,
+        })
+        .signers([alice.keypair])
+        .rpc();
+
+      assert.isNull(await provider.connection.getAccountInfo(alice.positionPda));
+      let marketState = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketState.openPositions, openBefore - 1);
+      let registry = await program.account.userRegistry.fetch(registryPda);
+      assert.isFalse(registry.markets.some((m) => m.equals(market.marketPda)));
+
+      // Supplying again recreates the position from scratch
+      await supply(market, alice, 50_000_000);
+
+      const recreated = await program.account.userPosition.fetch(alice.positionPda);
+      assert.isTrue(recreated.user.equals(alice.keypair.publicKey));
+      assert.isTrue(recreated.market.equals(market.marketPda));
+      assert.isTrue(recreated.supplyShares.gtn(0));
+      assert.equal(recreated.borrowShares.toNumber(), 0);
+      assert.equal(recreated.collateralAmount.toNumber(), 0);
+
+      marketState = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketState.openPositions, openBefore);
+      registry = await program.account.userRegistry.fetch(registryPda);
+      assert.isTrue(registry.markets.some((m) => m.equals(market.marketPda)));
+    });
+
+    it("Refuses to close a position with balances", async () => {
+      try {
+        await program.methods
+          .closePosition()
+          .accounts({
+            market: market.marketPda,
+            userPosition: alice.positionPda,
+            userRegistry: registryPda,
+            user: alice.keypair.publicKey,
+          })
+          .signers([alice.keypair])
+          .rpc();
+        assert.fail("Closing a funded position should fail");
+      } catch (error) {
+        assert.include(error.toString(), "PositionNotEmpty");
+      }
+    });
+  });
+
+  // Requires a program built with `--features test-utils`; skipped otherwise
+  describe("Borrow Ratio Breach (test-utils)", () => {
+    const hasTestUtils = program.idl.instructions.some(
+      (ix) => ix.name === "setLastUpdate"
+    );
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async function () {
+      if (!hasTestUtils) {
+        this.skip();
+      }
+      market = await createTestMarket();
+      alice = await createTestUser(market, 1000_000_000, 20_000_000_000);
+      await supply(market, alice, 1000_000_000);
+      await supplyCollateral(market, alice, 20_000_000_000); // 20 SOL = 2000 USDC
+      await borrow(market, alice, 900_000_000);
+
+      // Ceiling at the current 90% utilization
+      await program.methods
+        .setMaxBorrowRatio(9_000)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Accrues past the ceiling and emits RatioBreachEvent", async () => {
+      const slot = await provider.connection.getSlot();
+      const now = await provider.connection.getBlockTime(slot);
+      await (program.methods as any)
+        .setLastUpdate(new anchor.BN(now - 31_557_600))
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      // Any accruing operation triggers the check
+      const signature = await borrow(market, alice, 1);
+
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const events = [...parser.parseLogs(tx.meta.logMessages)];
+      const breach = events.find((e) => e.name === "ratioBreachEvent").data;
+
+      assert.equal(breach.maxBorrowRatioBps, 9_000);
+      assert.isTrue(
+        breach.totalBorrowAssets.muln(10_000).gt(breach.totalSupplyAssets.muln(9_000))
+      );
+    });
+  });
+
+  describe("Swapped Vaults", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 2000_000_000, 20_000_000_000);
+      await supply(market, alice, 1000_000_000);
+      await supplyCollateral(market, alice, 10_000_000_000);
+      await borrow(market, alice, 100_000_000);
+    });
+
+    async function expectError(tx: Promise<string>, code: string) {
+      try {
+        await tx;
+        assert.fail(`Expected ${code}`);
+      } catch (error) {
+        assert.include(error.toString(), code);
+      }
+    }
+
+    it("Rejects the collateral vault where the loan vault is expected", async () => {
+      const wrongVault = market.collateralVault.publicKey;
+
+      await expectError(
+        program.methods
+          .supply(new anchor.BN(1_000_000), new anchor.BN(0), new anchor.BN(0))
+          .accounts({
+            market: market.marketPda,
+            userPosition: alice.positionPda,
+            loanVault: wrongVault,
+            userTokenAccount: alice.loanAta,
+            user: alice.keypair.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([alice.keypair])
+          .rpc(),
+        "WrongLoanVault"
+      );
+
+      await expectError(
+        program.methods
+          .borrow(new anchor.BN(1_000_000), new anchor.BN(0), false)
+          .accounts({
+            market: market.marketPda,
+            userPosition: alice.positionPda,
+            loanVault: wrongVault,
+            userTokenAccount: alice.loanAta,
+            user: alice.keypair.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([alice.keypair])
+          .rpc(),
+        "WrongLoanVault"
+      );
+
+      await expectError(
+        program.methods
+          .withdraw(new anchor.BN(1_000_000), new anchor.BN(0))
+          .accounts({
+            market: market.marketPda,
+            userPosition: alice.positionPda,
+            user: alice.keypair.publicKey,
+            receiverTokenAccount: alice.loanAta,
+            loanVault: wrongVault,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([alice.keypair])
+          .rpc(),
+        "WrongLoanVault"
+      );
+
+      await expectError(
+        program.methods
+          .repay(new anchor.BN(1_000_000), new anchor.BN(0), false)
+          .accounts({
+            market: market.marketPda,
+            borrowerPosition: alice.positionPda,
+            payerPosition: null,
+            loanVault: wrongVault,
+            payerTokenAccount: alice.loanAta,
+            payer: alice.keypair.publicKey,
+            borrower: alice.keypair.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([alice.keypair])
+          .rpc(),
+        "WrongLoanVault"
+      );
+    });
+
+    it("Rejects the loan vault where the collateral vault is expected", async () => {
+      const wrongVault = market.loanVault.publicKey;
+
+      await expectError(
+        program.methods
+          .supplyCollateral(new anchor.BN(1_000_000_000))
+          .accounts({
+            market: market.marketPda,
+            userPosition: alice.positionPda,
+            collateralVault: wrongVault,
+            userCollateralAccount: alice.collateralAta,
+            user: alice.keypair.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([alice.keypair])
+          .rpc(),
+        "WrongCollateralVault"
+      );
+
+      await expectError(
+        program.methods
+          .withdrawCollateral(new anchor.BN(1_000_000_000))
+          .accounts({
+            market: market.marketPda,
+            userPosition: alice.positionPda,
+            user: alice.keypair.publicKey,
+            receiverCollateralAccount: alice.collateralAta,
+            collateralVault: wrongVault,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([alice.keypair])
+          .rpc(),
+        "WrongCollateralVault"
+      );
+    });
+  });
+
+  describe("Repay Without Debt", () => {
+    it("Rejects a repay for a position with no debt before any transfer", async () => {
+      const market = await createTestMarket();
+      const borrower = await createTestUser(market, 0, 10_000_000_000);
+      const helper = await createTestUser(market, 100_000_000, 0);
+      await supplyCollateral(market, borrower, 10_000_000_000);
+
+      const before = await provider.connection.getTokenAccountBalance(helper.loanAta);
+      try {
+        await program.methods
+          .repay(new anchor.BN(50_000_000), new anchor.BN(0), false)
+          .accounts({
+            market: market.marketPda,
+            borrowerPosition: borrower.positionPda,
+            payerPosition: null,
+            loanVault: market.loanVault.publicKey,
+            payerTokenAccount: helper.loanAta,
+            payer: helper.keypair.publicKey,
+            borrower: borrower.keypair.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([helper.keypair])
+          .rpc();
+        assert.fail("Repay without debt should fail");
+      } catch (error) {
+        assert.include(error.toString(), "NoDebtToRepay");
+      }
+
+      const after = await provider.connection.getTokenAccountBalance(helper.loanAta);
+      assert.equal(after.value.amount, before.value.amount);
+    });
+  });
+
+  describe("Inflation Attack Across Decimals", () => {
+    const MAX_DONATION_TOKENS = 1_000_000_000; // MAX_MARKET_TOKENS
+
+    async function withdrawAllShares(market: TestMarket, user: TestUser) {
+      const position = await program.account.userPosition.fetch(user.positionPda);
+      const before = await provider.connection.getTokenAccountBalance(user.loanAta);
+      await program.methods
+        .withdraw(new anchor.BN(0), position.supplyShares)
+        .accounts({
+          market: market.marketPda,
+          userPosition: user.positionPda,
+          user: user.keypair.publicKey,
+          receiverTokenAccount: user.loanAta,
+          loanVault: market.loanVault.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user.keypair])
+        .rpc();
+      const after = await provider.connection.getTokenAccountBalance(user.loanAta);
+      return new anchor.BN(after.value.amount).sub(new anchor.BN(before.value.amount));
+    }
+
+    async function donate(market: TestMarket, attacker: TestUser, amount: number) {
+      await transfer(
+        provider.connection,
+        attacker.keypair,
+        attacker.loanAta,
+        market.loanVault.publicKey,
+        attacker.keypair,
+        amount
+      );
+    }
+
+    for (const decimals of [6, 8, 9]) {
+      it(`Bounds a donate-then-supply attack on a ${decimals}-decimal loan token`, async () => {
+        // Powers of ten up to 1e18 are exact as numbers
+        const unit = 10 ** decimals;
+        const victimDeposit = 1_000 * unit;
+        const maxDonation = MAX_DONATION_TOKENS * unit;
+        // recommended_first_supply_dead_shares: 0.001 tokens at 1e6 shares per base unit
+        const deadShares = new anchor.BN(unit).muln(1_000);
+        const firstSupply = unit / 1_000 + 1;
+
+        const market = await createTestMarket(LLTV, 0, 0, 0, decimals);
+        await program.methods
+          .setFirstSupplyDeadShares(deadShares)
+          .accounts({ market: market.marketPda, authority: authority.publicKey })
+          .rpc();
+        const attacker = await createTestUser(market, maxDonation, 0);
+        await mintTo(
+          provider.connection,
+          authority.payer,
+          market.loanTokenMint,
+          attacker.loanAta,
+          authority.publicKey,
+          firstSupply + unit
+        );
+        const victim = await createTestUser(market, victimDeposit, 0);
+        const lateVictim = await createTestUser(market, victimDeposit, 0);
+
+        // Smallest first supply that clears the dead shares, then a one
+        // token donation
+        await supply(market, attacker, firstSupply);
+        await donate(market, attacker, unit);
+
+        await supply(market, victim, victimDeposit);
+        const victimRedeemed = await withdrawAllShares(market, victim);
+        assert.isTrue(
+          victimRedeemed.gte(new anchor.BN(victimDeposit - 1)),
+          `redeemed ${victimRedeemed.toString()}`
+        );
+
+        // 1e9 more tokens donated: never credited, so a later victim isn't
+        // diluted either
+        await donate(market, attacker, maxDonation);
+        await supply(market, lateVictim, victimDeposit);
+        const lateVictimRedeemed = await withdrawAllShares(market, lateVictim);
+        assert.isTrue(
+          lateVictimRedeemed.gte(new anchor.BN(victimDeposit - 1)),
+          `redeemed ${lateVictimRedeemed.toString()}`
+        );
+
+        // Donations are never credited: the attacker gets back at most the supply
+        const attackerRedeemed = await withdrawAllShares(market, attacker);
+        assert.isTrue(
+          attackerRedeemed.lte(new anchor.BN(firstSupply)),
+          `redeemed ${attackerRedeemed.toString()}`
+        );
+      });
+    }
+  });
+
+  describe("Position Freeze", () => {
+    let market: TestMarket;
+    let lender: TestUser;
+    let alice: TestUser;
+
+    async function setFrozen(user: TestUser, frozen: boolean) {
+      await program.methods
+        .setPositionFrozen(frozen)
+        .accounts({
+          market: market.marketPda,
+          userPosition: user.positionPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    }
+
+    async function expectFrozen(tx: Promise<string>) {
+      try {
+        await tx;
+        assert.fail("Frozen position should be blocked");
+      } catch (error) {
+        assert.include(error.toString(), "PositionFrozen");
+      }
+    }
+
+    function withdrawCollateral(user: TestUser, amount: number) {
+      return program.methods
+        .withdrawCollateral(new anchor.BN(amount))
+        .accounts({
+          market: market.marketPda,
+          userPosition: user.positionPda,
+          user: user.keypair.publicKey,
+          receiverCollateralAccount: user.collateralAta,
+          collateralVault: market.collateralVault.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user.keypair])
+        .rpc();
+    }
+
+    function withdraw(user: TestUser, assets: number) {
+      return program.methods
+        .withdraw(new anchor.BN(assets), new anchor.BN(0))
+        .accounts({
+          market: market.marketPda,
+          userPosition: user.positionPda,
+          user: user.keypair.publicKey,
+          receiverTokenAccount: user.loanAta,
+          loanVault: market.loanVault.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user.keypair])
+        .rpc();
+    }
+
+    before(async () => {
+      market = await createTestMarket();
+      lender = await createTestUser(market, 1000_000_000, 0);
+      alice = await createTestUser(market, 100_000_000, 10_000_000_000);
+      await supply(market, lender, 1000_000_000);
+      await supply(market, alice, 50_000_000);
+      await supplyCollateral(market, alice, 10_000_000_000);
+      await borrow(market, alice, 200_000_000);
+    });
+
+    it("Rejects freezing by a non-authority", async () => {
+      const outsider = anchor.web3.Keypair.generate();
+      try {
+        await program.methods
+          .setPositionFrozen(true)
+          .accounts({
+            market: market.marketPda,
+            userPosition: alice.positionPda,
+            authority: outsider.publicKey,
+          })
+          .signers([outsider])
+          .rpc();
+        assert.fail("Only the market authority can freeze positions");
+      } catch (error) {
+        assert.include(error.toString(), "Unauthorized");
+      }
+    });
+
+    it("Blocks borrow and withdrawals of a frozen position", async () => {
+      await setFrozen(alice, true);
+
+      await expectFrozen(borrow(market, alice, 10_000_000));
+      await expectFrozen(withdraw(alice, 10_000_000));
+      await expectFrozen(withdrawCollateral(alice, 1_000_000_000));
+    });
+
+    it("Still lets a frozen position repay and supply", async () => {
+      await program.methods
+        .repay(new anchor.BN(50_000_000), new anchor.BN(0), false)
+        .accounts({
+          market: market.marketPda,
+          borrowerPosition: alice.positionPda,
+          payerPosition: null,
+          loanVault: market.loanVault.publicKey,
+          payerTokenAccount: alice.loanAta,
+          payer: alice.keypair.publicKey,
+          borrower: alice.keypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([alice.keypair])
+        .rpc();
+      await supply(market, alice, 10_000_000);
+
+      const position = await program.account.userPosition.fetch(alice.positionPda);
+      assert.isTrue(position.frozen);
+    });
+
+    it("Leaves other positions untouched and unfreezes", async () => {
+      await withdraw(lender, 10_000_000);
+
+      await setFrozen(alice, false);
+      await withdrawCollateral(alice, 1_000_000_000);
+      await borrow(market, alice, 10_000_000);
+    });
+  });
+
+  describe("Market Reset", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+    const RESET_PARAMS = {
+      lltv: new anchor.BN(0.5 * LLTV_PRECISION),
+      irm: {
+        baseRateWad: new anchor.BN("100000000000000000"), // 10%
+        slope1Wad: new anchor.BN(0),
+        slope2Wad: new anchor.BN(0),
+        kinkUtilizationBps: 9_000,
+      },
+      borrowCap: new anchor.BN(0),
+      borrowCapRatioBps: 0,
+      maxBorrowPerPosition: new anchor.BN(0),
+    };
+
+    function resetMarket() {
+      return program.methods
+        .resetMarket(RESET_PARAMS)
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+          protocolConfig: protocolConfigPda,
+        })
+        .rpc();
+    }
+
+    before(async () => {
+      await ensureProtocolConfig(RESET_PARAMS.lltv.toNumber());
+      market = await createTestMarket();
+      alice = await createTestUser(market, 100_000_000, 10_000_000_000);
+      await supply(market, alice, 100_000_000);
+      await supplyCollateral(market, alice, 10_000_000_000);
+    });
+
+    it("Rejects a reset while balances remain", async () => {
+      try {
+        await resetMarket();
+        assert.fail("Reset should fail on a market in use");
+      } catch (error) {
+        assert.include(error.toString(), "MarketNotEmpty");
+      }
+    });
+
+    it("Resets an emptied market and applies the new parameters", async () => {
+      const position = await program.account.userPosition.fetch(alice.positionPda);
+      await program.methods
+        .withdraw(new anchor.BN(0), position.supplyShares)
+        .accounts({
+          market: market.marketPda,
+          userPosition: alice.positionPda,
+          user: alice.keypair.publicKey,
+          receiverTokenAccount: alice.loanAta,
+          loanVault: market.loanVault.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([alice.keypair])
+        .rpc();
+      await program.methods
+        .withdrawCollateral(new anchor.BN(10_000_000_000))
+        .accounts({
+          market: market.marketPda,
+          userPosition: alice.positionPda,
+          user: alice.keypair.publicKey,
+          receiverCollateralAccount: alice.collateralAta,
+          collateralVault: market.collateralVault.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([alice.keypair])
+        .rpc();
+      const [registryPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("user-registry"), alice.keypair.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .closePosition()
+        .accounts({
+          market: market.marketPda,
+          userPosition: alice.positionPda,
+          userRegistry: registryPda,
+          user: alice.keypair.publicKey,
+        })
+        .signers([alice.keypair])
+        .rpc();
+
+      await resetMarket();
+      const state = await program.account.market.fetch(market.marketPda);
+      assert.equal(state.version, 1);
+      assert.equal(state.lltv.toNumber(), 0.5 * LLTV_PRECISION);
+      assert.isTrue(state.currentBorrowRateWad.eq(RESET_PARAMS.irm.baseRateWad));
+      assert.isTrue(state.loanVault.equals(market.loanVault.publicKey));
+
+      // New positions are held to the 50% LLTV: 10 SOL ($1000) backs $500
+      const lender = await createTestUser(market, 1000_000_000, 0);
+      const bob = await createTestUser(market, 0, 10_000_000_000);
+      await supply(market, lender, 1000_000_000);
+      await supplyCollateral(market, bob, 10_000_000_000);
+      try {
+        await borrow(market, bob, 600_000_000);
+        assert.fail("Borrow above the new LLTV should fail");
+      } catch (error) {
+        assert.include(error.toString(), "InsufficientCollateral");
+      }
+      await borrow(market, bob, 400_000_000);
+    });
+  });
+
+  describe("Event Replay", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+    let liquidator: TestUser;
+    const parser = new anchor.EventParser(program.programId, program.coder);
+
+    async function balance(account: anchor.web3.PublicKey) {
+      const result = await provider.connection.getTokenAccountBalance(account);
+      return new anchor.BN(result.value.amount);
+    }
+
+    /// Market, position and token balances of `user`
+    async function snapshot(user: TestUser) {
+      return {
+        market: await program.account.market.fetch(market.marketPda),
+        position: await program.account.userPosition.fetch(user.positionPda),
+        loan: await balance(user.loanAta),
+        collateral: await balance(user.collateralAta),
+      };
+    }
+
+    /// Runs `send`, then returns the one `name` event it emitted with the
+    /// state before and after
+    async function replay(user: TestUser, name: string, send: () => Promise<string>) {
+      const before = await snapshot(user);
+      const signature = await send();
+      const after = await snapshot(user);
+
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const events = [...parser.parseLogs(tx.meta.logMessages)].filter((e) => e.name === name);
+      assert.lengthOf(events, 1, `${name} emitted once`);
+      return { event: events[0].data, before, after };
+    }
+
+    function assertBN(actual: anchor.BN, expected: anchor.BN, field: string) {
+      assert.equal(actual.toString(), expected.toString(), field);
+    }
+
+    function repay(user: TestUser, shares: anchor.BN) {
+      return program.methods
+        .repay(new anchor.BN(0), shares, false)
+        .accounts({
+          market: market.marketPda,
+          borrowerPosition: user.positionPda,
+          payerPosition: null,
+          loanVault: market.loanVault.publicKey,
+          payerTokenAccount: user.loanAta,
+          payer: user.keypair.publicKey,
+          borrower: user.keypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user.keypair])
+        .rpc();
+    }
+
+    before(async () => {
+      market = await createTestMarket();
+      const lender = await createTestUser(market, 2000_000_000, 0);
+      alice = await createTestUser(market, 1000_000_000, 10_000_000_000);
+      liquidator = await createTestUser(market, 1000_000_000, 0);
+      await supply(market, lender, 2000_000_000);
+
+      await program.methods
+        .setFee(0, 100) // 1% origination fee, so fee shares show up
+        .accounts({ market: market.marketPda, authority: authority.publicKey })
+        .rpc();
+    });
+
+    it("SupplyEvent mirrors the supply", async () => {
+      const { event, before, after } = await replay(alice, "supplyEvent", () =>
+        supply(market, alice, 500_000_000)
+      );
+      assert.isTrue(event.market.equals(market.marketPda));
+      assert.isTrue(event.user.equals(alice.keypair.publicKey));
+      assertBN(event.assets, before.loan.sub(after.loan), "assets");
+      assertBN(event.shares, after.position.supplyShares.sub(before.position.supplyShares), "shares");
+      assertBN(event.totalSupplyAssets, after.market.totalSupplyAssets, "totalSupplyAssets");
+      assertBN(event.totalSupplyShares, after.market.totalSupplyShares, "totalSupplyShares");
+    });
+
+    it("SupplyCollateralEvent mirrors the deposit", async () => {
+      const { event, before, after } = await replay(alice, "supplyCollateralEvent", () =>
+        program.methods
+          .supplyCollateral(new anchor.BN(10_000_000_000))
+          .accounts({
+            market: market.marketPda,
+            userPosition: alice.positionPda,
+            collateralVault: market.collateralVault.publicKey,
+            userCollateralAccount: alice.collateralAta,
+            user: alice.keypair.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([alice.keypair])
+          .rpc()
+      );
+      assert.isTrue(event.market.equals(market.marketPda));
+      assertBN(event.amount, before.collateral.sub(after.collateral), "amount");
+      assertBN(event.collateralAmount, after.position.collateralAmount, "collateralAmount");
+      assertBN(event.totalCollateral, after.market.totalCollateral, "totalCollateral");
+    });
+
+    it("BorrowEvent mirrors the borrow, fee shares included", async () => {
+      const { event, before, after } = await replay(alice, "borrowEvent", () =>
+        borrow(market, alice, 300_000_000)
+      );
+      assert.isTrue(event.market.equals(market.marketPda));
+      assertBN(event.assets, after.loan.sub(before.loan), "assets");
+      assert.isTrue(event.feeShares.gtn(0));
+      assertBN(
+        event.shares.add(event.feeShares),
+        after.position.borrowShares.sub(before.position.borrowShares),
+        "shares + feeShares"
+      );
+      assertBN(event.fee, after.market.reserves.sub(before.market.reserves), "fee");
+      assertBN(event.totalBorrowAssets, after.market.totalBorrowAssets, "totalBorrowAssets");
+      assertBN(event.totalBorrowShares, after.market.totalBorrowShares, "totalBorrowShares");
+    });
+
+    it("RepayEvent mirrors an oversized repay", async () => {
+      const position = await program.account.userPosition.fetch(alice.positionPda);
+      const { event, before, after } = await replay(alice, "repayEvent", () =>
+        repay(alice, position.borrowShares.muln(2))
+      );
+      assertBN(event.shares, before.position.borrowShares.sub(after.position.borrowShares), "shares");
+      assertBN(event.shares, position.borrowShares, "full debt burned");
+      assertBN(event.assets, before.loan.sub(after.loan), "assets");
+      assertBN(event.remainingBorrowShares, after.position.borrowShares, "remainingBorrowShares");
+      assertBN(event.totalBorrowAssets, after.market.totalBorrowAssets, "totalBorrowAssets");
+      assertBN(event.totalBorrowShares, after.market.totalBorrowShares, "totalBorrowShares");
+    });
+
+    it("WithdrawEvent mirrors the withdrawal", async () => {
+      const { event, before, after } = await replay(alice, "withdrawEvent", () =>
+        program.methods
+          .withdraw(new anchor.BN(100_000_000), new anchor.BN(0))
+          .accounts({
+            market: market.marketPda,
+            userPosition: alice.positionPda,
+            user: alice.keypair.publicKey,
+            receiverTokenAccount: alice.loanAta,
+            loanVault: market.loanVault.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([alice.keypair])
+          .rpc()
+      );
+      assertBN(event.assets, after.loan.sub(before.loan), "assets");
+      assertBN(event.shares, before.position.supplyShares.sub(after.position.supplyShares), "shares");
+      assertBN(event.totalSupplyAssets, after.market.totalSupplyAssets, "totalSupplyAssets");
+      assertBN(event.totalSupplyShares, after.market.totalSupplyShares, "totalSupplyShares");
+    });
+
+    it("WithdrawCollateralEvent mirrors the withdrawal", async () => {
+      const { event, before, after } = await replay(alice, "withdrawCollateralEvent", () =>
+        program.methods
+          .withdrawCollateral(new anchor.BN(1_000_000_000))
+          .accounts({
+            market: market.marketPda,
+            userPosition: alice.positionPda,
+            user: alice.keypair.publicKey,
+            receiverCollateralAccount: alice.collateralAta,
+            collateralVault: market.collateralVault.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([alice.keypair])
+          .rpc()
+      );
+      assertBN(event.assets, after.collateral.sub(before.collateral), "assets");
+      assertBN(event.remainingCollateral, after.position.collateralAmount, "remainingCollateral");
+    });
+
+    it("LiquidateEvent mirrors the liquidation", async () => {
+      // 9 SOL backs 720 USDC at 100 USDC/SOL, only 612 at 85
+      await borrow(market, alice, 700_000_000);
+      await program.methods
+        .setManualPrice(new anchor.BN(85_000), true)
+        .accounts({ market: market.marketPda, authority: authority.publicKey })
+        .rpc();
+
+      const loanBefore = await balance(liquidator.loanAta);
+      const collateralBefore = await balance(liquidator.collateralAta);
+
+      const { event, before, after } = await replay(alice, "liquidateEvent", () =>
+        program.methods
+          .batchLiquidate(new anchor.BN(0))
+          .accounts({
+            market: market.marketPda,
+            loanVault: market.loanVault.publicKey,
+            collateralVault: market.collateralVault.publicKey,
+            liquidatorLoanAccount: liquidator.loanAta,
+            liquidatorCollateralAccount: liquidator.collateralAta,
+            liquidator: liquidator.keypair.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts([
+            { pubkey: alice.positionPda, isWritable: true, isSigner: false },
+          ])
+          .signers([liquidator.keypair])
+          .rpc()
+      );
+      assert.isTrue(event.borrower.equals(alice.keypair.publicKey));
+      assertBN(
+        event.repaidShares,
+        before.position.borrowShares.sub(after.position.borrowShares),
+        "repaidShares"
+      );
+      assertBN(
+        event.seizedCollateral,
+        before.position.collateralAmount.sub(after.position.collateralAmount),
+        "seizedCollateral"
+      );
+      assertBN(event.repaidAssets, loanBefore.sub(await balance(liquidator.loanAta)), "repaidAssets");
+      assertBN(
+        event.seizedCollateral,
+        (await balance(liquidator.collateralAta)).sub(collateralBefore),
+        "liquidator collateral"
+      );
+      assertBN(event.remainingBorrowShares, after.position.borrowShares, "remainingBorrowShares");
+      assertBN(event.remainingCollateral, after.position.collateralAmount, "remainingCollateral");
+    });
+  });
+
+  describe("Self-Liquidation Toggle", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    function setDisallowSelfLiquidation(disallow: boolean) {
+      return program.methods
+        .setDisallowSelfLiquidation(disallow)
+        .accounts({ market: market.marketPda, authority: authority.publicKey })
+        .rpc();
+    }
+
+    function selfLiquidate() {
+      return program.methods
+        .batchLiquidate(new anchor.BN(0))
+        .accounts({
+          market: market.marketPda,
+          loanVault: market.loanVault.publicKey,
+          collateralVault: market.collateralVault.publicKey,
+          liquidatorLoanAccount: alice.loanAta,
+          liquidatorCollateralAccount: alice.collateralAta,
+          liquidator: alice.keypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([{ pubkey: alice.positionPda, isWritable: true, isSigner: false }])
+        .signers([alice.keypair])
+        .rpc();
+    }
+
+    before(async () => {
+      market = await createTestMarket();
+      const lender = await createTestUser(market, 2000_000_000, 0);
+      alice = await createTestUser(market, 0, 10_000_000_000);
+      await supply(market, lender, 2000_000_000);
+      await supplyCollateral(market, alice, 10_000_000_000); // 10 SOL
+      await borrow(market, alice, 700_000_000);
+
+      // 850 USDC of SOL no longer supports 700 USDC at 80% LLTV
+      await program.methods
+        .setManualPrice(new anchor.BN(85_000), true)
+        .accounts({ market: market.marketPda, authority: authority.publicKey })
+        .rpc();
+    });
+
+    it("Rejects self-liquidation when disallowed", async () => {
+      await setDisallowSelfLiquidation(true);
+      const before = await program.account.userPosition.fetch(alice.positionPda);
+
+      try {
+        await selfLiquidate();
+        assert.fail("Self-liquidation should be rejected");
+      } catch (error) {
+        assert.include(error.toString(), "SelfLiquidationDisallowed");
+      }
+
+      const after = await program.account.userPosition.fetch(alice.positionPda);
+      assert.equal(after.borrowShares.toString(), before.borrowShares.toString());
+    });
+
+    it("Allows self-liquidation by default", async () => {
+      await setDisallowSelfLiquidation(false);
+      const before = await program.account.userPosition.fetch(alice.positionPda);
+
+      await selfLiquidate();
+
+      const after = await program.account.userPosition.fetch(alice.positionPda);
+      assert.isTrue(after.borrowShares.lt(before.borrowShares));
+      assert.isTrue(after.collateralAmount.lt(before.collateralAmount));
+    });
+  });
+
+  describe("Borrow Constraints View", () => {
+    let market: TestMarket;
+
+    function getBorrowConstraints() {
+      return program.methods
+        .getBorrowConstraints()
+        .accounts({ market: market.marketPda })
+        .view();
+    }
+
+    before(async () => {
+      market = await createTestMarket();
+      const lender = await createTestUser(market, 1000_000_000, 0);
+      await supply(market, lender, 1000_000_000);
+
+      await program.methods
+        .setBorrowCaps(new anchor.BN(500_000_000), 9_000, new anchor.BN(100_000_000))
+        .accounts({ market: market.marketPda, authority: authority.publicKey })
+        .rpc();
+    });
+
+    it("Returns constraints matching the market configuration", async () => {
+      const constraints = await getBorrowConstraints();
+      const state = await program.account.market.fetch(market.marketPda);
+
+      assert.equal(constraints.lltv.toString(), state.lltv.toString());
+      assert.equal(constraints.liquidationThreshold.toString(), state.lltv.toString());
+      assert.equal(constraints.borrowCap.toString(), "500000000");
+      assert.equal(constraints.borrowCapRatioBps, 9_000);
+      assert.equal(constraints.effectiveBorrowCap.toString(), "500000000");
+      assert.equal(constraints.maxBorrowPerPosition.toString(), "100000000");
+      assert.equal(constraints.originationFeeBps, state.originationFeeBps);
+      assert.equal(constraints.borrowCooldownSecs, state.borrowCooldownSecs);
+      assert.equal(constraints.availableLiquidity.toString(), "1000000000");
+      assert.equal(constraints.maxBorrowable.toString(), "500000000");
+      assert.isTrue(constraints.priceInBounds);
+      assert.isTrue(constraints.priceFresh);
+      assert.isFalse(constraints.paused);
+      assert.isFalse(constraints.autoPaused);
+      assert.isTrue(constraints.borrowingEnabled);
+    });
+
+    it("Reports borrowing disabled while paused", async () => {
+      const setPaused = (paused: boolean) =>
+        program.methods
+          .setPaused(paused)
+          .accounts({ market: market.marketPda, authority: authority.publicKey })
+          .rpc();
+
+      await setPaused(true);
+      const constraints = await getBorrowConstraints();
+      assert.isTrue(constraints.paused);
+      assert.isFalse(constraints.borrowingEnabled);
+
+      await setPaused(false);
+      assert.isTrue((await getBorrowConstraints()).borrowingEnabled);
+    });
+  });
+
+  describe("Collateral Shortfall", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      const lender = await createTestUser(market, 2000_000_000, 0);
+      alice = await createTestUser(market, 0, 10_000_000_000);
+      await supply(market, lender, 2000_000_000);
+      await supplyCollateral(market, alice, 10_000_000_000); // 10 SOL = 1000 USDC
+    });
+
+    it("Returns the shortfall of a rejected over-borrow", async () => {
+      // 900 USDC needs 1125 USDC of collateral at 80% LLTV
+      const tx = await program.methods
+        .borrow(new anchor.BN(900_000_000), new anchor.BN(0), false)
+        .accounts({
+          market: market.marketPda,
+          userPosition: alice.positionPda,
+          loanVault: market.loanVault.publicKey,
+          userTokenAccount: alice.loanAta,
+          user: alice.keypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .transaction();
+      tx.feePayer = alice.keypair.publicKey;
+      tx.recentBlockhash = (await provider.connection.getLatestBlockhash()).blockhash;
+
+      const simulation = await provider.connection.simulateTransaction(tx, [alice.keypair]);
+      assert.isNotNull(simulation.value.err);
+      assert.isTrue(
+        simulation.value.logs.some((log) => log.includes("InsufficientCollateral"))
+      );
+
+      const returnData = simulation.value.returnData;
+      assert.isTrue(new anchor.web3.PublicKey(returnData.programId).equals(program.programId));
+      const shortfall = new anchor.BN(Buffer.from(returnData.data[0], "base64"), "le");
+      assert.equal(shortfall.toString(), "125000000");
+    });
+  });
+
+  describe("Market Manager", () => {
+    let market: TestMarket;
+    let manager: anchor.web3.Keypair;
+
+    async function expectUnauthorized(tx: Promise<string>) {
+      try {
+        await tx;
+        assert.fail("Manager should not be allowed");
+      } catch (error) {
+        assert.include(error.toString(), "Unauthorized");
+      }
+    }
+
+    before(async () => {
+      market = await createTestMarket();
+      manager = anchor.web3.Keypair.generate();
+    });
+
+    it("Only the authority can appoint a manager", async () => {
+      await expectUnauthorized(
+        program.methods
+          .setManager(manager.publicKey)
+          .accounts({ market: market.marketPda, authority: manager.publicKey })
+          .signers([manager])
+          .rpc()
+      );
+
+      await program.methods
+        .setManager(manager.publicKey)
+        .accounts({ market: market.marketPda, authority: authority.publicKey })
+        .rpc();
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.isTrue(marketAccount.manager.equals(manager.publicKey));
+    });
+
+    it("Manager can pause, unpause and set caps", async () => {
+      for (const paused of [true, false]) {
+        await program.methods
+          .setPaused(paused)
+          .accounts({ market: market.marketPda, authority: manager.publicKey })
+          .signers([manager])
+          .rpc();
+        const marketAccount = await program.account.market.fetch(market.marketPda);
+        assert.equal(marketAccount.paused, paused);
+      }
+
+      await program.methods
+        .setBorrowCaps(new anchor.BN(500_000_000), 0, new anchor.BN(0))
+        .accounts({ market: market.marketPda, authority: manager.publicKey })
+        .signers([manager])
+        .rpc();
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketAccount.borrowCap.toString(), "500000000");
+    });
+
+    it("Manager cannot change fees, the fee recipient or the oracle", async () => {
+      await expectUnauthorized(
+        program.methods
+          .setFee(100, 0)
+          .accounts({ market: market.marketPda, authority: manager.publicKey })
+          .signers([manager])
+          .rpc()
+      );
+
+      await expectUnauthorized(
+        program.methods
+          .setProtocolConfig(
+            0,
+            manager.publicKey, // fee recipient
+            new anchor.BN(LLTV),
+            new anchor.BN(0),
+            new anchor.BN("100000000000000000")
+          )
+          .accounts({
+            config: configPda,
+            protocolConfig: protocolConfigPda,
+            admin: manager.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([manager])
+          .rpc()
+      );
+
+      await expectUnauthorized(
+        program.methods
+          .setManualPrice(new anchor.BN(1_000), true)
+          .accounts({ market: market.marketPda, authority: manager.publicKey })
+          .signers([manager])
+          .rpc()
+      );
+    });
+
+    it("Removing the manager revokes its access", async () => {
+      await program.methods
+        .setManager(anchor.web3.PublicKey.default)
+        .accounts({ market: market.marketPda, authority: authority.publicKey })
+        .rpc();
+
+      await expectUnauthorized(
+        program.methods
+          .setPaused(true)
+          .accounts({ market: market.marketPda, authority: manager.publicKey })
+          .signers([manager])
+          .rpc()
+      );
+    });
+  });
+
+  describe("Share Quotes", () => {
+    let market: TestMarket;
+    let lender: TestUser;
+    let alice: TestUser;
+
+    function quote(side: object, assets: number, round: object) {
+      return program.methods
+        .quote(side as any, new anchor.BN(assets), round as any)
+        .accounts({ market: market.marketPda })
+        .view();
+    }
+
+    before(async () => {
+      market = await createTestMarket();
+      lender = await createTestUser(market, 2000_000_000, 0);
+      alice = await createTestUser(market, 0, 10_000_000_000);
+      await supply(market, lender, 1000_000_000);
+      await supplyCollateral(market, alice, 10_000_000_000);
+    });
+
+    it("Supply quote matches the shares minted", async () => {
+      const quoted = await quote({ supply: {} }, 333_333_333, { down: {} });
+      const before = await program.account.userPosition.fetch(lender.positionPda);
+
+      await supply(market, lender, 333_333_333);
+
+      const after = await program.account.userPosition.fetch(lender.positionPda);
+      assert.equal(after.supplyShares.sub(before.supplyShares).toString(), quoted.toString());
+    });
+
+    it("Borrow quote matches the debt shares issued", async () => {
+      const quoted = await quote({ borrow: {} }, 123_456_789, { up: {} });
+
+      await borrow(market, alice, 123_456_789);
+
+      const position = await program.account.userPosition.fetch(alice.positionPda);
+      assert.equal(position.borrowShares.toString(), quoted.toString());
+    });
+  });
+
+  describe("Collateral Cap", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    function setCollateralCap(collateralCap: number) {
+      return program.methods
+        .setCollateralCap(new anchor.BN(collateralCap))
+        .accounts({ market: market.marketPda, authority: authority.publicKey })
+        .rpc();
+    }
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 0, 20_000_000_000);
+    });
+
+    it("Accepts deposits up to the cap and rejects beyond it", async () => {
+      await setCollateralCap(10_000_000_000); // 10 SOL
+      await supplyCollateral(market, alice, 10_000_000_000);
+
+      try {
+        await supplyCollateral(market, alice, 1);
+        assert.fail("Deposit above the cap should fail");
+      } catch (error) {
+        assert.include(error.toString(), "CollateralCapExceeded");
+      }
+
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketAccount.totalCollateral.toString(), "10000000000");
+    });
+
+    it("Zero cap is unlimited", async () => {
+      await setCollateralCap(0);
+      await supplyCollateral(market, alice, 10_000_000_000);
+
+      const marketAccount = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketAccount.totalCollateral.toString(), "20000000000");
+    });
+  });
+
+  describe("Liquidation Preview", () => {
+    let market: TestMarket;
+    let bob: TestUser;
+    let liquidator: TestUser;
+
+    const preview = (repayAssets: anchor.BN) =>
+      program.methods
+        .previewLiquidation(repayAssets)
+        .accounts({ market: market.marketPda, userPosition: bob.positionPda })
+        .view();
+
+    // Interest accrued between the preview and the liquidation moves amounts by a few units
+    const assertClose = (actual: number, expected: number, label: string) =>
+      assert.isTrue(
+        Math.abs(actual - expected) <= expected / 10_000,
+        `${label}: ${actual} vs ${expected}`
+      );
+
+    before(async () => {
+      market = await createTestMarket();
+      const lender = await createTestUser(market, 2000_000_000, 0);
+      bob = await createTestUser(market, 0, 10_000_000_000);
+      liquidator = await createTestUser(market, 1000_000_000, 0);
+
+      await supply(market, lender, 2000_000_000);
+      await supplyCollateral(market, bob, 10_000_000_000); // 10 SOL
+      await borrow(market, bob, 700_000_000);
+    });
+
+    it("Returns a zeroed result for a healthy position", async () => {
+      const result = await preview(new anchor.BN("18446744073709551615"));
+
+      assert.isFalse(result.liquidatable);
+      assert.equal(result.repaidAssets.toNumber(), 0);
+      assert.equal(result.seizedCollateral.toNumber(), 0);
+    });
+
+    it("Matches the liquidation that follows", async () => {
+      // 85 USDC/SOL: max borrow 680 USDC < 700 USDC of debt
+      await program.methods
+        .setManualPrice(new anchor.BN(85_000), true)
+        .accounts({ market: market.marketPda, authority: authority.publicKey })
+        .rpc();
+
+      const result = await preview(new anchor.BN("18446744073709551615"));
+      assert.isTrue(result.liquidatable);
+      assert.isTrue(result.healthFactorBefore.ltn(100_000_000));
+      assert.isTrue(result.healthFactorAfter.gt(result.healthFactorBefore));
+      assert.equal(
+        result.liquidatorProfit.toString(),
+        result.seizedValue.sub(result.repaidAssets).toString()
+      );
+
+      const before = await program.account.userPosition.fetch(bob.positionPda);
+      const loanBefore = await provider.connection.getTokenAccountBalance(liquidator.loanAta);
+
+      await program.methods
+        .batchLiquidate(new anchor.BN(0))
+        .accounts({
+          market: market.marketPda,
+          loanVault: market.loanVault.publicKey,
+          collateralVault: market.collateralVault.publicKey,
+          liquidatorLoanAccount: liquidator.loanAta,
+          liquidatorCollateralAccount: liquidator.collateralAta,
+          liquidator: liquidator.keypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([{ pubkey: bob.positionPda, isWritable: true, isSigner: false }])
+        .signers([liquidator.keypair])
+        .rpc();
+
+      const after = await program.account.userPosition.fetch(bob.positionPda);
+      const loanAfter = await provider.connection.getTokenAccountBalance(liquidator.loanAta);
+      const seized = await provider.connection.getTokenAccountBalance(liquidator.collateralAta);
+
+      // The close factor is a share count: unaffected by interest
+      assert.equal(
+        before.borrowShares.sub(after.borrowShares).toString(),
+        result.repaidShares.toString()
+      );
+      assert.equal(after.borrowShares.toString(), result.remainingBorrowShares.toString());
+      assertClose(
+        Number(loanBefore.value.amount) - Number(loanAfter.value.amount),
+        result.repaidAssets.toNumber(),
+        "repaid"
+      );
+      assertClose(Number(seized.value.amount), result.seizedCollateral.toNumber(), "seized");
+      assertClose(
+        after.collateralAmount.toNumber(),
+        result.remainingCollateral.toNumber(),
+        "remaining collateral"
+      );
+    });
+  });
+
+  describe("Liquidate", () => {
+    let market: TestMarket;
+    let bob: TestUser;
+    let liquidator: TestUser;
+
+    const liquidate = (repayAssets: anchor.BN) =>
+      program.methods
+        .liquidate(repayAssets, new anchor.BN(0))
+        .accounts({
+          market: market.marketPda,
+          userPosition: bob.positionPda,
+          loanVault: market.loanVault.publicKey,
+          collateralVault: market.collateralVault.publicKey,
+          liquidatorLoanAccount: liquidator.loanAta,
+          liquidatorCollateralAccount: liquidator.collateralAta,
+          liquidator: liquidator.keypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([liquidator.keypair])
+        .rpc();
+
+    before(async () => {
+      market = await createTestMarket();
+      const lender = await createTestUser(market, 2000_000_000, 0);
+      bob = await createTestUser(market, 0, 10_000_000_000);
+      liquidator = await createTestUser(market, 1000_000_000, 0);
+
+      await supply(market, lender, 2000_000_000);
+      await supplyCollateral(market, bob, 10_000_000_000); // 10 SOL
+      await borrow(market, bob, 700_000_000);
+    });
+
+    it("Rejects a healthy position", async () => {
+      try {
+        await liquidate(new anchor.BN(100_000_000));
+        assert.fail("Healthy position should not be liquidatable");
+      } catch (error) {
+        assert.include(error.toString(), "PositionHealthy");
+      }
+    });
+
+    it("Repays the chosen amount and seizes collateral with the bonus", async () => {
+      // 85 USDC/SOL: max borrow 680 USDC < 700 USDC of debt
+      await program.methods
+        .setManualPrice(new anchor.BN(85_000), true)
+        .accounts({ market: market.marketPda, authority: authority.publicKey })
+        .rpc();
+
+      const before = await program.account.userPosition.fetch(bob.positionPda);
+      const loanBefore = await provider.connection.getTokenAccountBalance(liquidator.loanAta);
+
+      await liquidate(new anchor.BN(100_000_000));
+
+      const after = await program.account.userPosition.fetch(bob.positionPda);
+      const loanAfter = await provider.connection.getTokenAccountBalance(liquidator.loanAta);
+      const seized = await provider.connection.getTokenAccountBalance(liquidator.collateralAta);
+
+      const repaid = Number(loanBefore.value.amount) - Number(loanAfter.value.amount);
+      assert.isTrue(repaid <= 100_000_000 && repaid >= 99_999_999, `repaid ${repaid}`);
+      assert.isTrue(after.borrowShares.lt(before.borrowShares));
+
+      // 100 USDC × 1.05 / 85 USDC/SOL ≈ 1.2353 SOL
+      const seizedAmount = Number(seized.value.amount);
+      assert.isTrue(Math.abs(seizedAmount - 1_235_294_117) <= 13, `seized ${seizedAmount}`);
+      assert.equal(after.collateralAmount.toNumber(), 10_000_000_000 - seizedAmount);
+    });
+
+    it("Clamps the repay to the close factor", async () => {
+      const before = await program.account.userPosition.fetch(bob.positionPda);
+
+      await liquidate(new anchor.BN("18446744073709551615"));
+
+      const after = await program.account.userPosition.fetch(bob.positionPda);
+      const expected = before.borrowShares.sub(before.borrowShares.addn(1).divn(2));
+      assert.equal(after.borrowShares.toString(), expected.toString());
+    });
+  });
+
+  describe("Claim Fees", () => {
+    const hasTestUtils = program.idl.instructions.some(
+      (ix) => ix.name === "setLastUpdate"
+    );
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async function () {
+      if (!hasTestUtils) {
+        this.skip();
+      }
+      // fee_recipient = authority (see ensureProtocolConfig and the
+      // Protocol Config Defaults tests)
+      market = await createTestMarket(LLTV, 0, 0, 1_000); // 10% interest fee
+      alice = await createTestUser(market, 2000_000_000, 20_000_000_000);
+      await supply(market, alice, 2000_000_000);
+      await supplyCollateral(market, alice, 20_000_000_000);
+      await borrow(market, alice, 1000_000_000);
+
+      // A year of interest mints fee shares on the next accrual
+      const slot = await provider.connection.getSlot();
+      const now = await provider.connection.getBlockTime(slot);
+      await (program.methods as any)
+        .setLastUpdate(new anchor.BN(now - 31_557_600))
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    const claimFees = (signer: anchor.web3.Keypair | null) =>
+      program.methods
+        .claimFees()
+        .accounts({
+          market: market.marketPda,
+          protocolConfig: protocolConfigPda,
+          loanVault: market.loanVault.publicKey,
+          receiverTokenAccount: alice.loanAta,
+          feeRecipient: signer ? signer.publicKey : authority.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers(signer ? [signer] : [])
+        .rpc();
+
+    it("Rejects a signer other than the fee recipient", async () => {
+      try {
+        await claimFees(alice.keypair);
+        assert.fail("Only the fee recipient can claim");
+      } catch (error) {
+        assert.include(error.toString(), "Unauthorized");
+      }
+    });
+
+    it("Pays the fee shares out and burns them", async () => {
+      const balanceBefore = await provider.connection.getTokenAccountBalance(
+        alice.loanAta
+      );
+      await claimFees(null);
+      const balanceAfter = await provider.connection.getTokenAccountBalance(
+        alice.loanAta
+      );
+
+      // 10% of ~51 USDC of interest
+      const claimed =
+        Number(balanceAfter.value.amount) - Number(balanceBefore.value.amount);
+      assert.approximately(claimed, 5_127_000, 10_000);
+
+      const marketState = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketState.feeShares.toNumber(), 0);
+    });
+  });
+
+  describe("Withdraw Reserves", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+
+    before(async () => {
+      market = await createTestMarket();
+      alice = await createTestUser(market, 2000_000_000, 20_000_000_000);
+      await supply(market, alice, 2000_000_000);
+      await supplyCollateral(market, alice, 20_000_000_000);
+      await program.methods
+        .setFee(0, 100) // 1% origination fee
+        .accounts({
+          market: market.marketPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+      await borrow(market, alice, 1000_000_000); // 10 USDC to reserves
+    });
+
+    const withdrawReserves = (assets: number) =>
+      program.methods
+        .withdrawReserves(new anchor.BN(assets))
+        .accounts({
+          market: market.marketPda,
+          loanVault: market.loanVault.publicKey,
+          receiverTokenAccount: alice.loanAta,
+          authority: authority.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    it("Rejects more than the market's reserves", async () => {
+      try {
+        await withdrawReserves(10_000_001);
+        assert.fail("Withdrawing above reserves should fail");
+      } catch (error) {
+        assert.include(error.toString(), "InsufficientLiquidity");
+      }
+    });
+
+    it("Pays out the origination fees", async () => {
+      const balanceBefore = await provider.connection.getTokenAccountBalance(
+        alice.loanAta
+      );
+      await withdrawReserves(10_000_000);
+      const balanceAfter = await provider.connection.getTokenAccountBalance(
+        alice.loanAta
+      );
+
+      assert.equal(
+        Number(balanceAfter.value.amount) - Number(balanceBefore.value.amount),
+        10_000_000
+      );
+      const marketState = await program.account.market.fetch(market.marketPda);
+      assert.equal(marketState.reserves.toNumber(), 0);
+
+      // Supplier accounting is untouched: further supplies pass the vault check
+      await supply(market, alice, 1_000_000);
+    });
+  });
+
+  describe("Leverage Through A Swap", () => {
+    let market: TestMarket;
+    let alice: TestUser;
+    let poolLoanAccount: anchor.web3.PublicKey;
+    let poolCollateralAccount: anchor.web3.PublicKey;
+    const [poolAuthority] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("pool")],
+      mockSwap.programId
+    );
+
+    before(async () => {
+      market = await createTestMarket();
+      const lender = await createTestUser(market, 5000_000_000, 0);
+      await supply(market, lender, 5000_000_000);
+      alice = await createTestUser(market, 0, 10_000_000_000);
+      await supplyCollateral(market, alice, 10_000_000_000); // 10 SOL = 1000 USDC
+
+      // The pool sells SOL at the market's 100 USDC/SOL fixed price
+      const pool = await Promise.all(
+        [market.loanTokenMint, market.collateralTokenMint].map((mint) =>
+          getOrCreateAssociatedTokenAccount(
+            provider.connection,
+            authority.payer,
+            mint,
+            poolAuthority,
+            true
+          )
+        )
+      );
+      [poolLoanAccount, poolCollateralAccount] = pool.map((account) => account.address);
+      await mintTo(
+        provider.connection,
+        authority.payer,
+        market.collateralTokenMint,
+        poolCollateralAccount,
+        authority.publicKey,
+        100_000_000_000
+      );
+    });
+
+    it("Borrows, swaps and deposits to reach 3x leverage", async () => {
+      // Borrow 2000 USDC and swap it for 20 SOL: 30 SOL against 2000 USDC
+      const borrowAssets = new anchor.BN(2000_000_000);
+      const collateralOut = new anchor.BN(20_000_000_000);
+      const swapIx = await mockSwap.methods
+        .swap(borrowAssets, collateralOut)
+        .accounts({
+          userSource: alice.loanAta,
+          userDestination: alice.collateralAta,
+          poolSource: poolLoanAccount,
+          poolDestination: poolCollateralAccount,
+          poolAuthority,
+          user: alice.keypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .instruction();
+
+      await program.methods
+        .leverage(borrowAssets, collateralOut, swapIx.data)
+        .accounts({
+          market: market.marketPda,
+          userPosition: alice.positionPda,
+          loanVault: market.loanVault.publicKey,
+          collateralVault: market.collateralVault.publicKey,
+          userLoanAccount: alice.loanAta,
+          userCollateralAccount: alice.collateralAta,
+          user: alice.keypair.publicKey,
+          swapProgram: mockSwap.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(swapIx.keys)
+        .signers([alice.keypair])
+        .rpc();
+
+      const position = await program.account.userPosition.fetch(alice.positionPda);
+      const state = await program.account.market.fetch(market.marketPda);
+      assert.equal(position.collateralAmount.toString(), "30000000000");
+      assert.equal(state.totalBorrowAssets.toNumber(), 2000_000_000);
+
+      // Leverage = collateral value / equity = 3000 / (3000 - 2000)
+      const collateralValue = position.collateralAmount.divn(10); // lamports → USDC units
+      const equity = collateralValue.sub(state.totalBorrowAssets);
+      assert.equal(collateralValue.div(equity).toNumber(), 3);
+      const balance = await provider.connection.getTokenAccountBalance(alice.loanAta);
+      assert.equal(balance.value.amount, "0", "the borrow was spent on the swap");
+    });
+
+    it("Rejects a swap that delivers less than min_collateral_out", async () => {
+      const borrowAssets = new anchor.BN(100_000_000);
+      const swapIx = await mockSwap.methods
+        .swap(borrowAssets, new anchor.BN(500_000_000)) // half the fair 1 SOL
+        .accounts({
+          userSource: alice.loanAta,
+          userDestination: alice.collateralAta,
+          poolSource: poolLoanAccount,
+          poolDestination: poolCollateralAccount,
+          poolAuthority,
+          user: alice.keypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .instruction();
+
+      try {
+        await program.methods
+          .leverage(borrowAssets, new anchor.BN(1_000_000_000), swapIx.data)
+          .accounts({
+            market: market.marketPda,
+            userPosition: alice.positionPda,
+            loanVault: market.loanVault.publicKey,
+            collateralVault: market.collateralVault.publicKey,
+            userLoanAccount: alice.loanAta,
+            userCollateralAccount: alice.collateralAta,
+            user: alice.keypair.publicKey,
+            swapProgram: mockSwap.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts(swapIx.keys)
+          .signers([alice.keypair])
+          .rpc();
+        assert.fail("Leverage should fail on slippage");
+      } catch (error) {
+        assert.include(error.toString(), "SlippageExceeded");
+      }
+    });
+  });
+
+  describe("Rate Model At Init", () => {
+    it("Creates a market on a kinked rate model", async () => {
+      const irm = {
+        baseRateWad: new anchor.BN("20000000000000000"), // 2%
+        slope1Wad: new anchor.BN("80000000000000000"), // +8% up to the kink
+        slope2Wad: new anchor.BN("900000000000000000"), // +90% above it
+        kinkUtilizationBps: 8_000,
+      };
+      const market = await createTestMarket(LLTV, 0, 0, 0, USDC_DECIMALS, irm);
+      const state = await program.account.market.fetch(market.marketPda);
+      assert.isTrue(state.irmBaseRateWad.eq(irm.baseRateWad));
+      assert.isTrue(state.irmSlope1Wad.eq(irm.slope1Wad));
+      assert.isTrue(state.irmSlope2Wad.eq(irm.slope2Wad));
+      assert.equal(state.irmKinkUtilizationBps, 8_000);
+    });
+
+    it("Rejects a curve that leaves the protocol rate bounds", async () => {
+      // Tops out at 2% + 8% + 190% = 200%, above the 100% max rate
+      const irm = {
+        baseRateWad: new anchor.BN("20000000000000000"),
+        slope1Wad: new anchor.BN("80000000000000000"),
+        slope2Wad: new anchor.BN("1900000000000000000"),
+        kinkUtilizationBps: 8_000,
+      };
+      try {
+        await createTestMarket(LLTV, 0, 0, 0, USDC_DECIMALS, irm);
+        assert.fail("Market creation should fail");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidParameter");
+      }
+    });
+  });
+});
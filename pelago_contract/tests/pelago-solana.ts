@@ -35,6 +35,70 @@ describe("pelago-solana", () => {
   // Constants matching Rust implementation
   const LLTV_PRECISION = 100_000_000;
   const LLTV = 0.8 * LLTV_PRECISION; // 80%
+  // Rate model for initializeMarket: all zero keeps the fixed 5% rate
+  const FIXED_RATE_IRM = {
+    baseRateWad: new anchor.BN(0),
+    slope1Wad: new anchor.BN(0),
+    slope2Wad: new anchor.BN(0),
+    kinkUtilizationBps: 0,
+  };
+
+  const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const [protocolConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("protocol-config")],
+    program.programId
+  );
+  // initialize_config is restricted to the program's upgrade authority
+  const [programDataPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
+  // initialize_market checks every LLTV against the ProtocolConfig
+  // whitelist: create the config singletons if no earlier suite did, with
+  // permissive bounds, and enable `lltv`
+  async function ensureProtocolConfig(lltv: number) {
+    if (!(await provider.connection.getAccountInfo(configPda))) {
+      await program.methods
+        .initializeConfig(authority.publicKey)
+        .accounts({
+          config: configPda,
+          admin: authority.publicKey,
+          program: program.programId,
+          programData: programDataPda,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+    if (!(await provider.connection.getAccountInfo(protocolConfigPda))) {
+      await program.methods
+        .setProtocolConfig(
+          0,
+          authority.publicKey,
+          new anchor.BN(100_000_000), // MAX_LLTV
+          new anchor.BN(0),
+          new anchor.BN("1000000000000000000") // 100%
+        )
+        .accounts({
+          config: configPda,
+          protocolConfig: protocolConfigPda,
+          admin: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+    await program.methods
+      .enableLltv(new anchor.BN(lltv))
+      .accounts({
+        config: configPda,
+        protocolConfig: protocolConfigPda,
+        admin: authority.publicKey,
+      })
+      .rpc();
+  }
 
   before(async () => {
     // Airdrop SOL to user for transaction fees
@@ -123,12 +187,14 @@ describe("pelago-solana", () => {
     // Create vault keypairs
     loanVault = anchor.web3.Keypair.generate();
     collateralVault = anchor.web3.Keypair.generate();
+
+    await ensureProtocolConfig(LLTV);
   });
 
   describe("Market Initialization", () => {
     it("Initializes a new market with vaults", async () => {
       const tx = await program.methods
-        .initializeMarket(new anchor.BN(LLTV))
+        .initializeMarket(new anchor.BN(LLTV), new anchor.BN(0), new anchor.BN(0), 0, FIXED_RATE_IRM)
         .accounts({
           market: marketPda,
           loanTokenMint: loanTokenMint,
@@ -139,6 +205,7 @@ describe("pelago-solana", () => {
           systemProgram: anchor.web3.SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          protocolConfig: protocolConfigPda,
         })
         .signers([loanVault, collateralVault])
         .rpc();
@@ -200,7 +267,7 @@ describe("pelago-solana", () => {
 
       try {
         await program.methods
-          .initializeMarket(new anchor.BN(invalidLltv))
+          .initializeMarket(new anchor.BN(invalidLltv), new anchor.BN(0), new anchor.BN(0), 0, FIXED_RATE_IRM)
           .accounts({
             market: tempMarketPda,
             loanTokenMint: tempLoanMint,
@@ -211,6 +278,7 @@ describe("pelago-solana", () => {
             systemProgram: anchor.web3.SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
             rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            protocolConfig: protocolConfigPda,
           })
           .signers([tempLoanVault, tempCollateralVault])
           .rpc();
@@ -234,7 +302,7 @@ describe("pelago-solana", () => {
       const supplyAmount = 1000_000_000; // 1,000 USDC
 
       const tx = await program.methods
-        .supply(new anchor.BN(supplyAmount), new anchor.BN(0))
+        .supply(new anchor.BN(supplyAmount), new anchor.BN(0), new anchor.BN(0))
         .accounts({
           market: marketPda,
           userPosition: userPositionPda,
@@ -276,7 +344,7 @@ describe("pelago-solana", () => {
     it("Fails to supply zero amount", async () => {
       try {
         await program.methods
-          .supply(new anchor.BN(0), new anchor.BN(0))
+          .supply(new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
           .accounts({
             market: marketPda,
             userPosition: userPositionPda,
@@ -355,7 +423,7 @@ describe("pelago-solana", () => {
       const borrowAmount = 500_000_000; // 500 USDC
 
       const tx = await program.methods
-        .borrow(new anchor.BN(borrowAmount), new anchor.BN(0))
+        .borrow(new anchor.BN(borrowAmount), new anchor.BN(0), false)
         .accounts({
           market: marketPda,
           userPosition: userPositionPda,
@@ -426,7 +494,7 @@ describe("pelago-solana", () => {
 
       try {
         await program.methods
-          .borrow(new anchor.BN(borrowAmount), new anchor.BN(0))
+          .borrow(new anchor.BN(borrowAmount), new anchor.BN(0), false)
           .accounts({
             market: marketPda,
             userPosition: userPositionPda,
@@ -481,7 +549,7 @@ describe("pelago-solana", () => {
 
       try {
         await program.methods
-          .borrow(new anchor.BN(100_000_000), new anchor.BN(0)) // 100 USDC
+          .borrow(new anchor.BN(100_000_000), new anchor.BN(0), false) // 100 USDC
           .accounts({
             market: marketPda,
             userPosition: newUserPositionPda,
@@ -506,7 +574,7 @@ describe("pelago-solana", () => {
 
       try {
         await program.methods
-          .borrow(new anchor.BN(borrowAmount), new anchor.BN(0))
+          .borrow(new anchor.BN(borrowAmount), new anchor.BN(0), false)
           .accounts({
             market: marketPda,
             userPosition: userPositionPda,
@@ -583,7 +651,7 @@ describe("pelago-solana", () => {
 
       // Step 1: Supply loan assets
       await program.methods
-        .supply(new anchor.BN(2_000_000_000), new anchor.BN(0)) // 2,000 USDC
+        .supply(new anchor.BN(2_000_000_000), new anchor.BN(0), new anchor.BN(0)) // 2,000 USDC
         .accounts({
           market: marketPda,
           userPosition: testUserPositionPda,
@@ -614,7 +682,7 @@ describe("pelago-solana", () => {
       // Step 3: Borrow against collateral
       // 20 SOL * 100 USDC/SOL * 0.8 = 1600 USDC max
       await program.methods
-        .borrow(new anchor.BN(1_500_000_000), new anchor.BN(0)) // 1,500 USDC (safe)
+        .borrow(new anchor.BN(1_500_000_000), new anchor.BN(0), false) // 1,500 USDC (safe)
         .accounts({
           market: marketPda,
           userPosition: testUserPositionPda,